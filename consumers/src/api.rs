@@ -0,0 +1,89 @@
+use std::{net::SocketAddr, sync::Arc};
+
+use axum::{
+    Json, Router,
+    extract::{FromRef, State},
+    http::{StatusCode, header},
+    response::IntoResponse,
+    routing::get,
+};
+use drivers::QueueDriver;
+use tracing::{debug, error, info, instrument};
+
+use crate::metrics::{ConsumerHealth, ConsumerMetrics, ConsumerStats};
+
+#[derive(Clone)]
+pub struct ApiState {
+    pub metrics: Arc<ConsumerMetrics>,
+    // Bus the consumer reads from, asked for the queue depth.
+    pub bus: Arc<dyn QueueDriver>,
+}
+
+impl FromRef<ApiState> for Arc<ConsumerMetrics> {
+    fn from_ref(state: &ApiState) -> Self {
+        state.metrics.clone()
+    }
+}
+
+pub fn router(state: ApiState) -> Router {
+    Router::new()
+        .route("/metrics", get(prometheus))
+        .route("/stats", get(stats))
+        .route("/health", get(health))
+        .with_state(state)
+}
+
+// GET /metrics
+//
+// Handler outcome counters in the Prometheus text format.
+async fn prometheus(State(metrics): State<Arc<ConsumerMetrics>>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics.render(),
+    )
+}
+
+// GET /stats
+//
+// Handler outcome counters with the latest error of each category, as JSON.
+async fn stats(State(metrics): State<Arc<ConsumerMetrics>>) -> Json<ConsumerStats> {
+    Json(metrics.stats())
+}
+
+// GET /health
+//
+// Messages in flight, processing rate and queue depth (lag). Answers 503 once the consumer is
+// draining, so that load balancers and orchestrators stop counting on it.
+async fn health(State(state): State<ApiState>) -> (StatusCode, Json<ConsumerHealth>) {
+    let lag = match state.bus.backlog().await {
+        Ok(lag) => lag,
+        Err(e) => {
+            debug!("Failed to read the queue depth: {}", e);
+            None
+        }
+    };
+    let health = state.metrics.health(lag);
+    let status = if state.metrics.is_draining() {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+    (status, Json(health))
+}
+
+// Serve the metrics API on `addr` until the process exits.
+#[instrument(name = "Metrics API", skip(state))]
+pub async fn serve(addr: SocketAddr, state: ApiState) {
+    let listener = match tokio::net::TcpListener::bind(addr).await {
+        Ok(l) => l,
+        Err(e) => {
+            error!("Failed to bind metrics API on {}: {}", addr, e);
+            return;
+        }
+    };
+
+    info!("Metrics API listening on http://{}", addr);
+    if let Err(e) = axum::serve(listener, router(state)).await {
+        error!("Metrics API stopped: {}", e);
+    }
+}
@@ -0,0 +1,49 @@
+use clap::{Parser, Subcommand};
+
+use crate::{export::ExportFormat, replay::ReplayFormat};
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Consumer of crawled pages")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Consume pages from the message bus and write them to the configured sinks (default)
+    Consume,
+    /// Republish a stored crawl corpus to the message bus
+    Replay {
+        /// Format of the stored corpus
+        #[arg(long, value_enum)]
+        from: ReplayFormat,
+        /// File to replay, or a directory whose matching files are replayed in name order
+        #[arg(long)]
+        path: String,
+        /// Queue (or Kafka topic) to publish to, instead of RABBIT_QUEUE/KAFKA_TOPIC
+        #[arg(long)]
+        queue: Option<String>,
+        /// Number of pages published per batch
+        #[arg(long, default_value_t = 100)]
+        batch_size: usize,
+    },
+    /// Export a stored crawl corpus as compressed files partitioned by domain and day
+    Export {
+        /// Format of the stored corpus
+        #[arg(long, value_enum)]
+        from: ReplayFormat,
+        /// File to export, or a directory whose matching files are exported in name order
+        #[arg(long)]
+        path: String,
+        /// Directory the `domain=<host>/day=<YYYY-MM-DD>/` partitions are written to
+        #[arg(long)]
+        out: String,
+        /// Format of the exported files
+        #[arg(long, value_enum, default_value_t = ExportFormat::Jsonl)]
+        format: ExportFormat,
+        /// Maximum number of pages per file (pages are buffered per partition until then)
+        #[arg(long, default_value_t = 10_000)]
+        rows_per_file: usize,
+    },
+}
@@ -0,0 +1,33 @@
+use std::sync::Arc;
+
+use arrow_json::reader::{ReaderBuilder, infer_json_schema_from_iterator};
+use parquet::{arrow::ArrowWriter, basic::Compression, file::properties::WriterProperties};
+use serde_json::{Map, Value};
+
+// Encode records as a Snappy-compressed Parquet file, with a schema inferred from the batch
+// itself.
+pub fn encode(records: &[Map<String, Value>]) -> Result<Vec<u8>, String> {
+    let values: Vec<Value> = records.iter().cloned().map(Value::Object).collect();
+    let schema = infer_json_schema_from_iterator(values.iter().map(Ok))
+        .map_err(|e| format!("Failed to infer the Parquet schema: {e}"))?;
+    let schema = Arc::new(schema);
+
+    let mut decoder = ReaderBuilder::new(schema.clone())
+        .build_decoder()
+        .map_err(|e| e.to_string())?;
+    decoder.serialize(&values).map_err(|e| e.to_string())?;
+    let batch = decoder
+        .flush()
+        .map_err(|e| e.to_string())?
+        .ok_or("Empty Parquet batch")?;
+
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let mut data = Vec::new();
+    let mut writer =
+        ArrowWriter::try_new(&mut data, schema, Some(properties)).map_err(|e| e.to_string())?;
+    writer.write(&batch).map_err(|e| e.to_string())?;
+    writer.close().map_err(|e| e.to_string())?;
+    Ok(data)
+}
@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+
+use drivers::config::QdrantConfig;
+use serde::Deserialize;
+
+// Sinks the consumer writes pages to, loaded from `SINKS_FILE` (default `./sinks.toml`).
+#[derive(Debug, Deserialize)]
+pub struct SinksConfig {
+    #[serde(rename = "sink", default)]
+    pub sinks: Vec<SinkConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SinkConfig {
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: SinkKind,
+    // PageData fields forwarded to the sink (all fields when unset).
+    #[serde(default)]
+    pub fields: Option<Vec<String>>,
+    // Fields removed before writing.
+    #[serde(default)]
+    pub drop: Vec<String>,
+    // Per-field size limit: strings are truncated to N characters, lists to N items.
+    #[serde(default)]
+    pub truncate: HashMap<String, usize>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SinkKind {
+    // Print each page as a JSON line on stdout.
+    Stdout,
+    // Append each page as a JSON line to `path`. With `max_bytes`, the file is rotated to
+    // `<path>.<unix timestamp>` once it would grow past that size.
+    Jsonl {
+        path: String,
+        #[serde(default)]
+        max_bytes: Option<u64>,
+    },
+    // Upsert each page, keyed by URL, as a JSONB row of `table` (needs the `postgres` feature).
+    #[cfg_attr(not(feature = "postgres"), allow(dead_code))]
+    Postgres {
+        url: String,
+        #[serde(default = "default_table")]
+        table: String,
+    },
+    // Upload pages in batches of `batch_size` as objects under `prefix` of an S3-compatible
+    // bucket (needs the `s3` feature, and `parquet` for Parquet objects). Credentials are read
+    // from the `AWS_*` environment variables.
+    #[cfg_attr(not(feature = "s3"), allow(dead_code))]
+    S3 {
+        bucket: String,
+        #[serde(default)]
+        prefix: String,
+        // Endpoint of S3-compatible stores (MinIO, R2, ...); AWS when unset.
+        #[serde(default)]
+        endpoint: Option<String>,
+        #[serde(default)]
+        region: Option<String>,
+        #[serde(default)]
+        format: ObjectFormat,
+        #[serde(default = "default_batch_size")]
+        batch_size: usize,
+    },
+    // Split the visible text of each page into overlapping windows of `chunk_words` words, embed
+    // them with `model` through the OpenAI-compatible API at `endpoint` and upsert them into the
+    // Qdrant collection of `store`. The API keys are read from EMBEDDING_API_KEY and
+    // QDRANT_API_KEY.
+    Embedding {
+        endpoint: String,
+        model: String,
+        #[serde(default = "default_chunk_words")]
+        chunk_words: usize,
+        #[serde(default = "default_chunk_overlap")]
+        chunk_overlap: usize,
+        #[serde(default)]
+        store: QdrantConfig,
+    },
+    // Write each page as WARC request, response and metadata records to files of the `path`
+    // directory, named `<prefix>-<timestamp>-<serial>.warc.gz` and rotated at `max_bytes`.
+    Warc {
+        path: String,
+        #[serde(default = "default_warc_prefix")]
+        prefix: String,
+        #[serde(default = "default_warc_max_bytes")]
+        max_bytes: u64,
+        #[serde(default = "default_gzip")]
+        gzip: bool,
+    },
+}
+
+impl SinkKind {
+    // Kind name, safe to log (connection strings may hold credentials).
+    pub fn name(&self) -> &'static str {
+        match self {
+            SinkKind::Stdout => "stdout",
+            SinkKind::Jsonl { .. } => "jsonl",
+            SinkKind::Postgres { .. } => "postgres",
+            SinkKind::S3 { .. } => "s3",
+            SinkKind::Embedding { .. } => "embedding",
+            SinkKind::Warc { .. } => "warc",
+        }
+    }
+}
+
+// Encoding of the objects written by the S3 sink.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ObjectFormat {
+    #[default]
+    Jsonl,
+    Parquet,
+}
+
+fn default_table() -> String {
+    "pages".to_string()
+}
+
+fn default_batch_size() -> usize {
+    100
+}
+
+fn default_chunk_words() -> usize {
+    200
+}
+
+fn default_chunk_overlap() -> usize {
+    40
+}
+
+fn default_warc_prefix() -> String {
+    "crawl".to_string()
+}
+
+// 1 GiB, the usual size of web-archive files.
+fn default_warc_max_bytes() -> u64 {
+    1 << 30
+}
+
+fn default_gzip() -> bool {
+    true
+}
+
+impl SinksConfig {
+    // Load the sink configuration. Without a config file, pages are printed to stdout unchanged.
+    pub fn load() -> Result<Self, String> {
+        let path = std::env::var("SINKS_FILE").unwrap_or_else(|_| "./sinks.toml".to_string());
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(SinksConfig {
+                    sinks: vec![SinkConfig {
+                        name: "stdout".to_string(),
+                        kind: SinkKind::Stdout,
+                        fields: None,
+                        drop: Vec::new(),
+                        truncate: HashMap::new(),
+                    }],
+                });
+            }
+            Err(e) => return Err(format!("Failed to read {path}: {e}")),
+        };
+
+        let config: SinksConfig =
+            toml::from_str(&content).map_err(|e| format!("Invalid sink config {path}: {e}"))?;
+        if config.sinks.is_empty() {
+            return Err(format!("{path} does not declare any [[sink]]"));
+        }
+        Ok(config)
+    }
+}
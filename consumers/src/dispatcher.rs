@@ -0,0 +1,89 @@
+use models::PageData;
+use serde_json::Value;
+use tracing::{debug, error, info};
+
+use crate::{
+    config::SinksConfig,
+    metrics::{ErrorCategory, HandlerError},
+    sinks::{self, StorageSink},
+    transform::FieldFilter,
+};
+
+struct Route {
+    name: String,
+    filter: FieldFilter,
+    sink: Box<dyn StorageSink>,
+}
+
+// Hands every consumed page to all configured sinks, each with its own field selection.
+pub struct Dispatcher {
+    routes: Vec<Route>,
+}
+
+impl Dispatcher {
+    pub fn new(config: &SinksConfig) -> Result<Self, String> {
+        let routes = config
+            .sinks
+            .iter()
+            .map(|sink| {
+                info!("Configuring sink '{}' ({})", sink.name, sink.kind.name());
+                Ok(Route {
+                    name: sink.name.clone(),
+                    filter: FieldFilter::from_config(sink)?,
+                    sink: sinks::build(sink)?,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Dispatcher { routes })
+    }
+
+    // Write `page` to every sink. A failing sink does not prevent the others from receiving the
+    // page; the errors are reported together as `SinkUnavailable`.
+    pub fn dispatch(&self, page: &PageData) -> Result<(), HandlerError> {
+        let serialized = match serde_json::to_value(page) {
+            Ok(Value::Object(map)) => map,
+            Ok(_) => {
+                return Err(HandlerError::new(
+                    ErrorCategory::Transform,
+                    "PageData did not serialize to an object",
+                ));
+            }
+            Err(e) => {
+                return Err(HandlerError::new(
+                    ErrorCategory::Transform,
+                    format!("Failed to serialize page: {e}"),
+                ));
+            }
+        };
+
+        let mut errors = Vec::new();
+        for route in &self.routes {
+            let record = route.filter.apply(&serialized);
+            match route.sink.write(&record) {
+                Ok(()) => debug!("Page {} written to sink '{}'", page.url, route.name),
+                Err(e) => {
+                    error!("Sink '{}' failed for {}: {}", route.name, page.url, e);
+                    errors.push(format!("{}: {}", route.name, e));
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(HandlerError::new(
+                ErrorCategory::SinkUnavailable,
+                errors.join("; "),
+            ))
+        }
+    }
+
+    // Store what the sinks still buffer, once no page is being dispatched.
+    pub fn flush(&self) {
+        for route in &self.routes {
+            if let Err(e) = route.sink.flush() {
+                error!("Failed to flush sink '{}': {}", route.name, e);
+            }
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use reqwest::Client;
+use scraper::{Html, Node};
+use serde::Deserialize;
+use serde_json::json;
+
+// Elements whose text is not visible content.
+const SKIPPED_ELEMENTS: &[&str] = &["script", "style", "noscript", "template", "head"];
+// Inputs sent in one embeddings request.
+const MAX_INPUTS_PER_REQUEST: usize = 64;
+
+// Visible text of an HTML body: markup, scripts and styles removed, whitespace collapsed.
+pub fn clean_text(body: &str) -> String {
+    let document = Html::parse_document(body);
+    let mut words: Vec<&str> = Vec::new();
+    let mut stack = vec![document.tree.root()];
+    while let Some(node) = stack.pop() {
+        match node.value() {
+            Node::Text(text) => words.extend(text.split_whitespace()),
+            Node::Element(element) if SKIPPED_ELEMENTS.contains(&element.name()) => continue,
+            _ => {}
+        }
+        // children are pushed in reverse so that they are visited in document order
+        stack.extend(node.children().rev());
+    }
+    words.join(" ")
+}
+
+// Split `text` into windows of `size` words, each starting `size - overlap` words after the
+// previous one, so that a passage cut at a window boundary is whole in the next window.
+pub fn chunk(text: &str, size: usize, overlap: usize) -> Vec<String> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let step = size.saturating_sub(overlap).max(1);
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < words.len() {
+        let end = (start + size).min(words.len());
+        chunks.push(words[start..end].join(" "));
+        if end == words.len() {
+            break;
+        }
+        start += step;
+    }
+    chunks
+}
+
+#[derive(Deserialize)]
+struct EmbeddingsResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+// Client of an OpenAI-compatible embeddings API (`POST {endpoint}/embeddings`).
+pub struct EmbeddingClient {
+    client: Client,
+    endpoint: String,
+    model: String,
+    api_key: Option<String>,
+}
+
+impl EmbeddingClient {
+    // The API key, if the endpoint needs one, is read from EMBEDDING_API_KEY.
+    pub fn new(endpoint: &str, model: &str) -> Self {
+        EmbeddingClient {
+            client: Client::new(),
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            model: model.to_string(),
+            api_key: std::env::var("EMBEDDING_API_KEY").ok(),
+        }
+    }
+
+    // Embedding of every input, in input order.
+    pub async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        let mut vectors = Vec::with_capacity(inputs.len());
+        for batch in inputs.chunks(MAX_INPUTS_PER_REQUEST) {
+            vectors.extend(self.embed_batch(batch).await?);
+        }
+        Ok(vectors)
+    }
+
+    async fn embed_batch(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        let mut request = self
+            .client
+            .post(format!("{}/embeddings", self.endpoint))
+            .json(&json!({ "model": self.model, "input": inputs }));
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Embedding request to {} failed: {e}", self.endpoint))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("Embedding endpoint returned {status}: {body}"));
+        }
+        let mut response: EmbeddingsResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("Invalid embedding response: {e}"))?;
+        if response.data.len() != inputs.len() {
+            return Err(format!(
+                "Embedding endpoint returned {} vectors for {} inputs",
+                response.data.len(),
+                inputs.len()
+            ));
+        }
+        response.data.sort_by_key(|data| data.index);
+        Ok(response
+            .data
+            .into_iter()
+            .map(|data| data.embedding)
+            .collect())
+    }
+}
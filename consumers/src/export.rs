@@ -0,0 +1,228 @@
+use std::{
+    collections::BTreeMap,
+    fs::{File, OpenOptions},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use drivers::dates;
+use flate2::{Compression, write::GzEncoder};
+use models::PageData;
+use serde_json::{Map, Value};
+use tracing::{info, warn};
+
+use crate::replay::{self, ReplayFormat};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    // Gzip-compressed JSON lines, one PageData per line
+    Jsonl,
+    // Snappy-compressed Parquet files (needs the `parquet` feature)
+    Parquet,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Jsonl => "jsonl.gz",
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ExportStats {
+    pub files: usize,
+    pub exported: usize,
+    pub skipped: usize,
+    pub written: usize,
+}
+
+// Partition of the output: host of the page and UTC day it was crawled.
+type Partition = (String, String);
+
+// Export every page stored under `path` to `out`, partitioned Hive-style by domain and crawl day
+// (`domain=<host>/day=<YYYY-MM-DD>/part-<timestamp>-<serial>.<ext>`), so that analytics tools
+// (DuckDB, Spark, Athena, ...) can prune partitions. Each partition is buffered in memory and
+// written once it holds `rows_per_file` pages, then at the end of the export.
+//
+// Rows are full PageData records, so every file has the columns of `models::PageData`. In
+// Parquet files, fields holding objects or lists of objects (links, structured data, custom
+// fields, ...) are stored as JSON strings: their shape varies from page to page, and a column
+// type inferred from one batch would not match the next one.
+pub fn export(
+    from: ReplayFormat,
+    path: &str,
+    out: &str,
+    format: ExportFormat,
+    rows_per_file: usize,
+) -> Result<ExportStats, String> {
+    if format == ExportFormat::Parquet && cfg!(not(feature = "parquet")) {
+        return Err("Parquet export requires building with the `parquet` feature".to_string());
+    }
+    let mut writer = PartitionWriter {
+        out: PathBuf::from(out),
+        format,
+        timestamp: dates::compact_timestamp(unix_now()),
+        serial: 0,
+    };
+    let rows_per_file = rows_per_file.max(1);
+    let mut stats = ExportStats::default();
+    let mut partitions: BTreeMap<Partition, Vec<Map<String, Value>>> = BTreeMap::new();
+
+    for file in replay::input_files(Path::new(path), from)? {
+        info!("Exporting {}", file.display());
+        for record in replay::read(&file, from)? {
+            let page = match record {
+                Ok(page) => page,
+                Err(err) => {
+                    warn!("Skipping record of {}: {}", file.display(), err);
+                    stats.skipped += 1;
+                    continue;
+                }
+            };
+            let partition = partition(&page);
+            let Value::Object(mut row) = serde_json::to_value(&page).map_err(|e| e.to_string())?
+            else {
+                unreachable!("PageData serializes to a JSON object");
+            };
+            if format == ExportFormat::Parquet {
+                flatten(&mut row);
+            }
+
+            let rows = partitions.entry(partition.clone()).or_default();
+            rows.push(row);
+            stats.exported += 1;
+            if rows.len() >= rows_per_file {
+                writer.write(&partition, rows)?;
+                rows.clear();
+                stats.written += 1;
+            }
+        }
+        stats.files += 1;
+    }
+
+    for (partition, rows) in partitions.iter().filter(|(_, rows)| !rows.is_empty()) {
+        writer.write(partition, rows)?;
+        stats.written += 1;
+    }
+    Ok(stats)
+}
+
+struct PartitionWriter {
+    out: PathBuf,
+    format: ExportFormat,
+    // Start of the export, so that files of successive exports do not collide.
+    timestamp: String,
+    serial: u64,
+}
+
+impl PartitionWriter {
+    fn write(
+        &mut self,
+        (domain, day): &Partition,
+        rows: &[Map<String, Value>],
+    ) -> Result<(), String> {
+        let dir = self
+            .out
+            .join(format!("domain={domain}"))
+            .join(format!("day={day}"));
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+        let (file, path) = loop {
+            self.serial += 1;
+            let name = format!(
+                "part-{}-{:05}.{}",
+                self.timestamp,
+                self.serial,
+                self.format.extension()
+            );
+            let path = dir.join(name);
+            match OpenOptions::new().create_new(true).write(true).open(&path) {
+                Ok(file) => break (file, path),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(format!("Failed to create {}: {e}", path.display())),
+            }
+        };
+
+        let written = match self.format {
+            ExportFormat::Jsonl => write_jsonl(file, rows),
+            #[cfg(feature = "parquet")]
+            ExportFormat::Parquet => crate::columnar::encode(rows).and_then(|data| {
+                let mut file = file;
+                file.write_all(&data).map_err(|e| e.to_string())
+            }),
+            #[cfg(not(feature = "parquet"))]
+            ExportFormat::Parquet => unreachable!("rejected when the export starts"),
+        };
+        written.map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+        info!("Wrote {} pages to {}", rows.len(), path.display());
+        Ok(())
+    }
+}
+
+fn write_jsonl(file: File, rows: &[Map<String, Value>]) -> Result<(), String> {
+    let mut encoder = GzEncoder::new(BufWriter::new(file), Compression::default());
+    for row in rows {
+        serde_json::to_writer(&mut encoder, row).map_err(|e| e.to_string())?;
+        encoder.write_all(b"\n").map_err(|e| e.to_string())?;
+    }
+    encoder
+        .finish()
+        .and_then(|mut file| file.flush())
+        .map_err(|e| e.to_string())
+}
+
+// Domain and day partition of a page. Pages without a host or crawl time go to `unknown`.
+fn partition(page: &PageData) -> Partition {
+    let domain = host(&page.url)
+        .map(|host| {
+            host.chars()
+                .map(|c| match c {
+                    'a'..='z' | '0'..='9' | '.' | '-' => c,
+                    _ => '_',
+                })
+                .collect()
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+    let day = match page.crawl.crawled_at {
+        0 => "unknown".to_string(),
+        at => dates::day(at),
+    };
+    (domain, day)
+}
+
+// Lowercased host of an absolute URL, without credentials and port.
+fn host(url: &str) -> Option<String> {
+    let (_, rest) = url.split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let authority = authority.rsplit('@').next()?;
+    let host = match authority.strip_prefix('[') {
+        // IPv6 literal
+        Some(ipv6) => ipv6.split(']').next()?,
+        None => authority.split(':').next()?,
+    };
+    (!host.is_empty()).then(|| host.to_lowercase())
+}
+
+// Store objects and lists of objects as JSON strings (see `export`).
+fn flatten(row: &mut Map<String, Value>) {
+    for value in row.values_mut() {
+        let nested = match value {
+            Value::Object(_) => true,
+            Value::Array(items) => items.iter().any(|item| item.is_object() || item.is_array()),
+            _ => false,
+        };
+        if nested {
+            *value = Value::String(value.to_string());
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
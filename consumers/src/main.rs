@@ -1,31 +1,303 @@
-use drivers::rabbit::RabbitDriver;
-use models::PageData;
+mod api;
+mod cli;
+#[cfg(feature = "parquet")]
+mod columnar;
+mod config;
+mod dispatcher;
+mod embedding;
+mod export;
+mod metrics;
+mod replay;
+mod sinks;
+mod transform;
 
-fn process_message(page_data: PageData) -> Result<(), String> {
-    // Simulate processing the message...
-    println!("Processing message: {}", page_data);
-
-    Ok(())
-}
+use clap::Parser;
+use cli::{Cli, Command};
+use config::SinksConfig;
+use dispatcher::Dispatcher;
+use drivers::{
+    QueueDriver,
+    config::{BusConfig, RedisConfig},
+    dedup::{self, SeenSet},
+    errors::DriverError,
+    telemetry,
+};
+use export::ExportFormat;
+use metrics::{ConsumerMetrics, ErrorCategory, HandlerError};
+use replay::ReplayFormat;
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+use tokio::{
+    signal::unix::{SignalKind, signal},
+    sync::Mutex,
+};
+use tracing::{error, info, warn};
 
 #[tokio::main]
 async fn main() {
-    // Initialize tracing subscriber
-    tracing_subscriber::fmt::init();
+    // Initialize tracing subscriber (and the OTLP span exporter, when enabled)
+    let _telemetry = telemetry::init("consumers");
 
     // Initialize dotenv
     dotenv::dotenv().ok();
 
-    // connect to RabbitMQ
-    let rabbit = RabbitDriver::new()
+    match Cli::parse().command.unwrap_or(Command::Consume) {
+        Command::Consume => consume().await,
+        Command::Replay {
+            from,
+            path,
+            queue,
+            batch_size,
+        } => replay(from, path, queue, batch_size).await,
+        Command::Export {
+            from,
+            path,
+            out,
+            format,
+            rows_per_file,
+        } => export(from, path, out, format, rows_per_file),
+    }
+}
+
+async fn consume() {
+    // load the sinks pages are written to, each with its own field selection
+    let sinks = SinksConfig::load().expect("Failed to load sink configuration");
+    let dispatcher = Dispatcher::new(&sinks).expect("Failed to configure sinks");
+
+    // connect to the message bus selected by MESSAGE_BUS (RabbitMQ or Kafka). With RabbitMQ,
+    // RABBIT_CONSUMER_CONCURRENCY pages are handled at the same time; Kafka partitions are
+    // handled in order, one page at a time.
+    let bus_config = BusConfig::from_env().expect("Invalid message bus configuration");
+    let bus = drivers::connect_queue_driver_with(&bus_config)
         .await
-        .expect("Failed to build RabbitMQ client");
+        .expect("Failed to build message bus client");
+    if matches!(
+        bus_config.kind.trim().to_lowercase().as_str(),
+        "rabbitmq" | "rabbit"
+    ) {
+        info!(
+            "Consuming with {} workers (prefetch {})",
+            bus_config.rabbit.consumer_concurrency.max(1),
+            bus_config.rabbit.effective_prefetch()
+        );
+    }
+
+    // count handler outcomes by error category, served on METRICS_ADDR when set
+    let metrics = Arc::new(ConsumerMetrics::default());
+    let sampled = metrics.clone();
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(1));
+        loop {
+            ticker.tick().await;
+            sampled.sample();
+        }
+    });
+    if let Ok(addr) = std::env::var("METRICS_ADDR") {
+        let addr: SocketAddr = addr
+            .parse()
+            .expect("METRICS_ADDR must be a valid socket address");
+        let state = api::ApiState {
+            metrics: metrics.clone(),
+            bus: bus.clone(),
+        };
+        tokio::spawn(api::serve(addr, state));
+    }
+
+    // Time given to the pages in flight once a shutdown is requested.
+    let drain_timeout = env_secs("CONSUMER_DRAIN_TIMEOUT_SECS", 30);
 
-    // Start consuming messages
-    rabbit
-        .consume(process_message)
+    // with CONSUMER_DEDUP, message ids are recorded in Redis so that duplicates are skipped
+    let seen = dedup_enabled().then(|| Arc::new(seen_set(&bus_config)));
+
+    // Start consuming messages. Messages are decoded here rather than with `consume_json` so that
+    // every schema version is accepted (see `models::decode_page`) and invalid payloads are
+    // counted as validation errors. Sinks write synchronously, so each page
+    // is dispatched on the blocking pool while the bus keeps delivering. Pages a sink failed to
+    // store are requeued; invalid ones are dead-lettered. Each page is processed in a span joining
+    // the trace of the crawler request that published it (with the `otel` feature).
+    let dispatcher = Arc::new(dispatcher);
+    let handler_dispatcher = dispatcher.clone();
+    let handler_metrics = metrics.clone();
+    let handler = drivers::message_handler(move |data| {
+        let dispatcher = handler_dispatcher.clone();
+        let metrics = handler_metrics.clone();
+        async move {
+            metrics.start_message();
+            let headers = drivers::message_headers();
+            let span = tracing::info_span!(
+                "Process Page",
+                crawl_id = headers
+                    .get("x-crawl-id")
+                    .map(String::as_str)
+                    .unwrap_or_default(),
+                agent = headers
+                    .get("x-crawler-agent")
+                    .map(String::as_str)
+                    .unwrap_or_default(),
+                url = tracing::field::Empty
+            );
+            telemetry::set_parent(&span, &headers);
+            let dispatch_span = span.clone();
+            let outcome = tokio::task::spawn_blocking(move || {
+                let _entered = dispatch_span.enter();
+                models::decode_page(&data)
+                    .map_err(|e| {
+                        HandlerError::new(
+                            ErrorCategory::Validation,
+                            format!("Failed to decode message: {e}"),
+                        )
+                    })
+                    .and_then(|page_data| {
+                        dispatch_span.record("url", page_data.url.as_str());
+                        dispatcher.dispatch(&page_data)
+                    })
+            })
+            .await
+            .unwrap_or_else(|e| {
+                Err(HandlerError::new(
+                    ErrorCategory::SinkUnavailable,
+                    format!("Sink task failed: {e}"),
+                ))
+            });
+            let _entered = span.enter();
+            metrics.finish_message();
+            match outcome {
+                Ok(()) => {
+                    metrics.record_success();
+                    Ok(())
+                }
+                Err(e) => {
+                    metrics.record_error(&e);
+                    Err(e.into())
+                }
+            }
+        }
+    });
+    let handler = match seen {
+        Some(seen) => dedup::deduplicated(seen, handler),
+        None => handler,
+    };
+    let consuming = bus.consume(handler);
+    tokio::pin!(consuming);
+
+    // On SIGINT/SIGTERM, stop taking messages and let the pages in flight finish (and be acked)
+    // before exiting; messages prefetched but not handled are redelivered by the broker.
+    let mut sigterm = signal(SignalKind::terminate()).expect("Failed to listen for SIGTERM");
+    let result = tokio::select! {
+        result = &mut consuming => result,
+        _ = tokio::signal::ctrl_c() => drain(&bus, &metrics, consuming, drain_timeout, "SIGINT").await,
+        _ = sigterm.recv() => drain(&bus, &metrics, consuming, drain_timeout, "SIGTERM").await,
+    };
+    result.expect("Failed to start consuming messages");
+
+    // upload what the sinks still buffer (e.g. the partial batch of the `s3` sink)
+    tokio::task::spawn_blocking(move || dispatcher.flush())
         .await
-        .expect("Failed to start consuming messages");
+        .expect("Sink flush task failed");
+    if let Err(e) = bus.close().await {
+        error!("Failed to close message bus client: {}", e);
+    }
 
     println!("All agents have completed their tasks.");
 }
+
+fn env_secs(name: &str, default: u64) -> u64 {
+    std::env::var(name)
+        .ok()
+        .map(|secs| {
+            secs.trim()
+                .parse()
+                .unwrap_or_else(|_| panic!("{name} must be a number of seconds"))
+        })
+        .unwrap_or(default)
+}
+
+fn dedup_enabled() -> bool {
+    std::env::var("CONSUMER_DEDUP")
+        .map(|v| matches!(v.trim().to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+// Seen-set of the consumed queue (or topic), in the Redis instance of REDIS_HOST. Ids are kept
+// for CONSUMER_DEDUP_TTL_SECS (a day by default) and a page not handled within
+// CONSUMER_DEDUP_LEASE_SECS is handled again by its next delivery.
+fn seen_set(bus_config: &BusConfig) -> SeenSet {
+    let redis = RedisConfig::from_env().expect("Invalid Redis configuration");
+    let store = drivers::connect_cache_driver_with(&redis).expect("Failed to connect to Redis");
+    if store.is_memory() {
+        warn!(
+            "CONSUMER_DEDUP with CACHE_BACKEND=memory only drops duplicates seen by this process"
+        );
+    }
+    let namespace = match bus_config.kind.trim().to_lowercase().as_str() {
+        "kafka" => format!(
+            "{}:{}",
+            bus_config.kafka.topic,
+            bus_config.kafka.effective_group_id()
+        ),
+        _ => bus_config.rabbit.queue.clone(),
+    };
+    info!("Dropping duplicate messages of {}", namespace);
+    SeenSet::new(
+        Arc::new(Mutex::new(store)),
+        &namespace,
+        Duration::from_secs(env_secs("CONSUMER_DEDUP_TTL_SECS", 24 * 60 * 60)),
+        Duration::from_secs(env_secs("CONSUMER_DEDUP_LEASE_SECS", 300)),
+    )
+}
+
+// Stop the consumer and wait up to `timeout_secs` for the pages in flight.
+async fn drain(
+    bus: &Arc<dyn QueueDriver>,
+    metrics: &ConsumerMetrics,
+    consuming: impl Future<Output = Result<(), DriverError>>,
+    timeout_secs: u64,
+    signal: &str,
+) -> Result<(), DriverError> {
+    warn!(
+        "Received {}, finishing {} pages in flight",
+        signal,
+        metrics.in_flight()
+    );
+    metrics.set_draining();
+    bus.stop_consuming().await?;
+    match tokio::time::timeout(Duration::from_secs(timeout_secs), consuming).await {
+        Ok(result) => result,
+        Err(_) => {
+            warn!(
+                "{} pages still in flight after {}s, their messages will be redelivered",
+                metrics.in_flight(),
+                timeout_secs
+            );
+            Ok(())
+        }
+    }
+}
+
+// Republish a stored corpus so that it can be processed again by new sinks or extractors
+async fn replay(format: ReplayFormat, path: String, queue: Option<String>, batch_size: usize) {
+    let bus = drivers::connect_queue_driver_to(queue.as_deref())
+        .await
+        .expect("Failed to build message bus client");
+
+    let stats = replay::replay(bus.clone(), format, &path, batch_size)
+        .await
+        .expect("Failed to replay corpus");
+    if let Err(e) = bus.close().await {
+        tracing::error!("Failed to close message bus client: {}", e);
+    }
+
+    println!(
+        "Replayed {} pages from {} files ({} skipped)",
+        stats.published, stats.files, stats.skipped
+    );
+}
+
+// Write a stored corpus as compressed JSONL or Parquet files for analytics tools
+fn export(from: ReplayFormat, path: String, out: String, format: ExportFormat, rows: usize) {
+    let stats = export::export(from, &path, &out, format, rows).expect("Failed to export corpus");
+
+    println!(
+        "Exported {} pages from {} files to {} files in {} ({} skipped)",
+        stats.exported, stats.files, stats.written, out, stats.skipped
+    );
+}
@@ -0,0 +1,294 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt::{Display, Formatter, Write},
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use drivers::errors::ConsumeError;
+use serde::Serialize;
+
+// Period the processing rate is averaged over.
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+// Why a message could not be handled, so that a schema problem can be told apart from a sink
+// outage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    // The message is not a valid PageData (malformed JSON, wrong schema).
+    Validation,
+    // A sink could not store the record (database, disk or network failure).
+    SinkUnavailable,
+    // The page could not be turned into the records handed to the sinks.
+    Transform,
+}
+
+impl ErrorCategory {
+    pub const ALL: [ErrorCategory; 3] = [
+        ErrorCategory::Validation,
+        ErrorCategory::SinkUnavailable,
+        ErrorCategory::Transform,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCategory::Validation => "validation",
+            ErrorCategory::SinkUnavailable => "sink_unavailable",
+            ErrorCategory::Transform => "transform",
+        }
+    }
+}
+
+impl Display for ErrorCategory {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+// Error of the message handler, with its category.
+#[derive(Debug, Clone)]
+pub struct HandlerError {
+    pub category: ErrorCategory,
+    pub message: String,
+}
+
+impl HandlerError {
+    pub fn new(category: ErrorCategory, message: impl Into<String>) -> Self {
+        HandlerError {
+            category,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for HandlerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} error: {}", self.category, self.message)
+    }
+}
+
+// A sink outage may be over when the message is redelivered; invalid pages never will be valid.
+impl From<HandlerError> for ConsumeError {
+    fn from(error: HandlerError) -> Self {
+        match error.category {
+            ErrorCategory::SinkUnavailable => ConsumeError::Retryable(error.to_string()),
+            ErrorCategory::Validation | ErrorCategory::Transform => {
+                ConsumeError::Fatal(error.to_string())
+            }
+        }
+    }
+}
+
+// Most recent error of a category.
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorSample {
+    pub message: String,
+    // Unix timestamp (seconds) of the error.
+    pub at: u64,
+}
+
+// Per-category counters and latest error.
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryStats {
+    pub count: u64,
+    pub last_error: Option<ErrorSample>,
+}
+
+// Snapshot of the consumer metrics, as returned by the stats API.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConsumerStats {
+    pub handled: u64,
+    pub failed: u64,
+    pub errors: BTreeMap<ErrorCategory, CategoryStats>,
+}
+
+// State of the consumer, as returned by the health API.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConsumerHealth {
+    // `ok`, or `draining` once a shutdown was requested.
+    pub status: &'static str,
+    pub in_flight: u64,
+    pub handled: u64,
+    pub failed: u64,
+    // Messages processed per second over the last minute.
+    pub rate: f64,
+    // Messages waiting in the queue, when the broker reports it.
+    pub lag: Option<u64>,
+}
+
+// Outcome counters of the message handler, shared with the metrics API.
+#[derive(Default)]
+pub struct ConsumerMetrics {
+    handled: AtomicU64,
+    errors: [AtomicU64; ErrorCategory::ALL.len()],
+    last_errors: Mutex<BTreeMap<ErrorCategory, ErrorSample>>,
+    // Messages being handled.
+    in_flight: AtomicU64,
+    draining: AtomicBool,
+    // Processed messages counted by `sample`, oldest first.
+    samples: Mutex<VecDeque<(Instant, u64)>>,
+}
+
+impl ConsumerMetrics {
+    pub fn start_message(&self) {
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn finish_message(&self) {
+        self.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn in_flight(&self) -> u64 {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+
+    // The consumer stopped taking messages and finishes the ones in flight.
+    pub fn set_draining(&self) {
+        self.draining.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::Relaxed)
+    }
+
+    // Record the number of processed messages, called periodically to compute the rate.
+    pub fn sample(&self) {
+        let now = Instant::now();
+        let mut samples = self.samples.lock().unwrap();
+        samples.push_back((now, self.processed()));
+        while samples
+            .front()
+            .is_some_and(|(at, _)| now.duration_since(*at) > RATE_WINDOW)
+        {
+            samples.pop_front();
+        }
+    }
+
+    // Messages processed (handled or failed) per second over the sampled window.
+    pub fn rate(&self) -> f64 {
+        let samples = self.samples.lock().unwrap();
+        match (samples.front(), samples.back()) {
+            (Some((first_at, first)), Some((last_at, last))) if last_at > first_at => {
+                (last - first) as f64 / last_at.duration_since(*first_at).as_secs_f64()
+            }
+            _ => 0.0,
+        }
+    }
+
+    fn processed(&self) -> u64 {
+        let failed: u64 = self
+            .errors
+            .iter()
+            .map(|count| count.load(Ordering::Relaxed))
+            .sum();
+        self.handled.load(Ordering::Relaxed) + failed
+    }
+
+    pub fn health(&self, lag: Option<u64>) -> ConsumerHealth {
+        let stats = self.stats();
+        ConsumerHealth {
+            status: if self.is_draining() { "draining" } else { "ok" },
+            in_flight: self.in_flight(),
+            handled: stats.handled,
+            failed: stats.failed,
+            rate: self.rate(),
+            lag,
+        }
+    }
+
+    pub fn record_success(&self) {
+        self.handled.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_error(&self, error: &HandlerError) {
+        self.errors[error.category as usize].fetch_add(1, Ordering::Relaxed);
+        let sample = ErrorSample {
+            message: error.message.clone(),
+            at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+        };
+        self.last_errors
+            .lock()
+            .unwrap()
+            .insert(error.category, sample);
+    }
+
+    pub fn stats(&self) -> ConsumerStats {
+        let last_errors = self.last_errors.lock().unwrap();
+        let errors: BTreeMap<ErrorCategory, CategoryStats> = ErrorCategory::ALL
+            .into_iter()
+            .map(|category| {
+                let stats = CategoryStats {
+                    count: self.errors[category as usize].load(Ordering::Relaxed),
+                    last_error: last_errors.get(&category).cloned(),
+                };
+                (category, stats)
+            })
+            .collect();
+        ConsumerStats {
+            handled: self.handled.load(Ordering::Relaxed),
+            failed: errors.values().map(|stats| stats.count).sum(),
+            errors,
+        }
+    }
+
+    // Metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let stats = self.stats();
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "# HELP consumer_messages_handled_total Messages written to every sink."
+        );
+        let _ = writeln!(out, "# TYPE consumer_messages_handled_total counter");
+        let _ = writeln!(out, "consumer_messages_handled_total {}", stats.handled);
+        let _ = writeln!(
+            out,
+            "# HELP consumer_handler_errors_total Messages rejected by the handler, by category."
+        );
+        let _ = writeln!(out, "# TYPE consumer_handler_errors_total counter");
+        for (category, category_stats) in &stats.errors {
+            let _ = writeln!(
+                out,
+                "consumer_handler_errors_total{{category=\"{}\"}} {}",
+                category, category_stats.count
+            );
+        }
+        let _ = writeln!(
+            out,
+            "# HELP consumer_messages_in_flight Messages being handled."
+        );
+        let _ = writeln!(out, "# TYPE consumer_messages_in_flight gauge");
+        let _ = writeln!(out, "consumer_messages_in_flight {}", self.in_flight());
+        let _ = writeln!(
+            out,
+            "# HELP consumer_processing_rate Messages processed per second over the last minute."
+        );
+        let _ = writeln!(out, "# TYPE consumer_processing_rate gauge");
+        let _ = writeln!(out, "consumer_processing_rate {:.3}", self.rate());
+        let _ = writeln!(
+            out,
+            "# HELP consumer_handler_last_error_timestamp_seconds Time of the latest error."
+        );
+        let _ = writeln!(
+            out,
+            "# TYPE consumer_handler_last_error_timestamp_seconds gauge"
+        );
+        for (category, category_stats) in &stats.errors {
+            if let Some(sample) = &category_stats.last_error {
+                let _ = writeln!(
+                    out,
+                    "consumer_handler_last_error_timestamp_seconds{{category=\"{}\"}} {}",
+                    category, sample.at
+                );
+            }
+        }
+        out
+    }
+}
@@ -0,0 +1,135 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use drivers::QueueDriver;
+use flate2::read::MultiGzDecoder;
+use models::PageData;
+use tracing::{info, warn};
+
+pub mod jsonl;
+pub mod warc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReplayFormat {
+    // PageData records as written by the `jsonl` sink, one per line
+    Jsonl,
+    // WARC files; `response` records are turned into pages
+    Warc,
+}
+
+impl ReplayFormat {
+    // File name suffixes picked up when replaying a directory.
+    fn extensions(self) -> &'static [&'static str] {
+        match self {
+            ReplayFormat::Jsonl => &[".jsonl", ".jsonl.gz"],
+            ReplayFormat::Warc => &[".warc", ".warc.gz"],
+        }
+    }
+}
+
+// Stored pages read back from a corpus file. Errors concern single records, which are skipped.
+pub type Records = Box<dyn Iterator<Item = Result<PageData, String>>>;
+
+#[derive(Debug, Default)]
+pub struct ReplayStats {
+    pub files: usize,
+    pub published: usize,
+    pub skipped: usize,
+}
+
+// Republish every page stored under `path` to the message bus, `batch_size` pages at a time.
+pub async fn replay(
+    bus: Arc<dyn QueueDriver>,
+    format: ReplayFormat,
+    path: &str,
+    batch_size: usize,
+) -> Result<ReplayStats, String> {
+    let mut stats = ReplayStats::default();
+    let batch_size = batch_size.max(1);
+
+    for file in input_files(Path::new(path), format)? {
+        info!("Replaying {}", file.display());
+        let mut batch = Vec::with_capacity(batch_size);
+        for record in read(&file, format)? {
+            let page = match record {
+                Ok(page) => page,
+                Err(err) => {
+                    warn!("Skipping record of {}: {}", file.display(), err);
+                    stats.skipped += 1;
+                    continue;
+                }
+            };
+            batch.push(serde_json::to_vec(&page).map_err(|e| e.to_string())?);
+            if batch.len() == batch_size {
+                publish(&bus, &mut batch, &mut stats).await?;
+            }
+        }
+        publish(&bus, &mut batch, &mut stats).await?;
+        stats.files += 1;
+    }
+
+    Ok(stats)
+}
+
+async fn publish(
+    bus: &Arc<dyn QueueDriver>,
+    batch: &mut Vec<Vec<u8>>,
+    stats: &mut ReplayStats,
+) -> Result<(), String> {
+    if batch.is_empty() {
+        return Ok(());
+    }
+    bus.publish_batch(batch)
+        .await
+        .map_err(|e| format!("Failed to publish replayed pages: {e}"))?;
+    stats.published += batch.len();
+    info!("{} pages replayed", stats.published);
+    batch.clear();
+    Ok(())
+}
+
+// Pages stored in a corpus file of `format`.
+pub fn read(path: &Path, format: ReplayFormat) -> Result<Records, String> {
+    let reader = open(path)?;
+    Ok(match format {
+        ReplayFormat::Jsonl => Box::new(jsonl::read(reader)),
+        ReplayFormat::Warc => Box::new(warc::read(reader)),
+    })
+}
+
+// `path` itself, or the files of the directory `path` matching the format, in name order.
+pub fn input_files(path: &Path, format: ReplayFormat) -> Result<Vec<PathBuf>, String> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let entries =
+        std::fs::read_dir(path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|file| {
+            let name = file.file_name().unwrap_or_default().to_string_lossy();
+            format.extensions().iter().any(|ext| name.ends_with(ext))
+        })
+        .collect();
+    files.sort();
+
+    if files.is_empty() {
+        return Err(format!("No {:?} files found in {}", format, path.display()));
+    }
+    Ok(files)
+}
+
+// Open a corpus file, transparently decompressing `.gz` files.
+fn open(path: &Path) -> Result<Box<dyn BufRead>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
+    if path.extension().is_some_and(|ext| ext == "gz") {
+        Ok(Box::new(BufReader::new(MultiGzDecoder::new(file))))
+    } else {
+        Ok(Box::new(BufReader::new(file)))
+    }
+}
@@ -0,0 +1,32 @@
+use std::io::BufRead;
+
+use models::PageData;
+use serde_json::{Map, Value};
+
+// Read pages written by the `jsonl` sink. Sinks may drop or truncate fields, so each record is laid
+// over a default PageData: missing fields come back empty, but the page URL is required.
+pub fn read(reader: Box<dyn BufRead>) -> impl Iterator<Item = Result<PageData, String>> {
+    reader
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.as_ref().map_or(true, |l| !l.trim().is_empty()))
+        .map(|(n, line)| {
+            let line = line.map_err(|e| format!("line {}: {e}", n + 1))?;
+            parse(&line).map_err(|e| format!("line {}: {e}", n + 1))
+        })
+}
+
+fn parse(line: &str) -> Result<PageData, String> {
+    let record: Map<String, Value> = serde_json::from_str(line).map_err(|e| e.to_string())?;
+
+    let Value::Object(mut page) = serde_json::to_value(PageData::default()).unwrap() else {
+        unreachable!("PageData serializes to a JSON object");
+    };
+    page.extend(record);
+
+    let page: PageData = serde_json::from_value(Value::Object(page)).map_err(|e| e.to_string())?;
+    if page.url.is_empty() {
+        return Err("record has no url".to_string());
+    }
+    Ok(page)
+}
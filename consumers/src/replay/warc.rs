@@ -0,0 +1,168 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, Read},
+};
+
+use models::{PageData, SchemaVersion};
+
+// Read the `response` records of a WARC file as pages: target URI, HTTP status, headers and body.
+// Other records (requests, metadata, warcinfo, ...) are ignored. Links and meta tags are not
+// stored in WARC files and are left empty.
+pub fn read(reader: Box<dyn BufRead>) -> impl Iterator<Item = Result<PageData, String>> {
+    WarcReader {
+        reader,
+        offset: 0,
+        done: false,
+    }
+}
+
+struct WarcReader {
+    reader: Box<dyn BufRead>,
+    // Bytes consumed so far, used to locate malformed records.
+    offset: u64,
+    // Set at the end of the file or on a framing error, after which records cannot be located.
+    done: bool,
+}
+
+struct Record {
+    headers: HashMap<String, String>,
+    block: Vec<u8>,
+}
+
+impl Iterator for WarcReader {
+    type Item = Result<PageData, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let record = match self.next_record() {
+                Ok(Some(record)) => record,
+                Ok(None) => {
+                    self.done = true;
+                    break;
+                }
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+            };
+
+            let is_response = record.header("warc-type") == Some("response")
+                && record
+                    .header("content-type")
+                    .is_some_and(|t| t.starts_with("application/http"));
+            if is_response {
+                return Some(page_from_response(&record));
+            }
+        }
+        None
+    }
+}
+
+impl WarcReader {
+    fn next_record(&mut self) -> Result<Option<Record>, String> {
+        // Records are separated by blank lines.
+        let version = loop {
+            match self.read_line()? {
+                None => return Ok(None),
+                Some(line) if line.is_empty() => continue,
+                Some(line) => break line,
+            }
+        };
+        if !version.starts_with("WARC/") {
+            return Err(format!(
+                "expected a WARC record at byte {}, found {:?}",
+                self.offset, version
+            ));
+        }
+
+        let mut headers = HashMap::new();
+        loop {
+            match self.read_line()? {
+                None => return Err("truncated WARC record header".to_string()),
+                Some(line) if line.is_empty() => break,
+                Some(line) => {
+                    if let Some((name, value)) = line.split_once(':') {
+                        headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+                    }
+                }
+            }
+        }
+
+        let length = headers
+            .get("content-length")
+            .and_then(|l| l.parse::<usize>().ok())
+            .ok_or_else(|| format!("WARC record at byte {} has no Content-Length", self.offset))?;
+        let mut block = vec![0; length];
+        self.reader
+            .read_exact(&mut block)
+            .map_err(|e| format!("truncated WARC record: {e}"))?;
+        self.offset += length as u64;
+
+        Ok(Some(Record { headers, block }))
+    }
+
+    // Next line without its line terminator, or `None` at the end of the file.
+    fn read_line(&mut self) -> Result<Option<String>, String> {
+        let mut line = Vec::new();
+        let read = self
+            .reader
+            .read_until(b'\n', &mut line)
+            .map_err(|e| e.to_string())?;
+        if read == 0 {
+            return Ok(None);
+        }
+        self.offset += read as u64;
+        Ok(Some(
+            String::from_utf8_lossy(&line)
+                .trim_end_matches(['\r', '\n'])
+                .to_string(),
+        ))
+    }
+}
+
+impl Record {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+}
+
+fn page_from_response(record: &Record) -> Result<PageData, String> {
+    let url = record
+        .header("warc-target-uri")
+        .map(|uri| uri.trim_matches(['<', '>']).to_string())
+        .ok_or("response record has no WARC-Target-URI")?;
+
+    // The block is the raw HTTP response: status line, headers, blank line, body.
+    let (head, body) = match find(&record.block, b"\r\n\r\n") {
+        Some(end) => (&record.block[..end], &record.block[end + 4..]),
+        None => match find(&record.block, b"\n\n") {
+            Some(end) => (&record.block[..end], &record.block[end + 2..]),
+            None => (&record.block[..], &[][..]),
+        },
+    };
+    let head = String::from_utf8_lossy(head);
+    let mut lines = head.lines();
+
+    let status_code = lines
+        .next()
+        .and_then(|status| status.split_whitespace().nth(1))
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(|| format!("invalid HTTP status line in the response for {url}"))?;
+    let headers = lines
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    Ok(PageData {
+        schema_version: SchemaVersion::CURRENT,
+        url,
+        status_code,
+        headers,
+        body: String::from_utf8_lossy(body).to_string(),
+        ..Default::default()
+    })
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
@@ -0,0 +1,120 @@
+use serde_json::{Map, Value};
+
+pub mod embedding;
+pub mod jsonl;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+#[cfg(feature = "s3")]
+pub mod s3;
+pub mod stdout;
+pub mod warc;
+
+pub use embedding::EmbeddingSink;
+pub use jsonl::JsonlSink;
+#[cfg(feature = "postgres")]
+pub use postgres::PostgresSink;
+#[cfg(feature = "s3")]
+pub use s3::S3Sink;
+pub use stdout::StdoutSink;
+pub use warc::WarcSink;
+
+use crate::config::{SinkConfig, SinkKind};
+
+// Destination of consumed pages. Records are PageData serialized to JSON and already projected by
+// the sink's field filter.
+pub trait StorageSink: Send + Sync {
+    fn write(&self, record: &Map<String, Value>) -> Result<(), String>;
+    // Store the records buffered by the sink, called once the consumer stopped.
+    fn flush(&self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+// Instantiate the sink described by `config`.
+pub fn build(config: &SinkConfig) -> Result<Box<dyn StorageSink>, String> {
+    Ok(match &config.kind {
+        SinkKind::Stdout => Box::new(StdoutSink),
+        SinkKind::Jsonl { path, max_bytes } => Box::new(JsonlSink::open(path, *max_bytes)?),
+        #[cfg(feature = "postgres")]
+        SinkKind::Postgres { url, table } => {
+            // rows are keyed by URL
+            require_fields(config, &["url"])?;
+            Box::new(PostgresSink::connect(url, table)?)
+        }
+        #[cfg(feature = "s3")]
+        SinkKind::S3 {
+            bucket,
+            prefix,
+            endpoint,
+            region,
+            format,
+            batch_size,
+        } => Box::new(S3Sink::open(
+            bucket,
+            prefix,
+            endpoint.as_deref(),
+            region.as_deref(),
+            *format,
+            *batch_size,
+        )?),
+        SinkKind::Embedding {
+            endpoint,
+            model,
+            chunk_words,
+            chunk_overlap,
+            store,
+        } => {
+            // chunks are keyed by URL and computed from the body
+            require_fields(config, &["url", "body"])?;
+            Box::new(EmbeddingSink::open(
+                endpoint,
+                model,
+                *chunk_words,
+                *chunk_overlap,
+                store,
+            )?)
+        }
+        SinkKind::Warc {
+            path,
+            prefix,
+            max_bytes,
+            gzip,
+        } => {
+            require_fields(config, &["url", "status_code", "headers", "body"])?;
+            Box::new(WarcSink::open(path, prefix, *max_bytes, *gzip)?)
+        }
+        #[allow(unreachable_patterns)]
+        kind => {
+            return Err(format!(
+                "Sink '{}' of kind {} requires building with the `{}` feature",
+                config.name,
+                kind.name(),
+                kind.name()
+            ));
+        }
+    })
+}
+
+// Fail unless the field selection of `config` keeps every field of `fields`.
+fn require_fields(config: &SinkConfig, fields: &[&str]) -> Result<(), String> {
+    for field in fields {
+        let kept = config
+            .fields
+            .as_ref()
+            .is_none_or(|f| f.iter().any(|f| f == field))
+            && !config.drop.iter().any(|f| f == field);
+        if !kept {
+            return Err(format!(
+                "Sink '{}' must keep the {} field",
+                config.name, field
+            ));
+        }
+    }
+    Ok(())
+}
+
+// Run a future of an async client from a (synchronous) sink. Sinks are called from the consumer
+// handler, on a worker thread of the multi-threaded runtime.
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::task::block_in_place(|| tokio::runtime::Handle::current().block_on(future))
+}
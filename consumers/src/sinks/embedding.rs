@@ -0,0 +1,124 @@
+use drivers::{VectorPoint, VectorStoreDriver, config::QdrantConfig, qdrant::QdrantDriver};
+use serde_json::{Map, Value};
+use tracing::{debug, info};
+
+use crate::{
+    embedding::{EmbeddingClient, chunk, clean_text},
+    sinks::{StorageSink, block_on},
+};
+
+// Splits the cleaned text of each page into overlapping chunks, embeds them through an
+// OpenAI-compatible endpoint and upserts one point per chunk into a Qdrant collection. The
+// previous chunks of a page are replaced, so re-crawled pages do not keep stale chunks.
+pub struct EmbeddingSink {
+    client: EmbeddingClient,
+    store: Box<dyn VectorStoreDriver>,
+    chunk_words: usize,
+    chunk_overlap: usize,
+}
+
+impl EmbeddingSink {
+    pub fn open(
+        endpoint: &str,
+        model: &str,
+        chunk_words: usize,
+        chunk_overlap: usize,
+        store: &QdrantConfig,
+    ) -> Result<Self, String> {
+        if chunk_words == 0 || chunk_overlap >= chunk_words {
+            return Err(format!(
+                "chunk_overlap ({chunk_overlap}) must be smaller than chunk_words ({chunk_words})"
+            ));
+        }
+        let mut store = store.clone();
+        if store.api_key.is_none() {
+            store.api_key = std::env::var("QDRANT_API_KEY").ok();
+        }
+        info!(
+            "Embedding pages with {} into Qdrant collection {} at {}",
+            model, store.collection, store.url
+        );
+
+        Ok(EmbeddingSink {
+            client: EmbeddingClient::new(endpoint, model),
+            store: Box::new(QdrantDriver::with_config(&store)),
+            chunk_words,
+            chunk_overlap,
+        })
+    }
+
+    async fn embed(&self, record: &Map<String, Value>) -> Result<(), String> {
+        let url = record
+            .get("url")
+            .and_then(Value::as_str)
+            .ok_or("record has no url")?;
+        // pages opting out of AI usage are not embedded
+        let noai = record
+            .get("usage")
+            .and_then(|usage| usage.get("noai"))
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let body = record.get("body").and_then(Value::as_str).unwrap_or("");
+        let chunks = if noai || body.is_empty() {
+            Vec::new()
+        } else {
+            chunk(&clean_text(body), self.chunk_words, self.chunk_overlap)
+        };
+        // embed before removing the previous chunks, so a failing endpoint does not lose them
+        let vectors = self.client.embed(&chunks).await?;
+        self.store
+            .delete_matching("url", url)
+            .await
+            .map_err(|e| e.to_string())?;
+        if chunks.is_empty() {
+            debug!("Nothing to embed for {}", url);
+            return Ok(());
+        }
+
+        let title = record.get("title").cloned().unwrap_or(Value::Null);
+        let job = record
+            .get("provenance")
+            .and_then(|provenance| provenance.get("job"))
+            .cloned()
+            .unwrap_or(Value::Null);
+        let points: Vec<VectorPoint> = chunks
+            .into_iter()
+            .zip(vectors)
+            .enumerate()
+            .map(|(index, (text, vector))| {
+                let mut payload = Map::new();
+                payload.insert("url".to_string(), Value::from(url));
+                payload.insert("title".to_string(), title.clone());
+                payload.insert("job".to_string(), job.clone());
+                payload.insert("chunk".to_string(), Value::from(index));
+                payload.insert("text".to_string(), Value::from(text));
+                VectorPoint {
+                    id: point_id(url, index),
+                    vector,
+                    payload,
+                }
+            })
+            .collect();
+        self.store
+            .upsert(&points)
+            .await
+            .map_err(|e| e.to_string())?;
+        debug!("Embedded {} chunks of {}", points.len(), url);
+        Ok(())
+    }
+}
+
+impl StorageSink for EmbeddingSink {
+    fn write(&self, record: &Map<String, Value>) -> Result<(), String> {
+        block_on(self.embed(record))
+    }
+}
+
+// Stable id of the `index`-th chunk of `url` (64-bit FNV-1a).
+fn point_id(url: &str, index: usize) -> u64 {
+    format!("{url}#{index}")
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+        })
+}
@@ -0,0 +1,90 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    path::Path,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde_json::{Map, Value};
+use tracing::info;
+
+use crate::sinks::StorageSink;
+
+// Appends every record as a JSON line to a local file, rotating it once it reaches `max_bytes`.
+pub struct JsonlSink {
+    path: String,
+    max_bytes: Option<u64>,
+    // Open file and its current size.
+    file: Mutex<(File, u64)>,
+}
+
+impl JsonlSink {
+    pub fn open(path: &str, max_bytes: Option<u64>) -> Result<Self, String> {
+        if let Some(parent) = Path::new(path).parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+        }
+        let file = Self::append(path)?;
+        info!("Writing JSONL records to {}", path);
+
+        Ok(JsonlSink {
+            path: path.to_string(),
+            max_bytes,
+            file: Mutex::new(file),
+        })
+    }
+
+    fn append(path: &str) -> Result<(File, u64), String> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Failed to open {path}: {e}"))?;
+        let size = file
+            .metadata()
+            .map_err(|e| format!("Failed to read {path}: {e}"))?
+            .len();
+        Ok((file, size))
+    }
+
+    // Move the current file to `<path>.<unix timestamp>` (with a counter on collisions) and start
+    // a new one.
+    fn rotate(&self) -> Result<(File, u64), String> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let mut rotated = format!("{}.{timestamp}", self.path);
+        let mut n = 1;
+        while Path::new(&rotated).exists() {
+            rotated = format!("{}.{timestamp}-{n}", self.path);
+            n += 1;
+        }
+        std::fs::rename(&self.path, &rotated)
+            .map_err(|e| format!("Failed to rotate {} to {rotated}: {e}", self.path))?;
+        info!("Rotated {} to {}", self.path, rotated);
+        Self::append(&self.path)
+    }
+}
+
+impl StorageSink for JsonlSink {
+    fn write(&self, record: &Map<String, Value>) -> Result<(), String> {
+        let mut line = serde_json::to_vec(record).map_err(|e| e.to_string())?;
+        line.push(b'\n');
+
+        let mut file = self.file.lock().unwrap();
+        let (_, size) = &*file;
+        let full = self
+            .max_bytes
+            .is_some_and(|max| *size > 0 && size + line.len() as u64 > max);
+        if full {
+            *file = self.rotate()?;
+        }
+        let (file, size) = &mut *file;
+        file.write_all(&line)
+            .map_err(|e| format!("Failed to write to {}: {e}", self.path))?;
+        *size += line.len() as u64;
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde_json::{Map, Value};
+use sqlx::{AssertSqlSafe, PgPool, postgres::PgPoolOptions, types::Json};
+use tracing::info;
+
+use crate::sinks::{StorageSink, block_on};
+
+// Upserts every record as a JSONB row keyed by the page URL, so that recrawled pages replace
+// their previous version. The table is created on the first write if missing:
+//
+//   url TEXT PRIMARY KEY, record JSONB NOT NULL, written_at TIMESTAMPTZ NOT NULL
+pub struct PostgresSink {
+    pool: PgPool,
+    table: String,
+    table_ready: AtomicBool,
+}
+
+impl PostgresSink {
+    // Connections are opened on the first write, so that the consumer starts while the database
+    // is down (the pages are then rejected as `sink_unavailable`).
+    pub fn connect(url: &str, table: &str) -> Result<Self, String> {
+        // the table name is interpolated in the queries
+        if table.is_empty() || !table.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(format!(
+                "Invalid Postgres table name '{table}' (letters, digits and underscores only)"
+            ));
+        }
+        let pool = PgPoolOptions::new()
+            .max_connections(4)
+            .connect_lazy(url)
+            .map_err(|e| format!("Invalid Postgres connection string: {e}"))?;
+        info!("Writing records to Postgres table {}", table);
+
+        Ok(PostgresSink {
+            pool,
+            table: table.to_string(),
+            table_ready: AtomicBool::new(false),
+        })
+    }
+
+    async fn upsert(&self, url: &str, record: &Map<String, Value>) -> Result<(), sqlx::Error> {
+        if !self.table_ready.load(Ordering::Relaxed) {
+            sqlx::query(AssertSqlSafe(format!(
+                "CREATE TABLE IF NOT EXISTS {} (
+                    url TEXT PRIMARY KEY,
+                    record JSONB NOT NULL,
+                    written_at TIMESTAMPTZ NOT NULL DEFAULT now()
+                )",
+                self.table
+            )))
+            .execute(&self.pool)
+            .await?;
+            self.table_ready.store(true, Ordering::Relaxed);
+        }
+
+        // the table name is checked in `connect`
+        sqlx::query(AssertSqlSafe(format!(
+            "INSERT INTO {} (url, record) VALUES ($1, $2)
+             ON CONFLICT (url) DO UPDATE SET record = EXCLUDED.record, written_at = now()",
+            self.table
+        )))
+        .bind(url)
+        .bind(Json(record))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+impl StorageSink for PostgresSink {
+    fn write(&self, record: &Map<String, Value>) -> Result<(), String> {
+        let Some(Value::String(url)) = record.get("url") else {
+            return Err("Record has no url to key the row with".to_string());
+        };
+        block_on(self.upsert(url, record))
+            .map_err(|e| format!("Failed to write to Postgres table {}: {e}", self.table))
+    }
+}
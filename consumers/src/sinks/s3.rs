@@ -0,0 +1,167 @@
+use std::sync::{
+    Mutex,
+    atomic::{AtomicU64, Ordering},
+};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use object_store::{
+    ObjectStoreExt, PutPayload,
+    aws::{AmazonS3, AmazonS3Builder},
+    path::Path,
+};
+use serde_json::{Map, Value};
+use tracing::{error, info};
+
+use crate::{
+    config::ObjectFormat,
+    sinks::{StorageSink, block_on},
+};
+
+// Batches kept while uploads fail, before the oldest records are dropped.
+const MAX_PENDING_BATCHES: usize = 10;
+
+// Uploads records in batches of `batch_size` as JSONL or Parquet objects named
+// `<prefix><unix millis>-<sequence>.<ext>`.
+//
+// NOTE: records are acknowledged once buffered; the partial batch is uploaded by `flush` when the
+// consumer shuts down gracefully, and lost if it is killed. When an upload fails, its records stay
+// buffered and are uploaded with the next batch.
+pub struct S3Sink {
+    store: AmazonS3,
+    bucket: String,
+    prefix: String,
+    format: ObjectFormat,
+    batch_size: usize,
+    pending: Mutex<Vec<Map<String, Value>>>,
+    sequence: AtomicU64,
+}
+
+impl S3Sink {
+    pub fn open(
+        bucket: &str,
+        prefix: &str,
+        endpoint: Option<&str>,
+        region: Option<&str>,
+        format: ObjectFormat,
+        batch_size: usize,
+    ) -> Result<Self, String> {
+        if format == ObjectFormat::Parquet && cfg!(not(feature = "parquet")) {
+            return Err("Parquet objects require building with the `parquet` feature".to_string());
+        }
+        let mut builder = AmazonS3Builder::from_env().with_bucket_name(bucket);
+        if let Some(endpoint) = endpoint {
+            // S3-compatible stores are usually addressed by path and may be served over HTTP
+            builder = builder
+                .with_endpoint(endpoint)
+                .with_virtual_hosted_style_request(false)
+                .with_allow_http(true);
+        }
+        if let Some(region) = region {
+            builder = builder.with_region(region);
+        }
+        let store = builder
+            .build()
+            .map_err(|e| format!("Invalid S3 configuration for bucket {bucket}: {e}"))?;
+        info!("Writing {:?} objects to s3://{}/{}", format, bucket, prefix);
+
+        Ok(S3Sink {
+            store,
+            bucket: bucket.to_string(),
+            prefix: prefix.to_string(),
+            format,
+            batch_size: batch_size.max(1),
+            pending: Mutex::new(Vec::new()),
+            sequence: AtomicU64::new(0),
+        })
+    }
+
+    fn object_path(&self) -> Path {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or_default();
+        let sequence = self.sequence.fetch_add(1, Ordering::Relaxed);
+        let extension = match self.format {
+            ObjectFormat::Jsonl => "jsonl",
+            ObjectFormat::Parquet => "parquet",
+        };
+        Path::from(format!("{}{millis}-{sequence:06}.{extension}", self.prefix))
+    }
+
+    fn encode(&self, records: &[Map<String, Value>]) -> Result<Vec<u8>, String> {
+        match self.format {
+            ObjectFormat::Jsonl => {
+                let mut data = Vec::new();
+                for record in records {
+                    serde_json::to_writer(&mut data, record).map_err(|e| e.to_string())?;
+                    data.push(b'\n');
+                }
+                Ok(data)
+            }
+            #[cfg(feature = "parquet")]
+            ObjectFormat::Parquet => crate::columnar::encode(records),
+            #[cfg(not(feature = "parquet"))]
+            ObjectFormat::Parquet => unreachable!("rejected when the sink is opened"),
+        }
+    }
+
+    async fn upload(&self, records: &[Map<String, Value>]) -> Result<(), String> {
+        let data = self.encode(records)?;
+        let path = self.object_path();
+        self.store
+            .put(&path, PutPayload::from(data))
+            .await
+            .map_err(|e| format!("Failed to upload s3://{}/{}: {e}", self.bucket, path))?;
+        info!(
+            "Uploaded {} records to s3://{}/{}",
+            records.len(),
+            self.bucket,
+            path
+        );
+        Ok(())
+    }
+}
+
+impl StorageSink for S3Sink {
+    fn write(&self, record: &Map<String, Value>) -> Result<(), String> {
+        let batch = {
+            let mut pending = self.pending.lock().unwrap();
+            pending.push(record.clone());
+            if pending.len() < self.batch_size {
+                return Ok(());
+            }
+            std::mem::take(&mut *pending)
+        };
+
+        let result = block_on(self.upload(&batch));
+        if result.is_err() {
+            let mut pending = self.pending.lock().unwrap();
+            let mut kept = batch;
+            kept.append(&mut pending);
+            let excess = kept
+                .len()
+                .saturating_sub(self.batch_size * MAX_PENDING_BATCHES);
+            if excess > 0 {
+                error!(
+                    "S3 upload buffer full, dropping the {} oldest records",
+                    excess
+                );
+                kept.drain(..excess);
+            }
+            *pending = kept;
+        }
+        result
+    }
+
+    fn flush(&self) -> Result<(), String> {
+        let batch = std::mem::take(&mut *self.pending.lock().unwrap());
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let result = block_on(self.upload(&batch));
+        if result.is_err() {
+            self.pending.lock().unwrap().splice(0..0, batch);
+        }
+        result
+    }
+}
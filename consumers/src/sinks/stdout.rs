@@ -0,0 +1,14 @@
+use serde_json::{Map, Value};
+
+use crate::sinks::StorageSink;
+
+// Prints every record as a JSON line.
+pub struct StdoutSink;
+
+impl StorageSink for StdoutSink {
+    fn write(&self, record: &Map<String, Value>) -> Result<(), String> {
+        let line = serde_json::to_string(record).map_err(|e| e.to_string())?;
+        println!("{line}");
+        Ok(())
+    }
+}
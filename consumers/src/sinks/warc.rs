@@ -0,0 +1,20 @@
+use drivers::warc::WarcWriter;
+use serde_json::{Map, Value};
+
+use crate::sinks::StorageSink;
+
+// Writes every page as WARC records (`request`, `response` and `metadata`), see `WarcWriter`.
+// The crawler writes the same files with `sinks = ["warc"]`.
+pub struct WarcSink(WarcWriter);
+
+impl WarcSink {
+    pub fn open(dir: &str, prefix: &str, max_bytes: u64, gzip: bool) -> Result<Self, String> {
+        WarcWriter::open(dir, prefix, max_bytes, gzip).map(WarcSink)
+    }
+}
+
+impl StorageSink for WarcSink {
+    fn write(&self, record: &Map<String, Value>) -> Result<(), String> {
+        self.0.write(record)
+    }
+}
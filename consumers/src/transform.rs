@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+
+use models::PageData;
+use serde_json::{Map, Value};
+
+use crate::config::SinkConfig;
+
+// Field selection and transforms applied to a page before it is handed to a sink.
+#[derive(Debug, Clone)]
+pub struct FieldFilter {
+    fields: Option<Vec<String>>,
+    drop: Vec<String>,
+    truncate: HashMap<String, usize>,
+}
+
+impl FieldFilter {
+    // Build the filter of a sink, rejecting field names that PageData does not have.
+    pub fn from_config(config: &SinkConfig) -> Result<Self, String> {
+        let known = known_fields();
+        let referenced = config
+            .fields
+            .iter()
+            .flatten()
+            .chain(config.drop.iter())
+            .chain(config.truncate.keys());
+        for field in referenced {
+            if !known.contains(field) {
+                return Err(format!(
+                    "Sink '{}' references unknown field '{}' (known fields: {})",
+                    config.name,
+                    field,
+                    known.join(", ")
+                ));
+            }
+        }
+
+        Ok(FieldFilter {
+            fields: config.fields.clone(),
+            drop: config.drop.clone(),
+            truncate: config.truncate.clone(),
+        })
+    }
+
+    // Project a serialized page: keep the selected fields, remove dropped ones, and truncate.
+    pub fn apply(&self, page: &Map<String, Value>) -> Map<String, Value> {
+        let mut record: Map<String, Value> = page
+            .iter()
+            .filter(|(key, _)| self.fields.as_ref().is_none_or(|f| f.contains(key)))
+            .filter(|(key, _)| !self.drop.contains(key))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+
+        for (field, limit) in &self.truncate {
+            match record.get_mut(field) {
+                Some(Value::String(s)) => {
+                    if let Some((index, _)) = s.char_indices().nth(*limit) {
+                        s.truncate(index);
+                    }
+                }
+                Some(Value::Array(items)) => items.truncate(*limit),
+                _ => {}
+            }
+        }
+        record
+    }
+}
+
+// Top-level PageData field names.
+fn known_fields() -> Vec<String> {
+    match serde_json::to_value(PageData::default()) {
+        Ok(Value::Object(map)) => map.keys().cloned().collect(),
+        _ => Vec::new(),
+    }
+}
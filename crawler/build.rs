@@ -0,0 +1,15 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    // Generate the gRPC job service from `proto/crawler.proto`.
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/crawler.proto");
+        let descriptors = protox::compile(["proto/crawler.proto"], ["proto"])
+            .expect("Failed to parse proto/crawler.proto");
+        tonic_prost_build::configure()
+            .build_client(false)
+            .compile_fds(descriptors)
+            .expect("Failed to generate the gRPC service");
+    }
+}
@@ -0,0 +1,92 @@
+pub mod concurrency;
+pub mod deadletters;
+pub mod debug;
+pub mod frontier;
+pub mod jobs;
+pub mod proxies;
+pub mod stream;
+pub mod traps;
+pub mod urlfilter;
+pub mod workers;
+
+use std::{net::SocketAddr, sync::Arc};
+
+use axum::{
+    routing::{get, post},
+    Router,
+};
+use tracing::{error, info, instrument};
+
+use crate::{
+    clients::proxy::ProxyPool,
+    concurrency::FetchLimits,
+    controllers::{DeadLetterController, PageController, WorkerController},
+    events::PublishedPages,
+    frontier::Frontier,
+    jobs::JobManager,
+    traps::TrapDetector,
+    validators::UrlFilter,
+};
+
+// Shared state of the admin API handlers.
+#[derive(Clone)]
+pub struct AdminState {
+    pub page_controller: Arc<PageController>,
+    pub frontier: Frontier,
+    pub published_pages: PublishedPages,
+    pub jobs: JobManager,
+    pub workers: Arc<WorkerController>,
+    // Agents without a heartbeat for this long are listed as dead.
+    pub stale_after_secs: u64,
+    // Proxies the requests are spread over, if any.
+    pub proxies: Option<Arc<ProxyPool>>,
+    // Rules dropping the discovered links, with their counters.
+    pub url_filter: Arc<UrlFilter>,
+    // Spider traps detected so far, if detection is enabled.
+    pub traps: Option<Arc<TrapDetector>>,
+    // URLs fetched at once, adjustable while the crawl runs.
+    pub fetch_limits: FetchLimits,
+    // Requests that permanently failed, listed from `dead_letter_key`.
+    pub dead_letters: Arc<DeadLetterController>,
+    pub dead_letter_key: String,
+}
+
+pub fn router(state: AdminState) -> Router {
+    Router::new()
+        .route(
+            "/concurrency",
+            get(concurrency::limits).put(concurrency::set_parallelism),
+        )
+        .route("/dead-letters", get(deadletters::list))
+        .route("/debug/page", get(debug::page))
+        .route("/frontier", post(frontier::inject))
+        .route("/jobs", post(jobs::submit))
+        .route("/jobs/{job}", get(jobs::status))
+        .route("/jobs/{job}/pause", post(jobs::pause))
+        .route("/jobs/{job}/resume", post(jobs::resume))
+        .route("/jobs/{job}/cancel", post(jobs::cancel))
+        .route("/jobs/{job}/results", get(jobs::results))
+        .route("/proxies", get(proxies::list))
+        .route("/stream/pages", get(stream::pages))
+        .route("/traps", get(traps::list))
+        .route("/url-filter", get(urlfilter::dropped))
+        .route("/workers", get(workers::list))
+        .with_state(state)
+}
+
+// Serve the admin API on `addr` until the process exits.
+#[instrument(name = "Admin API", skip(state))]
+pub async fn serve(addr: SocketAddr, state: AdminState) {
+    let listener = match tokio::net::TcpListener::bind(addr).await {
+        Ok(l) => l,
+        Err(e) => {
+            error!("Failed to bind admin API on {}: {}", addr, e);
+            return;
+        }
+    };
+
+    info!("Admin API listening on http://{}", addr);
+    if let Err(e) = axum::serve(listener, router(state)).await {
+        error!("Admin API stopped: {}", e);
+    }
+}
@@ -0,0 +1,57 @@
+use axum::{extract::State, http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+use tracing::{info, instrument};
+
+use crate::{admin::AdminState, concurrency::FetchLimitsStats};
+
+#[derive(Debug, Deserialize)]
+pub struct ParallelismRequest {
+    parallelism: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum ConcurrencyResponse {
+    Limits(FetchLimitsStats),
+    Error { error: String },
+}
+
+// GET /concurrency
+//
+// URLs fetched at once by each agent, by the process and for each domain, with the fetches in
+// flight and the domains being fetched.
+#[instrument(skip(state))]
+pub async fn limits(State(state): State<AdminState>) -> Json<FetchLimitsStats> {
+    Json(state.fetch_limits.stats())
+}
+
+// PUT /concurrency {"parallelism": 4}
+//
+// Changes the URLs each agent fetches at once, within the process and per-domain limits. Agents
+// over the new value finish their fetches in flight before launching fewer. Answers with the
+// limits, or 400 for a parallelism of 0.
+#[instrument(skip(state))]
+pub async fn set_parallelism(
+    State(state): State<AdminState>,
+    Json(request): Json<ParallelismRequest>,
+) -> (StatusCode, Json<ConcurrencyResponse>) {
+    if request.parallelism == 0 {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ConcurrencyResponse::Error {
+                error: "parallelism must be at least 1".to_string(),
+            }),
+        );
+    }
+    state.fetch_limits.set_parallelism(request.parallelism);
+    info!(
+        event = "parallelism_changed",
+        parallelism = request.parallelism,
+        "Agents now fetch up to {} URLs at once",
+        request.parallelism
+    );
+    (
+        StatusCode::OK,
+        Json(ConcurrencyResponse::Limits(state.fetch_limits.stats())),
+    )
+}
@@ -0,0 +1,51 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{error, instrument};
+
+use crate::{
+    admin::AdminState, controllers::deadlettercontroller::DeadLetterControllerTrait,
+    repositories::DeadLetterEntry,
+};
+
+// Entries returned when no limit is given.
+const DEFAULT_LIMIT: usize = 100;
+
+#[derive(Debug, Deserialize)]
+pub struct DeadLettersQuery {
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum DeadLettersResponse {
+    Entries(Vec<DeadLetterEntry>),
+    Error { error: String },
+}
+
+// GET /dead-letters?limit=...
+//
+// Latest requests that permanently failed (100 unless `limit` is set, 0 for the whole list),
+// oldest first: the URL, the attempts, the last error and the worker that gave up on it.
+#[instrument(skip(state))]
+pub async fn list(
+    State(state): State<AdminState>,
+    Query(query): Query<DeadLettersQuery>,
+) -> (StatusCode, Json<DeadLettersResponse>) {
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    match state.dead_letters.list(&state.dead_letter_key, limit).await {
+        Ok(entries) => (StatusCode::OK, Json(DeadLettersResponse::Entries(entries))),
+        Err(e) => {
+            error!("Failed to list dead letters: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(DeadLettersResponse::Error {
+                    error: e.to_string(),
+                }),
+            )
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Html,
+};
+use serde::Deserialize;
+use tracing::{error, instrument};
+
+use crate::{
+    admin::AdminState,
+    controllers::pagecontroller::PageControllerTrait,
+    parsers::{
+        html::{parse_html, ParsedHtml},
+        license::apply_robots_headers,
+    },
+};
+
+#[derive(Debug, Deserialize)]
+pub struct PageQuery {
+    url: String,
+}
+
+// GET /debug/page?url=...
+//
+// Shows the stored body of a crawled page next to the links and meta tags the extraction pipeline
+// finds in it, to debug why a link was (not) discovered.
+#[instrument(skip(state))]
+pub async fn page(
+    State(state): State<AdminState>,
+    Query(query): Query<PageQuery>,
+) -> (StatusCode, Html<String>) {
+    let page = match state.page_controller.find(&query.url).await {
+        Ok(Some(page)) => page,
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Html(format!(
+                    "No stored page for {} (is DEBUG_STORE_PAGES enabled?)",
+                    escape_html(&query.url)
+                )),
+            )
+        }
+        Err(e) => {
+            error!("Failed to load stored page {}: {}", query.url, e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Html(format!(
+                    "Failed to load stored page: {}",
+                    escape_html(&e.to_string())
+                )),
+            );
+        }
+    };
+
+    // Re-run the extraction pipeline on the stored body.
+    let (mut parsed, parse_error) = match parse_html(&page.url, &page.body, None) {
+        Ok(parsed) => (parsed, None),
+        Err(e) => (ParsedHtml::default(), Some(e.to_string())),
+    };
+    apply_robots_headers(&mut parsed.usage, &page.headers);
+
+    let list = |items: &[String]| -> String {
+        items
+            .iter()
+            .map(|i| format!("<li>{}</li>", escape_html(i)))
+            .collect()
+    };
+    let links: String = parsed
+        .links
+        .iter()
+        .map(|link| {
+            let target: String =
+                url::form_urlencoded::byte_serialize(link.href.as_bytes()).collect();
+            format!(
+                "<li><a href=\"/debug/page?url={}\">{}</a> {}{}</li>",
+                target,
+                escape_html(&link.href),
+                escape_html(&link.anchor_text),
+                if link.is_nofollow { " (nofollow)" } else { "" }
+            )
+        })
+        .collect();
+
+    let html = format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>debug: {url}</title>
+<style>
+body {{ font-family: sans-serif; margin: 1em; }}
+.grid {{ display: grid; grid-template-columns: 1fr 1fr; gap: 1em; }}
+pre {{ white-space: pre-wrap; word-break: break-all; background: #f4f4f4; padding: 1em; max-height: 90vh; overflow: auto; }}
+.error {{ color: #b00; }}
+</style>
+</head>
+<body>
+<h1>{url}</h1>
+<p>Status {status} &middot; fetched at {fetched_at} &middot; {body_len} bytes</p>
+{parse_error}
+<div class="grid">
+<div><h2>Body</h2><pre>{body}</pre></div>
+<div>
+<h2>Links ({n_links})</h2><ul>{links}</ul>
+<h2>Meta ({n_meta})</h2><ul>{meta}</ul>
+<h2>Canonical URL</h2><p>{canonical_url}</p>
+<h2>Fingerprint</h2><p>{fingerprint}</p>
+<h2>Usage</h2><ul>{licenses}<li>Creative Commons: {creative_commons}</li><li>noai: {noai} &middot; noimageai: {noimageai}</li><li>noindex: {noindex} &middot; nofollow: {nofollow}</li></ul>
+<h2>Headers ({n_headers})</h2><ul>{headers}</ul>
+</div>
+</div>
+</body>
+</html>"#,
+        url = escape_html(&page.url),
+        status = page.status_code,
+        fetched_at = page.fetched_at,
+        body_len = page.body.len(),
+        parse_error = parse_error
+            .map(|e| format!(
+                "<p class=\"error\">Extraction failed: {}</p>",
+                escape_html(&e)
+            ))
+            .unwrap_or_default(),
+        body = escape_html(&page.body),
+        n_links = parsed.links.len(),
+        links = links,
+        n_meta = parsed.meta.len(),
+        meta = list(&parsed.meta),
+        canonical_url = escape_html(parsed.canonical_url.as_deref().unwrap_or("none")),
+        fingerprint = parsed
+            .fingerprint
+            .as_ref()
+            .map(|f| format!(
+                "sha256 {} &middot; simhash {:016x}",
+                f.content_hash, f.simhash
+            ))
+            .unwrap_or_else(|| "none".to_string()),
+        licenses = list(&parsed.usage.licenses),
+        creative_commons = escape_html(parsed.usage.creative_commons.as_deref().unwrap_or("none")),
+        noai = parsed.usage.noai,
+        noimageai = parsed.usage.noimageai,
+        noindex = parsed.robots.noindex,
+        nofollow = parsed.robots.nofollow,
+        n_headers = page.headers.len(),
+        headers = list(&page.headers),
+    );
+
+    (StatusCode::OK, Html(html))
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
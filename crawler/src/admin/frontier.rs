@@ -0,0 +1,66 @@
+use axum::{extract::State, http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+use tracing::{error, instrument};
+
+use crate::{admin::AdminState, frontier::InjectError};
+
+#[derive(Debug, Deserialize)]
+pub struct InjectRequest {
+    url: String,
+    #[serde(default)]
+    depth: u32,
+    #[serde(default)]
+    priority: u8,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InjectResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+// POST /frontier {"url": "...", "depth": 0, "priority": 0}
+//
+// Adds a URL to the running crawl through `Frontier::inject`. Answers 202 with the normalized URL,
+// 400 for invalid URLs, 422 for URLs out of scope, 409 for URLs already visited and 503 once the
+// agents stopped.
+#[instrument(skip(state))]
+pub async fn inject(
+    State(state): State<AdminState>,
+    Json(request): Json<InjectRequest>,
+) -> (StatusCode, Json<InjectResponse>) {
+    match state
+        .frontier
+        .inject(&request.url, request.depth, request.priority)
+        .await
+    {
+        Ok(url) => (
+            StatusCode::ACCEPTED,
+            Json(InjectResponse {
+                url: Some(url),
+                error: None,
+            }),
+        ),
+        Err(e) => {
+            let status = match &e {
+                InjectError::InvalidUrl(_) => StatusCode::BAD_REQUEST,
+                InjectError::OutOfScope(_) => StatusCode::UNPROCESSABLE_ENTITY,
+                InjectError::AlreadyVisited(_) => StatusCode::CONFLICT,
+                InjectError::Unavailable(_) => {
+                    error!("Failed to inject {}: {}", request.url, e);
+                    StatusCode::SERVICE_UNAVAILABLE
+                }
+                InjectError::Closed => StatusCode::SERVICE_UNAVAILABLE,
+            };
+            (
+                status,
+                Json(InjectResponse {
+                    url: None,
+                    error: Some(e.to_string()),
+                }),
+            )
+        }
+    }
+}
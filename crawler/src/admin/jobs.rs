@@ -0,0 +1,170 @@
+use std::convert::Infallible;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::sse::{Event, KeepAlive, Sse},
+    Json,
+};
+use futures_lite::{stream, Stream};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::instrument;
+
+use crate::{
+    admin::AdminState,
+    jobs::{JobError, JobOptions, JobStatus, SubmittedJob},
+};
+
+#[derive(Debug, Deserialize)]
+pub struct SubmitRequest {
+    seeds: Vec<String>,
+    #[serde(default)]
+    priority: u8,
+    #[serde(flatten)]
+    options: JobOptions,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum JobResponse {
+    Submitted(SubmittedJob),
+    Status(JobStatus),
+    Error { error: String },
+}
+
+// POST /jobs {"seeds": ["..."], "priority": 0, "max_depth": 2, "include": [], "exclude": []}
+//
+// Submits a job to the running crawl through the `JobManager`. `max_depth`, `include` and
+// `exclude` (regexes) are optional and narrow the crawl settings for the job. Answers 202 with the
+// job id and the accepted and rejected seeds, 400 when no seed was given or a pattern is invalid,
+// or 503 once the agents stopped.
+#[instrument(skip(state))]
+pub async fn submit(
+    State(state): State<AdminState>,
+    Json(request): Json<SubmitRequest>,
+) -> (StatusCode, Json<JobResponse>) {
+    match state
+        .jobs
+        .submit(&request.seeds, request.priority, request.options)
+        .await
+    {
+        Ok(job) => (StatusCode::ACCEPTED, Json(JobResponse::Submitted(job))),
+        Err(error) => (
+            error_code(&error),
+            Json(JobResponse::Error {
+                error: error.to_string(),
+            }),
+        ),
+    }
+}
+
+// GET /jobs/{job}
+//
+// Counters of a submitted job, or 404 for unknown jobs.
+#[instrument(skip(state))]
+pub async fn status(
+    State(state): State<AdminState>,
+    Path(job): Path<String>,
+) -> (StatusCode, Json<JobResponse>) {
+    match state.jobs.status(&job) {
+        Some(status) => (StatusCode::OK, Json(JobResponse::Status(status))),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(JobResponse::Error {
+                error: format!("Unknown job: {job}"),
+            }),
+        ),
+    }
+}
+
+// POST /jobs/{job}/pause
+//
+// Holds the pending requests of the job until it is resumed. Answers with the job status, 404 for
+// unknown jobs or 409 for cancelled jobs.
+#[instrument(skip(state))]
+pub async fn pause(
+    State(state): State<AdminState>,
+    Path(job): Path<String>,
+) -> (StatusCode, Json<JobResponse>) {
+    control(state.jobs.pause(&job))
+}
+
+// POST /jobs/{job}/resume
+#[instrument(skip(state))]
+pub async fn resume(
+    State(state): State<AdminState>,
+    Path(job): Path<String>,
+) -> (StatusCode, Json<JobResponse>) {
+    control(state.jobs.resume(&job))
+}
+
+// POST /jobs/{job}/cancel
+//
+// Drops the pending requests of the job. Pages being fetched when the job is cancelled are still
+// published.
+#[instrument(skip(state))]
+pub async fn cancel(
+    State(state): State<AdminState>,
+    Path(job): Path<String>,
+) -> (StatusCode, Json<JobResponse>) {
+    control(state.jobs.cancel(&job))
+}
+
+fn control(result: Result<JobStatus, JobError>) -> (StatusCode, Json<JobResponse>) {
+    match result {
+        Ok(status) => (StatusCode::OK, Json(JobResponse::Status(status))),
+        Err(error) => (
+            error_code(&error),
+            Json(JobResponse::Error {
+                error: error.to_string(),
+            }),
+        ),
+    }
+}
+
+fn error_code(error: &JobError) -> StatusCode {
+    match error {
+        JobError::Unknown(_) => StatusCode::NOT_FOUND,
+        JobError::InvalidState(_) => StatusCode::CONFLICT,
+        JobError::InvalidJob(_) => StatusCode::BAD_REQUEST,
+        JobError::Stopped => StatusCode::SERVICE_UNAVAILABLE,
+    }
+}
+
+// GET /jobs/{job}/results
+//
+// Server-sent `page` events for the pages of the job published from now on, in the format of
+// `GET /stream/pages`, or 404 for unknown jobs.
+#[instrument(skip(state))]
+pub async fn results(
+    State(state): State<AdminState>,
+    Path(job): Path<String>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, (StatusCode, Json<JobResponse>)> {
+    if !state.jobs.exists(&job) {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(JobResponse::Error {
+                error: format!("Unknown job: {job}"),
+            }),
+        ));
+    }
+
+    let receiver = state.jobs.results();
+    let events = stream::unfold((receiver, job), |(mut receiver, job)| async move {
+        loop {
+            let event = match receiver.recv().await {
+                Ok(summary) if summary.job.as_deref() == Some(job.as_str()) => Event::default()
+                    .event("page")
+                    .data(serde_json::to_string(&summary).unwrap_or_default()),
+                Ok(_) => continue,
+                Err(RecvError::Lagged(missed)) => {
+                    Event::default().event("lagged").data(missed.to_string())
+                }
+                Err(RecvError::Closed) => return None,
+            };
+            return Some((Ok(event), (receiver, job)));
+        }
+    });
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
@@ -0,0 +1,19 @@
+use axum::{extract::State, Json};
+use tracing::instrument;
+
+use crate::{admin::AdminState, clients::proxy::ProxyStats};
+
+// GET /proxies
+//
+// Health of the proxies of the pool: requests and failures so far, and whether each proxy is
+// cooling down after failing too many requests in a row. Empty without proxies.
+#[instrument(skip(state))]
+pub async fn list(State(state): State<AdminState>) -> Json<Vec<ProxyStats>> {
+    Json(
+        state
+            .proxies
+            .as_ref()
+            .map(|pool| pool.stats())
+            .unwrap_or_default(),
+    )
+}
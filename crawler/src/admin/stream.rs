@@ -0,0 +1,37 @@
+use std::convert::Infallible;
+
+use axum::{
+    extract::State,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures_lite::{stream, Stream};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, instrument};
+
+use crate::admin::AdminState;
+
+// GET /stream/pages
+//
+// Server-sent events with a summary of every page published from now on: `page` events carry a
+// JSON `PageSummary` (url, title, status, agent, ...), and `lagged` events report how many
+// summaries a slow client missed.
+#[instrument(skip(state))]
+pub async fn pages(
+    State(state): State<AdminState>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    debug!("New page stream subscriber");
+    let receiver = state.published_pages.subscribe();
+    let events = stream::unfold(receiver, |mut receiver| async move {
+        let event = match receiver.recv().await {
+            Ok(summary) => Event::default()
+                .event("page")
+                .data(serde_json::to_string(&summary).unwrap_or_default()),
+            Err(RecvError::Lagged(missed)) => {
+                Event::default().event("lagged").data(missed.to_string())
+            }
+            Err(RecvError::Closed) => return None,
+        };
+        Some((Ok(event), receiver))
+    });
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
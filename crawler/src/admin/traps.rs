@@ -0,0 +1,19 @@
+use axum::{extract::State, Json};
+use tracing::instrument;
+
+use crate::{admin::AdminState, traps::DetectedTrap};
+
+// GET /traps
+//
+// Spider traps detected so far: the domain, the kind of trap and the pattern of the links no
+// longer followed. Empty when trap detection is disabled.
+#[instrument(skip(state))]
+pub async fn list(State(state): State<AdminState>) -> Json<Vec<DetectedTrap>> {
+    Json(
+        state
+            .traps
+            .as_ref()
+            .map(|traps| traps.detected())
+            .unwrap_or_default(),
+    )
+}
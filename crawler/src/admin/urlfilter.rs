@@ -0,0 +1,14 @@
+use std::collections::HashMap;
+
+use axum::{extract::State, Json};
+use tracing::instrument;
+
+use crate::{admin::AdminState, validators::UrlRule};
+
+// GET /url-filter
+//
+// Number of discovered links dropped by every rule of the URL filter since the process started.
+#[instrument(skip(state))]
+pub async fn dropped(State(state): State<AdminState>) -> Json<HashMap<UrlRule, u64>> {
+    Json(state.url_filter.dropped())
+}
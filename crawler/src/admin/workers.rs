@@ -0,0 +1,67 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{error, instrument};
+
+use crate::{
+    admin::AdminState, controllers::workercontroller::WorkerControllerTrait,
+    crawler::unix_timestamp, repositories::WorkerHeartbeat,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct WorkersQuery {
+    crawler_type: Option<String>,
+    #[serde(default)]
+    all: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WorkerStatus {
+    #[serde(flatten)]
+    heartbeat: WorkerHeartbeat,
+    alive: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum WorkersResponse {
+    Workers(Vec<WorkerStatus>),
+    Error { error: String },
+}
+
+// GET /workers?crawler_type=...&all=true
+//
+// Agents of every process sharing the Redis instance, from their latest heartbeat. Only live
+// agents are listed unless `all` is set, in which case dead ones are flagged with `alive: false`.
+#[instrument(skip(state))]
+pub async fn list(
+    State(state): State<AdminState>,
+    Query(query): Query<WorkersQuery>,
+) -> (StatusCode, Json<WorkersResponse>) {
+    let heartbeats = match state.workers.list(query.crawler_type.as_deref()).await {
+        Ok(heartbeats) => heartbeats,
+        Err(e) => {
+            error!("Failed to list workers: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(WorkersResponse::Error {
+                    error: e.to_string(),
+                }),
+            );
+        }
+    };
+
+    let now = unix_timestamp();
+    let workers = heartbeats
+        .into_iter()
+        .map(|heartbeat| WorkerStatus {
+            alive: heartbeat.is_alive(now, state.stale_after_secs),
+            heartbeat,
+        })
+        .filter(|worker| query.all || worker.alive)
+        .collect();
+    (StatusCode::OK, Json(WorkersResponse::Workers(workers)))
+}
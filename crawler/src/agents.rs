@@ -1,2 +0,0 @@
-// define crawler agent
-pub mod crawler_agent;
@@ -0,0 +1,79 @@
+use std::{sync::Arc, time::Duration};
+
+use tokio::sync::watch;
+use tracing::{debug, error, info, warn};
+
+use crate::sinks::Sink;
+
+// Watches the depth of the queue the pages are published to, so that the crawl waits for the
+// consumers instead of growing the queue without limit. Once more than `high_water` messages are
+// waiting, the agents stop fetching (the request being executed completes); they resume once the
+// depth falls under `low_water`. Each change is logged as a `bus_backpressure` event.
+pub struct BackpressureController {
+    sink: Arc<dyn Sink>,
+    interval: Duration,
+    high_water: u64,
+    low_water: u64,
+    paused: watch::Sender<bool>,
+}
+
+impl BackpressureController {
+    pub fn new(sink: Arc<dyn Sink>, interval: Duration, high_water: u64, low_water: u64) -> Self {
+        BackpressureController {
+            sink,
+            interval,
+            high_water,
+            low_water,
+            paused: watch::Sender::new(false),
+        }
+    }
+
+    // Whether the agents must wait, updated by `run`, to hand over to `Crawler::watch_backlog`.
+    pub fn paused(&self) -> watch::Receiver<bool> {
+        self.paused.subscribe()
+    }
+
+    // Check the queue depth every interval, for as long as the process runs.
+    pub async fn run(self) {
+        let mut interval = tokio::time::interval(self.interval.max(Duration::from_secs(1)));
+        loop {
+            interval.tick().await;
+            match self.sink.backlog().await {
+                Ok(Some(depth)) => self.update(depth),
+                Ok(None) => {
+                    warn!("The message bus does not report its queue depth, stopping backpressure");
+                    self.paused.send_replace(false);
+                    return;
+                }
+                // NOTE: the agents stay paused (or running) until the depth can be read again.
+                Err(e) => error!("Failed to read the queue depth: {}", e),
+            }
+        }
+    }
+
+    fn update(&self, depth: u64) {
+        let paused = *self.paused.borrow();
+        debug!("Message bus queue depth: {}", depth);
+        if !paused && depth > self.high_water {
+            warn!(
+                event = "bus_backpressure",
+                paused = true,
+                depth,
+                high_water = self.high_water,
+                "{} messages waiting for the consumers, pausing the crawl",
+                depth
+            );
+            self.paused.send_replace(true);
+        } else if paused && depth < self.low_water {
+            info!(
+                event = "bus_backpressure",
+                paused = false,
+                depth,
+                low_water = self.low_water,
+                "Queue depth down to {}, resuming the crawl",
+                depth
+            );
+            self.paused.send_replace(false);
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use std::{str::FromStr, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+// What to do with a domain once bot-blocking responses are detected on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockStrategy {
+    // Wait an increasing delay before each request to the domain.
+    SlowDown,
+    // Route requests to the domain through the next fallback proxy.
+    SwitchProxy,
+    // Stop crawling the domain and set its requests aside for later.
+    Park,
+    // Pause the requests to the domain for a while, then resume crawling it.
+    Cooldown,
+}
+
+impl FromStr for BlockStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "slow_down" => Ok(BlockStrategy::SlowDown),
+            "switch_proxy" => Ok(BlockStrategy::SwitchProxy),
+            "park" => Ok(BlockStrategy::Park),
+            "cooldown" => Ok(BlockStrategy::Cooldown),
+            other => Err(format!(
+                "Unknown block strategy '{other}' (expected slow_down, switch_proxy, park or cooldown)"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BlockPolicy {
+    pub strategy: BlockStrategy,
+    // Delay applied the first time a domain is blocked, doubled on every new detection.
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    // Proxies to rotate through with the `SwitchProxy` strategy.
+    pub proxies: Vec<String>,
+    // How long requests to a blocked domain are paused with the `Cooldown` strategy.
+    pub cooldown: Duration,
+    // Bare 403/429 responses within `storm_window` after which a domain counts as blocking.
+    pub storm_threshold: u32,
+    pub storm_window: Duration,
+}
+
+impl BlockPolicy {
+    // Delay to apply after the domain was detected as blocking `count` times.
+    pub fn delay(&self, count: u32) -> Duration {
+        let factor = 2u32.saturating_pow(count.saturating_sub(1));
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+}
+
+// Signatures of common bot-protection pages, matched against the lowercased body.
+const CHALLENGE_SIGNATURES: &[&str] = &[
+    "cf-chl",
+    "challenge-platform",
+    "just a moment...",
+    "attention required! | cloudflare",
+    "cf-browser-verification",
+];
+const BLOCKED_SIGNATURES: &[&str] = &[
+    "captcha",
+    "access denied",
+    "request blocked",
+    "are you a robot",
+    "unusual traffic",
+    "bot detection",
+];
+
+// Responses refusing the crawler without saying why. A single one may be a forbidden page or a
+// short burst of rate limiting; a storm of them means the domain is blocking the crawler.
+pub fn is_refusal(status_code: u16) -> bool {
+    matches!(status_code, 403 | 429)
+}
+
+// Inspect a response and return the reason if it is a challenge or blocking page (bare refusals
+// are counted separately, see `is_refusal`).
+pub fn detect_block(status_code: u16, headers: &[String], body: Option<&str>) -> Option<String> {
+    if !matches!(status_code, 403 | 429 | 503) {
+        return None;
+    }
+
+    let body = body.unwrap_or_default().to_lowercase();
+    let behind_cloudflare = headers.iter().any(|h| {
+        let h = h.to_lowercase();
+        h == "server: cloudflare" || h.starts_with("cf-mitigated:")
+    });
+
+    if let Some(sig) = CHALLENGE_SIGNATURES.iter().find(|sig| body.contains(*sig)) {
+        return Some(format!("{status_code} challenge page ('{sig}')"));
+    }
+    if behind_cloudflare && status_code == 403 {
+        return Some("403 from Cloudflare".to_string());
+    }
+    if let Some(sig) = BLOCKED_SIGNATURES.iter().find(|sig| body.contains(*sig)) {
+        return Some(format!("{status_code} blocking page ('{sig}')"));
+    }
+    None
+}
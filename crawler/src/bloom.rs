@@ -0,0 +1,171 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+// Probabilistic set used to track visited URLs with a fixed memory footprint.
+//
+// NOTE: hashing is implemented here (FNV-1a + splitmix64) rather than with `std::hash` so that
+// every process computes the same bit positions and filters can be merged through Redis.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BloomFilter {
+    num_bits: u64,
+    num_hashes: u32,
+    #[serde(
+        serialize_with = "serialize_bits",
+        deserialize_with = "deserialize_bits"
+    )]
+    bits: Vec<u64>,
+}
+
+impl BloomFilter {
+    // Size the filter for `capacity` items with the given false-positive rate.
+    pub fn new(capacity: usize, false_positive_rate: f64) -> Self {
+        let capacity = capacity.max(1) as f64;
+        let ln2 = std::f64::consts::LN_2;
+        let num_bits = (-(capacity * false_positive_rate.ln()) / (ln2 * ln2)).ceil() as u64;
+        let num_bits = num_bits.max(64);
+        let num_hashes = ((num_bits as f64 / capacity) * ln2).round().max(1.0) as u32;
+
+        BloomFilter {
+            num_bits,
+            num_hashes,
+            bits: vec![0; num_bits.div_ceil(64) as usize],
+        }
+    }
+
+    pub fn insert(&mut self, item: &str) {
+        let positions: Vec<u64> = self.positions(item).collect();
+        for bit in positions {
+            self.bits[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+    }
+
+    pub fn contains(&self, item: &str) -> bool {
+        self.positions(item)
+            .all(|bit| self.bits[(bit / 64) as usize] & (1 << (bit % 64)) != 0)
+    }
+
+    // Union with another filter of the same shape. Returns false (leaving `self` untouched) if the
+    // filters were sized differently.
+    pub fn merge(&mut self, other: &BloomFilter) -> bool {
+        if self.num_bits != other.num_bits || self.num_hashes != other.num_hashes {
+            return false;
+        }
+        for (a, b) in self.bits.iter_mut().zip(other.bits.iter()) {
+            *a |= *b;
+        }
+        true
+    }
+
+    // Size of the bit array in bytes.
+    pub fn size_bytes(&self) -> usize {
+        self.bits.len() * 8
+    }
+
+    // Double hashing: bit_i = h1 + i * h2 (mod m)
+    fn positions(&self, item: &str) -> impl Iterator<Item = u64> + '_ {
+        let h1 = splitmix64(fnv1a(item.as_bytes()));
+        let h2 = splitmix64(h1) | 1;
+        (0..self.num_hashes as u64)
+            .map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % self.num_bits)
+    }
+}
+
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+pub(crate) fn splitmix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e3779b97f4a7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+// The bit array is stored as base64 to keep the JSON payload compact.
+fn serialize_bits<S: Serializer>(bits: &[u64], serializer: S) -> Result<S::Ok, S::Error> {
+    let bytes: Vec<u8> = bits.iter().flat_map(|w| w.to_le_bytes()).collect();
+    serializer.serialize_str(&STANDARD.encode(bytes))
+}
+
+fn deserialize_bits<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u64>, D::Error> {
+    let encoded = String::deserialize(deserializer)?;
+    let bytes = STANDARD.decode(encoded).map_err(serde::de::Error::custom)?;
+    Ok(bytes
+        .chunks(8)
+        .map(|chunk| {
+            let mut word = [0u8; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            u64::from_le_bytes(word)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    // 1 to 199 URL-like items.
+    fn items() -> impl Strategy<Value = Vec<String>> {
+        prop::collection::vec("[a-z0-9/:.?=%-]{0,40}", 1..200)
+    }
+
+    proptest! {
+        // Bloom filters have false positives but never false negatives.
+        #[test]
+        fn inserted_items_are_always_found(items in items()) {
+            let mut filter = BloomFilter::new(100, 0.01);
+            for item in &items {
+                filter.insert(item);
+            }
+            for item in &items {
+                prop_assert!(filter.contains(item));
+            }
+        }
+
+        #[test]
+        fn serde_round_trip_keeps_membership(items in items()) {
+            let mut filter = BloomFilter::new(100, 0.01);
+            for item in &items {
+                filter.insert(item);
+            }
+            let json = serde_json::to_string(&filter).unwrap();
+            let restored: BloomFilter = serde_json::from_str(&json).unwrap();
+            prop_assert_eq!(&restored.bits, &filter.bits);
+            for item in &items {
+                prop_assert!(restored.contains(item));
+            }
+        }
+    }
+
+    #[test]
+    fn merged_filters_contain_the_items_of_both() {
+        let mut a = BloomFilter::new(100, 0.01);
+        let mut b = BloomFilter::new(100, 0.01);
+        a.insert("https://example.com/a");
+        b.insert("https://example.com/b");
+        assert!(a.merge(&b));
+        assert!(a.contains("https://example.com/a"));
+        assert!(a.contains("https://example.com/b"));
+    }
+
+    #[test]
+    fn filters_of_different_sizes_are_not_merged() {
+        let mut small = BloomFilter::new(100, 0.01);
+        let mut large = BloomFilter::new(10_000, 0.01);
+        small.insert("https://example.com/a");
+        large.insert("https://example.com/b");
+        let before = small.bits.clone();
+        assert!(!small.merge(&large));
+        assert_eq!(small.bits, before);
+        // Same number of bits, different number of hash functions.
+        let other = BloomFilter {
+            num_hashes: small.num_hashes + 1,
+            ..small.clone()
+        };
+        assert!(!small.merge(&other));
+    }
+}
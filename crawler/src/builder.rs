@@ -0,0 +1,242 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use drivers::config::{BusConfig, RedisConfig};
+use futures_lite::Stream;
+use models::PageData;
+use tokio::{
+    sync::{mpsc, oneshot, watch},
+    task::JoinHandle,
+};
+use url::Url;
+
+use crate::{
+    config::{CrawlerConfig, OutputConfig, PolitenessConfig},
+    engine::{self, RunOptions},
+    progress::ProgressMode,
+    scope::ScopeMode,
+    sinks::Sink,
+};
+
+// Pages buffered between the agents and the consumer of a `PageStream`.
+const DEFAULT_BUFFER: usize = 256;
+
+// Entry point of the library: configures a crawl and runs it, yielding the crawled pages.
+//
+//     let mut pages = CrawlerBuilder::new()
+//         .seeds(["https://example.com/".parse()?])
+//         .max_depth(2)
+//         .scope(ScopeMode::SeedHost)
+//         .run()
+//         .await?;
+//     while let Some(page) = pages.next().await {
+//         println!("{} {}", page.status_code, page.url);
+//     }
+//
+// Seeds are required. Unset settings keep the defaults of `CrawlerConfig` (environment variables
+// are not read, use `CrawlerConfig::load` and `from_config` for that), and apply to this crawl
+// only. Crawl state lives in the store of `redis`.
+// Pages are also published to the sinks of `output` (the message bus of `bus` by default, unless
+// `publish(false)`) and to the sinks added with `sink`.
+pub struct CrawlerBuilder {
+    config: CrawlerConfig,
+    seeds: Vec<Url>,
+    progress: ProgressMode,
+    buffer: usize,
+    publish: bool,
+    sinks: Vec<Box<dyn Sink>>,
+}
+
+impl Default for CrawlerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CrawlerBuilder {
+    pub fn new() -> Self {
+        Self::from_config(CrawlerConfig::default())
+    }
+
+    // Start from a complete configuration, e.g. one loaded with `CrawlerConfig::load`.
+    pub fn from_config(config: CrawlerConfig) -> Self {
+        CrawlerBuilder {
+            config,
+            seeds: Vec::new(),
+            progress: ProgressMode::Off,
+            buffer: DEFAULT_BUFFER,
+            publish: true,
+            sinks: Vec::new(),
+        }
+    }
+
+    // URLs to start from (the seed files of the configuration are not read).
+    pub fn seeds(mut self, seeds: impl IntoIterator<Item = Url>) -> Self {
+        self.seeds = seeds.into_iter().collect();
+        self
+    }
+
+    // Number of link hops followed from the seeds.
+    pub fn max_depth(mut self, depth: u32) -> Self {
+        self.config.max_depth = depth;
+        self
+    }
+
+    // Which discovered links are followed, relative to the seeds.
+    pub fn scope(mut self, mode: ScopeMode) -> Self {
+        self.config.scope.mode = mode;
+        self
+    }
+
+    // Regexes a link must match (one of them) to be followed.
+    pub fn include(mut self, patterns: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.config.scope.include = patterns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    // Regexes of links never followed.
+    pub fn exclude(mut self, patterns: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.config.scope.exclude = patterns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    // robots.txt, retry and blocking behavior.
+    pub fn politeness(mut self, politeness: PolitenessConfig) -> Self {
+        self.config.politeness = politeness;
+        self
+    }
+
+    // Number of agents crawling concurrently.
+    pub fn agents(mut self, n_agents: usize) -> Self {
+        self.config.n_agents = n_agents;
+        self
+    }
+
+    // Store of the crawl state (Redis, or in memory with `backend = "memory"`).
+    pub fn redis(mut self, redis: RedisConfig) -> Self {
+        self.config.redis = redis;
+        self
+    }
+
+    // Message bus the pages are published to.
+    pub fn bus(mut self, bus: BusConfig) -> Self {
+        self.config.bus = bus;
+        self
+    }
+
+    // Publish the pages to the message bus when it is one of the `output` sinks (the default).
+    // Without it, no message bus is needed.
+    pub fn publish(mut self, publish: bool) -> Self {
+        self.publish = publish;
+        self
+    }
+
+    // Sinks the pages are published to (`bus`, `file`, `warc`) and their settings.
+    pub fn output(mut self, output: OutputConfig) -> Self {
+        self.config.output = output;
+        self
+    }
+
+    // Also send the pages to `sink`, alongside the configured sinks. Its failures do not affect
+    // the other sinks (see `SinkMulticast`).
+    pub fn sink(mut self, sink: impl Sink + 'static) -> Self {
+        self.sinks.push(Box::new(sink));
+        self
+    }
+
+    // Progress output on stderr (off by default).
+    pub fn progress(mut self, mode: ProgressMode) -> Self {
+        self.progress = mode;
+        self
+    }
+
+    // Pages buffered for the stream before the agents wait for it to be read.
+    pub fn buffer(mut self, pages: usize) -> Self {
+        self.buffer = pages.max(1);
+        self
+    }
+
+    // Start the crawl in the background, once it is set up. Fails without seeds, or when the
+    // configuration is invalid or the store or sinks cannot be reached.
+    pub async fn run(self) -> Result<PageStream, String> {
+        if self.seeds.is_empty() {
+            return Err("No seeds to crawl, see `CrawlerBuilder::seeds`".to_string());
+        }
+
+        let (sender, receiver) = mpsc::channel(self.buffer);
+        let (shutdown, shutdown_rx) = watch::channel(false);
+        let (ready, started) = oneshot::channel();
+        let options = RunOptions {
+            progress: self.progress,
+            seeds: Some(self.seeds),
+            pages: Some(sender),
+            publish: self.publish,
+            sinks: self.sinks,
+            shutdown: shutdown_rx,
+            ready: Some(ready),
+        };
+        let task = tokio::task::spawn(engine::run(self.config, options));
+        // The crawl ends without notifying when its setup failed.
+        if started.await.is_err() {
+            return Err(join(task)
+                .await
+                .err()
+                .unwrap_or_else(|| "The crawl stopped before starting its agents".to_string()));
+        }
+        Ok(PageStream {
+            receiver,
+            shutdown,
+            task: Some(task),
+        })
+    }
+}
+
+// Pages of a running crawl, in the order they are published. The stream ends once every agent
+// is done. Dropping it stops the crawl.
+pub struct PageStream {
+    receiver: mpsc::Receiver<PageData>,
+    shutdown: watch::Sender<bool>,
+    task: Option<JoinHandle<Result<(), String>>>,
+}
+
+impl PageStream {
+    // Ask the agents to stop after their current request and checkpoint their queue. Pages
+    // published until then are still yielded.
+    pub fn stop(&self) {
+        let _ = self.shutdown.send(true);
+    }
+
+    // Wait for the crawl to finish (after the stream ended or `stop`), reporting whether it
+    // failed.
+    pub async fn join(mut self) -> Result<(), String> {
+        // the agents may be waiting for the stream to be read
+        self.receiver.close();
+        match self.task.take() {
+            Some(task) => join(task).await,
+            None => Ok(()),
+        }
+    }
+}
+
+// Outcome of the crawl task.
+async fn join(task: JoinHandle<Result<(), String>>) -> Result<(), String> {
+    task.await
+        .map_err(|e| format!("Crawl failed: {e}"))?
+        .map_err(|e| format!("Crawl failed: {e}"))
+}
+
+impl Stream for PageStream {
+    type Item = PageData;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<PageData>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+impl Drop for PageStream {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
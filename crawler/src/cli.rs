@@ -0,0 +1,92 @@
+use clap::{Parser, Subcommand};
+
+use crawler_core::{
+    exporters::sitemap::SitemapFormat, progress::ProgressMode, reextract::ReextractSource,
+};
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Distributed web crawler")]
+pub struct Cli {
+    /// Configuration file (defaults to CRAWLER_CONFIG, then ./crawler.toml if it exists)
+    #[arg(long, global = true)]
+    pub config: Option<String>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Crawl starting from the configured seeds (default)
+    Crawl {
+        /// Progress output on stderr: bars on terminals (auto), bars, json lines, or off
+        #[arg(long, value_enum, default_value_t = ProgressMode::Auto)]
+        progress: ProgressMode,
+    },
+    /// Export a sitemap per crawled domain from the visited URLs stored in Redis
+    Sitemap {
+        /// Only export the sitemap of this host (e.g. example.com)
+        #[arg(long)]
+        domain: Option<String>,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = SitemapFormat::Xml)]
+        format: SitemapFormat,
+        /// Directory where sitemaps are written
+        #[arg(long, default_value = "./data/sitemaps")]
+        output: String,
+        /// URL the sitemaps are served from, referenced by sitemap_index.xml (defaults to the
+        /// root of each host)
+        #[arg(long)]
+        base_url: Option<String>,
+    },
+    /// Run a CSS selector against a single page and print the matches
+    TestSelector {
+        /// URL of the page to test against
+        #[arg(long)]
+        url: String,
+        /// CSS selector, e.g. "div.price"
+        #[arg(long)]
+        selector: String,
+        /// Use the page stored by DEBUG_STORE_PAGES instead of fetching it live
+        #[arg(long)]
+        cached: bool,
+        /// Print the outer HTML of each match instead of its text
+        #[arg(long)]
+        html: bool,
+    },
+    /// Estimate the number of pages and bandwidth of a crawl by sampling the seed pages
+    Estimate {
+        /// Number of seed pages to fetch
+        #[arg(long, default_value_t = 10)]
+        sample: usize,
+        /// Maximum depth to project (defaults to the configured max_depth)
+        #[arg(long)]
+        depth: Option<u32>,
+    },
+    /// Run the current extraction pipeline over stored page bodies and write updated PageData as JSON lines
+    Reextract {
+        /// Where bodies are read from: pages stored with DEBUG_STORE_PAGES, or a JSONL corpus
+        #[arg(long, value_enum, default_value_t = ReextractSource::Redis)]
+        from: ReextractSource,
+        /// JSONL corpus to read (with --from jsonl)
+        #[arg(long)]
+        path: Option<String>,
+        /// Only re-extract pages of this host (with --from redis)
+        #[arg(long)]
+        domain: Option<String>,
+        /// File the updated pages are written to
+        #[arg(long, default_value = "./data/reextracted.jsonl")]
+        output: String,
+    },
+    /// List the crawler agents sending heartbeats to the worker registry
+    Workers {
+        /// Only list the agents of this crawler type
+        #[arg(long)]
+        crawler_type: Option<String>,
+        /// Also list dead agents (no heartbeat for HEARTBEAT_STALE_AFTER_SECS)
+        #[arg(long)]
+        all: bool,
+        /// Print one JSON object per agent instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+}
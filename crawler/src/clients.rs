@@ -1,2 +1,6 @@
+pub mod backend;
+pub mod dns;
 pub mod http;
+pub mod proxy;
+pub mod rendering;
 pub mod robots;
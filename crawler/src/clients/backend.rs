@@ -0,0 +1,70 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use models::SkippedReason;
+use reqwest::header::HeaderMap;
+
+use crate::clients::http::RedirectPolicy;
+
+// Page downloaded by a `FetchBackend`.
+#[derive(Debug, Clone)]
+pub struct FetchedPage {
+    // URL the page was served from, after following redirects.
+    pub final_url: String,
+    // URLs that redirected the request, starting with the requested URL (empty without redirects).
+    pub redirect_chain: Vec<String>,
+    pub status_code: u16,
+    // Response headers as "Key: Value" strings.
+    pub headers: Vec<String>,
+    pub body: FetchedBody,
+}
+
+#[derive(Debug, Clone)]
+pub enum FetchedBody {
+    // Raw body, decoded by the parser from its declared or sniffed charset. Empty for 304 Not
+    // Modified responses.
+    Bytes(Vec<u8>),
+    // Body already decoded, e.g. the DOM serialized by a browser.
+    #[cfg_attr(not(feature = "render"), allow(dead_code))]
+    Text(String),
+    // The body was not downloaded.
+    Skipped(SkippedReason),
+}
+
+// Options of one fetch: those of the request, and of the render rule matching its URL if any (see
+// `RenderRules`).
+#[derive(Debug, Clone, Default)]
+pub struct FetchOptions {
+    // Additional request headers (e.g. the validators of a conditional request).
+    pub headers: HeaderMap,
+    // Media types whose body is downloaded; other responses are skipped. `None` accepts any.
+    pub allowed_content_types: Option<Arc<Vec<String>>>,
+    // Download PDF documents whatever the allowed media types, for their text to be extracted.
+    pub extract_pdf: bool,
+    // Maximum size of the PDF documents downloaded for extraction (`None` = unlimited).
+    pub max_pdf_bytes: Option<u64>,
+    // Redirects followed by the HTTP client (browsers follow them on their own).
+    pub redirects: Arc<RedirectPolicy>,
+    // CSS selector of an element to wait for before capturing the page (rendering only).
+    #[cfg_attr(not(feature = "render"), allow(dead_code))]
+    pub wait_for: Option<String>,
+    // Maximum time to load the page (rendering only).
+    #[cfg_attr(not(feature = "render"), allow(dead_code))]
+    pub timeout: Option<Duration>,
+}
+
+// How pages are downloaded: with the HTTP client (`HttpClient`, the default), a headless browser
+// rendering JavaScript (`RenderingClient`), or any other source of responses such as recorded
+// fixtures. Pages are parsed the same way whatever backend fetched them (see
+// `HttpRequest::execute`).
+#[async_trait]
+pub trait FetchBackend: Send + Sync + std::fmt::Debug {
+    // Name of the backend in logs.
+    fn name(&self) -> &'static str;
+
+    async fn fetch(
+        &self,
+        url: &str,
+        options: &FetchOptions,
+    ) -> Result<FetchedPage, Box<dyn std::error::Error + Send + Sync>>;
+}
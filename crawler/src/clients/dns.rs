@@ -0,0 +1,170 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use hickory_resolver::TokioResolver;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use tracing::{debug, info, warn};
+
+use crate::{
+    config::DnsConfig,
+    validators::{self, AddressPolicy, AddressRejected},
+};
+
+#[derive(Debug)]
+struct HostFailure {
+    // Failures in a row, doubling the backoff every time.
+    count: u32,
+    until: Instant,
+    reason: String,
+}
+
+// Error returned for the hosts backing off after failing to resolve or to accept connections.
+#[derive(Debug)]
+pub struct HostUnreachable(pub String);
+
+impl std::fmt::Display for HostUnreachable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for HostUnreachable {}
+
+// Resolver of the HTTP clients. Answers are cached by hickory for their TTL, and hosts that do not
+// resolve (NXDOMAIN, no address) or refuse connections are failed right away for a backoff that
+// doubles on every new failure, so the queued URLs of dead hosts do not wait for a lookup or a
+// connection timeout on every attempt.
+#[derive(Debug)]
+pub struct DnsCache {
+    resolver: TokioResolver,
+    failures: Mutex<HashMap<String, HostFailure>>,
+    failure_backoff: Duration,
+    max_failure_backoff: Duration,
+}
+
+impl DnsCache {
+    // Resolver with the system configuration (`/etc/resolv.conf`), or None when disabled.
+    pub fn from_config(config: &DnsConfig) -> Result<Option<Self>, String> {
+        if !config.cache {
+            return Ok(None);
+        }
+        let mut builder = TokioResolver::builder_tokio()
+            .map_err(|e| format!("Failed to read the system DNS configuration: {e}"))?;
+        builder.options_mut().cache_size = config.cache_size;
+        let resolver = builder
+            .build()
+            .map_err(|e| format!("Failed to build the DNS resolver: {e}"))?;
+        Ok(Some(DnsCache {
+            resolver,
+            failures: Mutex::new(HashMap::new()),
+            failure_backoff: Duration::from_secs(config.failure_backoff_secs),
+            max_failure_backoff: Duration::from_secs(config.max_failure_backoff_secs),
+        }))
+    }
+
+    // Record that `host` failed to resolve or to accept a connection. Failures while the host is
+    // backing off (including the ones it caused) do not extend the backoff.
+    pub fn report_unreachable(&self, host: &str, reason: String) {
+        let now = Instant::now();
+        let mut failures = self.failures.lock().unwrap();
+        let count = match failures.get(host) {
+            Some(failure) if failure.until > now => return,
+            Some(failure) => failure.count + 1,
+            None => 1,
+        };
+        let factor = 2u32.saturating_pow(count - 1);
+        let backoff = self
+            .failure_backoff
+            .saturating_mul(factor)
+            .min(self.max_failure_backoff);
+        warn!(
+            "Host {} is unreachable ({}), failing its requests for {:?}",
+            host, reason, backoff
+        );
+        failures.insert(
+            host.to_string(),
+            HostFailure {
+                count,
+                until: now + backoff,
+                reason,
+            },
+        );
+    }
+
+    // Record that `host` answered a request, clearing its failures.
+    pub fn report_reachable(&self, host: &str) {
+        if self.failures.lock().unwrap().remove(host).is_some() {
+            info!("Host {} is reachable again", host);
+        }
+    }
+
+    // Addresses of `host`, failing right away while it is backing off.
+    pub async fn lookup(
+        &self,
+        host: &str,
+    ) -> Result<Vec<IpAddr>, Box<dyn std::error::Error + Send + Sync>> {
+        if let Some(err) = self.backing_off(host) {
+            debug!("{}", err);
+            return Err(err.into());
+        }
+        let ips: Vec<_> = match self.resolver.lookup_ip(host).await {
+            Ok(lookup) => lookup.iter().collect(),
+            Err(e) => {
+                self.report_unreachable(host, format!("DNS lookup failed: {e}"));
+                return Err(e.into());
+            }
+        };
+        if ips.is_empty() {
+            self.report_unreachable(host, "no address".to_string());
+            return Err(HostUnreachable(format!("No address for {host}")).into());
+        }
+        Ok(ips)
+    }
+
+    // Error for the requests to `host` while it is backing off.
+    fn backing_off(&self, host: &str) -> Option<HostUnreachable> {
+        let failures = self.failures.lock().unwrap();
+        let failure = failures.get(host)?;
+        let remaining = failure.until.checked_duration_since(Instant::now())?;
+        Some(HostUnreachable(format!(
+            "Host {host} is unreachable ({}), retrying in {}s",
+            failure.reason,
+            remaining.as_secs()
+        )))
+    }
+}
+
+// `reqwest` resolver of the clients, over the DNS cache of the crawl when set, else over the
+// system resolver. Addresses refused by `policy` are dropped, so that the address connected to is
+// the one checked.
+pub struct PolicyResolver {
+    pub cache: Option<Arc<DnsCache>>,
+    pub policy: Arc<AddressPolicy>,
+}
+
+impl Resolve for PolicyResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let cache = self.cache.clone();
+        let policy = Arc::clone(&self.policy);
+        Box::pin(async move {
+            let host = name.as_str();
+            // A host cannot resolve to a public address when validated and to a private one when
+            // fetched (DNS rebinding): the addresses are checked again here.
+            let ips = validators::resolve(host, cache.as_deref()).await?;
+            let allowed: Vec<_> = ips
+                .iter()
+                .copied()
+                .filter(|ip| policy.allows(*ip))
+                .collect();
+            if allowed.is_empty() {
+                return Err(AddressRejected(format!("{host} resolves to {}", ips[0])).into());
+            }
+            let addrs: Addrs = Box::new(allowed.into_iter().map(|ip| SocketAddr::new(ip, 0)));
+            Ok(addrs)
+        })
+    }
+}
@@ -1,21 +1,144 @@
-use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
-use reqwest::{Client, Error, Proxy};
+use async_trait::async_trait;
+use models::SkippedReason;
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE, LOCATION, USER_AGENT};
+use reqwest::{redirect::Policy, Client, Error, Method, Proxy, Url};
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::time;
-use tracing::{debug, error, instrument, warn};
+use tracing::{debug, error, info, instrument, warn};
+
+use crate::{
+    clients::{
+        backend::{FetchBackend, FetchOptions, FetchedBody, FetchedPage},
+        dns::{DnsCache, PolicyResolver},
+    },
+    config::CrawlerConfig,
+    parsers::{charset, pdf},
+    provenance::CRAWLER_VERSION,
+    scope::ScopePolicy,
+    validators::{self, AddressError, AddressPolicy},
+};
+
+// Contact URL advertised in the default User-Agent.
+pub const DEFAULT_CONTACT_URL: &str = "https://github.com/lucadibello/distributed-web-crawler";
+
+// Redirects followed for one request before giving up (as reqwest's default policy).
+pub const DEFAULT_MAX_REDIRECTS: usize = 10;
+
+// How a request follows redirects: up to `max_hops` of them, and only to the targets allowed by
+// `scope` if set, so that a same-domain crawl does not leak to another domain through a 301.
+#[derive(Debug, Clone)]
+pub struct RedirectPolicy {
+    pub max_hops: usize,
+    pub scope: Option<Arc<ScopePolicy>>,
+}
+
+impl Default for RedirectPolicy {
+    fn default() -> Self {
+        RedirectPolicy {
+            max_hops: DEFAULT_MAX_REDIRECTS,
+            scope: None,
+        }
+    }
+}
+
+// URLs that answered a request with a redirect, in order, starting with the requested URL (empty
+// when the response was not redirected). Attached to the extensions of the responses returned by
+// `HttpClient`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RedirectChain(pub Vec<String>);
+
+// Settings shared by the HTTP clients of a crawl: the User-Agent they send, the addresses they may
+// connect to, the DNS cache they resolve hosts with (see `engine::configure`) and the size of the
+// bodies they download.
+#[derive(Debug, Clone)]
+pub struct ClientSettings {
+    pub user_agent: String,
+    pub address_policy: Arc<AddressPolicy>,
+    pub dns: Option<Arc<DnsCache>>,
+    // Responses with a larger body are aborted while streaming.
+    pub max_body_bytes: Option<u64>,
+}
+
+impl Default for ClientSettings {
+    // Default User-Agent, public addresses only, no DNS cache and no body size limit.
+    fn default() -> Self {
+        ClientSettings {
+            user_agent: default_user_agent(DEFAULT_CONTACT_URL),
+            address_policy: Arc::default(),
+            dns: None,
+            max_body_bytes: None,
+        }
+    }
+}
+
+impl ClientSettings {
+    pub fn from_config(config: &CrawlerConfig) -> Result<Self, String> {
+        let dns = match DnsCache::from_config(&config.dns) {
+            Ok(cache) => cache.map(Arc::new),
+            Err(err) => {
+                warn!("DNS cache disabled: {}", err);
+                None
+            }
+        };
+        let user_agent = config.fetch.user_agent();
+        if HeaderValue::from_str(&user_agent).is_err() {
+            return Err(format!("Invalid User-Agent: {user_agent}"));
+        }
+        Ok(ClientSettings {
+            user_agent,
+            address_policy: Arc::new(AddressPolicy::from_config(&config.fetch)?),
+            dns,
+            max_body_bytes: (config.fetch.max_body_bytes > 0)
+                .then_some(config.fetch.max_body_bytes),
+        })
+    }
+}
 
 pub struct HttpClientConfig {
-    pub user_agent: Option<String>,
+    pub settings: ClientSettings,
     pub proxy: Option<String>,
     pub timeout: Option<Duration>,
 }
 
+// Error returned when a response body exceeds the configured maximum size.
+#[derive(Debug)]
+pub struct BodyTooLarge {
+    pub limit: u64,
+}
+
+impl Display for BodyTooLarge {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Response body exceeds {} bytes", self.limit)
+    }
+}
+
+impl std::error::Error for BodyTooLarge {}
+
+// Error returned when a redirect is not followed: too many hops, a loop, or a target outside the
+// crawl scope.
+#[derive(Debug)]
+pub struct RedirectRejected(pub String);
+
+impl Display for RedirectRejected {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for RedirectRejected {}
+
 // A simple HTTP client wrapper that supports useful features for an HTTP crawler.
 #[derive(Debug)]
 pub struct HttpClient {
     client: Client,
     // We store the timeout so we can wrap GET requests explicitly.
     timeout: Option<Duration>,
+    max_body_bytes: Option<u64>,
+    // DNS cache the client resolves hosts with, told which hosts are unreachable.
+    dns: Option<Arc<DnsCache>>,
+    settings: ClientSettings,
 }
 
 impl HttpClient {
@@ -36,15 +159,31 @@ impl HttpClient {
             builder = builder.proxy(Proxy::all(proxy_url)?);
         }
 
-        // Set default headers (e.g., custom user agent) if provided.
-        if let Some(user_agent) = &config.user_agent {
-            debug!("Setting HTTP client user agent to {}", user_agent);
-            let mut headers = HeaderMap::new();
-            headers.insert(
-                USER_AGENT,
-                HeaderValue::from_str(user_agent).expect("Invalid user agent header value"),
-            );
-            builder = builder.default_headers(headers);
+        // Set default headers (e.g., custom user agent).
+        let user_agent = &config.settings.user_agent;
+        debug!("Setting HTTP client user agent to {}", user_agent);
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            USER_AGENT,
+            HeaderValue::from_str(user_agent).expect("Invalid user agent header value"),
+        );
+        builder = builder.default_headers(headers);
+
+        // Redirects are followed by `send`, which records them.
+        builder = builder.redirect(Policy::none());
+
+        // Resolve hosts through the DNS cache of the crawl when enabled, checking the addresses
+        // connected to against the address policy, unless the proxy resolves them.
+        let dns = config
+            .proxy
+            .is_none()
+            .then(|| config.settings.dns.clone())
+            .flatten();
+        if config.proxy.is_none() {
+            builder = builder.dns_resolver(Arc::new(PolicyResolver {
+                cache: dns.clone(),
+                policy: Arc::clone(&config.settings.address_policy),
+            }));
         }
 
         // Build the reqwest client.
@@ -53,9 +192,88 @@ impl HttpClient {
         Ok(HttpClient {
             client,
             timeout: config.timeout,
+            max_body_bytes: config.settings.max_body_bytes,
+            dns,
+            settings: config.settings,
         })
     }
 
+    // Settings the client was built with.
+    pub fn settings(&self) -> &ClientSettings {
+        &self.settings
+    }
+
+    // Check that the host of `url` resolves to addresses the client may connect to (see
+    // `validators::validate_address`).
+    pub async fn validate_address(&self, url: &str) -> Result<(), AddressError> {
+        validators::validate_address(
+            url,
+            &self.settings.address_policy,
+            self.settings.dns.as_deref(),
+        )
+        .await
+    }
+
+    // Reads the body of `response` as text, decoded from its declared or sniffed charset (see
+    // `charset::decode`).
+    pub async fn read_body(
+        &self,
+        response: reqwest::Response,
+    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let body = self.read_bytes(response).await?;
+        Ok(charset::decode(&body, content_type.as_deref()).0)
+    }
+
+    // Reads the raw body of `response`, streaming it chunk by chunk so that bodies larger than the
+    // configured maximum are aborted (with `BodyTooLarge`) instead of being buffered whole.
+    pub async fn read_bytes(
+        &self,
+        response: reqwest::Response,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        self.read_bytes_up_to(response, self.max_body_bytes).await
+    }
+
+    // Reads the raw body of `response` like `read_bytes`, with an explicit maximum (`None` reads
+    // the whole body).
+    pub async fn read_bytes_up_to(
+        &self,
+        mut response: reqwest::Response,
+        limit: Option<u64>,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        let Some(limit) = limit else {
+            return Ok(response.bytes().await?.to_vec());
+        };
+
+        // Reject announced oversized bodies before downloading anything.
+        if response.content_length().is_some_and(|len| len > limit) {
+            warn!(
+                "Response from {} announces more than {} bytes",
+                response.url(),
+                limit
+            );
+            return Err(Box::new(BodyTooLarge { limit }));
+        }
+
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if (body.len() + chunk.len()) as u64 > limit {
+                warn!(
+                    "Response from {} exceeded {} bytes, aborting",
+                    response.url(),
+                    limit
+                );
+                return Err(Box::new(BodyTooLarge { limit }));
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok(body)
+    }
+
     // Sends an asynchronous GET request to the specified URL with an explicit timeout.
     //
     // If a timeout is configured, the request will error if it takes longer than that duration.
@@ -63,20 +281,76 @@ impl HttpClient {
     pub async fn get(
         &self,
         url: &str,
+    ) -> Result<reqwest::Response, Box<dyn std::error::Error + Send + Sync>> {
+        debug!("Sending GET request to {}", url);
+        self.send(
+            Method::GET,
+            url,
+            HeaderMap::new(),
+            &RedirectPolicy::default(),
+        )
+        .await
+    }
+
+    // Sends an asynchronous GET request with additional headers (e.g. the validators of a
+    // conditional request), with the same timeout semantics as `get`.
+    #[instrument(skip(self, headers))]
+    pub async fn get_with_headers(
+        &self,
+        url: &str,
+        headers: HeaderMap,
+    ) -> Result<reqwest::Response, Box<dyn std::error::Error + Send + Sync>> {
+        debug!(
+            "Sending GET request to {} with {} headers",
+            url,
+            headers.len()
+        );
+        self.send(Method::GET, url, headers, &RedirectPolicy::default())
+            .await
+    }
+
+    // Sends an asynchronous HEAD request to the specified URL, with the same timeout semantics as
+    // `get`. Useful to collect response metadata without downloading the body.
+    #[instrument(skip(self))]
+    pub async fn head(
+        &self,
+        url: &str,
+    ) -> Result<reqwest::Response, Box<dyn std::error::Error + Send + Sync>> {
+        self.head_with_redirects(url, &RedirectPolicy::default())
+            .await
+    }
+
+    // Sends a HEAD request like `head`, following redirects according to `redirects`.
+    #[instrument(skip(self, redirects))]
+    pub async fn head_with_redirects(
+        &self,
+        url: &str,
+        redirects: &RedirectPolicy,
+    ) -> Result<reqwest::Response, Box<dyn std::error::Error + Send + Sync>> {
+        debug!("Sending HEAD request to {}", url);
+        self.send(Method::HEAD, url, HeaderMap::new(), redirects)
+            .await
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        url: &str,
+        headers: HeaderMap,
+        redirects: &RedirectPolicy,
     ) -> Result<reqwest::Response, Box<dyn std::error::Error + Send + Sync>> {
         // Use the configured timeout or fall back to 10 seconds if none is provided.
         let timeout_duration = self.timeout.unwrap_or(Duration::from_secs(10));
-        let request_future = self.client.get(url).send();
+        let request_future = self.follow(method, url, headers, redirects);
 
-        // Wrap the GET request in a Tokio timeout.
-        debug!("Sending GET request to {}", url);
+        // Wrap the request (with its redirects) in a Tokio timeout.
         match time::timeout(timeout_duration, request_future).await {
             Ok(result) => {
-                debug!("GET request to {} completed successfully", url);
-                result.map_err(|e| e.into())
+                debug!("Request to {} completed successfully", url);
+                result
             }
             Err(_) => {
-                warn!("GET request to {} timed out", url);
+                warn!("Request to {} timed out", url);
                 Err(Box::new(std::io::Error::new(
                     std::io::ErrorKind::TimedOut,
                     "Request timed out",
@@ -84,13 +358,176 @@ impl HttpClient {
             }
         }
     }
+
+    // Send the request with `headers`, following redirects as allowed by `redirects` and recording
+    // them in the `RedirectChain` extension of the response.
+    async fn follow(
+        &self,
+        method: Method,
+        url: &str,
+        headers: HeaderMap,
+        redirects: &RedirectPolicy,
+    ) -> Result<reqwest::Response, Box<dyn std::error::Error + Send + Sync>> {
+        let mut chain: Vec<String> = Vec::new();
+        let mut current = Url::parse(url)?;
+        loop {
+            let sent = self
+                .client
+                .request(method.clone(), current.clone())
+                .headers(headers.clone())
+                .send()
+                .await;
+            if let (Some(dns), Some(host)) = (&self.dns, current.host_str()) {
+                match &sent {
+                    Ok(_) => dns.report_reachable(host),
+                    Err(e) if e.is_connect() => dns.report_unreachable(host, e.to_string()),
+                    Err(_) => {}
+                }
+            }
+            let mut response = sent?;
+            let next = response
+                .status()
+                .is_redirection()
+                .then(|| response.headers().get(LOCATION))
+                .flatten()
+                .and_then(|location| location.to_str().ok())
+                .and_then(|location| current.join(location).ok());
+            let Some(next) = next else {
+                response.extensions_mut().insert(RedirectChain(chain));
+                return Ok(response);
+            };
+
+            chain.push(current.to_string());
+            if chain.len() > redirects.max_hops {
+                return Err(Box::new(RedirectRejected(format!(
+                    "More than {} redirects from {url}",
+                    redirects.max_hops
+                ))));
+            }
+            if chain.contains(&next.to_string()) {
+                return Err(Box::new(RedirectRejected(format!(
+                    "Redirect loop from {url} at {next}"
+                ))));
+            }
+            if redirects
+                .scope
+                .as_ref()
+                .is_some_and(|scope| !scope.allows(&next))
+            {
+                return Err(Box::new(RedirectRejected(format!(
+                    "Redirect from {current} to {next} leaves the crawl scope"
+                ))));
+            }
+            // Redirects to private addresses are refused like links to them.
+            self.validate_address(next.as_str()).await?;
+            debug!("Redirected from {} to {}", current, next);
+            current = next;
+        }
+    }
+}
+
+// Default backend of the crawl: GET requests, conditional when the options carry validators.
+// Bodies of media types that are not allowed, or larger than the maximum size, are not downloaded.
+#[async_trait]
+impl FetchBackend for HttpClient {
+    fn name(&self) -> &'static str {
+        "HTTP client"
+    }
+
+    async fn fetch(
+        &self,
+        url: &str,
+        options: &FetchOptions,
+    ) -> Result<FetchedPage, Box<dyn std::error::Error + Send + Sync>> {
+        debug!("Sending GET request to {}", url);
+        let response = self
+            .send(
+                Method::GET,
+                url,
+                options.headers.clone(),
+                &options.redirects,
+            )
+            .await?;
+        let status_code = response.status().as_u16();
+        let page = |body| FetchedPage {
+            final_url: response.url().to_string(),
+            redirect_chain: redirect_chain(&response),
+            status_code,
+            headers: header_lines(&response),
+            body,
+        };
+
+        // Unchanged pages come without a body.
+        if status_code == 304 {
+            return Ok(page(FetchedBody::Bytes(Vec::new())));
+        }
+
+        // PDF documents whose text is extracted have their own size limit; other binary downloads
+        // (PDFs, images, archives, ...) are skipped before reading the body.
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok());
+        let limit = if options.extract_pdf
+            && content_type.is_some_and(|c| validators::media_type(c) == pdf::PDF_MEDIA_TYPE)
+        {
+            options.max_pdf_bytes
+        } else {
+            if let Some(allowed) = &options.allowed_content_types {
+                if !validators::is_allowed_content_type(content_type, allowed) {
+                    let content_type = content_type.unwrap_or_default().to_string();
+                    info!("Skipping body with content type {}", content_type);
+                    let reason = SkippedReason::ContentType { content_type };
+                    return Ok(page(FetchedBody::Skipped(reason)));
+                }
+            }
+            self.max_body_bytes
+        };
+
+        // The metadata of the response is kept before its body is consumed.
+        let head = page(FetchedBody::Bytes(Vec::new()));
+        debug!("Reading response body");
+        let body = match self.read_bytes_up_to(response, limit).await {
+            Ok(body) => FetchedBody::Bytes(body),
+            Err(err) => match err.downcast_ref::<BodyTooLarge>() {
+                Some(BodyTooLarge { limit }) => {
+                    FetchedBody::Skipped(SkippedReason::BodyTooLarge { limit: *limit })
+                }
+                None => return Err(err),
+            },
+        };
+        Ok(FetchedPage { body, ..head })
+    }
+}
+
+// Headers of `response` as "Key: Value" strings.
+pub fn header_lines(response: &reqwest::Response) -> Vec<String> {
+    response
+        .headers()
+        .iter()
+        .map(|(name, value)| format!("{}: {}", name, value.to_str().unwrap_or("")))
+        .collect()
+}
+
+// URLs `response` was redirected through (see `RedirectChain`).
+pub fn redirect_chain(response: &reqwest::Response) -> Vec<String> {
+    response
+        .extensions()
+        .get::<RedirectChain>()
+        .map(|chain| chain.0.clone())
+        .unwrap_or_default()
+}
+
+// Descriptive User-Agent identifying the crawler, with a URL site operators can check.
+pub fn default_user_agent(contact_url: &str) -> String {
+    format!("distributed-web-crawler/{CRAWLER_VERSION} (+{contact_url})")
 }
 
 /// Returns a default HTTP client (without a custom timeout).
-pub fn get_default_http_client() -> HttpClient {
-    // Create a default configuration with no custom user agent, proxy, or timeout.
+pub fn get_default_http_client(settings: &ClientSettings) -> HttpClient {
+    // Create a default configuration with the crawl settings and no proxy or timeout.
     let config = HttpClientConfig {
-        user_agent: None,
+        settings: settings.clone(),
         proxy: None,
         timeout: None,
     };
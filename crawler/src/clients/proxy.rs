@@ -0,0 +1,181 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+use url::Url;
+
+use crate::{
+    clients::http::{ClientSettings, HttpClient, HttpClientConfig},
+    config::{ProxyConfig, ProxyRotation},
+};
+
+#[derive(Debug, Default)]
+struct ProxyHealth {
+    requests: u64,
+    failures: u64,
+    consecutive_failures: u32,
+    // Set while the proxy is cooling down after too many consecutive failures.
+    unhealthy_until: Option<Instant>,
+}
+
+#[derive(Debug)]
+struct PooledProxy {
+    // Proxy URL without its credentials, for logs and metrics.
+    name: String,
+    client: Arc<HttpClient>,
+    health: Mutex<ProxyHealth>,
+}
+
+// Health of a proxy of the pool, as reported by `GET /proxies`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProxyStats {
+    pub proxy: String,
+    pub healthy: bool,
+    pub requests: u64,
+    pub failures: u64,
+    pub consecutive_failures: u32,
+    // Seconds left before an unhealthy proxy is used again.
+    pub cooldown_secs: u64,
+}
+
+// Proxies the requests are spread over, shared by every agent: one after the other, or always the
+// same one for a domain (falling back to the next ones while it is unhealthy). A proxy failing
+// `failure_threshold` requests in a row (connection errors, timeouts, block pages) is set aside for
+// `cooldown`.
+#[derive(Debug)]
+pub struct ProxyPool {
+    proxies: Vec<PooledProxy>,
+    rotation: ProxyRotation,
+    next: AtomicUsize,
+    failure_threshold: u32,
+    cooldown: Duration,
+}
+
+impl ProxyPool {
+    // Pool of the configured proxies, each with its own HTTP client, or None without proxies.
+    pub fn from_config(
+        config: &ProxyConfig,
+        settings: &ClientSettings,
+    ) -> Result<Option<Self>, String> {
+        let proxies = config
+            .proxies
+            .iter()
+            .map(|proxy| {
+                let client = HttpClient::new_with_config(HttpClientConfig {
+                    settings: settings.clone(),
+                    proxy: Some(proxy.clone()),
+                    timeout: None,
+                })
+                .map_err(|e| format!("Invalid proxy '{}': {e}", redact(proxy)))?;
+                Ok(PooledProxy {
+                    name: redact(proxy),
+                    client: Arc::new(client),
+                    health: Mutex::new(ProxyHealth::default()),
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok((!proxies.is_empty()).then(|| ProxyPool {
+            proxies,
+            rotation: config.rotation,
+            next: AtomicUsize::new(0),
+            failure_threshold: config.failure_threshold.max(1),
+            cooldown: Duration::from_secs(config.cooldown_secs),
+        }))
+    }
+
+    // Proxy to send a request to `domain` through: its index in the pool (see `report`) and its
+    // client. When every proxy is cooling down, the preferred one is used anyway.
+    pub fn select(&self, domain: &str) -> (usize, Arc<HttpClient>) {
+        let n = self.proxies.len();
+        let start = match self.rotation {
+            ProxyRotation::RoundRobin => self.next.fetch_add(1, Ordering::Relaxed) % n,
+            ProxyRotation::Sticky => (hash(domain) % n as u64) as usize,
+        };
+        let now = Instant::now();
+        let index = (0..n)
+            .map(|offset| (start + offset) % n)
+            .find(|&index| self.proxies[index].is_healthy(now))
+            .unwrap_or(start);
+        (index, Arc::clone(&self.proxies[index].client))
+    }
+
+    // Record the outcome of a request sent through the proxy at `index`.
+    pub fn report(&self, index: usize, success: bool) {
+        let Some(proxy) = self.proxies.get(index) else {
+            return;
+        };
+        let mut health = proxy.health.lock().unwrap();
+        health.requests += 1;
+        if success {
+            health.consecutive_failures = 0;
+            if health.unhealthy_until.take().is_some() {
+                info!("Proxy {} is healthy again", proxy.name);
+            }
+            return;
+        }
+        health.failures += 1;
+        health.consecutive_failures += 1;
+        if health.consecutive_failures >= self.failure_threshold {
+            warn!(
+                "Proxy {} failed {} requests in a row, cooling down for {:?}",
+                proxy.name, health.consecutive_failures, self.cooldown
+            );
+            health.unhealthy_until = Some(Instant::now() + self.cooldown);
+            health.consecutive_failures = 0;
+        }
+    }
+
+    pub fn stats(&self) -> Vec<ProxyStats> {
+        let now = Instant::now();
+        self.proxies
+            .iter()
+            .map(|proxy| {
+                let health = proxy.health.lock().unwrap();
+                let cooldown = health
+                    .unhealthy_until
+                    .map(|until| until.saturating_duration_since(now))
+                    .unwrap_or_default();
+                ProxyStats {
+                    proxy: proxy.name.clone(),
+                    healthy: cooldown.is_zero(),
+                    requests: health.requests,
+                    failures: health.failures,
+                    consecutive_failures: health.consecutive_failures,
+                    cooldown_secs: cooldown.as_secs(),
+                }
+            })
+            .collect()
+    }
+}
+
+impl PooledProxy {
+    fn is_healthy(&self, now: Instant) -> bool {
+        let health = self.health.lock().unwrap();
+        health.unhealthy_until.is_none_or(|until| until <= now)
+    }
+}
+
+// Proxy URL without its credentials.
+pub fn redact(proxy: &str) -> String {
+    match Url::parse(proxy) {
+        Ok(mut url) => {
+            let _ = url.set_username("");
+            let _ = url.set_password(None);
+            url.to_string()
+        }
+        Err(_) => proxy.to_string(),
+    }
+}
+
+// Stable hash of a domain, so that it keeps its proxy across agents and restarts.
+fn hash(domain: &str) -> u64 {
+    let digest = Sha256::digest(domain.as_bytes());
+    u64::from_be_bytes(digest[..8].try_into().expect("8 bytes"))
+}
@@ -0,0 +1,207 @@
+use std::time::Duration;
+
+use url::Url;
+
+use crate::{clients::backend::FetchOptions, config::RenderConfig, rules::RuleTarget};
+
+#[derive(Debug)]
+struct RenderRule {
+    target: RuleTarget,
+    options: FetchOptions,
+}
+
+// Pages rendered in the headless browser instead of being fetched with the HTTP client: those of
+// the first rule matching their URL, by domain or URL pattern, with the options of that rule.
+#[derive(Debug)]
+pub struct RenderRules {
+    rules: Vec<RenderRule>,
+}
+
+impl RenderRules {
+    // Rules of the configuration, or None without rules.
+    pub fn from_config(config: &RenderConfig) -> Result<Option<Self>, String> {
+        let rules = config
+            .rules
+            .iter()
+            .map(|rule| {
+                let target = RuleTarget::from_config(
+                    rule.domain.as_deref(),
+                    rule.pattern.as_deref(),
+                    "render",
+                )?;
+                let max_render_ms = rule.max_render_ms.unwrap_or(config.max_render_ms);
+                Ok(RenderRule {
+                    target,
+                    options: FetchOptions {
+                        wait_for: rule.wait_for.clone(),
+                        timeout: Some(Duration::from_millis(max_render_ms)),
+                        ..FetchOptions::default()
+                    },
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok((!rules.is_empty()).then_some(RenderRules { rules }))
+    }
+
+    // Options to render `url` with, or None when it is fetched with the HTTP client.
+    pub fn options_for(&self, url: &str) -> Option<&FetchOptions> {
+        let url = Url::parse(url).ok()?;
+        self.rules
+            .iter()
+            .find(|rule| rule.target.matches(&url))
+            .map(|rule| &rule.options)
+    }
+}
+
+#[cfg(feature = "render")]
+pub use browser::RenderingClient;
+
+#[cfg(feature = "render")]
+mod browser {
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use chromiumoxide::{Browser, BrowserConfig, Page};
+    use futures_lite::StreamExt;
+    use tokio::{
+        task::JoinHandle,
+        time::{self, Instant},
+    };
+    use tracing::{debug, warn};
+
+    use crate::{
+        clients::backend::{FetchBackend, FetchOptions, FetchedBody, FetchedPage},
+        config::RenderConfig,
+    };
+
+    // Interval between two checks for the element a page waits for.
+    const WAIT_FOR_POLL: Duration = Duration::from_millis(100);
+
+    // Headless Chrome/Chromium rendering JavaScript-heavy pages, one tab per page. The browser is
+    // launched once and shared by every agent.
+    #[derive(Debug)]
+    pub struct RenderingClient {
+        browser: Browser,
+        // Task processing the events of the browser connection.
+        handler: JoinHandle<()>,
+    }
+
+    impl RenderingClient {
+        pub async fn launch(config: &RenderConfig, user_agent: &str) -> Result<Self, String> {
+            let mut builder = BrowserConfig::builder()
+                .arg(format!("--user-agent={user_agent}"))
+                .request_timeout(Duration::from_millis(config.max_render_ms));
+            if let Some(path) = &config.chrome_path {
+                builder = builder.chrome_executable(path);
+            }
+            if config.no_sandbox {
+                builder = builder.no_sandbox();
+            }
+            let (browser, mut events) = Browser::launch(builder.build()?)
+                .await
+                .map_err(|e| format!("Failed to launch the browser: {e}"))?;
+            let handler = tokio::spawn(async move {
+                while let Some(event) = events.next().await {
+                    if let Err(e) = event {
+                        warn!("Browser connection error: {}", e);
+                    }
+                }
+            });
+            Ok(RenderingClient { browser, handler })
+        }
+
+        // Load `url` in `page`, wait for the element of `options` if any, and capture the page.
+        // Loading fails after the render time of `options`; the page is captured as is when the
+        // element did not show up by then.
+        async fn render(
+            page: &Page,
+            url: &str,
+            options: &FetchOptions,
+        ) -> Result<FetchedPage, Box<dyn std::error::Error + Send + Sync>> {
+            let deadline = options.timeout.map(|timeout| Instant::now() + timeout);
+            let navigation = async {
+                page.goto(url).await?;
+                page.wait_for_navigation_response().await
+            };
+            let request = match deadline {
+                Some(deadline) => {
+                    time::timeout_at(deadline, navigation).await.map_err(|_| {
+                        warn!("Rendering {} timed out", url);
+                        std::io::Error::new(std::io::ErrorKind::TimedOut, "Rendering timed out")
+                    })??
+                }
+                None => navigation.await?,
+            }
+            .ok_or_else(|| format!("No navigation response for {url}"))?;
+            let response = request
+                .response
+                .as_ref()
+                .ok_or_else(|| format!("No response for {url}"))?;
+
+            if let Some(selector) = &options.wait_for {
+                // `find_element` fails until the element is in the DOM.
+                while page.find_element(selector.as_str()).await.is_err() {
+                    if deadline.is_some_and(|deadline| Instant::now() + WAIT_FOR_POLL >= deadline) {
+                        debug!(
+                            "{} not found on {}, capturing the page as is",
+                            selector, url
+                        );
+                        break;
+                    }
+                    time::sleep(WAIT_FOR_POLL).await;
+                }
+            }
+
+            let headers = response
+                .headers
+                .inner()
+                .as_object()
+                .map(|headers| {
+                    headers
+                        .iter()
+                        .map(|(name, value)| format!("{}: {}", name, value.as_str().unwrap_or("")))
+                        .collect()
+                })
+                .unwrap_or_default();
+            let redirect_chain = request
+                .redirect_chain
+                .iter()
+                .filter_map(|redirect| redirect.url.clone())
+                .collect();
+            Ok(FetchedPage {
+                final_url: page.url().await?.unwrap_or_else(|| response.url.clone()),
+                redirect_chain,
+                status_code: u16::try_from(response.status).unwrap_or_default(),
+                headers,
+                body: FetchedBody::Text(page.content().await?),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl FetchBackend for RenderingClient {
+        fn name(&self) -> &'static str {
+            "headless browser"
+        }
+
+        async fn fetch(
+            &self,
+            url: &str,
+            options: &FetchOptions,
+        ) -> Result<FetchedPage, Box<dyn std::error::Error + Send + Sync>> {
+            debug!("Rendering {} in the headless browser", url);
+            let page = self.browser.new_page("about:blank").await?;
+            let result = Self::render(&page, url, options).await;
+            if let Err(e) = page.close().await {
+                debug!("Failed to close the tab of {}: {}", url, e);
+            }
+            result
+        }
+    }
+
+    impl Drop for RenderingClient {
+        fn drop(&mut self) {
+            self.handler.abort();
+        }
+    }
+}
@@ -1,14 +1,34 @@
-use std::collections::HashMap;
-use url::Url;
-use tracing::{debug, info, warn};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tracing::{debug, error, info, warn};
+use url::{Position, Url};
 
+use crate::{
+    clients::http::HttpClient,
+    controllers::{robotscontroller::RobotsControllerTrait, RobotsController},
+    crawler::unix_timestamp,
+    repositories::RobotsRecord,
+};
+
+// robots.txt checks of an agent. Files are fetched with the given client (so they get its proxy,
+// timeout and user agent), then cached in Redis for `ttl` along with hosts without one, shared by
+// the agents of every process, and memoized locally for the same time.
 pub struct RobotsTxtClient {
-    cache: HashMap<String, String>,
+    client: Arc<HttpClient>,
+    controller: Arc<RobotsController>,
+    ttl: Duration,
+    // Our user-agent token (see `robots_token`).
+    token: String,
+    cache: HashMap<String, RobotsRecord>,
 }
 
 impl RobotsTxtClient {
-    pub fn new() -> Self {
+    // A `ttl` of zero keeps cached files forever.
+    pub fn new(client: Arc<HttpClient>, controller: Arc<RobotsController>, ttl: Duration) -> Self {
         RobotsTxtClient {
+            token: robots_token(&client.settings().user_agent),
+            client,
+            controller,
+            ttl,
             cache: HashMap::new(),
         }
     }
@@ -24,61 +44,236 @@ impl RobotsTxtClient {
             None => return false,
         };
 
-        if let Some(robots_txt) = self.cache.get(&domain) {
+        let record = match self.cached(&domain).await {
+            Some(record) => record,
+            None => match self.fetch(&url, &domain).await {
+                Some(record) => record,
+                None => return true, // If we can't fetch it, assume we can crawl.
+            },
+        };
+        match &record.body {
+            Some(robots_txt) => parse_robots_txt(robots_txt, &self.token, url_str),
+            None => true, // If robots.txt doesn't exist, assume we can crawl.
+        }
+    }
+
+    // Fresh robots.txt of the domain, from the local memo or the shared cache.
+    async fn cached(&mut self, domain: &str) -> Option<RobotsRecord> {
+        if let Some(record) = self.cache.get(domain).filter(|r| self.is_fresh(r)) {
             debug!("Found robots.txt for {} in cache", domain);
-            return self.parse_robots_txt(robots_txt, url_str);
+            return Some(record.clone());
         }
 
-        let robots_url = match url.join("/robots.txt") {
-            Ok(url) => url,
-            Err(_) => return false,
+        let record = match self.controller.find(domain).await {
+            Ok(record) => record.filter(|r| self.is_fresh(r))?,
+            Err(e) => {
+                error!("Failed to read the cached robots.txt of {}: {}", domain, e);
+                return None;
+            }
         };
+        debug!("Found robots.txt for {} in the shared cache", domain);
+        self.cache.insert(domain.to_string(), record.clone());
+        Some(record)
+    }
+
+    // Fetch the robots.txt of the domain and cache it. Hosts answering 4xx have no robots.txt,
+    // which is cached as well; network and server errors are not, so the file is fetched again.
+    async fn fetch(&mut self, url: &Url, domain: &str) -> Option<RobotsRecord> {
+        let robots_url = url.join("/robots.txt").ok()?;
 
         info!("Fetching robots.txt from {}", robots_url);
-        let response = match reqwest::get(robots_url).await {
+        let response = match self.client.get(robots_url.as_str()).await {
             Ok(res) => res,
             Err(e) => {
                 warn!("Failed to fetch robots.txt for {}: {}", domain, e);
-                return true; // If we can't fetch it, assume we can crawl.
+                return None;
             }
         };
 
-        if response.status().is_success() {
-            let body = match response.text().await {
-                Ok(text) => text,
-                Err(_) => return true, // Assume allowed if we can't read the body
-            };
-            let is_allowed = self.parse_robots_txt(&body, url_str);
-            self.cache.insert(domain, body);
-            is_allowed
+        let status = response.status();
+        let body = if status.is_success() {
+            match self.client.read_body(response).await {
+                Ok(body) => Some(body),
+                Err(e) => {
+                    warn!("Failed to read robots.txt for {}: {}", domain, e);
+                    return None;
+                }
+            }
+        } else if status.is_client_error() {
+            None
         } else {
-            true // If robots.txt doesn't exist, assume we can crawl.
+            warn!(
+                "Failed to fetch robots.txt for {}: status {}",
+                domain, status
+            );
+            return None;
+        };
+
+        let record = RobotsRecord {
+            body,
+            fetched_at: unix_timestamp(),
+        };
+        if let Err(e) = self.controller.save(domain, record.clone(), self.ttl).await {
+            error!("Failed to cache the robots.txt of {}: {}", domain, e);
         }
+        self.cache.insert(domain.to_string(), record.clone());
+        Some(record)
     }
 
-    fn parse_robots_txt(&self, robots_txt: &str, url_str: &str) -> bool {
-        let mut user_agent_found = false;
-        let mut disallow_all = false;
+    fn is_fresh(&self, record: &RobotsRecord) -> bool {
+        self.ttl.is_zero()
+            || unix_timestamp().saturating_sub(record.fetched_at) < self.ttl.as_secs()
+    }
+}
 
-        for line in robots_txt.lines() {
-            if line.starts_with("User-agent: *") {
-                user_agent_found = true;
-            }
+// Whether `url_str` may be crawled according to `robots_txt`. The rules of the groups naming our
+// user-agent `token` apply, else those of the `*` groups; the longest matching `Allow` or
+// `Disallow` path wins, and `Allow` wins ties.
+fn parse_robots_txt(robots_txt: &str, token: &str, url_str: &str) -> bool {
+    let path = match Url::parse(url_str) {
+        Ok(url) => url[Position::BeforePath..].to_string(),
+        Err(_) => return false,
+    };
 
-            if user_agent_found && line.starts_with("Disallow: /") {
-                disallow_all = true;
-            }
+    let mut specific: Vec<(bool, String)> = Vec::new();
+    let mut wildcard: Vec<(bool, String)> = Vec::new();
+    // Whether a group names our token, even without rules.
+    let mut named = false;
+    // User agents of the current group, and whether its rules started.
+    let mut agents: Vec<String> = Vec::new();
+    let mut in_rules = false;
 
-            if user_agent_found && line.starts_with("Disallow:") {
-                let path = line.split(":").collect::<Vec<&str>>()[1].trim();
-                if path == "/" {
-                    disallow_all = true;
-                } else if url_str.contains(path) {
-                    return false;
+    for line in robots_txt.lines() {
+        let line = line.split('#').next().unwrap_or_default();
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim().to_ascii_lowercase().as_str() {
+            "user-agent" => {
+                if in_rules {
+                    agents.clear();
+                    in_rules = false;
                 }
+                let agent = value.to_ascii_lowercase();
+                named |= agent == token;
+                agents.push(agent);
             }
+            directive @ ("allow" | "disallow") => {
+                in_rules = true;
+                // An empty `Disallow` allows everything.
+                if value.is_empty() {
+                    continue;
+                }
+                let rule = (directive == "allow", value.to_string());
+                if agents.iter().any(|agent| agent == token) {
+                    specific.push(rule.clone());
+                }
+                if agents.iter().any(|agent| agent == "*") {
+                    wildcard.push(rule);
+                }
+            }
+            _ => {}
         }
+    }
+
+    let rules = if named { specific } else { wildcard };
+    rules
+        .iter()
+        .filter(|(_, prefix)| path.starts_with(prefix.as_str()))
+        .max_by_key(|(allow, prefix)| (prefix.len(), *allow))
+        .is_none_or(|(allow, _)| *allow)
+}
+
+// Token matched against the `User-agent` lines of robots.txt files: the product name of the
+// User-Agent (e.g. `distributed-web-crawler` for `distributed-web-crawler/1.0 (+...)`).
+pub fn robots_token(user_agent: &str) -> String {
+    user_agent
+        .split(['/', ' '])
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
 
-        !disallow_all
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "distributed-web-crawler";
+
+    fn allowed(robots_txt: &str, path: &str) -> bool {
+        parse_robots_txt(robots_txt, TOKEN, &format!("https://example.com{path}"))
+    }
+
+    #[test]
+    fn a_group_naming_us_wins_over_the_wildcard_group() {
+        let robots_txt = "\
+User-agent: *
+Disallow: /
+
+User-agent: Distributed-Web-Crawler
+Disallow: /private
+";
+        assert!(allowed(robots_txt, "/docs"));
+        assert!(!allowed(robots_txt, "/private/page"));
+        // Other crawlers get the rules of the `*` group.
+        assert!(!parse_robots_txt(
+            robots_txt,
+            "otherbot",
+            "https://example.com/docs"
+        ));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn a_group_naming_us_without_rules_allows_everything() {
+        let robots_txt = "\
+User-agent: *
+Disallow: /
+
+User-agent: distributed-web-crawler
+";
+        assert!(allowed(robots_txt, "/docs"));
+    }
+
+    #[test]
+    fn allow_wins_ties_with_disallow() {
+        let robots_txt = "\
+User-agent: *
+Disallow: /docs
+Allow: /docs
+";
+        assert!(allowed(robots_txt, "/docs/intro"));
+    }
+
+    #[test]
+    fn the_longest_matching_rule_wins() {
+        let robots_txt = "\
+User-agent: *
+Allow: /docs
+Disallow: /docs/private
+";
+        assert!(allowed(robots_txt, "/docs/intro"));
+        assert!(!allowed(robots_txt, "/docs/private/page"));
+    }
+
+    #[test]
+    fn an_empty_disallow_allows_everything() {
+        assert!(allowed("User-agent: *\nDisallow:\n", "/docs"));
+    }
+
+    #[test]
+    fn the_token_is_the_product_name_of_the_user_agent() {
+        assert_eq!(
+            robots_token("distributed-web-crawler/1.2.0 (+https://example.com/bot)"),
+            TOKEN
+        );
+        assert_eq!(robots_token("Distributed-Web-Crawler"), TOKEN);
+        assert_eq!(
+            robots_token(&crate::clients::http::default_user_agent(
+                "https://example.com/bot"
+            )),
+            TOKEN
+        );
+    }
+}
@@ -0,0 +1,208 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{Display, Formatter},
+    sync::Arc,
+    time::Duration,
+};
+
+use drivers::errors::DriverError;
+use tracing::{debug, error, info, instrument};
+use url::Url;
+
+use crate::{
+    blocking::BlockStrategy,
+    controllers::{
+        blockcontroller::BlockControllerTrait, checkpointcontroller::CheckpointControllerTrait,
+        leasecontroller::LeaseControllerTrait, urlcontroller::UrlControllerTrait, BlockController,
+        CheckpointController, LeaseController, UrlController,
+    },
+    crawler::unix_timestamp,
+    repositories::CheckpointEntry,
+};
+
+// Lease taken by the process compacting the frontier.
+const LEASE: &str = "frontier-compaction";
+// Frontier lists persisted in Redis: shutdown checkpoints, external domain overflow and the
+// requests parked for blocked domains.
+const PATTERNS: [&str; 3] = ["checkpoint:*", "overflow:*", "parked:*"];
+
+// Outcome of a compaction pass.
+#[derive(Debug, Default)]
+pub struct CompactionReport {
+    pub lists: usize,
+    pub entries_removed: usize,
+    // Entries moved to the parked list of their (quarantined) domain.
+    pub entries_parked: usize,
+    // Serialized size of the removed entries.
+    pub bytes_reclaimed: usize,
+}
+
+impl Display for CompactionReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} entries removed ({} bytes) and {} parked across {} lists",
+            self.entries_removed, self.bytes_reclaimed, self.entries_parked, self.lists
+        )
+    }
+}
+
+// Garbage collector of the frontier lists persisted in Redis, which otherwise keep growing over
+// long-running crawls. Each pass:
+// - drops URLs that were visited since they were persisted (unless they are refreshes),
+// - drops repeated URLs within a list,
+// - drops entries older than the entry TTL,
+// - moves the entries of quarantined (parked) domains out of the checkpoint and overflow lists
+//   into the parked list of their domain.
+//
+// Every process of the deployment runs the task, but each interval only the process that wins
+// the lease of the interval compacts.
+pub struct FrontierCompactor {
+    checkpoint_controller: Arc<CheckpointController>,
+    url_controller: Arc<UrlController>,
+    block_controller: Arc<BlockController>,
+    lease_controller: LeaseController,
+    interval: Duration,
+    entry_ttl: Option<Duration>,
+}
+
+impl FrontierCompactor {
+    pub fn new(
+        checkpoint_controller: Arc<CheckpointController>,
+        url_controller: Arc<UrlController>,
+        block_controller: Arc<BlockController>,
+        lease_controller: LeaseController,
+        interval: Duration,
+        entry_ttl: Option<Duration>,
+    ) -> Self {
+        FrontierCompactor {
+            checkpoint_controller,
+            url_controller,
+            block_controller,
+            lease_controller,
+            interval,
+            entry_ttl,
+        }
+    }
+
+    // Compact the frontier every interval, for as long as the process runs.
+    pub async fn run(self) {
+        let period = self.interval.as_secs().max(1);
+        let mut interval = tokio::time::interval(Duration::from_secs(period));
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            match self
+                .lease_controller
+                .acquire(LEASE, unix_timestamp() / period)
+                .await
+            {
+                Ok(true) => {}
+                Ok(false) => {
+                    debug!("Frontier compaction is run by another process");
+                    continue;
+                }
+                Err(e) => {
+                    error!("Failed to acquire the frontier compaction lease: {}", e);
+                    continue;
+                }
+            }
+            match self.compact().await {
+                Ok(report) => info!(
+                    event = "frontier_compacted",
+                    lists = report.lists,
+                    entries_removed = report.entries_removed,
+                    entries_parked = report.entries_parked,
+                    bytes_reclaimed = report.bytes_reclaimed,
+                    "Compacted frontier: {}",
+                    report
+                ),
+                Err(e) => error!("Failed to compact the frontier: {}", e),
+            }
+        }
+    }
+
+    // Run a single compaction pass over every frontier list.
+    #[instrument(skip(self))]
+    pub async fn compact(&self) -> Result<CompactionReport, DriverError> {
+        let expires_before = self
+            .entry_ttl
+            .map(|ttl| unix_timestamp().saturating_sub(ttl.as_secs()));
+        let mut report = CompactionReport::default();
+        let mut quarantined: HashMap<String, bool> = HashMap::new();
+
+        for pattern in PATTERNS {
+            for key in self.checkpoint_controller.keys(pattern).await? {
+                report.lists += 1;
+                let is_parked_list = key.starts_with("parked:");
+
+                // NOTE: the list is only read here; the driver lock is not held while the visited
+                // set and the block list are queried.
+                let mut drop = HashSet::new();
+                // Domain of the entries to move to a parked list, by URL.
+                let mut to_park: HashMap<String, String> = HashMap::new();
+                for entry in self.checkpoint_controller.peek(&key).await? {
+                    let Ok(url) = Url::parse(&entry.url) else {
+                        drop.insert(entry.url);
+                        continue;
+                    };
+                    if !entry.refresh && self.url_controller.is_visited(url.clone()).await? {
+                        drop.insert(entry.url);
+                        continue;
+                    }
+                    let domain = url.host_str().unwrap_or_default().to_string();
+                    if !is_parked_list && self.is_quarantined(&domain, &mut quarantined).await? {
+                        drop.insert(entry.url.clone());
+                        to_park.insert(entry.url, domain);
+                    }
+                }
+
+                let removed = self
+                    .checkpoint_controller
+                    .compact(&key, &drop, expires_before)
+                    .await?;
+                let mut parked: HashMap<String, Vec<CheckpointEntry>> = HashMap::new();
+                for entry in removed {
+                    let expired = matches!(
+                        (entry.enqueued_at, expires_before),
+                        (Some(at), Some(before)) if at < before
+                    );
+                    // Only the first copy of a URL is parked, the others are duplicates.
+                    if let Some(domain) = to_park.remove(&entry.url).filter(|_| !expired) {
+                        report.entries_parked += 1;
+                        parked.entry(domain).or_default().push(entry);
+                        continue;
+                    }
+                    report.entries_removed += 1;
+                    report.bytes_reclaimed += serde_json::to_vec(&entry)
+                        .map(|b| b.len())
+                        .unwrap_or_default();
+                }
+                for (domain, entries) in parked {
+                    self.checkpoint_controller
+                        .save(&format!("parked:{domain}"), entries)
+                        .await?;
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    // Whether requests to `domain` are parked, cached for the duration of a pass.
+    async fn is_quarantined(
+        &self,
+        domain: &str,
+        cache: &mut HashMap<String, bool>,
+    ) -> Result<bool, DriverError> {
+        if let Some(quarantined) = cache.get(domain) {
+            return Ok(*quarantined);
+        }
+        let quarantined = self
+            .block_controller
+            .find(domain)
+            .await?
+            .is_some_and(|blocked| blocked.strategy == BlockStrategy::Park);
+        cache.insert(domain.to_string(), quarantined);
+        Ok(quarantined)
+    }
+}
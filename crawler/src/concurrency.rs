@@ -0,0 +1,127 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use serde::Serialize;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+// Bounds on the fetches in flight, shared by every agent of the process. Each agent fetches up to
+// `parallelism` URLs at once (adjustable while the crawl runs, see the admin API), the process at
+// most `max_concurrent` (0 = unlimited), and each domain at most `max_per_domain` (0 = unlimited),
+// so that fetching in parallel does not hammer a single site.
+#[derive(Clone)]
+pub struct FetchLimits {
+    parallelism: Arc<AtomicUsize>,
+    max_concurrent: usize,
+    global: Option<Arc<Semaphore>>,
+    max_per_domain: usize,
+    // Semaphores of the domains being fetched, dropped once nothing holds or waits for them.
+    domains: Arc<Mutex<HashMap<String, Arc<Semaphore>>>>,
+    in_flight: Arc<AtomicUsize>,
+}
+
+// Settings and load of the fetch limits, as reported by the admin API.
+#[derive(Debug, Clone, Serialize)]
+pub struct FetchLimitsStats {
+    pub parallelism: usize,
+    pub max_concurrent_fetches: usize,
+    pub max_fetches_per_domain: usize,
+    pub in_flight: usize,
+    pub domains: usize,
+}
+
+impl FetchLimits {
+    pub fn new(parallelism: usize, max_concurrent: usize, max_per_domain: usize) -> Self {
+        FetchLimits {
+            parallelism: Arc::new(AtomicUsize::new(parallelism.max(1))),
+            max_concurrent,
+            global: (max_concurrent > 0).then(|| Arc::new(Semaphore::new(max_concurrent))),
+            max_per_domain,
+            domains: Arc::default(),
+            in_flight: Arc::default(),
+        }
+    }
+
+    // URLs each agent fetches at once.
+    pub fn parallelism(&self) -> usize {
+        self.parallelism.load(Ordering::Relaxed)
+    }
+
+    // Change the URLs each agent fetches at once (at least 1). Agents fetching more URLs than the
+    // new value finish them, then launch fewer.
+    pub fn set_parallelism(&self, parallelism: usize) {
+        self.parallelism
+            .store(parallelism.max(1), Ordering::Relaxed);
+    }
+
+    // Wait for a slot of the domain, then one of the process. The slots are released when the
+    // permit is dropped.
+    pub async fn acquire(&self, domain: &str) -> FetchPermit {
+        let domain_permit = match self.max_per_domain {
+            0 => None,
+            max => {
+                let semaphore = Arc::clone(
+                    self.domains
+                        .lock()
+                        .unwrap()
+                        .entry(domain.to_string())
+                        .or_insert_with(|| Arc::new(Semaphore::new(max))),
+                );
+                // NOTE: the semaphores are never closed, so acquiring cannot fail.
+                semaphore.acquire_owned().await.ok()
+            }
+        };
+        let global_permit = match &self.global {
+            Some(global) => Arc::clone(global).acquire_owned().await.ok(),
+            None => None,
+        };
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        FetchPermit {
+            limits: self.clone(),
+            domain: domain.to_string(),
+            domain_permit,
+            _global_permit: global_permit,
+        }
+    }
+
+    pub fn stats(&self) -> FetchLimitsStats {
+        FetchLimitsStats {
+            parallelism: self.parallelism(),
+            max_concurrent_fetches: self.max_concurrent,
+            max_fetches_per_domain: self.max_per_domain,
+            in_flight: self.in_flight.load(Ordering::Relaxed),
+            domains: self.domains.lock().unwrap().len(),
+        }
+    }
+}
+
+// Slots of a fetch in flight (see `FetchLimits::acquire`).
+pub struct FetchPermit {
+    limits: FetchLimits,
+    domain: String,
+    domain_permit: Option<OwnedSemaphorePermit>,
+    _global_permit: Option<OwnedSemaphorePermit>,
+}
+
+impl Drop for FetchPermit {
+    fn drop(&mut self) {
+        self.limits.in_flight.fetch_sub(1, Ordering::Relaxed);
+        let Some(permit) = self.domain_permit.take() else {
+            return;
+        };
+        drop(permit);
+        // Permits and waiters hold a clone of the semaphore, taken under the lock: the domain is
+        // idle when the map holds the last one.
+        let mut domains = self.limits.domains.lock().unwrap();
+        if domains
+            .get(&self.domain)
+            .is_some_and(|semaphore| Arc::strong_count(semaphore) == 1)
+        {
+            domains.remove(&self.domain);
+        }
+    }
+}
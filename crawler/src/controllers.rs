@@ -1,3 +1,27 @@
+pub mod blockcontroller;
+pub mod budgetcontroller;
+pub mod checkpointcontroller;
+pub mod contentcontroller;
+pub mod crawlrecordcontroller;
+pub mod deadlettercontroller;
+pub mod externaldomaincontroller;
+pub mod leasecontroller;
+pub mod pagecontroller;
+pub mod robotscontroller;
+pub mod sharedfrontiercontroller;
 pub mod urlcontroller;
+pub mod workercontroller;
 
+pub use blockcontroller::BlockController;
+pub use budgetcontroller::BudgetController;
+pub use checkpointcontroller::CheckpointController;
+pub use contentcontroller::ContentController;
+pub use crawlrecordcontroller::CrawlRecordController;
+pub use deadlettercontroller::DeadLetterController;
+pub use externaldomaincontroller::ExternalDomainController;
+pub use leasecontroller::LeaseController;
+pub use pagecontroller::PageController;
+pub use robotscontroller::RobotsController;
+pub use sharedfrontiercontroller::SharedFrontierController;
 pub use urlcontroller::UrlController;
+pub use workercontroller::WorkerController;
@@ -0,0 +1,61 @@
+use std::sync::Arc;
+
+use drivers::{errors::DriverError, CacheDriver};
+use models::WorkerIdentity;
+use tokio::sync::Mutex;
+
+use crate::{
+    blocking::BlockPolicy,
+    repositories::{BlockedDomain, RefusalWindow},
+    services::blockservice::{BlockService, BlockServiceTrait},
+};
+
+pub trait BlockControllerTrait {
+    async fn find(&self, domain: &str) -> Result<Option<BlockedDomain>, DriverError>;
+    async fn record(
+        &self,
+        domain: &str,
+        reason: String,
+        detected_at: u64,
+        detected_by: &WorkerIdentity,
+    ) -> Result<BlockedDomain, DriverError>;
+    async fn record_refusal(&self, domain: &str, at: u64) -> Result<Option<u32>, DriverError>;
+}
+
+pub struct BlockController {
+    service: BlockService,
+}
+
+impl BlockController {
+    pub fn new(
+        driver: Arc<Mutex<dyn CacheDriver<str, BlockedDomain>>>,
+        refusals: Arc<Mutex<dyn CacheDriver<str, RefusalWindow>>>,
+        policy: BlockPolicy,
+    ) -> Self {
+        BlockController {
+            service: BlockService::new(driver, refusals, policy),
+        }
+    }
+}
+
+impl BlockControllerTrait for BlockController {
+    async fn find(&self, domain: &str) -> Result<Option<BlockedDomain>, DriverError> {
+        self.service.find(domain).await
+    }
+
+    async fn record(
+        &self,
+        domain: &str,
+        reason: String,
+        detected_at: u64,
+        detected_by: &WorkerIdentity,
+    ) -> Result<BlockedDomain, DriverError> {
+        self.service
+            .record(domain, reason, detected_at, detected_by)
+            .await
+    }
+
+    async fn record_refusal(&self, domain: &str, at: u64) -> Result<Option<u32>, DriverError> {
+        self.service.record_refusal(domain, at).await
+    }
+}
@@ -0,0 +1,36 @@
+use std::sync::Arc;
+
+use drivers::{errors::DriverError, CacheDriver};
+use tokio::sync::Mutex;
+
+use crate::services::budgetservice::{BudgetService, BudgetServiceTrait};
+
+pub trait BudgetControllerTrait {
+    async fn is_exhausted(&self, domain: &str) -> Result<bool, DriverError>;
+    async fn consume(&self, domain: &str, bytes: u64) -> Result<u64, DriverError>;
+}
+
+pub struct BudgetController {
+    service: BudgetService,
+}
+
+impl BudgetController {
+    pub fn new(
+        driver: Arc<Mutex<dyn CacheDriver<str, u64>>>,
+        max_bytes_per_domain: Option<u64>,
+    ) -> Self {
+        BudgetController {
+            service: BudgetService::new(driver, max_bytes_per_domain),
+        }
+    }
+}
+
+impl BudgetControllerTrait for BudgetController {
+    async fn is_exhausted(&self, domain: &str) -> Result<bool, DriverError> {
+        self.service.is_exhausted(domain).await
+    }
+
+    async fn consume(&self, domain: &str, bytes: u64) -> Result<u64, DriverError> {
+        self.service.consume(domain, bytes).await
+    }
+}
@@ -0,0 +1,61 @@
+use std::{collections::HashSet, sync::Arc};
+
+use drivers::{errors::DriverError, CacheDriver};
+use tokio::sync::Mutex;
+
+use crate::{
+    repositories::CheckpointEntry,
+    services::checkpointservice::{CheckpointService, CheckpointServiceTrait},
+};
+
+pub trait CheckpointControllerTrait {
+    async fn save(&self, key: &str, entries: Vec<CheckpointEntry>) -> Result<(), DriverError>;
+    async fn restore(&self, key: &str) -> Result<Vec<CheckpointEntry>, DriverError>;
+    async fn keys(&self, pattern: &str) -> Result<Vec<String>, DriverError>;
+    async fn peek(&self, key: &str) -> Result<Vec<CheckpointEntry>, DriverError>;
+    async fn compact(
+        &self,
+        key: &str,
+        drop: &HashSet<String>,
+        expires_before: Option<u64>,
+    ) -> Result<Vec<CheckpointEntry>, DriverError>;
+}
+
+pub struct CheckpointController {
+    service: CheckpointService,
+}
+
+impl CheckpointController {
+    pub fn new(driver: Arc<Mutex<dyn CacheDriver<str, Vec<CheckpointEntry>>>>) -> Self {
+        CheckpointController {
+            service: CheckpointService::new(driver),
+        }
+    }
+}
+
+impl CheckpointControllerTrait for CheckpointController {
+    async fn save(&self, key: &str, entries: Vec<CheckpointEntry>) -> Result<(), DriverError> {
+        self.service.save(key, entries).await
+    }
+
+    async fn restore(&self, key: &str) -> Result<Vec<CheckpointEntry>, DriverError> {
+        self.service.restore(key).await
+    }
+
+    async fn keys(&self, pattern: &str) -> Result<Vec<String>, DriverError> {
+        self.service.keys(pattern).await
+    }
+
+    async fn peek(&self, key: &str) -> Result<Vec<CheckpointEntry>, DriverError> {
+        self.service.peek(key).await
+    }
+
+    async fn compact(
+        &self,
+        key: &str,
+        drop: &HashSet<String>,
+        expires_before: Option<u64>,
+    ) -> Result<Vec<CheckpointEntry>, DriverError> {
+        self.service.compact(key, drop, expires_before).await
+    }
+}
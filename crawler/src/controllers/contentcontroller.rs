@@ -0,0 +1,44 @@
+use std::sync::Arc;
+
+use drivers::{errors::DriverError, CacheDriver};
+use models::ContentFingerprint;
+use tokio::sync::Mutex;
+
+use crate::{
+    repositories::ContentRecord,
+    services::contentservice::{ContentService, ContentServiceTrait},
+};
+
+pub trait ContentControllerTrait {
+    async fn claim(
+        &self,
+        url: &str,
+        fingerprint: &ContentFingerprint,
+    ) -> Result<Option<String>, DriverError>;
+}
+
+// Content fingerprints seen during the crawl, to detect pages served at several URLs.
+pub struct ContentController {
+    service: ContentService,
+}
+
+impl ContentController {
+    pub fn new(
+        driver: Arc<Mutex<dyn CacheDriver<str, Vec<ContentRecord>>>>,
+        max_distance: u32,
+    ) -> Self {
+        ContentController {
+            service: ContentService::new(driver, max_distance),
+        }
+    }
+}
+
+impl ContentControllerTrait for ContentController {
+    async fn claim(
+        &self,
+        url: &str,
+        fingerprint: &ContentFingerprint,
+    ) -> Result<Option<String>, DriverError> {
+        self.service.claim(url, fingerprint).await
+    }
+}
@@ -0,0 +1,36 @@
+use std::sync::Arc;
+
+use drivers::{errors::DriverError, CacheDriver};
+use tokio::sync::Mutex;
+
+use crate::{
+    repositories::CrawlRecord,
+    services::crawlrecordservice::{CrawlRecordService, CrawlRecordServiceTrait},
+};
+
+pub trait CrawlRecordControllerTrait {
+    async fn find(&self, url: &str) -> Result<Option<CrawlRecord>, DriverError>;
+    async fn record(&self, record: CrawlRecord) -> Result<Option<CrawlRecord>, DriverError>;
+}
+
+pub struct CrawlRecordController {
+    service: CrawlRecordService,
+}
+
+impl CrawlRecordController {
+    pub fn new(driver: Arc<Mutex<dyn CacheDriver<str, CrawlRecord>>>) -> Self {
+        CrawlRecordController {
+            service: CrawlRecordService::new(driver),
+        }
+    }
+}
+
+impl CrawlRecordControllerTrait for CrawlRecordController {
+    async fn find(&self, url: &str) -> Result<Option<CrawlRecord>, DriverError> {
+        self.service.find(url).await
+    }
+
+    async fn record(&self, record: CrawlRecord) -> Result<Option<CrawlRecord>, DriverError> {
+        self.service.record(record).await
+    }
+}
@@ -0,0 +1,40 @@
+use std::sync::Arc;
+
+use drivers::{errors::DriverError, CacheDriver};
+use tokio::sync::Mutex;
+
+use crate::{
+    repositories::DeadLetterEntry,
+    services::deadletterservice::{DeadLetterService, DeadLetterServiceTrait},
+};
+
+pub trait DeadLetterControllerTrait {
+    async fn record(&self, key: &str, entry: DeadLetterEntry) -> Result<(), DriverError>;
+    // Latest `limit` entries of the list (every entry when `limit` is 0), oldest first.
+    async fn list(&self, key: &str, limit: usize) -> Result<Vec<DeadLetterEntry>, DriverError>;
+}
+
+pub struct DeadLetterController {
+    service: DeadLetterService,
+}
+
+impl DeadLetterController {
+    pub fn new(
+        driver: Arc<Mutex<dyn CacheDriver<str, DeadLetterEntry>>>,
+        max_entries: usize,
+    ) -> Self {
+        DeadLetterController {
+            service: DeadLetterService::new(driver, max_entries),
+        }
+    }
+}
+
+impl DeadLetterControllerTrait for DeadLetterController {
+    async fn record(&self, key: &str, entry: DeadLetterEntry) -> Result<(), DriverError> {
+        self.service.record(key, entry).await
+    }
+
+    async fn list(&self, key: &str, limit: usize) -> Result<Vec<DeadLetterEntry>, DriverError> {
+        self.service.list(key, limit).await
+    }
+}
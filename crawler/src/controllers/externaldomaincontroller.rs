@@ -0,0 +1,32 @@
+use std::sync::Arc;
+
+use drivers::{errors::DriverError, CacheDriver};
+use tokio::sync::Mutex;
+
+use crate::services::externaldomainservice::{ExternalDomainService, ExternalDomainServiceTrait};
+
+pub trait ExternalDomainControllerTrait {
+    async fn admit(&self, domain: &str) -> Result<bool, DriverError>;
+}
+
+pub struct ExternalDomainController {
+    service: ExternalDomainService,
+}
+
+impl ExternalDomainController {
+    pub fn new(
+        driver: Arc<Mutex<dyn CacheDriver<str, u64>>>,
+        job: String,
+        max_domains_per_job: Option<u64>,
+    ) -> Self {
+        ExternalDomainController {
+            service: ExternalDomainService::new(driver, job, max_domains_per_job),
+        }
+    }
+}
+
+impl ExternalDomainControllerTrait for ExternalDomainController {
+    async fn admit(&self, domain: &str) -> Result<bool, DriverError> {
+        self.service.admit(domain).await
+    }
+}
@@ -0,0 +1,29 @@
+use std::sync::Arc;
+
+use drivers::{errors::DriverError, CacheDriver};
+use tokio::sync::Mutex;
+
+use crate::services::leaseservice::{LeaseService, LeaseServiceTrait};
+
+pub trait LeaseControllerTrait {
+    async fn acquire(&self, name: &str, epoch: u64) -> Result<bool, DriverError>;
+}
+
+// Leader election between the processes of a deployment, one epoch at a time.
+pub struct LeaseController {
+    service: LeaseService,
+}
+
+impl LeaseController {
+    pub fn new(driver: Arc<Mutex<dyn CacheDriver<str, u64>>>) -> Self {
+        LeaseController {
+            service: LeaseService::new(driver),
+        }
+    }
+}
+
+impl LeaseControllerTrait for LeaseController {
+    async fn acquire(&self, name: &str, epoch: u64) -> Result<bool, DriverError> {
+        self.service.acquire(name, epoch).await
+    }
+}
@@ -0,0 +1,41 @@
+use std::sync::Arc;
+
+use drivers::{errors::DriverError, CacheDriver};
+use tokio::sync::Mutex;
+
+use crate::{
+    repositories::StoredPage,
+    services::pageservice::{PageService, PageServiceTrait},
+};
+
+pub trait PageControllerTrait {
+    async fn store(&self, page: StoredPage) -> Result<(), DriverError>;
+    async fn find(&self, url: &str) -> Result<Option<StoredPage>, DriverError>;
+    async fn stored_urls(&self, host: Option<&str>) -> Result<Vec<String>, DriverError>;
+}
+
+pub struct PageController {
+    service: PageService,
+}
+
+impl PageController {
+    pub fn new(driver: Arc<Mutex<dyn CacheDriver<str, StoredPage>>>) -> Self {
+        PageController {
+            service: PageService::new(driver),
+        }
+    }
+}
+
+impl PageControllerTrait for PageController {
+    async fn store(&self, page: StoredPage) -> Result<(), DriverError> {
+        self.service.store(page).await
+    }
+
+    async fn find(&self, url: &str) -> Result<Option<StoredPage>, DriverError> {
+        self.service.find(url).await
+    }
+
+    async fn stored_urls(&self, host: Option<&str>) -> Result<Vec<String>, DriverError> {
+        self.service.stored_urls(host).await
+    }
+}
@@ -0,0 +1,47 @@
+use std::{sync::Arc, time::Duration};
+
+use drivers::{errors::DriverError, CacheDriver};
+use tokio::sync::Mutex;
+
+use crate::{
+    repositories::RobotsRecord,
+    services::robotsservice::{RobotsService, RobotsServiceTrait},
+};
+
+pub trait RobotsControllerTrait {
+    async fn find(&self, host: &str) -> Result<Option<RobotsRecord>, DriverError>;
+    async fn save(
+        &self,
+        host: &str,
+        record: RobotsRecord,
+        ttl: Duration,
+    ) -> Result<(), DriverError>;
+}
+
+// robots.txt cache shared by the agents of every process.
+pub struct RobotsController {
+    service: RobotsService,
+}
+
+impl RobotsController {
+    pub fn new(driver: Arc<Mutex<dyn CacheDriver<str, RobotsRecord>>>) -> Self {
+        RobotsController {
+            service: RobotsService::new(driver),
+        }
+    }
+}
+
+impl RobotsControllerTrait for RobotsController {
+    async fn find(&self, host: &str) -> Result<Option<RobotsRecord>, DriverError> {
+        self.service.find(host).await
+    }
+
+    async fn save(
+        &self,
+        host: &str,
+        record: RobotsRecord,
+        ttl: Duration,
+    ) -> Result<(), DriverError> {
+        self.service.save(host, record, ttl).await
+    }
+}
@@ -0,0 +1,66 @@
+use std::{sync::Arc, time::Duration};
+
+use drivers::{errors::DriverError, CacheDriver};
+use tokio::sync::Mutex;
+
+use crate::{
+    repositories::{FrontierEntry, FrontierLease},
+    services::sharedfrontierservice::{SharedFrontierService, SharedFrontierServiceTrait},
+};
+
+pub trait SharedFrontierControllerTrait {
+    async fn push(&self, entries: Vec<FrontierEntry>) -> Result<(), DriverError>;
+    async fn claim(
+        &self,
+        worker: &str,
+        max: usize,
+    ) -> Result<Vec<(u64, FrontierEntry)>, DriverError>;
+    async fn ack(&self, claim: u64) -> Result<(), DriverError>;
+    async fn pending(&self) -> Result<u64, DriverError>;
+    async fn reap(&self) -> Result<usize, DriverError>;
+}
+
+// Frontier shared by the crawler processes of a deployment, with leased claims.
+pub struct SharedFrontierController {
+    service: SharedFrontierService,
+}
+
+impl SharedFrontierController {
+    pub fn new(
+        driver: Arc<Mutex<dyn CacheDriver<str, FrontierEntry>>>,
+        lease_driver: Arc<Mutex<dyn CacheDriver<str, FrontierLease>>>,
+        counter_driver: Arc<Mutex<dyn CacheDriver<str, i64>>>,
+        key: String,
+        lease: Duration,
+    ) -> Self {
+        SharedFrontierController {
+            service: SharedFrontierService::new(driver, lease_driver, counter_driver, key, lease),
+        }
+    }
+}
+
+impl SharedFrontierControllerTrait for SharedFrontierController {
+    async fn push(&self, entries: Vec<FrontierEntry>) -> Result<(), DriverError> {
+        self.service.push(entries).await
+    }
+
+    async fn claim(
+        &self,
+        worker: &str,
+        max: usize,
+    ) -> Result<Vec<(u64, FrontierEntry)>, DriverError> {
+        self.service.claim(worker, max).await
+    }
+
+    async fn ack(&self, claim: u64) -> Result<(), DriverError> {
+        self.service.ack(claim).await
+    }
+
+    async fn pending(&self) -> Result<u64, DriverError> {
+        self.service.pending().await
+    }
+
+    async fn reap(&self) -> Result<usize, DriverError> {
+        self.service.reap().await
+    }
+}
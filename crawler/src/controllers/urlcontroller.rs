@@ -3,11 +3,20 @@ use std::sync::Arc;
 use drivers::{errors::DriverError, CacheDriver};
 use tokio::sync::Mutex;
 
-use crate::services::urlservice::{UrlService, UrlServiceTrait};
+use crate::{
+    bloom::BloomFilter,
+    services::urlservice::{UrlService, UrlServiceTrait},
+};
 
 pub trait UrlControllerTrait {
     async fn is_visited(&self, url: url::Url) -> Result<bool, DriverError>;
-    async fn mark_visited(&self, url: url::Url) -> Result<(), DriverError>;
+    // Mark `url` as visited; returns whether it was not visited yet.
+    async fn mark_visited(&self, url: url::Url) -> Result<bool, DriverError>;
+    // Mark `url` as visited unless it already was; returns whether the caller claimed it.
+    async fn claim(&self, url: url::Url) -> Result<bool, DriverError>;
+    async fn visited_urls(&self, host: Option<&str>) -> Result<Vec<url::Url>, DriverError>;
+    async fn sync(&self) -> Result<(), DriverError>;
+    fn switch_to_bloom(&self) -> bool;
 }
 
 pub struct UrlController {
@@ -20,6 +29,26 @@ impl UrlController {
             service: UrlService::new(driver),
         }
     }
+
+    pub fn new_with_bloom(
+        driver: Arc<Mutex<dyn CacheDriver<str, url::Url>>>,
+        bloom_driver: Arc<Mutex<dyn CacheDriver<str, BloomFilter>>>,
+        filter: BloomFilter,
+    ) -> Self {
+        UrlController {
+            service: UrlService::new_with_bloom(driver, bloom_driver, filter),
+        }
+    }
+
+    pub fn new_with_bloom_fallback(
+        driver: Arc<Mutex<dyn CacheDriver<str, url::Url>>>,
+        bloom_driver: Arc<Mutex<dyn CacheDriver<str, BloomFilter>>>,
+        filter: BloomFilter,
+    ) -> Self {
+        UrlController {
+            service: UrlService::new_with_bloom_fallback(driver, bloom_driver, filter),
+        }
+    }
 }
 
 impl UrlControllerTrait for UrlController {
@@ -27,7 +56,23 @@ impl UrlControllerTrait for UrlController {
         self.service.is_visited(url).await
     }
 
-    async fn mark_visited(&self, url: url::Url) -> Result<(), DriverError> {
+    async fn mark_visited(&self, url: url::Url) -> Result<bool, DriverError> {
         self.service.mark_visited(url).await
     }
+
+    async fn claim(&self, url: url::Url) -> Result<bool, DriverError> {
+        self.service.claim(url).await
+    }
+
+    async fn visited_urls(&self, host: Option<&str>) -> Result<Vec<url::Url>, DriverError> {
+        self.service.visited_urls(host).await
+    }
+
+    async fn sync(&self) -> Result<(), DriverError> {
+        self.service.sync().await
+    }
+
+    fn switch_to_bloom(&self) -> bool {
+        self.service.switch_to_bloom()
+    }
 }
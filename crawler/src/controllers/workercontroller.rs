@@ -0,0 +1,42 @@
+use std::sync::Arc;
+
+use drivers::{errors::DriverError, CacheDriver};
+use tokio::sync::Mutex;
+
+use crate::{
+    repositories::WorkerHeartbeat,
+    services::workerservice::{WorkerService, WorkerServiceTrait},
+};
+
+pub trait WorkerControllerTrait {
+    async fn beat(&self, heartbeat: WorkerHeartbeat) -> Result<(), DriverError>;
+    async fn remove(&self, crawler_type: &str, id: &str) -> Result<(), DriverError>;
+    async fn list(&self, crawler_type: Option<&str>) -> Result<Vec<WorkerHeartbeat>, DriverError>;
+}
+
+// Heartbeats of the crawler agents, to tell which ones are alive.
+pub struct WorkerController {
+    service: WorkerService,
+}
+
+impl WorkerController {
+    pub fn new(driver: Arc<Mutex<dyn CacheDriver<str, WorkerHeartbeat>>>) -> Self {
+        WorkerController {
+            service: WorkerService::new(driver),
+        }
+    }
+}
+
+impl WorkerControllerTrait for WorkerController {
+    async fn beat(&self, heartbeat: WorkerHeartbeat) -> Result<(), DriverError> {
+        self.service.beat(heartbeat).await
+    }
+
+    async fn remove(&self, crawler_type: &str, id: &str) -> Result<(), DriverError> {
+        self.service.remove(crawler_type, id).await
+    }
+
+    async fn list(&self, crawler_type: Option<&str>) -> Result<Vec<WorkerHeartbeat>, DriverError> {
+        self.service.list(crawler_type).await
+    }
+}
@@ -1,45 +1,354 @@
 use crate::{
-    clients::robots::RobotsTxtClient,
-    controllers::{urlcontroller::UrlControllerTrait, UrlController},
+    blocking::{detect_block, is_refusal, BlockPolicy, BlockStrategy},
+    clients::{
+        backend::FetchBackend,
+        http::{get_default_http_client, ClientSettings, HttpClient, RedirectPolicy},
+        proxy::{redact, ProxyPool},
+        rendering::RenderRules,
+        robots::RobotsTxtClient,
+    },
+    concurrency::FetchLimits,
+    config::LanguageFilterMode,
+    controllers::{
+        blockcontroller::BlockControllerTrait, budgetcontroller::BudgetControllerTrait,
+        checkpointcontroller::CheckpointControllerTrait, contentcontroller::ContentControllerTrait,
+        crawlrecordcontroller::CrawlRecordControllerTrait,
+        deadlettercontroller::DeadLetterControllerTrait,
+        externaldomaincontroller::ExternalDomainControllerTrait,
+        pagecontroller::PageControllerTrait,
+        sharedfrontiercontroller::SharedFrontierControllerTrait, urlcontroller::UrlControllerTrait,
+        BlockController, BudgetController, CheckpointController, ContentController,
+        CrawlRecordController, DeadLetterController, ExternalDomainController, PageController,
+        SharedFrontierController, UrlController,
+    },
+    events::{PageSummary, PublishedPages},
+    frontier::{
+        relevance::{LinkContext, RelevanceModel},
+        scorer::ExternalScorer,
+        strategy::{Bfs, FrontierStrategy},
+        Frontier,
+    },
+    jobs::{JobManager, JobState},
+    memory::{MemoryPressure, PressureLevel},
+    parsers::{language::LanguagePolicy, selectors::SelectorParser},
+    partitioner::AgentInbox,
+    progress::AgentProgress,
+    recrawl::{self, RecrawlPolicy},
+    repositories::{CheckpointEntry, CrawlRecord, DeadLetterEntry, FrontierEntry, StoredPage},
     requests::{
-        http::{HttpRequest, HttpResponse},
+        error::RequestError,
+        http::{CacheValidators, HttpRequest, HttpResponse},
         request::Request,
     },
+    retry::{parse_retry_after, RetryPolicy},
+    routing::RoutingPolicy,
+    scope::ScopePolicy,
+    sinks::Sink,
+    throttle::AdaptiveThrottle,
+    traps::TrapDetector,
+    validators::{self, UrlFilter},
 };
-use drivers::rabbit::RabbitDriver;
-use models::PageData;
-use std::{collections::LinkedList, sync::Arc};
-use tracing::{debug, error, info, instrument, warn};
+use drivers::{random_uuid, telemetry, EnqueueOptions};
+use futures_lite::{stream, Stream};
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use models::{CrawlMetadata, Link, PageData, Provenance, SchemaVersion};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{atomic::Ordering, Arc},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    sync::{mpsc, watch},
+    time::Instant,
+};
+use tracing::{debug, error, info, info_span, instrument, warn, Instrument, Span};
 use url::Url;
 
+// How often an idle agent checks for new requests.
+const IDLE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
 pub struct Crawler {
     name: String,
-    queue: LinkedList<HttpRequest>,
+    // Agent/job/deployment metadata attached to every published page.
+    provenance: Provenance,
+    // Pending requests, in the order of the frontier strategy (BFS unless `use_strategy` is
+    // called).
+    queue: Box<dyn FrontierStrategy>,
     url_controller: Arc<UrlController>,
-    rabbit: Arc<RabbitDriver>,
+    checkpoint_controller: Arc<CheckpointController>,
+    checkpoint_key: String,
+    budget_controller: Arc<BudgetController>,
+    dead_letter_controller: Arc<DeadLetterController>,
+    dead_letter_key: String,
+    // Caps the number of external domains entering the frontier per job.
+    external_domain_controller: Arc<ExternalDomainController>,
+    // Where links over the external domain quotas are set aside.
+    overflow_key: String,
+    // Set when raw responses should be stored for the debug page viewer.
+    page_controller: Option<Arc<PageController>>,
+    block_controller: Arc<BlockController>,
+    // Last crawl time of each published URL, read by incremental crawls.
+    crawl_record_controller: Arc<CrawlRecordController>,
+    // Where the crawled pages are published (message bus and/or local files, see
+    // `SinkMulticast`), unless they are only sent to `pages`.
+    sink: Option<Arc<dyn Sink>>,
     robots_client: RobotsTxtClient,
     max_depth: u32,
     respect_robots_txt: bool,
+    retry_policy: RetryPolicy,
+    block_policy: BlockPolicy,
+    // Which discovered links are enqueued.
+    scope_policy: ScopePolicy,
+    // Media types whose body is downloaded; other responses are published without a body. Empty
+    // allows every content type.
+    allowed_content_types: Arc<Vec<String>>,
+    // Counters read by the progress reporter.
+    progress: Arc<AgentProgress>,
+    // Also crawl the client-side routes discovered on single-page applications.
+    follow_spa_routes: bool,
+    // Recrawl known pages with conditional requests (see `CacheValidators`).
+    conditional_requests: bool,
+    // Languages the crawl is restricted to.
+    languages: Option<LanguagePolicy>,
+    // Publish the main content of HTML pages (see `readability::extract`).
+    extract_main_content: bool,
+    // Site-specific extraction profiles (see `SelectorParser`).
+    selectors: Option<Arc<SelectorParser>>,
+    // Publish the text of PDF documents (see `pdf::extract_text`).
+    extract_pdf: bool,
+    // Maximum size of the PDF documents whose text is extracted (`None` = unlimited).
+    max_pdf_bytes: Option<u64>,
+    // How redirects are followed (hop limit, scope of the targets).
+    redirects: Arc<RedirectPolicy>,
+    // Pages fetched by the rendering backend instead of the HTTP client, and that backend.
+    rendering: Option<(Arc<RenderRules>, Arc<dyn FetchBackend>)>,
+    // Client of the requests not sent through a proxy, built with the settings of the crawl.
+    client: Arc<HttpClient>,
+    // Proxies the requests are spread over, if any.
+    proxies: Option<Arc<ProxyPool>>,
+    // Clients of the fallback proxies of the `SwitchProxy` strategy, one per entry of
+    // `block_policy.proxies`.
+    fallback_clients: Vec<Arc<HttpClient>>,
+    // Rules dropping links not worth following (binary files, traps), if any.
+    url_filter: Option<Arc<UrlFilter>>,
+    // Spider traps detected from the discovered links, if enabled.
+    traps: Option<Arc<TrapDetector>>,
+    // Relevance of the discovered links for focused crawls, if any.
+    relevance: Option<Arc<RelevanceModel>>,
+    // Service scoring the discovered links for the frontier strategy, if any.
+    scorer: Option<ExternalScorer>,
+    // Source of URLs injected while the crawl runs, if any.
+    frontier: Option<Frontier>,
+    // Where the discovered links of domains owned by other agents are sent, and where theirs
+    // arrive, when crawling with several agents (see `LinkRouter`).
+    inbox: Option<AgentInbox>,
+    // Live feed of published pages, if any.
+    published_pages: Option<PublishedPages>,
+    // Receivers of a copy of every published page (library users).
+    pages: Vec<mpsc::Sender<PageData>>,
+    // Highest priority of the message bus queue, when it is a priority queue (see
+    // `message_priority`).
+    max_priority: Option<u8>,
+    // Routing keys of the published pages, when they go through a topic exchange.
+    routing: Option<Arc<RoutingPolicy>>,
+    // Memory pressure of Redis, tightening link admission when high (see `MemoryGuard`).
+    memory_pressure: PressureLevel,
+    // Set while the consumers fall behind, pausing the fetches (see `BackpressureController`).
+    backpressure: Option<watch::Receiver<bool>>,
+    // URLs fetched at once, shared with the other agents (one at a time unless `limit_fetches` is
+    // called).
+    limits: FetchLimits,
+    // Per-domain delays adapting to the health of the servers, shared with the other agents.
+    throttle: Option<Arc<AdaptiveThrottle>>,
+    // Content fingerprints seen during the crawl, when pages with already seen content are skipped.
+    content_controller: Option<Arc<ContentController>>,
+    // Freshness TTL of the published pages and prefix of the lists they are filed in for a
+    // recrawl, when crawling continuously.
+    recrawl: Option<(Arc<RecrawlPolicy>, String)>,
+    // Runtime jobs whose depth, scope and state apply to their requests, if any.
+    jobs: Option<JobManager>,
+    // Whether the agent waits for submitted jobs and injected URLs instead of stopping when its
+    // queue is empty (admin and gRPC APIs).
+    wait_for_jobs: bool,
+    // Requests of paused jobs, set aside until their job is resumed.
+    held: Vec<HttpRequest>,
+    // Requests to domains cooling down after blocking the crawler, set aside until the end of the
+    // cooldown.
+    cooling_down: Vec<(Instant, HttpRequest)>,
+    // Generation of the job states the held requests were last checked against.
+    jobs_generation: u64,
+    // Frontier shared with the other crawler processes, and the number of requests claimed from
+    // it at once, when crawling with several processes.
+    shared_frontier: Option<(Arc<SharedFrontierController>, usize)>,
+    // Claims of the shared frontier requests waiting in the queue or held.
+    queued_claims: HashSet<u64>,
+    // Claims of the requests done since the last acknowledgement.
+    finished_claims: Vec<u64>,
+    shutdown: watch::Receiver<bool>,
+}
+
+// A request ready to be fetched (see `Crawler::prepare`), with what its completion needs.
+struct Fetch {
+    req: HttpRequest,
+    domain: String,
+    // Proxy of the pool the request goes through, if any.
+    proxy: Option<usize>,
+    // Only the headers are fetched once the domain exhausted its byte budget.
+    over_budget: bool,
+    // Pause before the fetch, for domains blocking the crawler (`slow_down` strategy).
+    delay: Option<Duration>,
+    span: Span,
+}
+
+impl Fetch {
+    // Fetch the request once `limits` and `throttle` allow it. Borrows nothing from the agent, so
+    // that the agent keeps several fetches in flight.
+    async fn run(
+        self,
+        limits: FetchLimits,
+        throttle: Option<Arc<AdaptiveThrottle>>,
+    ) -> (Fetch, Result<HttpResponse, RequestError>) {
+        // Requests scheduled for a retry are pushed back into the queue, so by the time they are
+        // popped again the backoff has usually elapsed. Otherwise wait for the remainder.
+        if let Some(retry_at) = self.req.retry_at {
+            tokio::time::sleep_until(retry_at).await;
+        }
+        if let Some(delay) = self.delay {
+            tokio::time::sleep(delay).await;
+        }
+
+        if let Some(throttle) = &throttle {
+            throttle.wait(&self.domain).await;
+        }
+
+        let _permit = limits.acquire(&self.domain).await;
+        let result = if self.over_budget {
+            info!(
+                "Byte budget exhausted for {}, fetching headers only",
+                self.domain
+            );
+            self.req.head().await
+        } else {
+            self.req.execute().await
+        };
+        if let Some(throttle) = &throttle {
+            throttle.record(&self.domain, &result);
+        }
+        (self, result)
+    }
 }
 
 impl Crawler {
-    #[instrument(skip(url_controller, rabbit, seed), fields(name = %name))]
+    #[allow(clippy::too_many_arguments)]
+    #[instrument(
+        skip(
+            provenance,
+            url_controller,
+            checkpoint_controller,
+            budget_controller,
+            dead_letter_controller,
+            external_domain_controller,
+            page_controller,
+            block_controller,
+            crawl_record_controller,
+            sink,
+            robots_client,
+            retry_policy,
+            block_policy,
+            scope_policy,
+            allowed_content_types,
+            seed,
+            shutdown
+        ),
+        fields(name = %name, worker = %provenance.worker)
+    )]
     pub fn new(
         name: String,
+        provenance: Provenance,
         url_controller: Arc<UrlController>,
-        rabbit: Arc<RabbitDriver>,
+        checkpoint_controller: Arc<CheckpointController>,
+        checkpoint_key: String,
+        budget_controller: Arc<BudgetController>,
+        dead_letter_controller: Arc<DeadLetterController>,
+        dead_letter_key: String,
+        external_domain_controller: Arc<ExternalDomainController>,
+        overflow_key: String,
+        page_controller: Option<Arc<PageController>>,
+        block_controller: Arc<BlockController>,
+        crawl_record_controller: Arc<CrawlRecordController>,
+        sink: Option<Arc<dyn Sink>>,
+        robots_client: RobotsTxtClient,
         respect_robots_txt: bool,
         max_depth: u32,
+        retry_policy: RetryPolicy,
+        block_policy: BlockPolicy,
+        scope_policy: ScopePolicy,
+        allowed_content_types: Vec<String>,
         seed: Vec<Url>,
+        shutdown: watch::Receiver<bool>,
     ) -> Self {
         let mut agent = Crawler {
             name,
-            queue: LinkedList::<HttpRequest>::new(),
+            provenance,
+            queue: Box::new(Bfs::default()),
             url_controller,
-            rabbit,
-            robots_client: RobotsTxtClient::new(),
+            checkpoint_controller,
+            checkpoint_key,
+            budget_controller,
+            dead_letter_controller,
+            dead_letter_key,
+            external_domain_controller,
+            overflow_key,
+            page_controller,
+            block_controller,
+            crawl_record_controller,
+            sink,
+            robots_client,
             max_depth,
             respect_robots_txt,
+            retry_policy,
+            block_policy,
+            scope_policy,
+            allowed_content_types: Arc::new(allowed_content_types),
+            progress: Arc::new(AgentProgress::default()),
+            follow_spa_routes: false,
+            conditional_requests: false,
+            languages: None,
+            extract_main_content: false,
+            selectors: None,
+            extract_pdf: false,
+            max_pdf_bytes: None,
+            redirects: Arc::default(),
+            rendering: None,
+            client: Arc::new(get_default_http_client(&ClientSettings::default())),
+            proxies: None,
+            fallback_clients: Vec::new(),
+            url_filter: None,
+            traps: None,
+            relevance: None,
+            scorer: None,
+            frontier: None,
+            inbox: None,
+            published_pages: None,
+            pages: Vec::new(),
+            max_priority: None,
+            routing: None,
+            memory_pressure: PressureLevel::default(),
+            backpressure: None,
+            limits: FetchLimits::new(1, 0, 0),
+            throttle: None,
+            content_controller: None,
+            recrawl: None,
+            jobs: None,
+            wait_for_jobs: false,
+            held: Vec::new(),
+            cooling_down: Vec::new(),
+            jobs_generation: 0,
+            shared_frontier: None,
+            queued_claims: HashSet::new(),
+            finished_claims: Vec::new(),
+            shutdown,
         };
 
         // push seed URLs into the queue if present
@@ -52,62 +361,870 @@ impl Crawler {
         agent
     }
 
+    // Re-enqueue requests restored from a previous checkpoint (or planned by an incremental crawl),
+    // preserving their depth, the number of attempts already made and whether they are refreshes.
+    pub fn resume(&mut self, entries: Vec<CheckpointEntry>) {
+        info!("Resuming {} requests from checkpoint", entries.len());
+        for entry in entries {
+            let mut req = HttpRequest::new(&entry.url, entry.depth);
+            req.attempts = entry.attempts;
+            req.refresh = entry.refresh;
+            req.origin_seed = entry.origin_seed;
+            req.path = entry.path;
+            self.push(req);
+        }
+    }
+
+    // Publish the pages with a message priority from 0 to `max_priority`, so that consumers of a
+    // priority queue get the most relevant pages first (see `message_priority`).
+    pub fn prioritize_messages(&mut self, max_priority: u8) {
+        self.max_priority = (max_priority > 0).then_some(max_priority);
+    }
+
+    // Publish the pages with a routing key of their content type, language and category (see
+    // `RoutingPolicy`).
+    pub fn route_pages(&mut self, routing: Arc<RoutingPolicy>) {
+        self.routing = Some(routing);
+    }
+
+    // Report progress through the given counters (see `ProgressReporter`).
+    pub fn report_progress(&mut self, progress: Arc<AgentProgress>) {
+        progress
+            .queued
+            .store(self.queue.len() as u64, Ordering::Relaxed);
+        self.progress = progress;
+    }
+
+    // Crawl the pending requests in the order of `strategy` (see `FrontierStrategy`).
+    pub fn use_strategy(&mut self, mut strategy: Box<dyn FrontierStrategy>) {
+        for req in self.queue.drain() {
+            strategy.push(req);
+        }
+        self.queue = strategy;
+    }
+
+    // Enqueue the client-side routes discovered on single-page applications along with their links
+    // (see `routes::discover_routes`).
+    pub fn follow_spa_routes(&mut self) {
+        self.follow_spa_routes = true;
+    }
+
+    // Publish the article text and title of HTML pages, without their navigation, banners and
+    // footers.
+    pub fn extract_main_content(&mut self) {
+        self.extract_main_content = true;
+    }
+
+    // Publish the text of PDF documents up to `max_bytes` (`None` = unlimited) instead of skipping
+    // them. Only effective when built with the `pdf` feature.
+    pub fn extract_pdfs(&mut self, max_bytes: Option<u64>) {
+        self.extract_pdf = true;
+        self.max_pdf_bytes = max_bytes;
+    }
+
+    // Follow up to `max_hops` redirects, only to targets within the crawl scope if `in_scope`.
+    pub fn limit_redirects(&mut self, max_hops: usize, in_scope: bool) {
+        self.redirects = Arc::new(RedirectPolicy {
+            max_hops,
+            scope: in_scope.then(|| Arc::new(self.scope_policy.clone())),
+        });
+    }
+
+    // Fetch the pages matching `rules` with `renderer` (a headless browser) instead of the HTTP
+    // client.
+    pub fn render_with(&mut self, rules: Arc<RenderRules>, renderer: Arc<dyn FetchBackend>) {
+        self.rendering = Some((rules, renderer));
+    }
+
+    // Send the requests with `client` (User-Agent, address policy, DNS cache of the crawl).
+    pub fn use_client(&mut self, client: Arc<HttpClient>) {
+        self.client = client;
+    }
+
+    // Send the requests to domains blocking the crawler through `clients` (built for the fallback
+    // proxies of `block_policy`) with the `SwitchProxy` strategy. Without them, the strategy
+    // degrades to slowing down.
+    pub fn use_fallback_proxies(&mut self, clients: Vec<Arc<HttpClient>>) {
+        self.fallback_clients = clients;
+    }
+
+    // Send the requests through the proxies of `pool`, reporting their failures to it.
+    pub fn use_proxies(&mut self, pool: Arc<ProxyPool>) {
+        self.proxies = Some(pool);
+    }
+
+    // Drop the discovered links rejected by `filter` (binary files, over-long URLs, traps).
+    pub fn filter_urls(&mut self, filter: Arc<UrlFilter>) {
+        self.url_filter = Some(filter);
+    }
+
+    // Stop following the links of the spider traps detected by `detector` (calendars, faceted
+    // search, session ids).
+    pub fn detect_traps(&mut self, detector: Arc<TrapDetector>) {
+        self.traps = Some(detector);
+    }
+
+    // Extract the fields of the site-specific profiles of `selectors` from the pages they match.
+    pub fn extract_fields(&mut self, selectors: Arc<SelectorParser>) {
+        self.selectors = Some(selectors);
+    }
+
+    // Only publish pages in the languages of `policy`; with the `crawl` filter, pages in other
+    // languages are not followed either.
+    pub fn filter_languages(&mut self, policy: LanguagePolicy) {
+        self.languages = Some(policy);
+    }
+
+    // Recrawl known pages with the validators of their previous crawl, so that pages that did not
+    // change are neither downloaded nor published again.
+    pub fn send_conditional_requests(&mut self) {
+        self.conditional_requests = true;
+    }
+
+    // Score the discovered links with `relevance`, dropping those below its threshold (see
+    // `RelevanceModel`).
+    pub fn rank_links(&mut self, relevance: Arc<RelevanceModel>) {
+        self.relevance = Some(relevance);
+    }
+
+    // Score the discovered links with `scorer` before enqueuing them (see `ExternalScorer`).
+    pub fn score_links(&mut self, scorer: ExternalScorer) {
+        self.scorer = Some(scorer);
+    }
+
+    // Poll `frontier` for injected URLs while crawling (see `Frontier::inject`).
+    pub fn poll_frontier(&mut self, frontier: Frontier) {
+        self.frontier = Some(frontier);
+    }
+
+    // Send the discovered links to the agent owning their domain, and crawl those routed to this
+    // agent (see `LinkRouter`).
+    pub fn route_links(&mut self, inbox: AgentInbox) {
+        self.inbox = Some(inbox);
+    }
+
+    // Announce every published page on `published_pages` (see `PublishedPages`).
+    pub fn stream_published(&mut self, published_pages: PublishedPages) {
+        self.published_pages = Some(published_pages);
+    }
+
+    // Send a copy of every published page to `pages`. The channel is bounded: a slow receiver
+    // slows the agent down instead of buffering pages without limit.
+    pub fn send_pages(&mut self, pages: mpsc::Sender<PageData>) {
+        self.pages.push(pages);
+    }
+
+    // Stream of the pages published by this agent (see `send_pages`), buffering up to `buffer`
+    // pages. The stream ends when the agent is dropped.
+    pub fn pages(&mut self, buffer: usize) -> impl Stream<Item = PageData> {
+        let (sender, receiver) = mpsc::channel(buffer.max(1));
+        self.send_pages(sender);
+        stream::unfold(receiver, |mut receiver| async move {
+            receiver.recv().await.map(|page| (page, receiver))
+        })
+    }
+
+    // Admit fewer discovered links while Redis is short on memory (see `MemoryGuard`).
+    pub fn watch_memory(&mut self, memory_pressure: PressureLevel) {
+        self.memory_pressure = memory_pressure;
+    }
+
+    // Stop fetching while `paused` is set, i.e. the message bus queue is too deep (see
+    // `BackpressureController`).
+    pub fn watch_backlog(&mut self, paused: watch::Receiver<bool>) {
+        self.backpressure = Some(paused);
+    }
+
+    // Fetch several URLs at once, within the limits shared by the agents (see `FetchLimits`).
+    pub fn limit_fetches(&mut self, limits: FetchLimits) {
+        self.limits = limits;
+    }
+
+    // Space the fetches of each domain by a delay adapting to its response times and errors (see
+    // `AdaptiveThrottle`).
+    pub fn throttle_domains(&mut self, throttle: Arc<AdaptiveThrottle>) {
+        self.throttle = Some(throttle);
+    }
+
+    // Skip the pages whose content was already seen at another URL (see `ContentController`).
+    pub fn dedupe_content(&mut self, content_controller: Arc<ContentController>) {
+        self.content_controller = Some(content_controller);
+    }
+
+    // Crawl continuously: published pages are filed for a recrawl under `key` once their TTL
+    // expires (see `RecrawlScheduler`), and the agent waits for new requests instead of stopping
+    // when its queue is empty.
+    pub fn schedule_recrawls(&mut self, policy: Arc<RecrawlPolicy>, key: String) {
+        self.recrawl = Some((policy, key));
+    }
+
+    // Apply the depth, scope and state of the runtime jobs to their requests (see `JobManager`).
+    pub fn control_jobs(&mut self, jobs: JobManager) {
+        self.jobs_generation = jobs.generation();
+        self.jobs = Some(jobs);
+    }
+
+    // Wait for jobs submitted or URLs injected through the APIs until shut down, instead of
+    // stopping once the queue is empty.
+    pub fn wait_for_jobs(&mut self) {
+        self.wait_for_jobs = true;
+    }
+
+    // Pull requests from the frontier shared by the crawler processes (see
+    // `SharedFrontierController`), `claim_batch` at a time, and push discovered links to it.
+    pub fn share_frontier(
+        &mut self,
+        shared_frontier: Arc<SharedFrontierController>,
+        claim_batch: usize,
+    ) {
+        self.shared_frontier = Some((shared_frontier, claim_batch.max(1)));
+    }
+
+    // Claim a batch of requests from the shared frontier once the queue is empty. Requests that
+    // were visited since they were queued are acknowledged right away.
+    async fn claim_shared(&mut self) {
+        let Some((shared_frontier, claim_batch)) = self.shared_frontier.clone() else {
+            return;
+        };
+        if !self.queue.is_empty() {
+            return;
+        }
+        let worker = format!("{}/{}", self.provenance.worker, self.name);
+        let claimed = match shared_frontier.claim(&worker, claim_batch).await {
+            Ok(claimed) => claimed,
+            Err(err) => {
+                error!("Error claiming requests from the shared frontier: {}", err);
+                return;
+            }
+        };
+        for (claim, entry) in claimed {
+            if !entry.refresh {
+                if let Ok(url) = Url::parse(&entry.url) {
+                    if let Ok(true) = self.url_controller.is_visited(url).await {
+                        debug!("Shared frontier request already visited: {}", entry.url);
+                        self.finished_claims.push(claim);
+                        continue;
+                    }
+                }
+            }
+            let mut req = HttpRequest::new(&entry.url, entry.depth);
+            req.attempts = entry.attempts;
+            req.refresh = entry.refresh;
+            req.job = entry.job;
+            req.origin_seed = entry.origin_seed;
+            req.path = entry.path;
+            req.claim = Some(claim);
+            self.push(req);
+        }
+    }
+
+    // Acknowledge the shared frontier requests that are done.
+    async fn ack_finished(&mut self) {
+        let Some((shared_frontier, _)) = &self.shared_frontier else {
+            return;
+        };
+        for claim in self.finished_claims.drain(..) {
+            if let Err(err) = shared_frontier.ack(claim).await {
+                error!(
+                    "Error acknowledging shared frontier request {}: {}",
+                    claim, err
+                );
+            }
+        }
+    }
+
+    // Whether the other processes may still push requests to the shared frontier.
+    async fn shared_frontier_busy(&self) -> bool {
+        let Some((shared_frontier, _)) = &self.shared_frontier else {
+            return false;
+        };
+        match shared_frontier.pending().await {
+            Ok(pending) => pending > 0,
+            Err(err) => {
+                error!("Error reading the shared frontier: {}", err);
+                true
+            }
+        }
+    }
+
+    // Pop the next request to execute. Requests of paused jobs are set aside and requests of
+    // cancelled jobs dropped on the way.
+    fn next_request(&mut self) -> Option<HttpRequest> {
+        loop {
+            let req = self.queue.pop()?;
+            if let Some(claim) = req.claim {
+                self.queued_claims.remove(&claim);
+            }
+            let Some(jobs) = &self.jobs else {
+                return Some(req);
+            };
+            match jobs.state(req.job.as_deref()) {
+                JobState::Running => return Some(req),
+                JobState::Paused => {
+                    self.queued_claims.extend(req.claim);
+                    self.held.push(req);
+                }
+                JobState::Cancelled => {
+                    debug!("Dropping request of cancelled job: {}", req.target);
+                    self.finished_claims.extend(req.claim);
+                }
+            }
+        }
+    }
+
+    // Set a request to a cooling-down domain aside for `cooldown`.
+    fn cool_down(&mut self, req: HttpRequest, cooldown: Duration) {
+        self.queued_claims.extend(req.claim);
+        self.cooling_down.push((Instant::now() + cooldown, req));
+    }
+
+    // Move the requests whose domain finished cooling down back into the queue.
+    fn release_cooled_down(&mut self) {
+        let now = Instant::now();
+        let (ready, cooling): (Vec<_>, Vec<_>) = std::mem::take(&mut self.cooling_down)
+            .into_iter()
+            .partition(|(until, _)| *until <= now);
+        self.cooling_down = cooling;
+        for (_, req) in ready {
+            self.push(req);
+        }
+    }
+
+    // Move the held requests of resumed jobs back into the queue, once job states changed.
+    fn release_resumed(&mut self) {
+        let Some(jobs) = &self.jobs else {
+            return;
+        };
+        let generation = jobs.generation();
+        if generation == self.jobs_generation {
+            return;
+        }
+        self.jobs_generation = generation;
+
+        let mut held = Vec::new();
+        let mut resumed = Vec::new();
+        for req in self.held.drain(..) {
+            match jobs.state(req.job.as_deref()) {
+                JobState::Running => resumed.push(req),
+                JobState::Paused => held.push(req),
+                JobState::Cancelled => {
+                    debug!("Dropping request of cancelled job: {}", req.target);
+                    if let Some(claim) = req.claim {
+                        self.queued_claims.remove(&claim);
+                        self.finished_claims.push(claim);
+                    }
+                }
+            }
+        }
+        self.held = held;
+        for req in resumed {
+            self.push(req);
+        }
+    }
+
+    // Move the URLs injected since the last poll into the queue. Prioritized injections are
+    // crawled next, the others after the pending requests.
+    fn pull_injected(&mut self) {
+        let Some(frontier) = &self.frontier else {
+            return;
+        };
+        let (prioritized, normal): (Vec<_>, Vec<_>) = frontier
+            .take()
+            .into_iter()
+            .partition(|injection| injection.priority > 0);
+        for injection in prioritized.into_iter().rev() {
+            let mut req = HttpRequest::new(&injection.url, injection.depth);
+            req.job = injection.job;
+            req.refresh = injection.refresh;
+            req.origin_seed = injection.origin_seed;
+            req.path = injection.path;
+            self.queue.push_next(req);
+        }
+        for injection in normal {
+            let mut req = HttpRequest::new(&injection.url, injection.depth);
+            req.job = injection.job;
+            req.refresh = injection.refresh;
+            req.origin_seed = injection.origin_seed;
+            req.path = injection.path;
+            self.push(req);
+        }
+    }
+
+    // Move the links routed to this agent by the others into the queue.
+    fn pull_routed(&mut self) {
+        let Some(inbox) = &mut self.inbox else {
+            return;
+        };
+        for req in inbox.receive() {
+            self.push(req);
+        }
+    }
+
+    // Queue a discovered link, or hand it to the agent owning its domain.
+    fn push_or_route(&mut self, req: HttpRequest) {
+        let req = match &self.inbox {
+            Some(inbox) => inbox.route(req),
+            None => Some(req),
+        };
+        if let Some(req) = req {
+            self.push(req);
+        }
+    }
+
+    // Report to the other agents whether this one ran out of requests. Returns whether another
+    // agent is still busy, i.e. may route links to this one: stopping now would leave them to the
+    // agents that found them.
+    fn report_idle(&mut self, idle: bool) -> bool {
+        self.inbox
+            .as_mut()
+            .is_some_and(|inbox| inbox.set_idle(idle))
+    }
+
+    // Stop receiving routed links, queueing those that arrived in the meantime. Returns whether
+    // any did.
+    fn close_inbox(&mut self) -> bool {
+        let Some(inbox) = &mut self.inbox else {
+            return false;
+        };
+        let routed = inbox.close();
+        let received = !routed.is_empty();
+        for req in routed {
+            self.push(req);
+        }
+        received
+    }
+
     // Handle new request by pushing it to the queue.
     #[instrument(skip(self, req), fields(url = %req.target))]
     pub fn push(&mut self, req: HttpRequest) {
         debug!("Pushing new request to the queue");
-        self.queue.push_back(req);
+        self.queued_claims.extend(req.claim);
+        self.queue.push(req);
+    }
+
+    // Span of a request popped from the queue, entered while it is prepared, fetched and
+    // completed. Each request is the root of its own trace (joined by the consumers of its page),
+    // rather than a child of the long-lived agent span.
+    fn request_span(&self, req: &HttpRequest) -> Span {
+        info_span!(
+            parent: None,
+            "execute",
+            url = %req.target,
+            domain = tracing::field::Empty,
+            depth = req.depth,
+            agent = %self.name,
+            crawl_id = %self.provenance.job
+        )
     }
 
-    // Execute one queued request
-    #[instrument(skip(self))]
-    async fn execute(&mut self) -> Result<HttpResponse, String> {
-        // Pull new request from the queue. The request is removed from the queue.
-        let req = self.queue.pop_front().ok_or("Queue is empty")?;
+    // Get the request ready to be fetched: robots.txt, byte budget, proxy, fallback strategy of
+    // blocking domains and URL claim. Returns `None` when another agent (or an earlier request)
+    // already claimed the URL.
+    async fn prepare(&mut self, mut req: HttpRequest) -> Result<Option<Fetch>, String> {
         debug!(
             "Executing request for URL: {} at depth {}",
             req.target, req.depth
         );
 
+        // Work on the normalized URL, so that its variants are claimed and recorded once.
+        if let Ok(url) = Url::parse(&req.target) {
+            req.target = url.to_string();
+        }
+
         // Ensure the request is allowed by robots.txt if configured.
         if self.respect_robots_txt && !self.robots_client.is_allowed(&req.target).await {
             warn!("URL is not allowed by robots.txt: {}", req.target);
             return Err(format!("URL is not allowed by robots.txt: {}", req.target));
         }
 
-        // Execute the request asynchronously.
-        let res = req
-            .execute()
-            .await
-            .map_err(|e| format!("Request error: {e}",))?;
+        // Refreshed pages are only downloaded if they changed since their previous crawl.
+        if req.refresh && self.conditional_requests {
+            match self.crawl_record_controller.find(&req.target).await {
+                Ok(Some(record)) if record.etag.is_some() || record.last_modified.is_some() => {
+                    req.validators = Some(CacheValidators {
+                        etag: record.etag,
+                        last_modified: record.last_modified,
+                    });
+                }
+                Ok(_) => {}
+                Err(err) => error!("Error reading crawl record of {}: {}", req.target, err),
+            }
+        }
+
+        // Once a domain exhausted its byte budget, only collect HEAD-level metadata for it.
+        let domain = Url::parse(&req.target)
+            .ok()
+            .and_then(|u| u.host_str().map(str::to_string))
+            .unwrap_or_default();
+        Span::current().record("domain", domain.as_str());
+        let over_budget = match self.budget_controller.is_exhausted(&domain).await {
+            Ok(exhausted) => exhausted,
+            Err(err) => {
+                error!("Error checking byte budget for {}: {}", domain, err);
+                false
+            }
+        };
+
+        req.redirects = Arc::clone(&self.redirects);
+        req.client = Some(Arc::clone(&self.client));
+
+        // Spread the requests over the proxy pool, if any.
+        let mut proxy = self.proxies.as_ref().map(|pool| {
+            let (index, client) = pool.select(&domain);
+            req.client = Some(client);
+            index
+        });
+
+        // Apply the fallback strategy of domains that were detected as blocking the crawler.
+        let mut delay = None;
+        match self.block_controller.find(&domain).await {
+            Ok(Some(blocked)) => match blocked.strategy {
+                BlockStrategy::Park => {
+                    let message = format!("Domain {} is parked: {}", domain, blocked.reason);
+                    self.park(&domain, req).await;
+                    return Err(message);
+                }
+                BlockStrategy::Cooldown => {
+                    let remaining = blocked.cooldown_until.saturating_sub(unix_timestamp());
+                    if remaining > 0 {
+                        let message = format!(
+                            "Domain {} is cooling down for {}s: {}",
+                            domain, remaining, blocked.reason
+                        );
+                        self.cool_down(req, Duration::from_secs(remaining));
+                        return Err(message);
+                    }
+                }
+                BlockStrategy::SwitchProxy if !self.fallback_clients.is_empty() => {
+                    let index = blocked.proxy_index % self.fallback_clients.len();
+                    debug!(
+                        "Routing request to blocked domain {} through fallback proxy {}",
+                        domain,
+                        self.block_policy
+                            .proxies
+                            .get(index)
+                            .map(|proxy| redact(proxy))
+                            .unwrap_or_default()
+                    );
+                    req.client = Some(Arc::clone(&self.fallback_clients[index]));
+                    // The fallback proxy replaces the one of the pool.
+                    proxy = None;
+                }
+                // NOTE: without fallback proxies, SwitchProxy degrades to SlowDown.
+                _ => {
+                    debug!(
+                        "Slowing down request to blocked domain {} by {}ms",
+                        domain, blocked.delay_ms
+                    );
+                    delay = Some(Duration::from_millis(blocked.delay_ms));
+                }
+            },
+            Ok(None) => {}
+            Err(err) => error!("Error checking block state for {}: {}", domain, err),
+        }
+
+        // Claim the URL right before fetching it, so that a page linked from many others is fetched
+        // by a single agent. Retries and refreshes already own their URL; requests parked or
+        // cooling down above are claimed once they are actually fetched.
+        if req.attempts == 0 && !req.refresh {
+            if let Ok(url) = Url::parse(&req.target) {
+                match self.url_controller.claim(url).await {
+                    Ok(true) => {
+                        self.progress.visited.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Ok(false) => {
+                        info!("URL already visited: {}", req.target);
+                        return Ok(None);
+                    }
+                    Err(err) => error!("Error claiming {}: {}", req.target, err),
+                }
+            }
+        }
+
+        if !over_budget {
+            req.allowed_content_types = (!self.allowed_content_types.is_empty())
+                .then(|| Arc::clone(&self.allowed_content_types));
+            req.main_content = self.extract_main_content;
+            req.selectors = self.selectors.clone();
+            req.extract_pdf = self.extract_pdf;
+            req.max_pdf_bytes = self.max_pdf_bytes;
+            if let Some((rules, renderer)) = &self.rendering {
+                req.backend = rules
+                    .options_for(&req.target)
+                    .map(|options| (Arc::clone(renderer), options.clone()));
+            }
+        }
+
+        Ok(Some(Fetch {
+            req,
+            domain,
+            proxy,
+            over_budget,
+            delay,
+            span: Span::current(),
+        }))
+    }
+
+    // Handle the response of a fetched request: block detection, retries, deduplication, link
+    // discovery and publication of the page.
+    async fn complete(
+        &mut self,
+        fetch: Fetch,
+        result: Result<HttpResponse, RequestError>,
+    ) -> Result<HttpResponse, String> {
+        let Fetch {
+            req, domain, proxy, ..
+        } = fetch;
+
+        let mut block = result.as_ref().ok().and_then(|res| {
+            let body = res.extra.as_ref().map(|e| e.body.as_str());
+            detect_block(res.status_code, &res.headers, body)
+        });
+        // Bare refusals only mark the domain as blocking once they add up to a storm.
+        if let Ok(res) = &result {
+            if block.is_none() && is_refusal(res.status_code) {
+                match self
+                    .block_controller
+                    .record_refusal(&domain, unix_timestamp())
+                    .await
+                {
+                    Ok(Some(count)) => {
+                        block = Some(format!(
+                            "{} 403/429 responses within {:?}",
+                            count, self.block_policy.storm_window
+                        ))
+                    }
+                    Ok(None) => {}
+                    Err(err) => error!("Error counting refusals of {}: {}", domain, err),
+                }
+            }
+        }
+
+        // Proxies whose requests fail or get blocked are set aside for a while.
+        if let (Some(pool), Some(index)) = (&self.proxies, proxy) {
+            let failed = block.is_some()
+                || matches!(
+                    result,
+                    Err(RequestError::Timeout
+                        | RequestError::Connection(_)
+                        | RequestError::Http(_))
+                );
+            pool.report(index, !failed);
+        }
+
+        // Servers that are rate limiting or temporarily unavailable may say when to come back:
+        // reschedule the request accordingly rather than treating the response as a block.
+        if let Ok(res) = &result {
+            if matches!(res.status_code, 429 | 503) {
+                if let Some(delay) = res.header("retry-after").and_then(parse_retry_after) {
+                    info!(
+                        "{} answered {} with Retry-After {:?}",
+                        req.target, res.status_code, delay
+                    );
+                    let err = RequestError::Status(res.status_code);
+                    return Err(self.handle_failure(req, err, Some(delay)).await);
+                }
+            }
+        }
+
+        // Detect bot-blocking responses first, so that they are handled by the fallback strategy
+        // instead of being retried blindly.
+        if let Some(reason) = block {
+            return Err(self.handle_block(req, &domain, reason).await);
+        }
+
+        // Server errors are treated as failures so that they go through the retry policy, while
+        // client errors and rejected redirects are permanent: the URL is marked visited and never
+        // published.
+        let result = result.and_then(|res| match res.status_code {
+            400.. => Err(RequestError::Status(res.status_code)),
+            _ => Ok(res),
+        });
+        let res = match result {
+            Ok(res) => res,
+            Err(err) => {
+                if matches!(
+                    err,
+                    RequestError::Status(400..=499)
+                        | RequestError::Redirect(_)
+                        | RequestError::AddressRejected(_)
+                ) {
+                    self.mark_visited(&req.target).await;
+                }
+                return Err(self.handle_failure(req, err, None).await);
+            }
+        };
         info!("Request executed successfully");
 
-        // Enroll discovered links into the queue.
-        if req.depth < self.max_depth {
-            if let Some(extra) = &res.extra {
-                debug!("Found {} links", extra.links.len());
-                // parse the Url to ensure it's valid
-                let target_url =
-                    Url::parse(&req.target).map_err(|e| format!("Invalid URL: {e}"))?;
-
-                // check if url is already visited
-                if let Ok(visited) = self.url_controller.is_visited(target_url.clone()).await {
-                    if visited {
-                        info!("URL already visited: {}", target_url);
+        // Pages that did not change since their previous crawl are neither published nor followed
+        // again; only their crawl record and recrawl are renewed.
+        if res.not_modified() {
+            info!(
+                event = "recrawled",
+                url = %req.target,
+                changed = false,
+                not_modified = true,
+                "Page crawled again"
+            );
+            self.mark_visited(&req.target).await;
+            let previous = match self.crawl_record_controller.find(&req.target).await {
+                Ok(previous) => previous,
+                Err(err) => {
+                    error!("Error reading crawl record of {}: {}", req.target, err);
+                    None
+                }
+            };
+            let content_hash = previous.and_then(|record| record.content_hash);
+            self.record_crawl(&req, &res, content_hash).await;
+            return Ok(res);
+        }
+
+        // The client follows redirects: record the final location and mark it visited, so that
+        // other URLs redirecting to it (or the location itself) are not crawled again. The
+        // requested URL is marked below like any other page.
+        if res.final_url != req.target {
+            info!(
+                event = "redirect",
+                from = %req.target,
+                to = %res.final_url,
+                "Request was redirected"
+            );
+            if let Ok(final_url) = Url::parse(&res.final_url) {
+                match self.url_controller.is_visited(final_url.clone()).await {
+                    Ok(true) if !req.refresh => {
+                        info!("Redirect target already visited: {}", final_url);
                         return Ok(res);
                     }
-                } else {
-                    error!("Error checking if URL is visited: {}", target_url);
+                    Ok(_) => {
+                        if let Err(err) = self.url_controller.mark_visited(final_url).await {
+                            error!("Error marking URL as visited: {}", err);
+                        }
+                    }
+                    Err(err) => error!("Error checking if URL is visited: {}", err),
                 }
+            }
+        }
 
-                // otherwise, mark it as visited
-                if let Err(err) = self.url_controller.mark_visited(target_url).await {
-                    error!("Error marking URL as visited: {}", err);
+        // Pages declaring a canonical URL are deduplicated by it: the canonical URL is marked
+        // visited, and mirrors of an already visited page (tracking parameters, print versions,
+        // ...) are neither published nor followed.
+        if let Some(canonical) = res.canonical_url.as_deref() {
+            if canonical != req.target && canonical != res.final_url {
+                if let Ok(canonical) = Url::parse(canonical) {
+                    match self.url_controller.is_visited(canonical.clone()).await {
+                        Ok(true) if !req.refresh => {
+                            info!(
+                                event = "duplicate",
+                                url = %req.target,
+                                canonical = %canonical,
+                                "Canonical URL already visited"
+                            );
+                            self.mark_visited(&req.target).await;
+                            return Ok(res);
+                        }
+                        Ok(_) => {
+                            if let Err(err) = self.url_controller.mark_visited(canonical).await {
+                                error!("Error marking URL as visited: {}", err);
+                            }
+                        }
+                        Err(err) => error!("Error checking if URL is visited: {}", err),
+                    }
+                }
+            }
+        }
+
+        // Pages whose content was already seen at another URL (session parameters, mirrors without
+        // a canonical URL, ...) are neither published nor followed.
+        if let (Some(content_controller), Some(fingerprint)) =
+            (self.content_controller.clone(), &res.fingerprint)
+        {
+            match content_controller.claim(&req.target, fingerprint).await {
+                Ok(Some(original)) if !req.refresh => {
+                    info!(
+                        event = "duplicate",
+                        url = %req.target,
+                        original = %original,
+                        "Content already seen"
+                    );
+                    self.mark_visited(&req.target).await;
+                    return Ok(res);
+                }
+                Ok(_) => {}
+                Err(err) => error!("Error checking the content fingerprint: {}", err),
+            }
+        }
+
+        // Pages in other languages than those of the crawl are not published, and not followed
+        // with the `crawl` filter.
+        let publish = match &self.languages {
+            Some(policy) if !policy.allows(res.language.as_deref()) => {
+                info!(
+                    event = "language_filtered",
+                    url = %req.target,
+                    language = res.language.as_deref().unwrap_or_default(),
+                    "Page language not allowed"
+                );
+                if policy.mode == LanguageFilterMode::Crawl {
+                    self.mark_visited(&req.target).await;
+                    return Ok(res);
+                }
+                false
+            }
+            _ => true,
+        };
+
+        // Skipped pages have no links to follow: mark them visited so they are not fetched again.
+        if let Some(reason) = &res.skipped {
+            info!("Skipped body of {}: {}", req.target, reason);
+            self.mark_visited(&req.target).await;
+        }
+
+        // Refreshed pages are published even if already visited; pages new to the crawl are
+        // marked visited here since their links are not followed.
+        if req.refresh {
+            self.mark_visited(&req.target).await;
+        }
+
+        // Account the downloaded body against the domain budget.
+        if let Some(extra) = &res.extra {
+            if let Err(err) = self
+                .budget_controller
+                .consume(&domain, extra.body.len() as u64)
+                .await
+            {
+                error!("Error updating byte budget for {}: {}", domain, err);
+            }
+
+            // Keep the raw response around for the debug page viewer if enabled.
+            if let Some(page_controller) = &self.page_controller {
+                let page = StoredPage {
+                    url: req.target.clone(),
+                    status_code: res.status_code,
+                    headers: res.headers.clone(),
+                    body: extra.body.clone(),
+                    fetched_at: unix_timestamp(),
+                };
+                if let Err(err) = page_controller.store(page).await {
+                    error!("Error storing page {}: {}", req.target, err);
                 }
+            }
+        }
 
+        // Enroll discovered links into the queue, up to the depth of the job if it has one.
+        let max_depth = self
+            .jobs
+            .as_ref()
+            .and_then(|jobs| jobs.max_depth(req.job.as_deref()))
+            .unwrap_or(self.max_depth);
+        if req.depth < max_depth {
+            if let Some(extra) = &res.extra {
+                debug!("Found {} links", extra.links.len());
                 // now, we need to process the links found during the crawl
-                for link in extra.links.iter() {
-                    self.push(HttpRequest::new(link.as_ref(), req.depth + 1));
+                self.enqueue_links(&extra.links, &req).await;
+                if self.follow_spa_routes && !extra.routes.is_empty() {
+                    debug!("Found {} client-side routes", extra.routes.len());
+                    let routes: Vec<Link> = extra.routes.iter().cloned().map(Link::from).collect();
+                    self.enqueue_links(&routes, &req).await;
                 }
             }
         } else {
@@ -117,45 +1234,735 @@ impl Crawler {
             );
         }
 
-        // store the page data in the RabbitMQ queue.
+        if !publish {
+            return Ok(res);
+        }
+
+        // publish the page data.
         let page_data = PageData {
+            schema_version: SchemaVersion::CURRENT,
             url: req.target.clone(),
             title: res.title.clone(),
             status_code: res.status_code,
             headers: res.headers.clone(),
             meta: res.meta.clone(),
-            links: res.extra.as_ref().unwrap().links.clone(),
-            body: res.extra.as_ref().unwrap().body.clone(),
+            usage: res.usage.clone(),
+            robots: res.robots,
+            canonical_url: res.canonical_url.clone(),
+            fingerprint: res.fingerprint.clone(),
+            language: res.language.clone(),
+            main_content: res.main_content.clone(),
+            structured_data: res.structured_data.clone(),
+            custom_fields: res.custom_fields.clone(),
+            links: res
+                .extra
+                .as_ref()
+                .map(|e| e.links.clone())
+                .unwrap_or_default(),
+            body: res
+                .extra
+                .as_ref()
+                .map(|e| e.body.clone())
+                .unwrap_or_default(),
+            crawl: CrawlMetadata {
+                crawled_at: res.fetched_at,
+                depth: req.depth,
+                fetch_duration_ms: res.fetch_duration_ms,
+                redirect_chain: res.redirect_chain.clone(),
+                final_url: res.final_url.clone(),
+                origin_seed: Some(req.origin_seed().to_string()),
+                path_from_seed: req.path.clone(),
+                encoding: res.extra.as_ref().and_then(|e| e.encoding.clone()),
+                content_type: res.header("content-type").map(validators::media_type),
+            },
+            provenance: self.provenance.clone(),
+            skipped: res.skipped.clone(),
         };
 
-        // enqueue the page data to RabbitMQ for further processing
-        self.rabbit
-            .enqueue(page_data)
+        // publish the page data to the sinks (message bus for further processing, local files).
+        // The crawl, job and agent are sent as headers so that consumers can route or trace a
+        // message without decoding it, along with the trace context of the request (with the
+        // `otel` feature). Each publish gets a unique message id, so that consumers can drop the
+        // duplicates of at-least-once delivery.
+        if let Some(sink) = &self.sink {
+            let job = req.job.as_deref().unwrap_or(&self.provenance.job);
+            let mut options = EnqueueOptions {
+                correlation_id: Some(job.to_string()),
+                message_id: Some(random_uuid()),
+                priority: self
+                    .max_priority
+                    .map(|max| message_priority(max, req.relevance, req.depth)),
+                routing_key: self
+                    .routing
+                    .as_ref()
+                    .map(|routing| routing.routing_key(&page_data)),
+                ..EnqueueOptions::default()
+            }
+            .header("x-crawl-id", self.provenance.job.as_str())
+            .header("x-crawl-job", job)
+            .header("x-crawler-agent", self.provenance.agent.as_str())
+            .header("x-schema-version", page_data.schema_version.0.to_string())
+            // replaced with the encoding by drivers that compress the payload
+            .header("x-compression", "none");
+            telemetry::inject_context(&mut options.headers);
+            sink.send(&page_data, &options).await?;
+        }
+        self.progress.published.fetch_add(1, Ordering::Relaxed);
+        if let Some(published_pages) = &self.published_pages {
+            published_pages.send(PageSummary {
+                url: page_data.url.clone(),
+                title: page_data.title.clone(),
+                status_code: page_data.status_code,
+                agent: self.provenance.agent.clone(),
+                job: req.job.clone(),
+                skipped: page_data.skipped.as_ref().map(|s| s.to_string()),
+                published_at: unix_timestamp(),
+            });
+        }
+        for pages in &self.pages {
+            // the receiver may be gone, the page was published anyway
+            let _ = pages.send(page_data.clone()).await;
+        }
+
+        let content_hash = res.extra.as_ref().map(|e| content_hash(&e.body));
+        if let Some(previous) = self
+            .record_crawl(&req, &res, content_hash.clone())
             .await
-            .map_err(|e| format!("RabbitMQ enqueue error: {e}"))?;
+            .filter(|previous| previous.content_hash.is_some())
+        {
+            let changed = previous.content_hash != content_hash;
+            info!(
+                event = "recrawled",
+                url = %req.target,
+                changed,
+                "Page crawled again"
+            );
+        }
 
         // Return the response (useful for logging)
         Ok(res)
     }
 
-    // Crawler main loop
-    #[instrument(skip(self))]
+    // Remember when the page was crawled, what it contained and its validators, so incremental
+    // crawls can skip it until it changes, and file it for a recrawl once its TTL expires.
+    // Returns the previous record of the page, if any.
+    async fn record_crawl(
+        &self,
+        req: &HttpRequest,
+        res: &HttpResponse,
+        content_hash: Option<String>,
+    ) -> Option<CrawlRecord> {
+        let recrawl_at = self.recrawl.as_ref().and_then(|(policy, _)| {
+            let ttl = Url::parse(&req.target)
+                .ok()
+                .and_then(|url| policy.ttl(&url))?;
+            Some(res.fetched_at + ttl.as_secs())
+        });
+        // 304 responses may omit the validators, which then stay valid.
+        let validators = req.validators.clone().unwrap_or_default();
+        let record = CrawlRecord {
+            url: req.target.clone(),
+            status_code: res.status_code,
+            crawled_at: res.fetched_at,
+            content_hash,
+            recrawl_at,
+            etag: res.header("etag").map(str::to_string).or(validators.etag),
+            last_modified: res
+                .header("last-modified")
+                .map(str::to_string)
+                .or(validators.last_modified),
+        };
+        let previous = match self.crawl_record_controller.record(record).await {
+            Ok(previous) => previous,
+            Err(err) => {
+                error!("Error recording crawl of {}: {}", req.target, err);
+                None
+            }
+        };
+        if let (Some((_, key)), Some(due_at)) = (&self.recrawl, recrawl_at) {
+            if let Err(err) = recrawl::file(&self.checkpoint_controller, key, req, due_at).await {
+                error!("Error scheduling recrawl of {}: {}", req.target, err);
+            }
+        }
+        previous
+    }
+
+    // Enqueue the in-scope links discovered on a page. Links to external domains are admitted up
+    // to the per-page and per-job quotas; the others are recorded in the overflow list so they can
+    // be crawled later. Links are one level deeper than `parent` and share its seed.
+    async fn enqueue_links(&mut self, links: &[Link], parent: &HttpRequest) {
+        let depth = parent.depth + 1;
+        let job = parent.job.as_deref();
+        let origin_seed = parent.origin_seed();
+        let path = parent.path_to_links();
+        let memory_pressure = self.memory_pressure.get();
+        if memory_pressure == MemoryPressure::Critical {
+            debug!(
+                "Redis memory pressure is critical, not admitting {} links",
+                links.len()
+            );
+            return;
+        }
+
+        // External domains of this page, and whether they were admitted.
+        let mut external: HashMap<String, bool> = HashMap::new();
+        let mut overflow = Vec::new();
+        let mut admitted_links = Vec::new();
+
+        for link in links {
+            if link.is_nofollow && self.scope_policy.skip_nofollow {
+                debug!("Skipping nofollow link: {}", link);
+                continue;
+            }
+            let url = match Url::parse(&link.href) {
+                Ok(url) => url,
+                Err(err) => {
+                    debug!("Invalid link {}: {}", link, err);
+                    continue;
+                }
+            };
+            if let Some(Err(rule)) = self.url_filter.as_ref().map(|filter| filter.check(&url)) {
+                debug!("Link dropped ({}): {}", rule, link);
+                continue;
+            }
+            if !self.scope_policy.allows(&url) {
+                debug!("Link out of scope: {}", link);
+                continue;
+            }
+            if self
+                .jobs
+                .as_ref()
+                .is_some_and(|jobs| !jobs.allows(job, &url))
+            {
+                debug!("Link out of the scope of its job: {}", link);
+                continue;
+            }
+            if self.traps.as_ref().is_some_and(|traps| traps.is_trap(&url)) {
+                debug!("Link in a spider trap: {}", link);
+                continue;
+            }
+            // Links visited already are not queued again. They may still be visited before they are
+            // fetched, which the claim of `execute` catches.
+            match self.url_controller.is_visited(url.clone()).await {
+                Ok(true) => {
+                    debug!("Link already visited: {}", link);
+                    continue;
+                }
+                Ok(false) => {}
+                Err(err) => error!("Error checking if {} is visited: {}", url, err),
+            }
+
+            // Focused crawls drop the links below the relevance threshold.
+            let relevance = match &self.relevance {
+                Some(model) => {
+                    let relevance = model.score(&LinkContext {
+                        url: &url,
+                        anchor_text: Some(&link.anchor_text),
+                    });
+                    if relevance < model.threshold {
+                        debug!("Link below relevance threshold: {} ({})", link, relevance);
+                        continue;
+                    }
+                    Some(relevance)
+                }
+                None => None,
+            };
+
+            if self.scope_policy.is_external(&url) {
+                // Under memory pressure external links are dropped rather than admitted or set
+                // aside in the overflow list.
+                if memory_pressure >= MemoryPressure::Elevated {
+                    debug!("Redis memory pressure is elevated, dropping {}", link);
+                    continue;
+                }
+                let domain = url.host_str().unwrap_or_default().to_lowercase();
+                let admitted = match external.get(&domain) {
+                    Some(admitted) => *admitted,
+                    None => {
+                        let admitted = self.admit_external(&domain, &external).await;
+                        external.insert(domain, admitted);
+                        admitted
+                    }
+                };
+                if !admitted {
+                    overflow.push(CheckpointEntry {
+                        url: link.href.clone(),
+                        depth,
+                        attempts: 0,
+                        refresh: false,
+                        origin_seed: Some(origin_seed.to_string()),
+                        path: path.clone(),
+                        enqueued_at: Some(unix_timestamp()),
+                    });
+                    continue;
+                }
+            }
+
+            admitted_links.push((url.to_string(), relevance));
+        }
+
+        // With a shared frontier, links go to the other processes as well; scores and relevance
+        // are only used to order in-process queues.
+        if let Some((shared_frontier, _)) = &self.shared_frontier {
+            let entries: Vec<FrontierEntry> = admitted_links
+                .into_iter()
+                .map(|(link, _)| FrontierEntry {
+                    url: link,
+                    depth,
+                    attempts: 0,
+                    refresh: false,
+                    job: job.map(str::to_string),
+                    origin_seed: Some(origin_seed.to_string()),
+                    path: path.clone(),
+                    pushed_at: unix_timestamp(),
+                })
+                .collect();
+            if let Err(err) = shared_frontier.push(entries).await {
+                error!("Error pushing links to the shared frontier: {}", err);
+            }
+            admitted_links = Vec::new();
+        }
+        // Ask the external scorer (if any) for the priority of the new links.
+        let scores = match &self.scorer {
+            Some(scorer) if !admitted_links.is_empty() => {
+                let urls: Vec<String> = admitted_links.iter().map(|(l, _)| l.clone()).collect();
+                scorer.score(&urls).await
+            }
+            _ => HashMap::new(),
+        };
+        for (link, relevance) in admitted_links {
+            let mut req = HttpRequest::new(&link, depth);
+            req.job = job.map(str::to_string);
+            req.score = scores.get(&link).copied();
+            req.relevance = relevance;
+            req.origin_seed = Some(origin_seed.to_string());
+            req.path = path.clone();
+            self.push_or_route(req);
+        }
+
+        if !overflow.is_empty() {
+            info!(
+                "External domain quota reached, {} links set aside in '{}'",
+                overflow.len(),
+                self.overflow_key
+            );
+            if let Err(err) = self
+                .checkpoint_controller
+                .save(&self.overflow_key, overflow)
+                .await
+            {
+                error!("Error recording overflow links: {}", err);
+            }
+        }
+    }
+
+    // Whether a new external domain may enter the frontier, given the domains already seen on the
+    // current page.
+    async fn admit_external(&self, domain: &str, seen: &HashMap<String, bool>) -> bool {
+        let admitted_on_page = seen.values().filter(|admitted| **admitted).count();
+        if self
+            .scope_policy
+            .max_external_domains_per_page
+            .is_some_and(|max| admitted_on_page >= max)
+        {
+            return false;
+        }
+        match self.external_domain_controller.admit(domain).await {
+            Ok(admitted) => admitted,
+            Err(err) => {
+                error!(
+                    "Error checking external domain quota for {}: {}",
+                    domain, err
+                );
+                true
+            }
+        }
+    }
+
+    // Mark `url` as visited so it is not crawled again, logging failures.
+    async fn mark_visited(&self, url: &str) {
+        match Url::parse(url) {
+            Ok(url) => {
+                if let Err(err) = self.url_controller.mark_visited(url).await {
+                    error!("Error marking URL as visited: {}", err);
+                }
+            }
+            Err(err) => error!("Invalid URL {}: {}", url, err),
+        }
+    }
+
+    // Re-enqueue a failed request with backoff if the retry policy allows it, otherwise record it
+    // in the dead-letter list. `retry_after` overrides the backoff with a server-provided delay.
+    // Returns the error message to report.
+    async fn handle_failure(
+        &mut self,
+        mut req: HttpRequest,
+        err: RequestError,
+        retry_after: Option<Duration>,
+    ) -> String {
+        let message = format!("Request error: {err}");
+        req.attempts += 1;
+
+        if self.retry_policy.should_retry(req.attempts, &err) {
+            let delay = match retry_after {
+                Some(delay) => delay.min(self.retry_policy.max_retry_after),
+                None => self.retry_policy.backoff(req.attempts),
+            };
+            warn!(
+                "Request to {} failed (attempt {}/{}), retrying in {:?}: {}",
+                req.target, req.attempts, self.retry_policy.max_attempts, delay, err
+            );
+            req.retry_at = Some(Instant::now() + delay);
+            self.push(req);
+            return message;
+        }
+
+        // Malformed URLs and URLs of private addresses are not worth inspecting later, everything
+        // else is a permanent failure.
+        if !matches!(
+            err,
+            RequestError::InvalidUrl(_) | RequestError::AddressRejected(_)
+        ) {
+            let entry = DeadLetterEntry {
+                url: req.target.clone(),
+                depth: req.depth,
+                attempts: req.attempts,
+                error: err.to_string(),
+                failed_at: unix_timestamp(),
+                worker: self.provenance.worker.clone(),
+            };
+            if let Err(e) = self
+                .dead_letter_controller
+                .record(&self.dead_letter_key, entry)
+                .await
+            {
+                error!("Failed to record {} in dead-letter list: {}", req.target, e);
+            }
+        }
+
+        message
+    }
+
+    // Record a blocking response for the domain and requeue the request so that the fallback
+    // strategy is applied on the next attempt.
+    async fn handle_block(&mut self, mut req: HttpRequest, domain: &str, reason: String) -> String {
+        let message = format!("Domain {domain} is blocking the crawler: {reason}");
+        match self
+            .block_controller
+            .record(
+                domain,
+                reason.clone(),
+                unix_timestamp(),
+                &self.provenance.worker,
+            )
+            .await
+        {
+            Ok(state) => warn!(
+                event = "blocked_domain",
+                domain,
+                reason = %reason,
+                strategy = ?state.strategy,
+                count = state.count,
+                worker = %self.provenance.worker,
+                "Blocked domain detected"
+            ),
+            Err(err) => error!("Error recording block state for {}: {}", domain, err),
+        }
+
+        // Blocked attempts count towards the retry budget; requests that keep being blocked are
+        // parked rather than dropped.
+        req.attempts += 1;
+        if self.block_policy.strategy == BlockStrategy::Park
+            || req.attempts >= self.retry_policy.max_attempts
+        {
+            self.park(domain, req).await;
+        } else if self.block_policy.strategy == BlockStrategy::Cooldown {
+            self.cool_down(req, self.block_policy.cooldown);
+        } else {
+            self.push(req);
+        }
+        message
+    }
+
+    // Set a request aside in Redis (`parked:<domain>`) so that it can be crawled later.
+    async fn park(&self, domain: &str, req: HttpRequest) {
+        let entry = CheckpointEntry {
+            url: req.target,
+            depth: req.depth,
+            attempts: req.attempts,
+            refresh: req.refresh,
+            origin_seed: req.origin_seed,
+            path: req.path,
+            enqueued_at: Some(unix_timestamp()),
+        };
+        if let Err(err) = self
+            .checkpoint_controller
+            .save(&format!("parked:{domain}"), vec![entry])
+            .await
+        {
+            error!("Error parking request for {}: {}", domain, err);
+        }
+    }
+
+    // Crawler main loop. Up to `parallelism` requests are fetched at once (see `FetchLimits`);
+    // the agent prepares and completes them one at a time, so the fetches in flight progress
+    // while it waits for the next response.
+    #[instrument(skip(self), fields(agent = %self.name))]
     pub async fn start(&mut self) {
         info!("Starting crawler agent {}", self.name);
+        let mut in_flight = FuturesUnordered::new();
         // Continue processing while there are requests in the queue.
-        while !self.queue.is_empty() {
-            match self.execute().await {
-                Ok(response) => {
-                    info!(
-                        "Processed response with status code: {}",
-                        response.status_code
-                    );
+        loop {
+            self.ack_finished().await;
+            self.pull_injected();
+            self.pull_routed();
+            self.release_resumed();
+            self.release_cooled_down();
+            self.claim_shared().await;
+            if in_flight.is_empty() {
+                if self.queue.is_empty() && self.held.is_empty() && self.cooling_down.is_empty() {
+                    // Continuous crawls and crawls serving the APIs wait for recrawls, jobs and
+                    // injected URLs until shut down, shared frontier crawls until no process has
+                    // requests left, and agents until the others cannot route them links anymore.
+                    let peers_busy = self.report_idle(true);
+                    if self.recrawl.is_none()
+                        && !self.wait_for_jobs
+                        && !self.shared_frontier_busy().await
+                        && !peers_busy
+                    {
+                        if self.close_inbox() {
+                            continue;
+                        }
+                        break;
+                    }
+                    self.idle().await;
+                    if *self.shutdown.borrow() {
+                        // Links routed meanwhile are saved with the queue.
+                        if self.close_inbox() {
+                            continue;
+                        }
+                        break;
+                    }
+                    continue;
                 }
-                Err(err) => {
-                    error!("Error executing request: {}", err);
+
+                self.report_idle(false);
+
+                // Stop picking new requests once a shutdown was requested. The requests being
+                // fetched have already completed at this point, so only the pending queue is left.
+                if *self.shutdown.borrow() {
+                    self.shutdown().await;
+                    return;
+                }
+
+                // Wait for the consumers to catch up before fetching more pages.
+                if self.backlog_paused() {
+                    self.wait_for_backlog().await;
+                    continue;
+                }
+            }
+
+            // Fill the free slots, unless a shutdown was requested or the consumers fall behind:
+            // the fetches in flight complete either way.
+            while in_flight.len() < self.limits.parallelism()
+                && !*self.shutdown.borrow()
+                && !self.backlog_paused()
+            {
+                let Some(req) = self.next_request() else {
+                    break;
+                };
+                let span = self.request_span(&req);
+                let (claim, url, depth) = (req.claim, req.target.clone(), req.depth);
+                match self.prepare(req).instrument(span.clone()).await {
+                    Ok(Some(fetch)) => {
+                        let throttle = self.throttle.clone();
+                        in_flight.push(fetch.run(self.limits.clone(), throttle).instrument(span));
+                    }
+                    Ok(None) => self.finish(claim, &url, depth, Ok(None)),
+                    Err(err) => self.finish(claim, &url, depth, Err(err)),
                 }
             }
+
+            // Every pending request may belong to a paused job or a cooling-down domain: wait for
+            // it to be resumed.
+            let Some((fetch, result)) = in_flight.next().await else {
+                self.idle().await;
+                continue;
+            };
+            let span = fetch.span.clone();
+            let (claim, url, depth) = (fetch.req.claim, fetch.req.target.clone(), fetch.req.depth);
+            let result = self.complete(fetch, result).instrument(span).await;
+            self.finish(claim, &url, depth, result.map(Some));
         }
+        self.ack_finished().await;
         info!("Crawler agent finished");
     }
+
+    // Account for a request that is done, successfully or not. `None` stands for requests whose
+    // URL was already claimed.
+    fn finish(
+        &mut self,
+        claim: Option<u64>,
+        url: &str,
+        depth: u32,
+        result: Result<Option<HttpResponse>, String>,
+    ) {
+        // Requests pushed back for a retry keep their claim until they are done.
+        if let Some(claim) = claim.filter(|claim| !self.queued_claims.contains(claim)) {
+            self.finished_claims.push(claim);
+        }
+        match result {
+            Ok(Some(response)) => {
+                info!(
+                    event = "page_processed",
+                    url = %url,
+                    depth,
+                    status = response.status_code,
+                    duration_ms = response.fetch_duration_ms,
+                    "Processed response with status code: {}",
+                    response.status_code
+                );
+                self.progress.processed.fetch_add(1, Ordering::Relaxed);
+            }
+            Ok(None) => {}
+            Err(err) => {
+                error!(url = %url, depth, "Error executing request: {}", err);
+                self.progress.failed.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.progress
+            .queued
+            .store(self.queue.len() as u64, Ordering::Relaxed);
+    }
+
+    // Wait for new requests (recrawls, injected URLs or resumed jobs), or until a shutdown is
+    // requested.
+    async fn idle(&self) {
+        let mut shutdown = self.shutdown.clone();
+        tokio::select! {
+            _ = shutdown.wait_for(|stop| *stop) => {}
+            _ = tokio::time::sleep(IDLE_POLL_INTERVAL) => {}
+        }
+    }
+
+    fn backlog_paused(&self) -> bool {
+        self.backpressure
+            .as_ref()
+            .is_some_and(|paused| *paused.borrow())
+    }
+
+    // Wait until the backpressure is released, or until a shutdown is requested.
+    async fn wait_for_backlog(&self) {
+        let Some(mut paused) = self.backpressure.clone() else {
+            return;
+        };
+        let mut shutdown = self.shutdown.clone();
+        tokio::select! {
+            _ = shutdown.wait_for(|stop| *stop) => {}
+            _ = paused.wait_for(|paused| !*paused) => {}
+        }
+    }
+
+    // Persist the remaining queue (and the requests of paused jobs and cooling-down domains) so
+    // that the next run can resume from this point.
+    #[instrument(skip(self), fields(name = %self.name))]
+    pub async fn shutdown(&mut self) {
+        // Links routed to this agent are saved with its queue.
+        self.close_inbox();
+        let held = std::mem::take(&mut self.held);
+        let cooling = std::mem::take(&mut self.cooling_down)
+            .into_iter()
+            .map(|(_, req)| req);
+        let requests: Vec<HttpRequest> = self
+            .queue
+            .drain()
+            .into_iter()
+            .chain(held)
+            .chain(cooling)
+            .collect();
+
+        // With a shared frontier, the pending requests are handed to the other processes.
+        if let Some((shared_frontier, _)) = self.shared_frontier.clone() {
+            let pending = requests.len();
+            let claims: Vec<u64> = requests.iter().filter_map(|req| req.claim).collect();
+            let entries: Vec<FrontierEntry> = requests
+                .into_iter()
+                .map(|req| FrontierEntry {
+                    url: req.target,
+                    depth: req.depth,
+                    attempts: req.attempts,
+                    refresh: req.refresh,
+                    job: req.job,
+                    origin_seed: req.origin_seed,
+                    path: req.path,
+                    pushed_at: unix_timestamp(),
+                })
+                .collect();
+            match shared_frontier.push(entries).await {
+                Ok(_) => {
+                    info!(
+                        "Returned {} pending requests to the shared frontier",
+                        pending
+                    );
+                    self.finished_claims.extend(claims);
+                    self.ack_finished().await;
+                }
+                // Claims are left to expire, so the requests are handed out again anyway.
+                Err(err) => error!(
+                    "Failed to return {} pending requests to the shared frontier: {}",
+                    pending, err
+                ),
+            }
+            return;
+        }
+
+        let entries: Vec<CheckpointEntry> = requests
+            .into_iter()
+            .map(|req| CheckpointEntry {
+                url: req.target,
+                depth: req.depth,
+                attempts: req.attempts,
+                refresh: req.refresh,
+                origin_seed: req.origin_seed,
+                path: req.path,
+                enqueued_at: Some(unix_timestamp()),
+            })
+            .collect();
+        let pending = entries.len();
+
+        match self
+            .checkpoint_controller
+            .save(&self.checkpoint_key, entries)
+            .await
+        {
+            Ok(_) => info!("Checkpointed {} pending requests", pending),
+            Err(err) => error!("Failed to checkpoint {} pending requests: {}", pending, err),
+        }
+    }
+}
+
+// Priority of the message of a page in a queue with priorities 0 to `max`: the relevance of the
+// link it was discovered from when links are scored (focused crawls), otherwise its depth, seeds
+// first. Either way, deep low-value pages come last.
+fn message_priority(max: u8, relevance: Option<f64>, depth: u32) -> u8 {
+    match relevance {
+        Some(relevance) => (relevance.clamp(0.0, 1.0) * f64::from(max)).round() as u8,
+        None => max.saturating_sub(depth.min(u32::from(u8::MAX)) as u8),
+    }
+}
+
+// SHA-256 (hex) of a page body, recorded to tell whether a recrawled page changed.
+fn content_hash(body: &str) -> String {
+    Sha256::digest(body.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+// Current Unix timestamp in seconds.
+pub fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
 }
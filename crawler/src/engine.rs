@@ -0,0 +1,994 @@
+use std::{sync::Arc, time::Duration};
+
+use models::{PageData, Provenance};
+use tokio::sync::{mpsc, oneshot, watch, Mutex};
+use tracing::{error, info, warn};
+use url::Url;
+
+use crate::{
+    admin, backpressure, blocking, bloom, clients, compaction, concurrency,
+    config::{CrawlerConfig, DedupMode},
+    controllers::{
+        self, checkpointcontroller::CheckpointControllerTrait,
+        sharedfrontiercontroller::SharedFrontierControllerTrait, urlcontroller::UrlControllerTrait,
+    },
+    crawler::{self, Crawler},
+    estimator, events, frontier, heartbeat, incremental, jobs, memory, parsers, partitioner,
+    progress::{self, ProgressMode},
+    provenance, recrawl, repositories, retry, routing, scope,
+    sinks::{Sink, SinkMulticast},
+    throttle, traps, validators, worker,
+};
+
+// Settings of a crawl run that do not come from the configuration.
+pub struct RunOptions {
+    // Progress output on stderr.
+    pub progress: ProgressMode,
+    // Seeds crawled instead of those of `seeds_file`/`seeds_dir`.
+    pub seeds: Option<Vec<Url>>,
+    // Receives a copy of every published page.
+    pub pages: Option<mpsc::Sender<PageData>>,
+    // Publish the pages to the message bus, when it is one of the configured sinks. Without it,
+    // no message bus is needed.
+    pub publish: bool,
+    // Sinks the pages are sent to, besides those of the configuration.
+    pub sinks: Vec<Box<dyn Sink>>,
+    // Set to true to stop the crawl: agents finish their current request and checkpoint their
+    // queue.
+    pub shutdown: watch::Receiver<bool>,
+    // Notified once the crawl is set up, right before the agents start: `run` failing before
+    // that is a setup error (invalid configuration, unreachable store or sinks).
+    pub ready: Option<oneshot::Sender<()>>,
+}
+
+// Settings of `config` (User-Agent, address policy, DNS cache) shared by the HTTP clients of a
+// crawl.
+pub fn configure(config: &CrawlerConfig) -> Result<clients::http::ClientSettings, String> {
+    clients::http::ClientSettings::from_config(config)
+}
+
+// Load seeds from the seeds file or directory (highest priority first), falling back to the
+// default seeds
+pub async fn load_seeds(config: &CrawlerConfig) -> Vec<url::Url> {
+    let loaded = match &config.seeds_file {
+        Some(file) => repositories::load_seeds_from_file(file).await,
+        None => repositories::load_seeds_from_dir(&config.seeds_dir).await,
+    };
+    let seeds = match loaded {
+        Ok(seeds) if !seeds.is_empty() => seeds,
+        Ok(_) => {
+            error!("No seeds found. Fallback to default (generic) seeds.");
+            repositories::load_default_seeds()
+        }
+        Err(e) => {
+            error!(
+                "Failed to load seeds: {}. Fallback to default (generic) seeds.",
+                e
+            );
+            repositories::load_default_seeds()
+        }
+    };
+    seeds.into_iter().map(|seed| seed.url).collect()
+}
+
+// Run the crawler agents until the queue is exhausted or a shutdown is requested. Fails when the
+// crawl cannot be set up, or when an agent panicked.
+pub async fn run(config: CrawlerConfig, options: RunOptions) -> Result<(), String> {
+    let client_settings = configure(&config)?;
+
+    // connect to Redis (or the store selected by CACHE_BACKEND)
+    let redis = drivers::connect_cache_driver_with(&config.redis)
+        .map_err(|e| format!("Failed to build cache client: {e}"))?;
+    if redis.is_memory() {
+        warn!("Crawl state is kept in memory: it is lost on exit and not shared between processes");
+    }
+
+    // Sinks the pages are published to: the message bus (RabbitMQ or Kafka, unless pages are
+    // only streamed) and/or local JSONL and WARC files.
+    let mut sinks = SinkMulticast::from_config(&config.output, &config.bus, options.publish)
+        .await
+        .map_err(|e| format!("Failed to open the sinks: {e}"))?;
+    for sink in options.sinks {
+        sinks.push(sink);
+    }
+    let sink: Option<Arc<dyn Sink>> = if sinks.is_empty() {
+        if options.pages.is_none() {
+            warn!("Pages are neither published nor streamed");
+        }
+        None
+    } else {
+        info!("Publishing pages to: {}", sinks.names().join(", "));
+        Some(Arc::new(sinks))
+    };
+
+    // Pause the crawl while the consumers fall behind the message bus queue.
+    let backpressure_config = &config.backpressure;
+    let rabbit = &config.bus.rabbit;
+    let unmeasured_exchange = options.publish
+        && matches!(
+            config.bus.kind.trim().to_lowercase().as_str(),
+            "rabbitmq" | "rabbit"
+        )
+        && rabbit.exchange.is_some()
+        && rabbit.backlog_queues.is_empty();
+    if backpressure_config.high_water > 0 && unmeasured_exchange {
+        warn!(
+            "Backpressure disabled: pages are published to exchange '{}', set RABBIT_BACKLOG_QUEUES to the consumer queues to watch",
+            rabbit.exchange.as_deref().unwrap_or_default()
+        );
+    }
+    let backlog_paused = match &sink {
+        Some(sink) if backpressure_config.high_water > 0 && !unmeasured_exchange => {
+            let high_water = backpressure_config.high_water;
+            let low_water = match backpressure_config.low_water {
+                0 => high_water / 2,
+                low_water if low_water >= high_water => {
+                    warn!(
+                        "BACKPRESSURE_LOW_WATER must be under BACKPRESSURE_HIGH_WATER, using {}",
+                        high_water / 2
+                    );
+                    high_water / 2
+                }
+                low_water => low_water,
+            };
+            info!(
+                "Pausing the crawl over {} queued messages, resuming under {}",
+                high_water, low_water
+            );
+            let controller = backpressure::BackpressureController::new(
+                Arc::clone(sink),
+                Duration::from_secs(backpressure_config.interval_secs),
+                high_water,
+                low_water,
+            );
+            let paused = controller.paused();
+            tokio::task::spawn(controller.run());
+            Some(paused)
+        }
+        _ => None,
+    };
+
+    // Identity of this worker, attached to published pages and shared Redis records.
+    let worker = worker::identity(&config.worker);
+    info!("Worker identity: {}", worker);
+    info!("User-Agent: {}", client_settings.user_agent);
+    // Client of the requests not sent through a proxy, shared by every agent.
+    let http_client = Arc::new(
+        clients::http::HttpClient::new_with_config(clients::http::HttpClientConfig {
+            settings: client_settings.clone(),
+            proxy: None,
+            timeout: None,
+        })
+        .map_err(|e| format!("Failed to create the HTTP client: {e}"))?,
+    );
+
+    let crawler_type = config.crawler_type.clone();
+
+    // Job and configuration fingerprint recorded in the provenance of every published page.
+    let job = provenance::job_id(&config, crawler::unix_timestamp());
+    let config_hash = provenance::config_hash(&config);
+    info!(
+        "Job {} (crawler v{}, config {})",
+        job,
+        provenance::CRAWLER_VERSION,
+        config_hash
+    );
+
+    let max_depth = config.max_depth;
+    // Pages are published with a priority when the RabbitMQ queue is a priority queue.
+    let max_priority = config.bus.rabbit.max_priority;
+    let frontier_config = &config.frontier;
+    let spa_routes = config.fetch.spa_routes;
+    let conditional_requests = config.fetch.conditional_requests;
+    let main_content = config.fetch.main_content;
+    let extract_pdf = config.fetch.extract_pdf && cfg!(feature = "pdf");
+    if config.fetch.extract_pdf && !extract_pdf {
+        warn!("Ignoring EXTRACT_PDF: the crawler was built without the `pdf` feature");
+    }
+    let max_pdf_bytes = (config.fetch.max_pdf_bytes > 0).then_some(config.fetch.max_pdf_bytes);
+
+    // Relevance of the discovered links (focused crawls), shared so the TF-IDF statistics are
+    // learned from every agent
+    let relevance_config = &frontier_config.relevance;
+    let relevance = Arc::new(frontier::relevance::RelevanceModel::from_kinds(
+        &relevance_config.scorers,
+        &frontier_config.keywords,
+        scope::parse_patterns(&relevance_config.url_patterns).map_err(|e| {
+            format!("Relevance URL patterns must be valid regular expressions: {e}")
+        })?,
+        relevance_config.threshold,
+    ));
+
+    // External URL scorer ordering the best-first frontier, if configured
+    let scorer = match &frontier_config.scorer.url {
+        Some(url) => {
+            if frontier_config.strategy != frontier::strategy::StrategyKind::BestFirst {
+                warn!("The URL scorer is only used by the best_first frontier strategy");
+            }
+            Some(
+                frontier::scorer::ExternalScorer::new(
+                    url.clone(),
+                    frontier_config.scorer.batch_size,
+                    Duration::from_millis(frontier_config.scorer.timeout_ms),
+                    frontier_config.scorer.cache_size,
+                )
+                .map_err(|e| format!("Failed to create the URL scorer: {e}"))?,
+            )
+        }
+        None => None,
+    };
+    let respect_robots_txt = config.politeness.respect_robots_txt;
+    let robots_cache_ttl = Duration::from_secs(config.politeness.robots_cache_ttl_secs);
+
+    // Per-domain byte budget (0 disables it)
+    let max_bytes_per_domain =
+        (config.fetch.max_bytes_per_domain > 0).then_some(config.fetch.max_bytes_per_domain);
+
+    let retry_config = &config.politeness.retry;
+    let retry_policy = retry::RetryPolicy::new(
+        retry_config.max_attempts,
+        Duration::from_millis(retry_config.base_delay_ms),
+        Duration::from_millis(retry_config.max_delay_ms),
+        Duration::from_secs(retry_config.retry_after_max_secs),
+    );
+
+    // Store raw responses in Redis for the debug page viewer (disabled by default)
+    let store_pages = config.fetch.store_pages;
+
+    // Fallback strategy for domains detected as blocking the crawler
+    let block_config = &config.politeness.block;
+    let block_policy = blocking::BlockPolicy {
+        strategy: block_config.strategy,
+        base_delay: Duration::from_millis(block_config.base_delay_ms),
+        max_delay: Duration::from_millis(block_config.max_delay_ms),
+        proxies: block_config.fallback_proxies.clone(),
+        cooldown: Duration::from_secs(block_config.cooldown_secs),
+        storm_threshold: block_config.storm_threshold.max(1),
+        storm_window: Duration::from_secs(block_config.storm_window_secs),
+    };
+    // Clients of the fallback proxies of the `switch_proxy` strategy, shared by every agent.
+    let fallback_clients = block_policy
+        .proxies
+        .iter()
+        .map(|proxy| {
+            clients::http::HttpClient::new_with_config(clients::http::HttpClientConfig {
+                settings: client_settings.clone(),
+                proxy: Some(proxy.clone()),
+                timeout: None,
+            })
+            .map(Arc::new)
+            .map_err(|e| {
+                format!(
+                    "Invalid fallback proxy '{}': {e}",
+                    clients::proxy::redact(proxy)
+                )
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    // Media types whose body is downloaded (empty allows every content type)
+    let allowed_content_types: Vec<String> = config
+        .fetch
+        .allowed_content_types
+        .iter()
+        .map(|t| t.trim().to_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect();
+
+    // Seeds to start the crawl from
+    let mut seeds = match options.seeds {
+        Some(seeds) => seeds,
+        None => load_seeds(&config).await,
+    };
+
+    // The crawl scope: which discovered links are followed, relative to the seeds
+    let scope_config = &config.scope;
+    let mut scope_policy = scope::ScopePolicy::new(
+        scope_config.mode,
+        scope::parse_patterns(&scope_config.include)
+            .map_err(|e| format!("Scope include rules must be valid regular expressions: {e}"))?,
+        scope::parse_patterns(&scope_config.exclude)
+            .map_err(|e| format!("Scope exclude rules must be valid regular expressions: {e}"))?,
+        &seeds,
+    );
+
+    // Quotas on the external domains entering the frontier (0 = unlimited); links over them are
+    // set aside in the overflow list.
+    scope_policy.max_external_domains_per_page = (scope_config.max_external_domains_per_page > 0)
+        .then_some(scope_config.max_external_domains_per_page);
+    scope_policy.skip_nofollow = scope_config.skip_nofollow;
+    let max_external_domains_per_job = scope_config.max_external_domains_per_job;
+
+    // Redirects followed per request, only within the scope if required
+    let max_redirects = config.fetch.max_redirects;
+    let redirects_in_scope = scope_config.redirects;
+
+    // Site-specific extraction profiles
+    let selectors = parsers::selectors::SelectorParser::from_config(&config.extraction)
+        .map_err(|e| format!("Extraction profiles must be valid: {e}"))?
+        .map(Arc::new);
+
+    // Languages the crawl or its output is restricted to
+    let language_policy =
+        parsers::language::LanguagePolicy::new(&config.language.languages, config.language.filter)
+            .map_err(|e| format!("Languages must be ISO 639-3 codes: {e}"))?;
+
+    // Routing keys of the pages, when they are published through a RabbitMQ topic exchange
+    let routing = match &config.bus.rabbit.exchange {
+        Some(exchange) => {
+            info!(
+                "Publishing pages to the exchange {} by routing key",
+                exchange
+            );
+            Some(Arc::new(
+                routing::RoutingPolicy::from_config(&config.routing)
+                    .map_err(|e| format!("Routing categories must be valid: {e}"))?,
+            ))
+        }
+        None => None,
+    };
+
+    // Proxies the requests of every agent are spread over
+    let proxy_pool = clients::proxy::ProxyPool::from_config(&config.proxy, &client_settings)
+        .map_err(|e| format!("Proxies must be valid URLs: {e}"))?
+        .map(Arc::new);
+
+    // Rules dropping the discovered links not worth following, shared by every agent
+    let url_filter = Arc::new(validators::UrlFilter::from_config(&config.url_filter));
+
+    // Spider traps detected from the links of every agent
+    let trap_detector = traps::TrapDetector::from_config(&config.traps).map(Arc::new);
+
+    // Pages rendered in the headless browser, launched once for every agent
+    let render_rules = clients::rendering::RenderRules::from_config(&config.render)
+        .map_err(|e| format!("Render rules must be valid: {e}"))?;
+    let rendering: Option<(
+        Arc<clients::rendering::RenderRules>,
+        Arc<dyn clients::backend::FetchBackend>,
+    )> = match render_rules {
+        #[cfg(feature = "render")]
+        Some(rules) => {
+            let renderer = clients::rendering::RenderingClient::launch(
+                &config.render,
+                &client_settings.user_agent,
+            )
+            .await
+            .map_err(|e| format!("Failed to launch the headless browser: {e}"))?;
+            info!("Rendering the pages matching the render rules in a headless browser");
+            Some((Arc::new(rules), Arc::new(renderer)))
+        }
+        #[cfg(not(feature = "render"))]
+        Some(_) => {
+            warn!("Ignoring the render rules: the crawler was built without the `render` feature");
+            None
+        }
+        None => None,
+    };
+
+    // Print a cost estimate before crawling if requested
+    if config.estimate_before_crawl {
+        let estimate = estimator::estimate(&seeds, max_depth, 10, Arc::clone(&http_client)).await;
+        info!("{}", estimate);
+    }
+
+    // Set the number of agents (threads) you want to run concurrently.
+    let n_agents = config.n_agents.max(1);
+    info!("Number of agents: {}", n_agents);
+
+    // URLs fetched at once by each agent, by the process and for each domain.
+    let fetch_limits = concurrency::FetchLimits::new(
+        config.fetch.parallelism,
+        config.fetch.max_concurrent_fetches,
+        config.politeness.max_fetches_per_domain,
+    );
+    info!(
+        "Fetching up to {} URLs at once per agent ({} per domain)",
+        fetch_limits.parallelism(),
+        config.politeness.max_fetches_per_domain
+    );
+
+    // Slow down the domains whose servers answer slowly or with errors, per process.
+    let adaptive_config = &config.politeness.adaptive;
+    let adaptive_throttle = adaptive_config.enabled.then(|| {
+        info!(
+            "Adapting the per-domain delays to the servers ({}ms to {}ms)",
+            adaptive_config.min_delay_ms, adaptive_config.max_delay_ms
+        );
+        Arc::new(throttle::AdaptiveThrottle::from_config(adaptive_config))
+    });
+
+    // create UrlController to mark visited URLs
+    // NOTE: we use two Arc here because both UrlController and RedisDriver may be shared
+    // independently across multiple agents (e.g. each agent currently has one UrlController, but
+    // in the future we may want to have multiple controllers based on the same driver.
+    let memory_guard_config = &config.memory_guard;
+    // The memory guard watches Redis: there is nothing to watch with the other backends.
+    let memory_guard_interval = if redis.is_redis() {
+        memory_guard_config.interval_secs
+    } else {
+        0
+    };
+    let redis = Arc::new(Mutex::new(redis));
+    let url_controller = Arc::new(match config.dedup.mode {
+        // The memory guard switches exact mode to a bloom filter when Redis runs low on memory.
+        DedupMode::Exact if memory_guard_interval > 0 => {
+            let filter =
+                bloom::BloomFilter::new(config.dedup.bloom_capacity, config.dedup.bloom_fp_rate);
+            controllers::UrlController::new_with_bloom_fallback(
+                redis.clone(),
+                redis.clone(),
+                filter,
+            )
+        }
+        DedupMode::Exact => controllers::UrlController::new(redis.clone()),
+        DedupMode::Bloom => {
+            let capacity = config.dedup.bloom_capacity;
+            let fp_rate = config.dedup.bloom_fp_rate;
+            let filter = bloom::BloomFilter::new(capacity, fp_rate);
+            info!(
+                "Using bloom filter dedup ({} bytes for {} URLs at {} false-positive rate)",
+                filter.size_bytes(),
+                capacity,
+                fp_rate
+            );
+            controllers::UrlController::new_with_bloom(redis.clone(), redis.clone(), filter)
+        }
+    });
+
+    // In bloom mode, load the filter shared through Redis and keep merging it periodically.
+    if let Err(e) = url_controller.sync().await {
+        error!("Failed to load bloom filter from Redis: {}", e);
+    }
+    if config.dedup.mode == DedupMode::Bloom || memory_guard_interval > 0 {
+        let sync_interval = config.dedup.bloom_sync_interval_secs;
+        let url_controller = Arc::clone(&url_controller);
+        tokio::task::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(sync_interval.max(1)));
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                if let Err(e) = url_controller.sync().await {
+                    error!("Failed to sync bloom filter: {}", e);
+                }
+            }
+        });
+    }
+
+    // Degrade gracefully when Redis gets close to its memory limit.
+    let memory_pressure = if memory_guard_interval > 0 {
+        let guard = memory::MemoryGuard::new(
+            redis.clone(),
+            Arc::clone(&url_controller),
+            Duration::from_secs(memory_guard_interval),
+            (memory_guard_config.max_bytes > 0).then_some(memory_guard_config.max_bytes),
+            memory_guard_config.elevated_ratio,
+            memory_guard_config.critical_ratio,
+        );
+        let level = guard.level();
+        tokio::task::spawn(guard.run());
+        level
+    } else {
+        memory::PressureLevel::default()
+    };
+
+    let checkpoint_controller = Arc::new(controllers::CheckpointController::new(redis.clone()));
+    let budget_controller = Arc::new(controllers::BudgetController::new(
+        redis.clone(),
+        max_bytes_per_domain,
+    ));
+    let dead_letter_controller = Arc::new(controllers::DeadLetterController::new(
+        redis.clone(),
+        config.politeness.retry.dead_letter_max_entries,
+    ));
+    let page_controller = Arc::new(controllers::PageController::new(redis.clone()));
+    let crawl_record_controller = Arc::new(controllers::CrawlRecordController::new(redis.clone()));
+    let worker_controller = Arc::new(controllers::WorkerController::new(redis.clone()));
+    let robots_controller = Arc::new(controllers::RobotsController::new(redis.clone()));
+    // robots.txt files are fetched with the same client configuration as pages.
+    let robots_http_client = Arc::clone(&http_client);
+    let external_domain_controller = Arc::new(controllers::ExternalDomainController::new(
+        redis.clone(),
+        job.clone(),
+        (max_external_domains_per_job > 0).then_some(max_external_domains_per_job),
+    ));
+    let block_controller = Arc::new(controllers::BlockController::new(
+        redis.clone(),
+        redis.clone(),
+        block_policy.clone(),
+    ));
+
+    // Fingerprints of the content seen so far, shared by every process of the crawl.
+    let content_controller = config.dedup.content.then(|| {
+        Arc::new(controllers::ContentController::new(
+            redis.clone(),
+            config.dedup.near_duplicate_distance,
+        ))
+    });
+
+    // Keep the frontier lists persisted in Redis bounded (one process compacts at a time).
+    let compaction_config = &config.frontier.compaction;
+    if compaction_config.interval_secs > 0 {
+        let compactor = compaction::FrontierCompactor::new(
+            Arc::clone(&checkpoint_controller),
+            Arc::clone(&url_controller),
+            Arc::clone(&block_controller),
+            controllers::LeaseController::new(redis.clone()),
+            Duration::from_secs(compaction_config.interval_secs),
+            (compaction_config.entry_ttl_secs > 0)
+                .then(|| Duration::from_secs(compaction_config.entry_ttl_secs)),
+        );
+        tokio::task::spawn(compactor.run());
+    }
+
+    // Entry point for URLs injected while the crawl runs (admin API)
+    let frontier = frontier::Frontier::new(scope_policy.clone(), Arc::clone(&url_controller));
+
+    // Live feed of the published pages (admin API)
+    let published_pages = events::PublishedPages::new();
+
+    // Jobs submitted while the crawl runs (admin and gRPC APIs)
+    let job_manager = jobs::JobManager::new(
+        job.clone(),
+        frontier.clone(),
+        scope_policy.clone(),
+        published_pages.clone(),
+    );
+
+    let dead_letter_key = format!("deadletter:{crawler_type}");
+
+    // Start the admin API if an address was configured
+    if let Some(addr) = &config.admin_addr {
+        let addr = addr
+            .parse()
+            .map_err(|e| format!("The admin address must be a valid socket address: {e}"))?;
+        let state = admin::AdminState {
+            page_controller: Arc::clone(&page_controller),
+            frontier: frontier.clone(),
+            published_pages: published_pages.clone(),
+            jobs: job_manager.clone(),
+            workers: Arc::clone(&worker_controller),
+            stale_after_secs: config.heartbeat.stale_after_secs,
+            proxies: proxy_pool.clone(),
+            url_filter: Arc::clone(&url_filter),
+            traps: trap_detector.clone(),
+            fetch_limits: fetch_limits.clone(),
+            dead_letters: Arc::clone(&dead_letter_controller),
+            dead_letter_key: dead_letter_key.clone(),
+        };
+        tokio::task::spawn(admin::serve(addr, state));
+    }
+
+    // Start the gRPC job service if an address was configured
+    if let Some(addr) = &config.grpc_addr {
+        #[cfg(feature = "grpc")]
+        {
+            let addr = addr
+                .parse()
+                .map_err(|e| format!("The gRPC address must be a valid socket address: {e}"))?;
+            tokio::task::spawn(crate::grpc::serve(addr, job_manager.clone()));
+        }
+        #[cfg(not(feature = "grpc"))]
+        warn!(
+            "Ignoring the gRPC address {}: the crawler was built without the `grpc` feature",
+            addr
+        );
+    }
+    // Jobs and URLs may be submitted through the APIs at any time: the agents wait for them until
+    // shut down instead of stopping once their queue is empty.
+    let accepts_jobs =
+        config.admin_addr.is_some() || (cfg!(feature = "grpc") && config.grpc_addr.is_some());
+
+    // In continuous mode, pages are crawled again once their freshness TTL expired (one process
+    // schedules the recrawls at a time).
+    let recrawl_key = format!("recrawl:{crawler_type}");
+    let recrawl_policy = if config.recrawl.enabled {
+        Some(Arc::new(
+            recrawl::RecrawlPolicy::from_config(&config.recrawl)
+                .map_err(|e| format!("Recrawl rules must be valid: {e}"))?,
+        ))
+    } else {
+        None
+    };
+    if recrawl_policy.is_some() {
+        let scheduler = recrawl::RecrawlScheduler::new(
+            Arc::clone(&checkpoint_controller),
+            Arc::clone(&crawl_record_controller),
+            controllers::LeaseController::new(redis.clone()),
+            frontier.clone(),
+            recrawl_key.clone(),
+            Duration::from_secs(config.recrawl.interval_secs),
+        );
+        tokio::task::spawn(scheduler.run());
+    }
+    let overflow_key = format!("overflow:{crawler_type}");
+
+    // Resume from the queue persisted by a previous graceful shutdown, if any.
+    let checkpoint_key = format!("checkpoint:{crawler_type}");
+    let mut checkpoint = match checkpoint_controller.restore(&checkpoint_key).await {
+        Ok(entries) => entries,
+        Err(e) => {
+            error!("Failed to restore checkpoint '{}': {}", checkpoint_key, e);
+            Vec::new()
+        }
+    };
+    if !checkpoint.is_empty() {
+        info!(
+            "Resuming {} pending requests from checkpoint '{}'",
+            checkpoint.len(),
+            checkpoint_key
+        );
+        seeds.clear();
+    }
+
+    // In incremental mode, only crawl the sitemap URLs that changed since their last crawl.
+    if config.incremental.enabled && checkpoint.is_empty() {
+        let max_sitemaps = config.incremental.sitemap_max_files;
+        let plan =
+            incremental::plan(&seeds, &crawl_record_controller, max_sitemaps, &http_client).await;
+        info!(
+            "Incremental crawl: {} changed URLs, {} unchanged, {} seeds without sitemap",
+            plan.changed.len(),
+            plan.unchanged,
+            plan.fallback_seeds.len()
+        );
+
+        // Changed pages are refreshed without following their links: new pages are discovered
+        // through the sitemaps.
+        checkpoint = plan
+            .changed
+            .into_iter()
+            .map(|url| repositories::CheckpointEntry {
+                url: url.to_string(),
+                depth: max_depth,
+                attempts: 0,
+                refresh: true,
+                origin_seed: None,
+                path: Vec::new(),
+                enqueued_at: None,
+            })
+            .collect();
+        seeds = plan.fallback_seeds;
+    }
+
+    // Optionally crawl the links previously set aside by the external domain quotas.
+    if config.crawl_overflow {
+        match checkpoint_controller.restore(&overflow_key).await {
+            Ok(overflow) => {
+                info!(
+                    "Crawling {} links from overflow list '{}'",
+                    overflow.len(),
+                    overflow_key
+                );
+                checkpoint.extend(overflow);
+            }
+            Err(e) => error!("Failed to restore overflow list '{}': {}", overflow_key, e),
+        }
+    }
+
+    // With a shared frontier, the processes of the deployment pull their requests from Redis:
+    // the seeds are only pushed by the first process, and expired claims are handed out again
+    // (one process reaps them at a time).
+    let shared_config = &frontier_config.shared;
+    let shared_frontier = if shared_config.enabled {
+        let shared_frontier = Arc::new(controllers::SharedFrontierController::new(
+            redis.clone(),
+            redis.clone(),
+            redis.clone(),
+            format!("shared:{crawler_type}"),
+            Duration::from_secs(shared_config.lease_secs),
+        ));
+        let now = crawler::unix_timestamp();
+        let mut entries: Vec<repositories::FrontierEntry> = checkpoint
+            .drain(..)
+            .map(|entry| repositories::FrontierEntry {
+                url: entry.url,
+                depth: entry.depth,
+                attempts: entry.attempts,
+                refresh: entry.refresh,
+                job: None,
+                origin_seed: entry.origin_seed,
+                path: entry.path,
+                pushed_at: now,
+            })
+            .collect();
+        match shared_frontier.pending().await {
+            Ok(0) => entries.extend(seeds.drain(..).map(|seed| repositories::FrontierEntry {
+                url: seed.to_string(),
+                depth: 0,
+                attempts: 0,
+                refresh: false,
+                job: None,
+                origin_seed: None,
+                path: Vec::new(),
+                pushed_at: now,
+            })),
+            Ok(pending) => {
+                info!(
+                    "Joining the shared frontier with {} pending requests, skipping the seeds",
+                    pending
+                );
+                seeds.clear();
+            }
+            Err(e) => {
+                error!("Failed to read the shared frontier: {}", e);
+                seeds.clear();
+            }
+        }
+        if let Err(e) = shared_frontier.push(entries).await {
+            error!(
+                "Failed to push the initial requests to the shared frontier: {}",
+                e
+            );
+        }
+
+        let reaper = frontier::shared::LeaseReaper::new(
+            Arc::clone(&shared_frontier),
+            controllers::LeaseController::new(redis),
+            format!("frontier-reaper:{crawler_type}"),
+            Duration::from_secs(shared_config.reap_interval_secs),
+        );
+        tokio::task::spawn(reaper.run());
+        Some(Arc::clone(&shared_frontier))
+    } else {
+        None
+    };
+
+    // Report per-agent progress on stderr (progress bars or JSON lines)
+    let progress_interval = Duration::from_millis(config.progress_interval_ms.max(1));
+    let mut progress_reporter =
+        progress::ProgressReporter::new(options.progress, progress_interval);
+    if config.status_interval_secs > 0 {
+        progress_reporter.log_status(Duration::from_secs(config.status_interval_secs));
+    }
+    // Publish the heartbeats of the agents in the worker registry
+    let mut heartbeat_reporter = (config.heartbeat.interval_secs > 0).then(|| {
+        heartbeat::HeartbeatReporter::new(
+            Arc::clone(&worker_controller),
+            worker.clone(),
+            crawler_type.clone(),
+            job.clone(),
+            Duration::from_secs(config.heartbeat.interval_secs),
+        )
+    });
+
+    // The crawl is set up: nothing fails from here on.
+    if let Some(ready) = options.ready {
+        let _ = ready.send(());
+    }
+
+    let mut handles = Vec::new();
+    // Every domain is crawled by a single agent: fresh seeds and restored requests are split by
+    // domain, and the links discovered by an agent are routed to the agent owning their domain.
+    let partitioner = partitioner::DomainPartitioner::new(n_agents);
+    let mut seed_chunks = partitioner
+        .partition(seeds, |seed| Some(seed.clone()))
+        .into_iter();
+    let mut checkpoint_chunks = partitioner
+        .partition(checkpoint, |entry| url::Url::parse(&entry.url).ok())
+        .into_iter();
+    let (link_router, receivers) = partitioner::LinkRouter::new(partitioner);
+    // NOTE: the receivers of the agents not started are dropped, so their links stay with the
+    // agents that found them.
+    let mut receivers = receivers.into_iter();
+
+    for current_id in 1..=n_agents {
+        // Split the work among agents: either fresh seeds or the restored checkpoint.
+        let seeds_chunk = seed_chunks.next().unwrap_or_default();
+        let checkpoint_chunk = checkpoint_chunks.next().unwrap_or_default();
+        let receiver = receivers.next();
+        // Continuous and shared frontier crawls, and crawls serving the APIs, start every agent,
+        // as requests may come in later.
+        if seeds_chunk.is_empty()
+            && checkpoint_chunk.is_empty()
+            && recrawl_policy.is_none()
+            && shared_frontier.is_none()
+            && !accepts_jobs
+        {
+            continue;
+        }
+
+        let log_name = format!("crawler-{crawler_type}-{current_id}");
+        let agent_url_controller = Arc::clone(&url_controller);
+        let agent_checkpoint_controller = Arc::clone(&checkpoint_controller);
+        let checkpoint_key = checkpoint_key.clone();
+        let agent_budget_controller = Arc::clone(&budget_controller);
+        let agent_dead_letter_controller = Arc::clone(&dead_letter_controller);
+        let dead_letter_key = dead_letter_key.clone();
+        let agent_external_domain_controller = Arc::clone(&external_domain_controller);
+        let overflow_key = overflow_key.clone();
+        let agent_page_controller = store_pages.then(|| Arc::clone(&page_controller));
+        let agent_block_controller = Arc::clone(&block_controller);
+        let agent_crawl_record_controller = Arc::clone(&crawl_record_controller);
+        let robots_client = clients::robots::RobotsTxtClient::new(
+            Arc::clone(&robots_http_client),
+            Arc::clone(&robots_controller),
+            robots_cache_ttl,
+        );
+        let block_policy = block_policy.clone();
+        let fallback_clients = fallback_clients.clone();
+        let scope_policy = scope_policy.clone();
+        let retry_policy = retry_policy.clone();
+        let allowed_content_types = allowed_content_types.clone();
+        let provenance = Provenance {
+            agent: log_name.clone(),
+            job: job.clone(),
+            crawler_version: provenance::CRAWLER_VERSION.to_string(),
+            config_hash: config_hash.clone(),
+            worker: worker.clone(),
+        };
+        let sink = sink.clone();
+        let shutdown = options.shutdown.clone();
+        let pages = options.pages.clone();
+        let agent_progress = progress_reporter.register(&log_name);
+        if let Some(heartbeat_reporter) = &mut heartbeat_reporter {
+            heartbeat_reporter.register(&log_name, Arc::clone(&agent_progress));
+        }
+        let frontier = frontier.clone();
+        let strategy = frontier::strategy::new_strategy(frontier_config.strategy);
+        let relevance = Arc::clone(&relevance);
+        let scorer = scorer.clone();
+        let published_pages = published_pages.clone();
+        let memory_pressure = memory_pressure.clone();
+        let backlog_paused = backlog_paused.clone();
+        let http_client = Arc::clone(&http_client);
+        let fetch_limits = fetch_limits.clone();
+        let adaptive_throttle = adaptive_throttle.clone();
+        let agent_content_controller = content_controller.clone();
+        let job_manager = job_manager.clone();
+        let recrawl_policy = recrawl_policy.clone();
+        let language_policy = language_policy.clone();
+        let selectors = selectors.clone();
+        let rendering = rendering.clone();
+        let proxy_pool = proxy_pool.clone();
+        let routing = routing.clone();
+        let url_filter = Arc::clone(&url_filter);
+        let trap_detector = trap_detector.clone();
+        let shared_frontier = shared_frontier.clone();
+        let claim_batch = shared_config.claim_batch;
+        let inbox = receiver.map(|receiver| link_router.inbox(current_id - 1, receiver));
+        let recrawl_key = recrawl_key.clone();
+
+        // start the agent in a separate task
+        let handle = tokio::task::spawn(async move {
+            // create new crawler instance
+            let mut agent = Crawler::new(
+                log_name,
+                provenance,
+                agent_url_controller,
+                agent_checkpoint_controller,
+                checkpoint_key,
+                agent_budget_controller,
+                agent_dead_letter_controller,
+                dead_letter_key,
+                agent_external_domain_controller,
+                overflow_key,
+                agent_page_controller,
+                agent_block_controller,
+                agent_crawl_record_controller,
+                sink,
+                robots_client,
+                respect_robots_txt,
+                max_depth,
+                retry_policy,
+                block_policy,
+                scope_policy,
+                allowed_content_types,
+                seeds_chunk,
+                shutdown,
+            );
+            agent.use_strategy(strategy);
+            agent.rank_links(relevance);
+            agent.limit_redirects(max_redirects, redirects_in_scope);
+            agent.filter_urls(url_filter);
+            if let Some(trap_detector) = trap_detector {
+                agent.detect_traps(trap_detector);
+            }
+            if spa_routes {
+                agent.follow_spa_routes();
+            }
+            if conditional_requests {
+                agent.send_conditional_requests();
+            }
+            if main_content {
+                agent.extract_main_content();
+            }
+            if extract_pdf {
+                agent.extract_pdfs(max_pdf_bytes);
+            }
+            if let Some(language_policy) = language_policy {
+                agent.filter_languages(language_policy);
+            }
+            if let Some(selectors) = selectors {
+                agent.extract_fields(selectors);
+            }
+            if let Some((rules, renderer)) = rendering {
+                agent.render_with(rules, renderer);
+            }
+            if let Some(proxy_pool) = proxy_pool {
+                agent.use_proxies(proxy_pool);
+            }
+            if let Some(scorer) = scorer {
+                agent.score_links(scorer);
+            }
+            agent.resume(checkpoint_chunk);
+            if max_priority > 0 {
+                agent.prioritize_messages(max_priority);
+            }
+            if let Some(routing) = routing {
+                agent.route_pages(routing);
+            }
+            agent.report_progress(agent_progress);
+            agent.poll_frontier(frontier);
+            if let Some(inbox) = inbox {
+                agent.route_links(inbox);
+            }
+            agent.stream_published(published_pages);
+            agent.watch_memory(memory_pressure);
+            if let Some(paused) = backlog_paused {
+                agent.watch_backlog(paused);
+            }
+            agent.use_client(http_client);
+            agent.use_fallback_proxies(fallback_clients);
+            agent.limit_fetches(fetch_limits);
+            if let Some(adaptive_throttle) = adaptive_throttle {
+                agent.throttle_domains(adaptive_throttle);
+            }
+            if let Some(content_controller) = agent_content_controller {
+                agent.dedupe_content(content_controller);
+            }
+            agent.control_jobs(job_manager);
+            if accepts_jobs {
+                agent.wait_for_jobs();
+            }
+            if let Some(recrawl_policy) = recrawl_policy {
+                agent.schedule_recrawls(recrawl_policy, recrawl_key);
+            }
+            if let Some(shared_frontier) = shared_frontier {
+                agent.share_frontier(shared_frontier, claim_batch);
+            }
+            if let Some(pages) = pages {
+                agent.send_pages(pages);
+            }
+
+            // start agent asynchronously
+            agent.start().await;
+        });
+        handles.push(handle);
+    }
+
+    // Wait for all agents to complete.
+    let progress = progress_reporter.spawn();
+    let heartbeat = heartbeat_reporter.map(heartbeat::HeartbeatReporter::spawn);
+    // An agent that panicked fails the crawl, once the others are done and the state flushed.
+    let mut failed = None;
+    for handle in handles {
+        if let Err(e) = handle.await {
+            error!("Crawler agent failed: {}", e);
+            failed.get_or_insert_with(|| format!("Crawler agent failed: {e}"));
+        }
+    }
+    // No agent is left to crawl the jobs and URLs submitted through the APIs from now on.
+    frontier.close();
+    progress.finish().await;
+    info!(
+        "Links dropped by the URL filter: {:?}",
+        url_filter.dropped()
+    );
+    if let Some(heartbeat) = heartbeat {
+        heartbeat.finish().await;
+    }
+
+    // Flush the latest visited state before exiting.
+    if let Err(e) = url_controller.sync().await {
+        error!("Failed to sync bloom filter: {}", e);
+    }
+
+    // Make sure every page is in the files, or reached the broker, before exiting.
+    if let Some(sink) = sink {
+        if let Err(e) = sink.close().await {
+            error!("Failed to close the sinks: {}", e);
+        }
+    }
+
+    failed.map_or(Ok(()), Err)
+}
@@ -0,0 +1,128 @@
+use std::{fmt::Display, sync::Arc};
+
+use rand::seq::IndexedRandom;
+use tracing::{info, instrument, warn};
+use url::Url;
+
+use crate::{
+    clients::http::HttpClient,
+    requests::{http::HttpRequest, request::Request},
+};
+
+// Projection of the size of a crawl, computed from a sample of the seed pages.
+#[derive(Debug, Clone)]
+pub struct CrawlEstimate {
+    pub seeds: usize,
+    pub sampled: usize,
+    pub failed: usize,
+    pub avg_links: f64,
+    pub avg_bytes: f64,
+    // Expected number of pages fetched at each depth, starting from the seeds at depth 0.
+    pub pages_per_depth: Vec<f64>,
+}
+
+impl CrawlEstimate {
+    pub fn total_pages(&self) -> f64 {
+        self.pages_per_depth.iter().sum()
+    }
+
+    pub fn total_bytes(&self) -> f64 {
+        self.total_pages() * self.avg_bytes
+    }
+}
+
+impl Display for CrawlEstimate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Crawl estimate ({} seeds, {} sampled, {} failed)",
+            self.seeds, self.sampled, self.failed
+        )?;
+        writeln!(
+            f,
+            "  avg links/page: {:.1}, avg page size: {}",
+            self.avg_links,
+            human_bytes(self.avg_bytes)
+        )?;
+        for (depth, pages) in self.pages_per_depth.iter().enumerate() {
+            writeln!(f, "  depth {depth}: ~{pages:.0} pages")?;
+        }
+        write!(
+            f,
+            "  total: ~{:.0} pages, ~{} downloaded (upper bound, before dedup)",
+            self.total_pages(),
+            human_bytes(self.total_bytes())
+        )
+    }
+}
+
+// Fetch up to `sample_size` random seeds and project the number of pages and bytes of a crawl
+// following links up to `max_depth`, assuming every page links to `avg_links` new pages. Pages are
+// fetched with `client`.
+#[instrument(name = "Estimate crawl", skip(seeds, client), fields(seeds = seeds.len()))]
+pub async fn estimate(
+    seeds: &[Url],
+    max_depth: u32,
+    sample_size: usize,
+    client: Arc<HttpClient>,
+) -> CrawlEstimate {
+    let sample: Vec<&Url> = seeds
+        .choose_multiple(&mut rand::rng(), sample_size.min(seeds.len()))
+        .collect();
+    info!("Sampling {} seed pages", sample.len());
+
+    let mut failed = 0;
+    let mut links = Vec::new();
+    let mut bytes = Vec::new();
+    for seed in &sample {
+        let mut req = HttpRequest::new(seed.as_str(), 0);
+        req.client = Some(Arc::clone(&client));
+        match req.execute().await {
+            Ok(res) => {
+                if let Some(extra) = res.extra {
+                    links.push(extra.links.len() as f64);
+                    bytes.push(extra.body.len() as f64);
+                }
+            }
+            Err(e) => {
+                warn!("Failed to sample {}: {}", seed, e);
+                failed += 1;
+            }
+        }
+    }
+
+    let mean = |v: &[f64]| {
+        if v.is_empty() {
+            0.0
+        } else {
+            v.iter().sum::<f64>() / v.len() as f64
+        }
+    };
+    let avg_links = mean(&links);
+    let avg_bytes = mean(&bytes);
+
+    let mut pages_per_depth = vec![seeds.len() as f64];
+    for depth in 1..=max_depth as usize {
+        pages_per_depth.push(pages_per_depth[depth - 1] * avg_links);
+    }
+
+    CrawlEstimate {
+        seeds: seeds.len(),
+        sampled: sample.len(),
+        failed,
+        avg_links,
+        avg_bytes,
+        pages_per_depth,
+    }
+}
+
+fn human_bytes(bytes: f64) -> String {
+    const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB", "PB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
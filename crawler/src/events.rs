@@ -0,0 +1,50 @@
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+// Pages buffered for slow subscribers before they start missing summaries.
+const CAPACITY: usize = 1024;
+
+// Short description of a page published to the message bus, streamed live by the admin API.
+#[derive(Debug, Clone, Serialize)]
+pub struct PageSummary {
+    pub url: String,
+    pub title: String,
+    pub status_code: u16,
+    pub agent: String,
+    // Job submitted at runtime the page belongs to, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job: Option<String>,
+    // Why the body was not downloaded, if it was not.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<String>,
+    pub published_at: u64,
+}
+
+// Broadcast of the pages published by every agent. Publishing never blocks: summaries are dropped
+// when nobody listens, and subscribers that fall behind skip the oldest ones.
+#[derive(Clone)]
+pub struct PublishedPages {
+    sender: broadcast::Sender<PageSummary>,
+}
+
+impl Default for PublishedPages {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PublishedPages {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CAPACITY);
+        PublishedPages { sender }
+    }
+
+    pub fn send(&self, summary: PageSummary) {
+        // An error only means there are no subscribers right now.
+        let _ = self.sender.send(summary);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<PageSummary> {
+        self.sender.subscribe()
+    }
+}
@@ -0,0 +1,145 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use tracing::{info, instrument, warn};
+use url::Url;
+
+use crate::controllers::{urlcontroller::UrlControllerTrait, UrlController};
+
+// Maximum number of URLs allowed in a single sitemap file by the sitemap protocol.
+const MAX_URLS_PER_SITEMAP: usize = 50_000;
+
+// Sitemap index listing every file, written when the export produced more than one.
+const SITEMAP_INDEX: &str = "sitemap_index.xml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SitemapFormat {
+    // XML sitemap following https://www.sitemaps.org/protocol.html
+    Xml,
+    // Plain text list, one URL per line
+    Txt,
+}
+
+// Export one sitemap per crawled host (or only for `host` if given) from the visited records.
+// Hosts over the protocol limit are split into several files; whenever more than one file is
+// written, `sitemap_index.xml` references each of them, at `base_url` if given, else at the root
+// of their host (`https://<host>/<file>`). Returns the paths of the written files.
+#[instrument(name = "Export sitemaps", skip(url_controller))]
+pub async fn export_sitemaps(
+    url_controller: &UrlController,
+    host: Option<&str>,
+    format: SitemapFormat,
+    output_dir: &str,
+    base_url: Option<&str>,
+) -> Result<Vec<String>, String> {
+    let visited = url_controller
+        .visited_urls(host)
+        .await
+        .map_err(|e| format!("Failed to list visited URLs: {e}"))?;
+    info!(count = visited.len(), "Loaded visited URLs");
+
+    // group URLs by host, sorted for stable output
+    let mut by_host: BTreeMap<String, Vec<Url>> = BTreeMap::new();
+    for url in visited {
+        if let Some(h) = url.host_str() {
+            by_host.entry(h.to_string()).or_default().push(url);
+        }
+    }
+
+    fs::create_dir_all(output_dir)
+        .map_err(|e| format!("Failed to create output dir '{output_dir}': {e}"))?;
+
+    let mut written = Vec::new();
+    // public location of each written file, for the index
+    let mut locations = Vec::new();
+    for (host, mut urls) in by_host {
+        urls.sort();
+        urls.dedup();
+
+        let chunks: Vec<&[Url]> = urls.chunks(MAX_URLS_PER_SITEMAP).collect();
+        if chunks.len() > 1 {
+            warn!(
+                "{} has {} URLs, splitting into {} sitemaps",
+                host,
+                urls.len(),
+                chunks.len()
+            );
+        }
+
+        for (i, chunk) in chunks.iter().enumerate() {
+            let suffix = if chunks.len() > 1 {
+                format!("-{}", i + 1)
+            } else {
+                String::new()
+            };
+            let (content, ext) = match format {
+                SitemapFormat::Xml => (render_xml(chunk), "xml"),
+                SitemapFormat::Txt => (render_txt(chunk), "txt"),
+            };
+            let file = format!("{host}{suffix}.{ext}");
+            let path = Path::new(output_dir).join(&file);
+            fs::write(&path, content)
+                .map_err(|e| format!("Failed to write '{}': {e}", path.display()))?;
+            info!("Wrote {} URLs to {}", chunk.len(), path.display());
+            written.push(path.display().to_string());
+            locations.push(match base_url {
+                Some(base) => format!("{}/{file}", base.trim_end_matches('/')),
+                None => format!("https://{host}/{file}"),
+            });
+        }
+    }
+
+    if locations.len() > 1 {
+        let path = Path::new(output_dir).join(SITEMAP_INDEX);
+        fs::write(&path, render_index(&locations))
+            .map_err(|e| format!("Failed to write '{}': {e}", path.display()))?;
+        info!(
+            "Wrote the index of {} sitemaps to {}",
+            locations.len(),
+            path.display()
+        );
+        written.push(path.display().to_string());
+    }
+
+    Ok(written)
+}
+
+// Sitemap index referencing the sitemaps at `locations`.
+pub fn render_index(locations: &[String]) -> String {
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <sitemapindex xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for location in locations {
+        out.push_str("  <sitemap><loc>");
+        out.push_str(&escape_xml(location));
+        out.push_str("</loc></sitemap>\n");
+    }
+    out.push_str("</sitemapindex>\n");
+    out
+}
+
+pub fn render_xml(urls: &[Url]) -> String {
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for url in urls {
+        out.push_str("  <url><loc>");
+        out.push_str(&escape_xml(url.as_str()));
+        out.push_str("</loc></url>\n");
+    }
+    out.push_str("</urlset>\n");
+    out
+}
+
+pub fn render_txt(urls: &[Url]) -> String {
+    urls.iter().map(|u| format!("{u}\n")).collect()
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
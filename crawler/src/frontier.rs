@@ -0,0 +1,197 @@
+pub mod relevance;
+pub mod scorer;
+pub mod shared;
+pub mod strategy;
+
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    fmt::{Display, Formatter},
+    sync::{
+        atomic::{self, AtomicBool, AtomicU64},
+        Arc, Mutex,
+    },
+};
+
+use tracing::{debug, info, instrument};
+use url::Url;
+
+use crate::{
+    controllers::{urlcontroller::UrlControllerTrait, UrlController},
+    scope::ScopePolicy,
+    validators,
+};
+
+// Entry point for URLs added to a running crawl from outside of it (the admin API, or code
+// embedding the crawler). Injected URLs go through the same checks as discovered links: URL
+// validation and normalization, crawl scope and visited set. They are handed to the first agent
+// that polls the frontier.
+#[derive(Clone)]
+pub struct Frontier {
+    scope_policy: ScopePolicy,
+    url_controller: Arc<UrlController>,
+    pending: Arc<Mutex<BinaryHeap<Injection>>>,
+    // Keeps injections of the same priority in FIFO order.
+    sequence: Arc<AtomicU64>,
+    // Set once the agents stopped: nothing would crawl the URLs injected from then on.
+    closed: Arc<AtomicBool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Injection {
+    // Normalized URL.
+    pub url: String,
+    pub depth: u32,
+    // Higher priorities are crawled first; 0 queues the URL behind the pending requests.
+    pub priority: u8,
+    // Job the URL was submitted for, if any.
+    pub job: Option<String>,
+    // Crawl the URL again even if it was already visited (recrawls).
+    pub refresh: bool,
+    // Seed and path the URL was first discovered from (recrawls).
+    pub origin_seed: Option<String>,
+    pub path: Vec<String>,
+    sequence: u64,
+}
+
+impl Ord for Injection {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+impl PartialOrd for Injection {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Debug)]
+pub enum InjectError {
+    InvalidUrl(String),
+    OutOfScope(String),
+    AlreadyVisited(String),
+    // The visited set could not be checked.
+    Unavailable(String),
+    // The agents stopped (see `Frontier::close`).
+    Closed,
+}
+
+impl Display for InjectError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InjectError::InvalidUrl(e) => write!(f, "Invalid URL: {e}"),
+            InjectError::OutOfScope(url) => write!(f, "URL out of scope: {url}"),
+            InjectError::AlreadyVisited(url) => write!(f, "URL already visited: {url}"),
+            InjectError::Unavailable(e) => write!(f, "Visited set unavailable: {e}"),
+            InjectError::Closed => write!(f, "The crawl has stopped"),
+        }
+    }
+}
+
+impl Frontier {
+    pub fn new(scope_policy: ScopePolicy, url_controller: Arc<UrlController>) -> Self {
+        Frontier {
+            scope_policy,
+            url_controller,
+            pending: Arc::new(Mutex::new(BinaryHeap::new())),
+            sequence: Arc::new(AtomicU64::new(0)),
+            closed: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    // Refuse the URLs injected from now on, once no agent is left to crawl them.
+    pub fn close(&self) {
+        self.closed.store(true, atomic::Ordering::Relaxed);
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(atomic::Ordering::Relaxed)
+    }
+
+    // Add `url` to the crawl at `depth` (0 for a new seed). Returns the normalized URL.
+    pub async fn inject(&self, url: &str, depth: u32, priority: u8) -> Result<String, InjectError> {
+        self.inject_for_job(None, url, depth, priority).await
+    }
+
+    // Like `inject`, attributing the URL and the pages discovered from it to `job`.
+    #[instrument(skip(self))]
+    pub async fn inject_for_job(
+        &self,
+        job: Option<&str>,
+        url: &str,
+        depth: u32,
+        priority: u8,
+    ) -> Result<String, InjectError> {
+        if self.is_closed() {
+            return Err(InjectError::Closed);
+        }
+        validators::validate_url(url).map_err(InjectError::InvalidUrl)?;
+        let url = Url::parse(url).map_err(|e| InjectError::InvalidUrl(e.to_string()))?;
+        if !self.scope_policy.allows(&url) {
+            return Err(InjectError::OutOfScope(url.to_string()));
+        }
+        match self.url_controller.is_visited(url.clone()).await {
+            Ok(true) => return Err(InjectError::AlreadyVisited(url.to_string())),
+            Ok(false) => {}
+            Err(e) => return Err(InjectError::Unavailable(e.to_string())),
+        }
+
+        let injection = Injection {
+            url: url.to_string(),
+            depth,
+            priority,
+            job: job.map(str::to_string),
+            refresh: false,
+            origin_seed: None,
+            path: Vec::new(),
+            sequence: self.sequence.fetch_add(1, atomic::Ordering::Relaxed),
+        };
+        info!("Injected {} (depth {}, priority {})", url, depth, priority);
+        self.pending
+            .lock()
+            .expect("frontier lock poisoned")
+            .push(injection);
+        Ok(url.to_string())
+    }
+
+    // Crawl `url` again, although it was already visited (see `RecrawlScheduler`). Returns the
+    // normalized URL.
+    pub fn refresh(
+        &self,
+        url: &str,
+        depth: u32,
+        origin_seed: Option<String>,
+        path: Vec<String>,
+    ) -> Result<String, InjectError> {
+        let url = Url::parse(url).map_err(|e| InjectError::InvalidUrl(e.to_string()))?;
+        if !self.scope_policy.allows(&url) {
+            return Err(InjectError::OutOfScope(url.to_string()));
+        }
+
+        let injection = Injection {
+            url: url.to_string(),
+            depth,
+            priority: 0,
+            job: None,
+            refresh: true,
+            origin_seed,
+            path,
+            sequence: self.sequence.fetch_add(1, atomic::Ordering::Relaxed),
+        };
+        debug!("Scheduled recrawl of {} (depth {})", url, depth);
+        self.pending
+            .lock()
+            .expect("frontier lock poisoned")
+            .push(injection);
+        Ok(url.to_string())
+    }
+
+    // Remove the pending injections, highest priority first.
+    pub fn take(&self) -> Vec<Injection> {
+        let pending = std::mem::take(&mut *self.pending.lock().expect("frontier lock poisoned"));
+        pending.into_sorted_vec().into_iter().rev().collect()
+    }
+}
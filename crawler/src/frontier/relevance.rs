@@ -0,0 +1,215 @@
+use std::{collections::HashMap, str::FromStr, sync::Mutex};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+// Link discovered on a page, as seen by the relevance scorers.
+pub struct LinkContext<'a> {
+    pub url: &'a Url,
+    // Text of the anchor, when known.
+    pub anchor_text: Option<&'a str>,
+}
+
+// Scores how relevant a discovered link is to the topic of a focused crawl, from 0 (unrelated) to
+// 1 (on topic).
+pub trait RelevanceScorer: Send + Sync {
+    fn score(&self, link: &LinkContext) -> f64;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScorerKind {
+    Keyword,
+    UrlPattern,
+    AnchorTfidf,
+}
+
+impl FromStr for ScorerKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keyword" => Ok(ScorerKind::Keyword),
+            "url_pattern" => Ok(ScorerKind::UrlPattern),
+            "anchor_tfidf" => Ok(ScorerKind::AnchorTfidf),
+            other => Err(format!(
+                "Unknown relevance scorer '{other}' (expected keyword, url_pattern or anchor_tfidf)"
+            )),
+        }
+    }
+}
+
+// Share of the keywords found in the URL or the anchor text.
+pub struct KeywordScorer {
+    keywords: Vec<String>,
+}
+
+impl KeywordScorer {
+    pub fn new(keywords: &[String]) -> Self {
+        KeywordScorer {
+            keywords: keywords.iter().map(|k| k.to_lowercase()).collect(),
+        }
+    }
+}
+
+impl RelevanceScorer for KeywordScorer {
+    fn score(&self, link: &LinkContext) -> f64 {
+        if self.keywords.is_empty() {
+            return 0.0;
+        }
+        let text = format!(
+            "{} {}",
+            link.url.as_str().to_lowercase(),
+            link.anchor_text.unwrap_or_default().to_lowercase()
+        );
+        let matches = self
+            .keywords
+            .iter()
+            .filter(|keyword| text.contains(keyword.as_str()))
+            .count();
+        matches as f64 / self.keywords.len() as f64
+    }
+}
+
+// 1 for URLs matching one of the patterns, 0 otherwise.
+pub struct UrlPatternScorer {
+    patterns: Vec<Regex>,
+}
+
+impl UrlPatternScorer {
+    pub fn new(patterns: Vec<Regex>) -> Self {
+        UrlPatternScorer { patterns }
+    }
+}
+
+impl RelevanceScorer for UrlPatternScorer {
+    fn score(&self, link: &LinkContext) -> f64 {
+        if self.patterns.iter().any(|p| p.is_match(link.url.as_str())) {
+            1.0
+        } else {
+            0.0
+        }
+    }
+}
+
+// Cosine similarity between the keywords and the anchor text, weighted by TF-IDF. Document
+// frequencies are learned from the anchors scored so far, so common navigation words ("home",
+// "next") weigh less than rare topical ones. Links without anchor text are scored on the words of
+// their URL path.
+pub struct AnchorTfidfScorer {
+    keywords: Vec<String>,
+    stats: Mutex<DocumentFrequencies>,
+}
+
+#[derive(Default)]
+struct DocumentFrequencies {
+    documents: u64,
+    terms: HashMap<String, u64>,
+}
+
+impl DocumentFrequencies {
+    fn idf(&self, term: &str) -> f64 {
+        let df = self.terms.get(term).copied().unwrap_or_default();
+        ((1 + self.documents) as f64 / (1 + df) as f64).ln() + 1.0
+    }
+}
+
+impl AnchorTfidfScorer {
+    pub fn new(keywords: &[String]) -> Self {
+        AnchorTfidfScorer {
+            keywords: keywords.iter().flat_map(|k| tokenize(k)).collect(),
+            stats: Mutex::new(DocumentFrequencies::default()),
+        }
+    }
+}
+
+impl RelevanceScorer for AnchorTfidfScorer {
+    fn score(&self, link: &LinkContext) -> f64 {
+        let tokens = match link.anchor_text.filter(|text| !text.trim().is_empty()) {
+            Some(text) => tokenize(text),
+            None => tokenize(link.url.path()),
+        };
+        if tokens.is_empty() || self.keywords.is_empty() {
+            return 0.0;
+        }
+
+        let mut counts: HashMap<&str, f64> = HashMap::new();
+        for token in &tokens {
+            *counts.entry(token.as_str()).or_default() += 1.0;
+        }
+
+        let mut stats = self.stats.lock().expect("tf-idf lock poisoned");
+        stats.documents += 1;
+        for term in counts.keys() {
+            *stats.terms.entry(term.to_string()).or_default() += 1;
+        }
+
+        // Anchor vector: term frequency * idf; keyword vector: idf.
+        let anchor: HashMap<&str, f64> = counts
+            .iter()
+            .map(|(term, count)| (*term, count / tokens.len() as f64 * stats.idf(term)))
+            .collect();
+        let mut dot = 0.0;
+        let mut keyword_norm = 0.0;
+        for keyword in &self.keywords {
+            let weight = stats.idf(keyword);
+            keyword_norm += weight * weight;
+            dot += weight * anchor.get(keyword.as_str()).copied().unwrap_or_default();
+        }
+        let anchor_norm = anchor.values().map(|w| w * w).sum::<f64>().sqrt();
+        if dot == 0.0 {
+            return 0.0;
+        }
+        (dot / (keyword_norm.sqrt() * anchor_norm)).clamp(0.0, 1.0)
+    }
+}
+
+// Lowercased alphanumeric words.
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+// Relevance of a discovered link for a focused crawl: the mean score of the configured scorers.
+// Links scoring below `threshold` are not crawled.
+pub struct RelevanceModel {
+    scorers: Vec<Box<dyn RelevanceScorer>>,
+    pub threshold: f64,
+}
+
+impl RelevanceModel {
+    pub fn new(scorers: Vec<Box<dyn RelevanceScorer>>, threshold: f64) -> Self {
+        RelevanceModel { scorers, threshold }
+    }
+
+    // Build the scorers of `kinds` from the crawl keywords and URL patterns.
+    pub fn from_kinds(
+        kinds: &[ScorerKind],
+        keywords: &[String],
+        url_patterns: Vec<Regex>,
+        threshold: f64,
+    ) -> Self {
+        let scorers = kinds
+            .iter()
+            .map(|kind| -> Box<dyn RelevanceScorer> {
+                match kind {
+                    ScorerKind::Keyword => Box::new(KeywordScorer::new(keywords)),
+                    ScorerKind::UrlPattern => Box::new(UrlPatternScorer::new(url_patterns.clone())),
+                    ScorerKind::AnchorTfidf => Box::new(AnchorTfidfScorer::new(keywords)),
+                }
+            })
+            .collect();
+        RelevanceModel::new(scorers, threshold)
+    }
+
+    pub fn score(&self, link: &LinkContext) -> f64 {
+        if self.scorers.is_empty() {
+            return 0.0;
+        }
+        let total: f64 = self.scorers.iter().map(|scorer| scorer.score(link)).sum();
+        total / self.scorers.len() as f64
+    }
+}
@@ -0,0 +1,165 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use reqwest::{header::CONTENT_TYPE, Client};
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+use tracing::{debug, instrument, warn};
+
+// How long the scorer is left alone after a failed call.
+const COOLDOWN: Duration = Duration::from_secs(30);
+
+#[derive(Serialize)]
+struct ScoreRequest<'a> {
+    urls: &'a [String],
+}
+
+#[derive(Deserialize)]
+struct ScoreResponse {
+    scores: Vec<f64>,
+}
+
+// Client of a user-supplied scoring service (e.g. an ML relevance model), whose scores order the
+// `best_first` frontier. Candidate URLs are sent in batches as `{"urls": [...]}` and the service
+// answers `{"scores": [...]}` in the same order; higher scores are crawled first.
+//
+// Scores are cached, and the crawl never waits on a failing service: calls time out, and after a
+// failure the scorer is skipped for a while, leaving URLs unscored (ordered by relevance and
+// depth).
+#[derive(Clone)]
+pub struct ExternalScorer {
+    client: Client,
+    endpoint: String,
+    batch_size: usize,
+    cache: Arc<Mutex<ScoreCache>>,
+    // Set after a failed call; no calls are made before this instant.
+    unavailable_until: Arc<Mutex<Option<Instant>>>,
+}
+
+// Scores of the most recently scored URLs, evicted in insertion order.
+struct ScoreCache {
+    scores: HashMap<String, f64>,
+    order: VecDeque<String>,
+    capacity: usize,
+}
+
+impl ScoreCache {
+    fn insert(&mut self, url: String, score: f64) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.scores.insert(url.clone(), score).is_none() {
+            self.order.push_back(url);
+        }
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.scores.remove(&oldest);
+            }
+        }
+    }
+}
+
+impl ExternalScorer {
+    pub fn new(
+        endpoint: String,
+        batch_size: usize,
+        timeout: Duration,
+        cache_size: usize,
+    ) -> Result<Self, String> {
+        let client = Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| format!("Failed to build the scorer client: {e}"))?;
+        Ok(ExternalScorer {
+            client,
+            endpoint,
+            batch_size: batch_size.max(1),
+            cache: Arc::new(Mutex::new(ScoreCache {
+                scores: HashMap::new(),
+                order: VecDeque::new(),
+                capacity: cache_size,
+            })),
+            unavailable_until: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    // Scores of `urls`, from the cache or the service. URLs the service could not score are left
+    // out of the result.
+    #[instrument(skip(self, urls), fields(urls = urls.len()))]
+    pub async fn score(&self, urls: &[String]) -> HashMap<String, f64> {
+        let mut scores = HashMap::new();
+        let mut missing = Vec::new();
+        {
+            let cache = self.cache.lock().expect("scorer cache lock poisoned");
+            for url in urls {
+                match cache.scores.get(url) {
+                    Some(score) => {
+                        scores.insert(url.clone(), *score);
+                    }
+                    None => missing.push(url.clone()),
+                }
+            }
+        }
+
+        for batch in missing.chunks(self.batch_size) {
+            if !self.available() {
+                debug!("Scorer unavailable, {} URLs left unscored", batch.len());
+                break;
+            }
+            match self.request(batch).await {
+                Ok(batch_scores) => {
+                    let mut cache = self.cache.lock().expect("scorer cache lock poisoned");
+                    for (url, score) in batch.iter().zip(batch_scores) {
+                        cache.insert(url.clone(), score);
+                        scores.insert(url.clone(), score);
+                    }
+                }
+                Err(e) => {
+                    warn!(
+                        "URL scorer failed, skipping it for {}s: {}",
+                        COOLDOWN.as_secs(),
+                        e
+                    );
+                    *self.unavailable_until.lock().expect("scorer lock poisoned") =
+                        Some(Instant::now() + COOLDOWN);
+                    break;
+                }
+            }
+        }
+        scores
+    }
+
+    fn available(&self) -> bool {
+        let unavailable_until = self.unavailable_until.lock().expect("scorer lock poisoned");
+        unavailable_until.is_none_or(|until| Instant::now() >= until)
+    }
+
+    async fn request(&self, urls: &[String]) -> Result<Vec<f64>, String> {
+        let body = serde_json::to_vec(&ScoreRequest { urls }).map_err(|e| e.to_string())?;
+        let response = self
+            .client
+            .post(&self.endpoint)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("status {}", response.status()));
+        }
+        let bytes = response.bytes().await.map_err(|e| e.to_string())?;
+        let response: ScoreResponse =
+            serde_json::from_slice(&bytes).map_err(|e| format!("invalid response: {e}"))?;
+        if response.scores.len() != urls.len() {
+            return Err(format!(
+                "expected {} scores, got {}",
+                urls.len(),
+                response.scores.len()
+            ));
+        }
+        Ok(response.scores)
+    }
+}
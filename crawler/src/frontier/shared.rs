@@ -0,0 +1,75 @@
+use std::{sync::Arc, time::Duration};
+
+use tracing::{debug, error, info};
+
+use crate::{
+    controllers::{
+        leasecontroller::LeaseControllerTrait,
+        sharedfrontiercontroller::SharedFrontierControllerTrait, LeaseController,
+        SharedFrontierController,
+    },
+    crawler::unix_timestamp,
+};
+
+// Hands the shared frontier entries of crashed or stalled workers out again once their lease
+// expired (see `SharedFrontierService::reap`).
+//
+// Every process of the deployment runs the task, but each interval only the process that wins
+// the lease of the interval reaps.
+pub struct LeaseReaper {
+    shared_frontier: Arc<SharedFrontierController>,
+    lease_controller: LeaseController,
+    name: String,
+    interval: Duration,
+}
+
+impl LeaseReaper {
+    pub fn new(
+        shared_frontier: Arc<SharedFrontierController>,
+        lease_controller: LeaseController,
+        name: String,
+        interval: Duration,
+    ) -> Self {
+        LeaseReaper {
+            shared_frontier,
+            lease_controller,
+            name,
+            interval,
+        }
+    }
+
+    // Reap expired claims every interval, for as long as the process runs.
+    pub async fn run(self) {
+        let period = self.interval.as_secs().max(1);
+        let mut interval = tokio::time::interval(Duration::from_secs(period));
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            match self
+                .lease_controller
+                .acquire(&self.name, unix_timestamp() / period)
+                .await
+            {
+                Ok(true) => {}
+                Ok(false) => {
+                    debug!("Expired shared frontier claims are reaped by another process");
+                    continue;
+                }
+                Err(e) => {
+                    error!("Failed to acquire the shared frontier reaper lease: {}", e);
+                    continue;
+                }
+            }
+            match self.shared_frontier.reap().await {
+                Ok(0) => debug!("No expired shared frontier claims"),
+                Ok(reaped) => info!(
+                    event = "frontier_claims_expired",
+                    entries = reaped,
+                    "Queued {} expired shared frontier claims again",
+                    reaped
+                ),
+                Err(e) => error!("Failed to reap the shared frontier: {}", e),
+            }
+        }
+    }
+}
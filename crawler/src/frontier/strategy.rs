@@ -0,0 +1,200 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BinaryHeap, LinkedList},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::requests::http::HttpRequest;
+
+// Order in which an agent crawls its pending requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StrategyKind {
+    // Breadth-first: requests are crawled in the order they were discovered.
+    Bfs,
+    // Depth-first: the most recently discovered request is crawled first.
+    Dfs,
+    // Best-first: requests with the highest external score first (when a scorer is configured),
+    // then the most relevant ones (see `RelevanceModel`), then the shallowest.
+    BestFirst,
+}
+
+impl FromStr for StrategyKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bfs" => Ok(StrategyKind::Bfs),
+            "dfs" => Ok(StrategyKind::Dfs),
+            "best_first" => Ok(StrategyKind::BestFirst),
+            other => Err(format!(
+                "Unknown frontier strategy '{other}' (expected bfs, dfs or best_first)"
+            )),
+        }
+    }
+}
+
+// Pending requests of an agent, popped in the order defined by the strategy.
+pub trait FrontierStrategy: Send + Sync {
+    fn push(&mut self, req: HttpRequest);
+    // Queue `req` to be crawled before every pending request, whatever the strategy (prioritized
+    // injections).
+    fn push_next(&mut self, req: HttpRequest);
+    fn pop(&mut self) -> Option<HttpRequest>;
+    fn len(&self) -> usize;
+    // Remove every pending request, in crawl order.
+    fn drain(&mut self) -> Vec<HttpRequest>;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+pub fn new_strategy(kind: StrategyKind) -> Box<dyn FrontierStrategy> {
+    match kind {
+        StrategyKind::Bfs => Box::new(Bfs::default()),
+        StrategyKind::Dfs => Box::new(Dfs::default()),
+        StrategyKind::BestFirst => Box::new(BestFirst::default()),
+    }
+}
+
+#[derive(Default)]
+pub struct Bfs {
+    queue: LinkedList<HttpRequest>,
+}
+
+impl FrontierStrategy for Bfs {
+    fn push(&mut self, req: HttpRequest) {
+        self.queue.push_back(req);
+    }
+
+    fn push_next(&mut self, req: HttpRequest) {
+        self.queue.push_front(req);
+    }
+
+    fn pop(&mut self) -> Option<HttpRequest> {
+        self.queue.pop_front()
+    }
+
+    fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    fn drain(&mut self) -> Vec<HttpRequest> {
+        std::mem::take(&mut self.queue).into_iter().collect()
+    }
+}
+
+#[derive(Default)]
+pub struct Dfs {
+    stack: Vec<HttpRequest>,
+}
+
+impl FrontierStrategy for Dfs {
+    fn push(&mut self, req: HttpRequest) {
+        self.stack.push(req);
+    }
+
+    fn push_next(&mut self, req: HttpRequest) {
+        self.stack.push(req);
+    }
+
+    fn pop(&mut self) -> Option<HttpRequest> {
+        self.stack.pop()
+    }
+
+    fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    fn drain(&mut self) -> Vec<HttpRequest> {
+        std::mem::take(&mut self.stack).into_iter().rev().collect()
+    }
+}
+
+#[derive(Default)]
+pub struct BestFirst {
+    heap: BinaryHeap<Scored>,
+    // Keeps requests of the same score in FIFO order.
+    sequence: u64,
+}
+
+// Compared field by field: requests pushed next (latest first), external score, relevance
+// (missing scores count as 0), shallower depth, then FIFO.
+struct Scored {
+    next: u64,
+    external: f64,
+    relevance: f64,
+    depth: Reverse<u32>,
+    sequence: u64,
+    req: HttpRequest,
+}
+
+impl Ord for Scored {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.next
+            .cmp(&other.next)
+            .then_with(|| self.external.total_cmp(&other.external))
+            .then_with(|| self.relevance.total_cmp(&other.relevance))
+            .then_with(|| self.depth.cmp(&other.depth))
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+impl PartialOrd for Scored {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Scored {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Scored {}
+
+impl BestFirst {
+    fn insert(&mut self, req: HttpRequest, next: bool) {
+        self.sequence += 1;
+        let next = if next { self.sequence } else { 0 };
+        self.heap.push(Scored {
+            next,
+            external: req.score.unwrap_or_default(),
+            relevance: req.relevance.unwrap_or_default(),
+            depth: Reverse(req.depth),
+            sequence: self.sequence,
+            req,
+        });
+    }
+}
+
+impl FrontierStrategy for BestFirst {
+    fn push(&mut self, req: HttpRequest) {
+        self.insert(req, false);
+    }
+
+    fn push_next(&mut self, req: HttpRequest) {
+        self.insert(req, true);
+    }
+
+    fn pop(&mut self) -> Option<HttpRequest> {
+        self.heap.pop().map(|scored| scored.req)
+    }
+
+    fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    fn drain(&mut self) -> Vec<HttpRequest> {
+        std::mem::take(&mut self.heap)
+            .into_sorted_vec()
+            .into_iter()
+            .rev()
+            .map(|scored| scored.req)
+            .collect()
+    }
+}
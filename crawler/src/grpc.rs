@@ -0,0 +1,188 @@
+use std::{net::SocketAddr, pin::Pin};
+
+use futures_lite::{stream, Stream};
+use tokio::sync::broadcast::error::RecvError;
+use tonic::{transport::Server, Request, Response, Status};
+use tracing::{error, info, instrument, warn};
+
+use crate::{
+    events::PageSummary,
+    jobs::{JobError, JobManager, JobOptions, JobState, JobStatus},
+};
+
+pub mod pb {
+    tonic::include_proto!("crawler");
+}
+
+use pb::crawler_service_server::{CrawlerService, CrawlerServiceServer};
+
+// gRPC counterpart of the admin API job endpoints (`proto/crawler.proto`), backed by the same
+// `JobManager`.
+pub struct JobService {
+    jobs: JobManager,
+}
+
+type ResultStream = Pin<Box<dyn Stream<Item = Result<pb::PageResult, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl CrawlerService for JobService {
+    #[instrument(skip(self))]
+    async fn submit_job(
+        &self,
+        request: Request<pb::SubmitJobRequest>,
+    ) -> Result<Response<pb::SubmitJobResponse>, Status> {
+        let request = request.into_inner();
+        let priority = u8::try_from(request.priority)
+            .map_err(|_| Status::invalid_argument("priority must be between 0 and 255"))?;
+        let submitted = self
+            .jobs
+            .submit(
+                &request.seeds,
+                priority,
+                JobOptions {
+                    max_depth: request.max_depth,
+                    include: request.include,
+                    exclude: request.exclude,
+                },
+            )
+            .await
+            .map_err(error_status)?;
+
+        Ok(Response::new(pb::SubmitJobResponse {
+            job: submitted.job,
+            accepted: submitted.accepted,
+            rejected: submitted
+                .rejected
+                .into_iter()
+                .map(|r| pb::RejectedSeed {
+                    url: r.url,
+                    reason: r.reason,
+                })
+                .collect(),
+        }))
+    }
+
+    #[instrument(skip(self))]
+    async fn get_status(
+        &self,
+        request: Request<pb::GetStatusRequest>,
+    ) -> Result<Response<pb::JobStatus>, Status> {
+        let job = request.into_inner().job;
+        let status = self
+            .jobs
+            .status(&job)
+            .ok_or_else(|| Status::not_found(format!("Unknown job: {job}")))?;
+        Ok(Response::new(job_status(status)))
+    }
+
+    #[instrument(skip(self))]
+    async fn pause_job(
+        &self,
+        request: Request<pb::JobControlRequest>,
+    ) -> Result<Response<pb::JobStatus>, Status> {
+        control(self.jobs.pause(&request.into_inner().job))
+    }
+
+    #[instrument(skip(self))]
+    async fn resume_job(
+        &self,
+        request: Request<pb::JobControlRequest>,
+    ) -> Result<Response<pb::JobStatus>, Status> {
+        control(self.jobs.resume(&request.into_inner().job))
+    }
+
+    #[instrument(skip(self))]
+    async fn cancel_job(
+        &self,
+        request: Request<pb::JobControlRequest>,
+    ) -> Result<Response<pb::JobStatus>, Status> {
+        control(self.jobs.cancel(&request.into_inner().job))
+    }
+
+    type StreamResultsStream = ResultStream;
+
+    // Streams the pages of the job published after the call; a client too slow to keep up skips
+    // the oldest pages.
+    #[instrument(skip(self))]
+    async fn stream_results(
+        &self,
+        request: Request<pb::StreamResultsRequest>,
+    ) -> Result<Response<Self::StreamResultsStream>, Status> {
+        let job = request.into_inner().job;
+        if !self.jobs.exists(&job) {
+            return Err(Status::not_found(format!("Unknown job: {job}")));
+        }
+
+        let receiver = self.jobs.results();
+        let results = stream::unfold((receiver, job), |(mut receiver, job)| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(summary) if summary.job.as_deref() == Some(job.as_str()) => {
+                        return Some((Ok(page_result(summary)), (receiver, job)));
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!("Result stream of job {} missed {} pages", job, missed)
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        });
+        Ok(Response::new(Box::pin(results)))
+    }
+}
+
+fn control(result: Result<JobStatus, JobError>) -> Result<Response<pb::JobStatus>, Status> {
+    result
+        .map(|status| Response::new(job_status(status)))
+        .map_err(error_status)
+}
+
+fn error_status(error: JobError) -> Status {
+    match error {
+        JobError::Unknown(_) => Status::not_found(error.to_string()),
+        JobError::InvalidState(_) => Status::failed_precondition(error.to_string()),
+        JobError::InvalidJob(_) => Status::invalid_argument(error.to_string()),
+        JobError::Stopped => Status::unavailable(error.to_string()),
+    }
+}
+
+fn job_status(status: JobStatus) -> pb::JobStatus {
+    let state = match status.state {
+        JobState::Running => "running",
+        JobState::Paused => "paused",
+        JobState::Cancelled => "cancelled",
+    };
+    pb::JobStatus {
+        job: status.job,
+        submitted_at: status.submitted_at,
+        seeds: status.seeds,
+        rejected: status.rejected,
+        published: status.published,
+        last_published_at: status.last_published_at,
+        state: state.to_string(),
+        max_depth: status.max_depth,
+    }
+}
+
+fn page_result(summary: PageSummary) -> pb::PageResult {
+    pb::PageResult {
+        url: summary.url,
+        title: summary.title,
+        status_code: summary.status_code.into(),
+        agent: summary.agent,
+        job: summary.job.unwrap_or_default(),
+        skipped: summary.skipped,
+        published_at: summary.published_at,
+    }
+}
+
+// Serve the gRPC job service on `addr` until the process exits.
+#[instrument(name = "gRPC API", skip(jobs))]
+pub async fn serve(addr: SocketAddr, jobs: JobManager) {
+    info!("gRPC job service listening on {}", addr);
+    let service = CrawlerServiceServer::new(JobService { jobs });
+    if let Err(e) = Server::builder().add_service(service).serve(addr).await {
+        error!("gRPC job service stopped: {}", e);
+    }
+}
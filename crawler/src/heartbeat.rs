@@ -0,0 +1,119 @@
+use std::{
+    sync::{atomic::Ordering, Arc},
+    time::{Duration, Instant},
+};
+
+use models::WorkerIdentity;
+use tokio::{sync::watch, task::JoinHandle};
+use tracing::{debug, error};
+
+use crate::{
+    controllers::{workercontroller::WorkerControllerTrait, WorkerController},
+    crawler::unix_timestamp,
+    progress::AgentProgress,
+    repositories::WorkerHeartbeat,
+};
+
+// Publishes a heartbeat of every agent of the process into the worker registry, so operators of a
+// multi-node deployment can list the live agents (`GET /workers`, `crawler workers`).
+pub struct HeartbeatReporter {
+    controller: Arc<WorkerController>,
+    worker: WorkerIdentity,
+    crawler_type: String,
+    job: String,
+    interval: Duration,
+    agents: Vec<(String, Arc<AgentProgress>)>,
+}
+
+// Running reporter, stopped with `finish`.
+pub struct HeartbeatHandle {
+    done: watch::Sender<bool>,
+    task: JoinHandle<()>,
+}
+
+impl HeartbeatReporter {
+    pub fn new(
+        controller: Arc<WorkerController>,
+        worker: WorkerIdentity,
+        crawler_type: String,
+        job: String,
+        interval: Duration,
+    ) -> Self {
+        HeartbeatReporter {
+            controller,
+            worker,
+            crawler_type,
+            job,
+            interval,
+            agents: Vec::new(),
+        }
+    }
+
+    // Report the counters of the agent `name` (see `ProgressReporter::register`).
+    pub fn register(&mut self, name: &str, progress: Arc<AgentProgress>) {
+        self.agents.push((name.to_string(), progress));
+    }
+
+    // Id of the agent `name` in the worker registry.
+    fn agent_id(&self, name: &str) -> String {
+        format!("{}/{name}", self.worker.hostname)
+    }
+
+    // Send heartbeats every interval until `finish` is called on the returned handle, then remove
+    // the agents from the registry.
+    pub fn spawn(self) -> HeartbeatHandle {
+        let (done, mut done_rx) = watch::channel(false);
+        let task = tokio::task::spawn(async move {
+            let mut interval = tokio::time::interval(self.interval);
+            let mut last = Instant::now();
+            let mut last_processed = vec![0; self.agents.len()];
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = done_rx.changed() => break,
+                }
+
+                let elapsed = last.elapsed().as_secs_f64().max(f64::EPSILON);
+                last = Instant::now();
+                for ((name, progress), last_processed) in
+                    self.agents.iter().zip(last_processed.iter_mut())
+                {
+                    let processed = progress.processed.load(Ordering::Relaxed)
+                        + progress.failed.load(Ordering::Relaxed);
+                    let heartbeat = WorkerHeartbeat {
+                        id: self.agent_id(name),
+                        agent: name.clone(),
+                        worker: self.worker.clone(),
+                        crawler_type: self.crawler_type.clone(),
+                        job: self.job.clone(),
+                        pages_per_sec: processed.saturating_sub(*last_processed) as f64 / elapsed,
+                        queue_depth: progress.queued.load(Ordering::Relaxed),
+                        processed,
+                        last_seen: unix_timestamp(),
+                    };
+                    *last_processed = processed;
+                    if let Err(e) = self.controller.beat(heartbeat).await {
+                        error!("Failed to send the heartbeat of {}: {}", name, e);
+                    }
+                }
+            }
+
+            for (name, _) in &self.agents {
+                debug!("Removing {} from the worker registry", name);
+                let id = self.agent_id(name);
+                if let Err(e) = self.controller.remove(&self.crawler_type, &id).await {
+                    error!("Failed to remove {} from the worker registry: {}", name, e);
+                }
+            }
+        });
+        HeartbeatHandle { done, task }
+    }
+}
+
+impl HeartbeatHandle {
+    // Stop sending heartbeats and deregister the agents.
+    pub async fn finish(self) {
+        let _ = self.done.send(true);
+        let _ = self.task.await;
+    }
+}
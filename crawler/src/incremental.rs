@@ -0,0 +1,191 @@
+use std::collections::{BTreeMap, HashSet, VecDeque};
+
+use tracing::{debug, info, instrument, warn};
+use url::Url;
+
+use crate::{
+    clients::http::HttpClient,
+    controllers::{crawlrecordcontroller::CrawlRecordControllerTrait, CrawlRecordController},
+    parsers::sitemap::parse_sitemap,
+};
+
+// Work of an incremental crawl, planned from the sitemaps of the seed domains.
+#[derive(Debug, Default)]
+pub struct IncrementalPlan {
+    // Sitemap URLs that are new or whose `lastmod` is newer than their last crawl.
+    pub changed: Vec<Url>,
+    // Sitemap URLs left out because they did not change since their last crawl.
+    pub unchanged: usize,
+    // Seeds of domains without a usable sitemap, crawled as usual.
+    pub fallback_seeds: Vec<Url>,
+}
+
+// Read the sitemaps of every seed domain and keep the URLs that changed since they were last
+// crawled according to their `CrawlRecord`. At most `max_sitemaps` sitemap files are read per
+// domain (sitemap indexes included).
+#[instrument(name = "Plan incremental crawl", skip(seeds, crawl_records, client), fields(seeds = seeds.len()))]
+pub async fn plan(
+    seeds: &[Url],
+    crawl_records: &CrawlRecordController,
+    max_sitemaps: usize,
+    client: &HttpClient,
+) -> IncrementalPlan {
+    // group seeds by origin, sorted for stable output
+    let mut by_origin: BTreeMap<String, Vec<Url>> = BTreeMap::new();
+    for seed in seeds {
+        by_origin
+            .entry(seed.origin().ascii_serialization())
+            .or_default()
+            .push(seed.clone());
+    }
+
+    let mut plan = IncrementalPlan::default();
+    for (origin, seeds) in by_origin {
+        let Ok(origin_url) = Url::parse(&origin) else {
+            plan.fallback_seeds.extend(seeds);
+            continue;
+        };
+
+        let entries = sitemap_entries(client, &origin_url, max_sitemaps).await;
+        if entries.is_empty() {
+            info!("No sitemap entries for {}, crawling its seeds", origin);
+            plan.fallback_seeds.extend(seeds);
+            continue;
+        }
+
+        let (mut changed, mut unchanged) = (0, 0);
+        for (url, lastmod) in entries {
+            let record = match crawl_records.find(url.as_str()).await {
+                Ok(record) => record,
+                Err(e) => {
+                    warn!("Failed to load crawl record of {}: {}", url, e);
+                    None
+                }
+            };
+            // Pages never crawled are always included. Pages without `lastmod` cannot be
+            // compared, so they are left to full crawls.
+            let is_changed = match (record, lastmod) {
+                (None, _) => true,
+                (Some(record), Some(lastmod)) => lastmod > record.crawled_at,
+                (Some(_), None) => false,
+            };
+            if is_changed {
+                plan.changed.push(url);
+                changed += 1;
+            } else {
+                unchanged += 1;
+            }
+        }
+        info!(
+            "{}: {} changed and {} unchanged sitemap URLs",
+            origin, changed, unchanged
+        );
+        plan.unchanged += unchanged;
+    }
+
+    plan
+}
+
+// Page URLs of the origin listed in its sitemaps, with their `lastmod`. Sitemaps are taken from
+// the `Sitemap:` lines of robots.txt, falling back to `/sitemap.xml`.
+async fn sitemap_entries(
+    client: &HttpClient,
+    origin: &Url,
+    max_sitemaps: usize,
+) -> Vec<(Url, Option<u64>)> {
+    let mut pending: VecDeque<String> = robots_sitemaps(client, origin).await.into();
+    if pending.is_empty() {
+        if let Ok(default) = origin.join("/sitemap.xml") {
+            pending.push_back(default.to_string());
+        }
+    }
+
+    let mut seen_sitemaps = HashSet::new();
+    let mut seen_urls = HashSet::new();
+    let mut entries = Vec::new();
+    while let Some(location) = pending.pop_front() {
+        if !seen_sitemaps.insert(location.clone()) {
+            continue;
+        }
+        if seen_sitemaps.len() > max_sitemaps {
+            warn!(
+                "Read {} sitemaps of {}, ignoring the rest",
+                max_sitemaps, origin
+            );
+            break;
+        }
+        if location.ends_with(".gz") {
+            warn!("Skipping compressed sitemap {}", location);
+            continue;
+        }
+
+        let Some(xml) = fetch_text(client, &location).await else {
+            continue;
+        };
+        let sitemap = parse_sitemap(&xml);
+        debug!(
+            "Sitemap {}: {} URLs, {} nested sitemaps",
+            location,
+            sitemap.urls.len(),
+            sitemap.sitemaps.len()
+        );
+        pending.extend(sitemap.sitemaps.into_iter().map(|s| s.loc));
+
+        // Only keep pages of the same host: sitemaps may not list URLs of other sites.
+        for entry in sitemap.urls {
+            match Url::parse(&entry.loc) {
+                Ok(url) if url.host_str() == origin.host_str() => {
+                    if seen_urls.insert(url.to_string()) {
+                        entries.push((url, entry.lastmod));
+                    }
+                }
+                Ok(url) => debug!("Ignoring sitemap URL of another host: {}", url),
+                Err(e) => debug!("Ignoring invalid sitemap URL {}: {}", entry.loc, e),
+            }
+        }
+    }
+
+    entries
+}
+
+// Sitemap locations declared in the robots.txt of the origin.
+async fn robots_sitemaps(client: &HttpClient, origin: &Url) -> Vec<String> {
+    let Ok(robots_url) = origin.join("/robots.txt") else {
+        return Vec::new();
+    };
+    let Some(robots_txt) = fetch_text(client, robots_url.as_str()).await else {
+        return Vec::new();
+    };
+
+    robots_txt
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.trim()
+                .eq_ignore_ascii_case("sitemap")
+                .then(|| value.trim().to_string())
+        })
+        .filter(|location| !location.is_empty())
+        .collect()
+}
+
+async fn fetch_text(client: &HttpClient, url: &str) -> Option<String> {
+    let response = match client.get(url).await {
+        Ok(response) => response,
+        Err(e) => {
+            warn!("Failed to fetch {}: {}", url, e);
+            return None;
+        }
+    };
+    if !response.status().is_success() {
+        debug!("{} answered {}", url, response.status());
+        return None;
+    }
+    match response.text().await {
+        Ok(text) => Some(text),
+        Err(e) => {
+            warn!("Failed to read {}: {}", url, e);
+            None
+        }
+    }
+}
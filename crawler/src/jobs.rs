@@ -0,0 +1,336 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{info, instrument, warn};
+use url::Url;
+
+use crate::{
+    crawler::unix_timestamp,
+    events::{PageSummary, PublishedPages},
+    frontier::Frontier,
+    scope::{parse_patterns, ScopePolicy},
+};
+
+// Jobs submitted to the running crawl from the admin APIs (REST and gRPC). A job is a set of
+// seeds injected through the frontier; the requests it produces carry its id, so its pages can be
+// counted and streamed separately from the rest of the crawl, and the agents can apply its depth,
+// scope and state (paused or cancelled) to them.
+#[derive(Clone)]
+pub struct JobManager {
+    // Job of the crawl started with the process; submitted jobs are named after it.
+    crawl_job: String,
+    frontier: Frontier,
+    scope_policy: ScopePolicy,
+    published_pages: PublishedPages,
+    jobs: Arc<Mutex<HashMap<String, Job>>>,
+    next_id: Arc<AtomicU64>,
+    // Bumped whenever a job is paused, resumed or cancelled.
+    generation: Arc<AtomicU64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Running,
+    // Requests of the job are held by the agents until it is resumed.
+    Paused,
+    // Requests of the job are dropped.
+    Cancelled,
+}
+
+// Options of a submitted job, applied on top of the settings of the crawl.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct JobOptions {
+    // Link hops followed from the seeds of the job (the crawl `max_depth` when unset).
+    #[serde(default)]
+    pub max_depth: Option<u32>,
+    // Regexes a link of the job must match (one of them) when not empty.
+    #[serde(default)]
+    pub include: Vec<String>,
+    // Regexes of links of the job never followed.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+struct Job {
+    status: JobStatus,
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+}
+
+#[derive(Debug)]
+pub enum JobError {
+    Unknown(String),
+    // The job cannot go to the requested state (e.g. resuming a cancelled job).
+    InvalidState(String),
+    // The submitted job has no seed or an invalid pattern.
+    InvalidJob(String),
+    // The agents stopped, so a submitted job would never be crawled.
+    Stopped,
+}
+
+impl std::fmt::Display for JobError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JobError::Unknown(job) => write!(f, "Unknown job: {job}"),
+            JobError::InvalidState(e) | JobError::InvalidJob(e) => write!(f, "{e}"),
+            JobError::Stopped => write!(f, "The crawl has stopped and accepts no more jobs"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobStatus {
+    pub job: String,
+    pub submitted_at: u64,
+    pub state: JobState,
+    pub max_depth: Option<u32>,
+    // Seeds accepted by the frontier.
+    pub seeds: u64,
+    // Seeds rejected (invalid, already visited, ...).
+    pub rejected: u64,
+    // Pages published for the job so far.
+    pub published: u64,
+    pub last_published_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RejectedSeed {
+    pub url: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SubmittedJob {
+    pub job: String,
+    // Normalized seeds queued for crawling.
+    pub accepted: Vec<String>,
+    pub rejected: Vec<RejectedSeed>,
+}
+
+impl JobManager {
+    pub fn new(
+        crawl_job: String,
+        frontier: Frontier,
+        scope_policy: ScopePolicy,
+        published_pages: PublishedPages,
+    ) -> Self {
+        let manager = JobManager {
+            crawl_job,
+            frontier,
+            scope_policy,
+            published_pages,
+            jobs: Arc::new(Mutex::new(HashMap::new())),
+            next_id: Arc::new(AtomicU64::new(1)),
+            generation: Arc::new(AtomicU64::new(0)),
+        };
+        manager.spawn_counter();
+        manager
+    }
+
+    // Queue `seeds` as a new job. Seeds extend the crawl scope, so jobs may target new domains.
+    #[instrument(skip(self, seeds), fields(seeds = seeds.len()))]
+    pub async fn submit(
+        &self,
+        seeds: &[String],
+        priority: u8,
+        options: JobOptions,
+    ) -> Result<SubmittedJob, JobError> {
+        if self.frontier.is_closed() {
+            return Err(JobError::Stopped);
+        }
+        if seeds.is_empty() {
+            return Err(JobError::InvalidJob(
+                "A job needs at least one seed".to_string(),
+            ));
+        }
+        let include = parse_patterns(&options.include).map_err(JobError::InvalidJob)?;
+        let exclude = parse_patterns(&options.exclude).map_err(JobError::InvalidJob)?;
+        let job = format!(
+            "{}-job-{}",
+            self.crawl_job,
+            self.next_id.fetch_add(1, Ordering::Relaxed)
+        );
+
+        // The job is registered before its seeds are injected, so that agents apply its options
+        // to the very first requests.
+        self.jobs.lock().expect("jobs lock poisoned").insert(
+            job.clone(),
+            Job {
+                status: JobStatus {
+                    job: job.clone(),
+                    submitted_at: unix_timestamp(),
+                    state: JobState::Running,
+                    max_depth: options.max_depth,
+                    seeds: 0,
+                    rejected: 0,
+                    published: 0,
+                    last_published_at: None,
+                },
+                include,
+                exclude,
+            },
+        );
+
+        let mut submitted = SubmittedJob {
+            job: job.clone(),
+            accepted: Vec::new(),
+            rejected: Vec::new(),
+        };
+        for seed in seeds {
+            if let Ok(url) = Url::parse(seed) {
+                self.scope_policy.add_seed(&url);
+            }
+            match self
+                .frontier
+                .inject_for_job(Some(&job), seed, 0, priority)
+                .await
+            {
+                Ok(url) => submitted.accepted.push(url),
+                Err(e) => submitted.rejected.push(RejectedSeed {
+                    url: seed.clone(),
+                    reason: e.to_string(),
+                }),
+            }
+        }
+
+        info!(
+            "Submitted job {} ({} seeds, {} rejected)",
+            job,
+            submitted.accepted.len(),
+            submitted.rejected.len()
+        );
+        if let Some(registered) = self.jobs.lock().expect("jobs lock poisoned").get_mut(&job) {
+            registered.status.seeds = submitted.accepted.len() as u64;
+            registered.status.rejected = submitted.rejected.len() as u64;
+        }
+        Ok(submitted)
+    }
+
+    pub fn status(&self, job: &str) -> Option<JobStatus> {
+        self.jobs
+            .lock()
+            .expect("jobs lock poisoned")
+            .get(job)
+            .map(|job| job.status.clone())
+    }
+
+    // Hold the requests of `job` until it is resumed.
+    pub fn pause(&self, job: &str) -> Result<JobStatus, JobError> {
+        self.transition(job, JobState::Paused)
+    }
+
+    pub fn resume(&self, job: &str) -> Result<JobStatus, JobError> {
+        self.transition(job, JobState::Running)
+    }
+
+    // Drop the pending requests of `job`; pages being fetched are still published.
+    pub fn cancel(&self, job: &str) -> Result<JobStatus, JobError> {
+        self.transition(job, JobState::Cancelled)
+    }
+
+    fn transition(&self, job: &str, state: JobState) -> Result<JobStatus, JobError> {
+        let mut jobs = self.jobs.lock().expect("jobs lock poisoned");
+        let status = &mut jobs
+            .get_mut(job)
+            .ok_or_else(|| JobError::Unknown(job.to_string()))?
+            .status;
+        if status.state == JobState::Cancelled && state != JobState::Cancelled {
+            return Err(JobError::InvalidState(format!("Job {job} was cancelled")));
+        }
+        if status.state != state {
+            info!("Job {} is now {:?}", job, state);
+            status.state = state;
+            self.generation.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(status.clone())
+    }
+
+    // State of `job`; requests outside of submitted jobs are always running.
+    pub fn state(&self, job: Option<&str>) -> JobState {
+        job.and_then(|job| {
+            self.jobs
+                .lock()
+                .expect("jobs lock poisoned")
+                .get(job)
+                .map(|job| job.status.state)
+        })
+        .unwrap_or(JobState::Running)
+    }
+
+    // Changes whenever a job changes state, so agents know when to look at the requests they
+    // hold again.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Relaxed)
+    }
+
+    // Depth limit of `job`, if it overrides the one of the crawl.
+    pub fn max_depth(&self, job: Option<&str>) -> Option<u32> {
+        let job = job?;
+        self.jobs
+            .lock()
+            .expect("jobs lock poisoned")
+            .get(job)
+            .and_then(|job| job.status.max_depth)
+    }
+
+    // Whether a link discovered for `job` is in the scope of the job (the crawl scope is checked
+    // separately).
+    pub fn allows(&self, job: Option<&str>, url: &Url) -> bool {
+        let Some(job) = job else {
+            return true;
+        };
+        let jobs = self.jobs.lock().expect("jobs lock poisoned");
+        let Some(job) = jobs.get(job) else {
+            return true;
+        };
+        let url = url.as_str();
+        (job.include.is_empty() || job.include.iter().any(|p| p.is_match(url)))
+            && !job.exclude.iter().any(|p| p.is_match(url))
+    }
+
+    pub fn exists(&self, job: &str) -> bool {
+        self.jobs
+            .lock()
+            .expect("jobs lock poisoned")
+            .contains_key(job)
+    }
+
+    // Summaries of the pages published from now on (filter them by `PageSummary::job`).
+    pub fn results(&self) -> broadcast::Receiver<PageSummary> {
+        self.published_pages.subscribe()
+    }
+
+    // Count the pages published for each job.
+    fn spawn_counter(&self) {
+        let mut receiver = self.published_pages.subscribe();
+        let jobs = Arc::clone(&self.jobs);
+        tokio::task::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(summary) => {
+                        let Some(job) = summary.job else {
+                            continue;
+                        };
+                        if let Some(job) = jobs.lock().expect("jobs lock poisoned").get_mut(&job) {
+                            job.status.published += 1;
+                            job.status.last_published_at = Some(summary.published_at);
+                        }
+                    }
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!("Job counters missed {} published pages", missed)
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,49 @@
+// Crawl engine of the distributed web crawler, used by the `crawler` binary and embeddable in
+// other programs through `CrawlerBuilder`.
+pub mod admin;
+pub mod backpressure;
+pub mod blocking;
+pub mod bloom;
+pub mod builder;
+pub mod clients;
+pub mod compaction;
+pub mod concurrency;
+pub mod config;
+pub mod controllers;
+pub mod crawler;
+pub mod engine;
+pub mod estimator;
+pub mod events;
+pub mod exporters;
+pub mod frontier;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod heartbeat;
+pub mod incremental;
+pub mod jobs;
+pub mod memory;
+pub mod parsers;
+pub mod partitioner;
+pub mod progress;
+pub mod provenance;
+pub mod recrawl;
+pub mod reextract;
+pub mod repositories;
+pub mod requests;
+pub mod retry;
+pub mod routing;
+pub mod rules;
+pub mod scope;
+pub mod services;
+pub mod sinks;
+pub mod throttle;
+pub mod traps;
+pub mod validators;
+pub mod worker;
+pub mod writer;
+
+pub use builder::{CrawlerBuilder, PageStream};
+pub use config::CrawlerConfig;
+pub use models::PageData;
+pub use scope::ScopeMode;
+pub use sinks::{Sink, SinkMulticast};
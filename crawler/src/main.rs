@@ -1,114 +1,314 @@
-mod clients;
-mod controllers;
-mod crawler;
-mod repositories;
-mod requests;
-mod services;
-mod validators;
+mod cli;
 
 use std::sync::Arc;
 
-use crawler::Crawler;
-use drivers::{rabbit::RabbitDriver, redis::RedisDriver};
-use tokio::sync::Mutex;
-use tracing::{error, info};
+use clap::Parser;
+use cli::{Cli, Command};
+use crawler_core::{
+    clients,
+    config::CrawlerConfig,
+    controllers::{
+        self, pagecontroller::PageControllerTrait, workercontroller::WorkerControllerTrait,
+    },
+    crawler,
+    engine::{self, RunOptions},
+    estimator, exporters, parsers, progress, provenance,
+    reextract::{self, ReextractSource},
+    requests::{
+        http::{HttpRequest, HttpResponse},
+        request::Request,
+    },
+    worker,
+};
+use models::Provenance;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::{watch, Mutex};
+use tracing::{error, warn};
 
 #[tokio::main]
 async fn main() {
-    // Initialize tracing subscriber
-    tracing_subscriber::fmt::init();
+    // Initialize tracing subscriber (and the OTLP span exporter, when enabled)
+    let _telemetry = drivers::telemetry::init("crawler");
 
     // Initialize dotenv
     dotenv::dotenv().ok();
 
-    // connect to Redis
-    let redis = RedisDriver::new().expect("Failed to build Redis client");
+    let default_command = Command::Crawl {
+        progress: progress::ProgressMode::Auto,
+    };
+    let cli = Cli::parse();
 
-    // connect to RabbitMQ
-    let rabbit = RabbitDriver::new()
-        .await
-        .expect("Failed to build RabbitMQ client");
+    // Load the configuration file (if any) and apply the environment overrides
+    let config_path = cli.config.or_else(|| std::env::var("CRAWLER_CONFIG").ok());
+    let config = CrawlerConfig::load(config_path.as_deref()).expect("Invalid configuration");
 
-    // Fetch crawler type from environment variable or default to "default"
-    let crawler_type = std::env::var("CRAWLER_TYPE").unwrap_or_else(|_| "default".to_string());
+    match cli.command.unwrap_or(default_command) {
+        Command::Crawl { progress } => crawl(config, progress).await,
+        Command::Sitemap {
+            domain,
+            format,
+            output,
+            base_url,
+        } => sitemap(&config, domain, format, output, base_url).await,
+        Command::TestSelector {
+            url,
+            selector,
+            cached,
+            html,
+        } => test_selector(&config, url, selector, cached, html).await,
+        Command::Estimate { sample, depth } => {
+            let max_depth = depth.unwrap_or(config.max_depth);
+            let seeds = engine::load_seeds(&config).await;
+            let client = Arc::new(clients::http::get_default_http_client(&client_settings(
+                &config,
+            )));
+            let estimate = estimator::estimate(&seeds, max_depth, sample, client).await;
+            println!("{estimate}");
+        }
+        Command::Reextract {
+            from,
+            path,
+            domain,
+            output,
+        } => reextract(&config, from, path, domain, output).await,
+        Command::Workers {
+            crawler_type,
+            all,
+            json,
+        } => workers(&config, crawler_type, all, json).await,
+    }
+}
 
-    // Fetch max depth from environment variable or default to 2
-    let max_depth = std::env::var("MAX_DEPTH")
-        .unwrap_or_else(|_| "2".to_string())
-        .parse::<u32>()
-        .expect("MAX_DEPTH must be a valid u32");
+// Settings of the HTTP clients (User-Agent, address policy, DNS cache), exiting when invalid
+fn client_settings(config: &CrawlerConfig) -> clients::http::ClientSettings {
+    match engine::configure(config) {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("Invalid HTTP client settings: {e}");
+            std::process::exit(1);
+        }
+    }
+}
 
-    // Fetch respect_robots_txt from environment variable or default to true
-    let respect_robots_txt = std::env::var("RESPECT_ROBOTS_TXT")
-        .unwrap_or_else(|_| "true".to_string())
-        .parse::<bool>()
-        .expect("RESPECT_ROBOTS_TXT must be a valid boolean");
+// Run a CSS selector against a single page (live or stored) and print the matches
+async fn test_selector(
+    config: &CrawlerConfig,
+    url: String,
+    selector: String,
+    cached: bool,
+    html: bool,
+) {
+    let (body, links, meta) = if cached {
+        let redis = drivers::connect_cache_driver_with(&config.redis)
+            .expect("Failed to build cache client");
+        let page_controller = controllers::PageController::new(Arc::new(Mutex::new(redis)));
+        let page = match page_controller.find(&url).await {
+            Ok(Some(page)) => page,
+            Ok(None) => {
+                eprintln!("No stored page for {url} (is DEBUG_STORE_PAGES enabled?)");
+                std::process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("Failed to load stored page: {e}");
+                std::process::exit(1);
+            }
+        };
+        let parsed = parsers::html::parse_html(&page.url, &page.body, None).unwrap_or_default();
+        (page.body, parsed.links, parsed.meta)
+    } else {
+        let mut req = HttpRequest::new(&url, 0);
+        req.client = Some(Arc::new(clients::http::get_default_http_client(
+            &client_settings(config),
+        )));
+        match req.execute().await {
+            Ok(HttpResponse {
+                meta,
+                extra: Some(extra),
+                ..
+            }) => (extra.body, extra.links, meta),
+            Ok(_) => {
+                eprintln!("No body returned for {url}");
+                std::process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("Failed to fetch {url}: {e}");
+                std::process::exit(1);
+            }
+        }
+    };
 
-    // Toy seeds to showcase usage
-    let seeds = match repositories::load_seeds_from_dir("./seeds").await {
-        Ok(u) => u,
+    let matches = match parsers::html::select(&body, &selector) {
+        Ok(m) => m,
         Err(e) => {
-            error!(
-                "Failed to load seeds from directory: {}. Fallback to default (generic) seeds.",
-                e
-            );
-            repositories::load_default_seeds()
+            eprintln!("{e}");
+            std::process::exit(1);
         }
     };
 
-    // Set the number of agents (threads) you want to run concurrently.
-    let n_agents = std::env::var("N_AGENTS")
-        .unwrap_or_else(|_| num_cpus::get().to_string())
-        .parse::<usize>()
-        .unwrap();
-    info!("Number of agents: {}", n_agents);
-
-    let chunk_size = seeds.len().div_ceil(n_agents);
-    let mut handles = Vec::new();
-    let mut id_counter: u16 = 1;
-
-    // create UrlController to mark visited URLs
-    // NOTE: we use two Arc here because both UrlController and RedisDriver may be shared
-    // independently across multiple agents (e.g. each agent currently has one UrlController, but
-    // in the future we may want to have multiple controllers based on the same driver.
-    let url_controller = Arc::new(controllers::UrlController::new(Arc::new(Mutex::new(redis))));
-
-    // wrap RabbitMQ driver in Arc to share it across multiple agents
-    let rabbit = Arc::new(rabbit);
-
-    for chunk in seeds.chunks(chunk_size) {
-        // Convert the chunk of seeds (which are String) into Vec<&str> for the agent.
-        let seeds_chunk = chunk.to_vec();
-
-        // Create immutable borrow of the id_counter for the current agent.
-        let current_id = id_counter;
-        let crawler_type = crawler_type.clone();
-        let log_name = format!("crawler-{crawler_type}-{current_id}");
-        let agent_url_controller = Arc::clone(&url_controller);
-        let rabbit = Arc::clone(&rabbit);
-
-        // start the agent in a separate tas
-        let handle = tokio::task::spawn(async move {
-            // create new crawler instance
-            let mut agent = Crawler::new(
-                log_name,
-                agent_url_controller,
-                rabbit,
-                respect_robots_txt,
-                max_depth,
-                seeds_chunk,
-            );
-
-            // start agent asynchronously
-            agent.start().await;
-        });
-        handles.push(handle);
-        id_counter += 1;
+    println!(
+        "{} match(es) for '{}' on {} ({} links, {} meta tags extracted)",
+        matches.len(),
+        selector,
+        url,
+        links.len(),
+        meta.len()
+    );
+    for (i, m) in matches.iter().enumerate() {
+        println!("[{}] {}", i + 1, if html { &m.html } else { &m.text });
     }
+}
+
+// Run the current extraction pipeline over stored bodies and write the updated pages
+async fn reextract(
+    config: &CrawlerConfig,
+    from: ReextractSource,
+    path: Option<String>,
+    domain: Option<String>,
+    output: String,
+) {
+    let page_controller = (from == ReextractSource::Redis).then(|| {
+        let redis = drivers::connect_cache_driver_with(&config.redis)
+            .expect("Failed to build cache client");
+        controllers::PageController::new(Arc::new(Mutex::new(redis)))
+    });
+
+    // Site-specific extraction profiles of the current configuration
+    let selectors = parsers::selectors::SelectorParser::from_config(&config.extraction)
+        .expect("Extraction profiles must be valid");
+
+    // Re-extracted pages carry the provenance of this run, not of the original crawl.
+    let provenance = Provenance {
+        agent: "reextract".to_string(),
+        job: provenance::job_id(config, crawler::unix_timestamp()),
+        crawler_version: provenance::CRAWLER_VERSION.to_string(),
+        config_hash: provenance::config_hash(config),
+        worker: worker::identity(&config.worker),
+    };
+
+    match reextract::reextract(
+        from,
+        page_controller.as_ref(),
+        path.as_deref(),
+        domain.as_deref(),
+        &output,
+        &provenance,
+        selectors.as_ref(),
+    )
+    .await
+    {
+        Ok(stats) => println!(
+            "Re-extracted {} pages to {} ({} skipped)",
+            stats.extracted, output, stats.skipped
+        ),
+        Err(e) => {
+            error!("Re-extraction failed: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
 
-    // Wait for all agents to complete.
-    for handle in handles {
-        handle.await.unwrap();
+// Export sitemaps of the crawled domains
+async fn sitemap(
+    config: &CrawlerConfig,
+    domain: Option<String>,
+    format: exporters::sitemap::SitemapFormat,
+    output: String,
+    base_url: Option<String>,
+) {
+    let redis =
+        drivers::connect_cache_driver_with(&config.redis).expect("Failed to build cache client");
+    let url_controller = controllers::UrlController::new(Arc::new(Mutex::new(redis)));
+
+    match exporters::sitemap::export_sitemaps(
+        &url_controller,
+        domain.as_deref(),
+        format,
+        &output,
+        base_url.as_deref(),
+    )
+    .await
+    {
+        Ok(files) => println!("Exported {} sitemap(s) to {}", files.len(), output),
+        Err(e) => {
+            error!("Sitemap export failed: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+// List the agents registered in the worker registry, from their latest heartbeat
+async fn workers(config: &CrawlerConfig, crawler_type: Option<String>, all: bool, json: bool) {
+    let redis =
+        drivers::connect_cache_driver_with(&config.redis).expect("Failed to build cache client");
+    let worker_controller = controllers::WorkerController::new(Arc::new(Mutex::new(redis)));
+    let heartbeats = match worker_controller.list(crawler_type.as_deref()).await {
+        Ok(heartbeats) => heartbeats,
+        Err(e) => {
+            eprintln!("Failed to list workers: {e}");
+            std::process::exit(1);
+        }
+    };
+
+    let now = crawler::unix_timestamp();
+    let stale_after = config.heartbeat.stale_after_secs;
+    let workers: Vec<_> = heartbeats
+        .into_iter()
+        .map(|heartbeat| (heartbeat.is_alive(now, stale_after), heartbeat))
+        .filter(|(alive, _)| all || *alive)
+        .collect();
+    if json {
+        for (alive, heartbeat) in &workers {
+            let mut line = serde_json::to_value(heartbeat).unwrap_or_default();
+            line["alive"] = (*alive).into();
+            println!("{line}");
+        }
+        return;
+    }
+
+    println!(
+        "{:<40} {:<12} {:<6} {:>10} {:>8} {:>10} {:>10}",
+        "AGENT", "TYPE", "STATE", "PAGES/SEC", "QUEUE", "PROCESSED", "LAST SEEN"
+    );
+    for (alive, heartbeat) in &workers {
+        println!(
+            "{:<40} {:<12} {:<6} {:>10.2} {:>8} {:>10} {:>9}s",
+            heartbeat.id,
+            heartbeat.crawler_type,
+            if *alive { "alive" } else { "dead" },
+            heartbeat.pages_per_sec,
+            heartbeat.queue_depth,
+            heartbeat.processed,
+            now.saturating_sub(heartbeat.last_seen)
+        );
+    }
+    println!("{} worker(s)", workers.len());
+}
+
+// Run the crawler agents until the queue is exhausted or a shutdown is requested
+async fn crawl(config: CrawlerConfig, progress: progress::ProgressMode) {
+    // Broadcast SIGINT/SIGTERM to all agents so they can checkpoint their queue before exiting.
+    let (shutdown_tx, shutdown) = watch::channel(false);
+    tokio::task::spawn(async move {
+        let mut sigterm = signal(SignalKind::terminate()).expect("Failed to listen for SIGTERM");
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => warn!("Received SIGINT, shutting down"),
+            _ = sigterm.recv() => warn!("Received SIGTERM, shutting down"),
+        }
+        let _ = shutdown_tx.send(true);
+    });
+
+    let options = RunOptions {
+        progress,
+        seeds: None,
+        pages: None,
+        publish: true,
+        sinks: Vec::new(),
+        shutdown,
+        ready: None,
+    };
+    if let Err(e) = engine::run(config, options).await {
+        error!("Crawl failed: {}", e);
+        std::process::exit(1);
     }
 
     println!("All agents have completed their tasks.");
@@ -0,0 +1,167 @@
+use std::{
+    fmt::{Display, Formatter},
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use drivers::{redis::MemoryUsage, CacheStore};
+use tokio::sync::Mutex;
+use tracing::{debug, error, info, warn};
+
+use crate::controllers::{urlcontroller::UrlControllerTrait, UrlController};
+
+// How close Redis is to its memory limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MemoryPressure {
+    Normal,
+    // New visited URLs go to the bloom filter and no new external domains are admitted.
+    Elevated,
+    // No newly discovered links are admitted: agents only drain their queues.
+    Critical,
+}
+
+impl Display for MemoryPressure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            MemoryPressure::Normal => "normal",
+            MemoryPressure::Elevated => "elevated",
+            MemoryPressure::Critical => "critical",
+        };
+        write!(f, "{name}")
+    }
+}
+
+// Latest memory pressure seen by the `MemoryGuard`, shared with the agents.
+#[derive(Clone, Default)]
+pub struct PressureLevel(Arc<AtomicU8>);
+
+impl PressureLevel {
+    pub fn get(&self) -> MemoryPressure {
+        Self::decode(self.0.load(Ordering::Relaxed))
+    }
+
+    // Store `pressure`, returning the previous level.
+    fn set(&self, pressure: MemoryPressure) -> MemoryPressure {
+        Self::decode(self.0.swap(pressure as u8, Ordering::Relaxed))
+    }
+
+    fn decode(level: u8) -> MemoryPressure {
+        match level {
+            0 => MemoryPressure::Normal,
+            1 => MemoryPressure::Elevated,
+            _ => MemoryPressure::Critical,
+        }
+    }
+}
+
+// Watches the memory usage of Redis so that a long crawl degrades instead of hitting the Redis
+// OOM error. Once usage crosses `elevated_ratio` of the limit, new visited URLs are tracked in the
+// bloom filter (exact mode) and the agents stop admitting new external domains; over
+// `critical_ratio` they stop admitting discovered links altogether. Each change of pressure is
+// logged as a `redis_memory_pressure` event.
+pub struct MemoryGuard {
+    redis: Arc<Mutex<CacheStore>>,
+    url_controller: Arc<UrlController>,
+    level: PressureLevel,
+    interval: Duration,
+    // Memory limit; the `maxmemory` of Redis when unset.
+    max_bytes: Option<u64>,
+    elevated_ratio: f64,
+    critical_ratio: f64,
+}
+
+impl MemoryGuard {
+    pub fn new(
+        redis: Arc<Mutex<CacheStore>>,
+        url_controller: Arc<UrlController>,
+        interval: Duration,
+        max_bytes: Option<u64>,
+        elevated_ratio: f64,
+        critical_ratio: f64,
+    ) -> Self {
+        MemoryGuard {
+            redis,
+            url_controller,
+            level: PressureLevel::default(),
+            interval,
+            max_bytes,
+            elevated_ratio,
+            critical_ratio,
+        }
+    }
+
+    // Pressure level updated by `run`, to hand over to `Crawler::watch_memory`.
+    pub fn level(&self) -> PressureLevel {
+        self.level.clone()
+    }
+
+    // Check the memory usage of Redis every interval, for as long as the process runs.
+    pub async fn run(self) {
+        let mut interval = tokio::time::interval(self.interval.max(Duration::from_secs(1)));
+        loop {
+            interval.tick().await;
+            let usage = match self.redis.lock().await.memory_usage() {
+                Ok(usage) => usage,
+                Err(e) => {
+                    error!("Failed to read Redis memory usage: {}", e);
+                    continue;
+                }
+            };
+            let Some(limit) = self.max_bytes.or(usage.max_bytes) else {
+                warn!(
+                    "Neither Redis maxmemory nor a memory guard limit is set, stopping the guard"
+                );
+                return;
+            };
+            self.update(usage, limit);
+        }
+    }
+
+    fn update(&self, usage: MemoryUsage, limit: u64) {
+        let ratio = usage.used_bytes as f64 / limit.max(1) as f64;
+        let pressure = if ratio >= self.critical_ratio {
+            MemoryPressure::Critical
+        } else if ratio >= self.elevated_ratio {
+            MemoryPressure::Elevated
+        } else {
+            MemoryPressure::Normal
+        };
+        debug!(
+            "Redis memory usage: {} of {} bytes ({})",
+            usage.used_bytes, limit, pressure
+        );
+
+        let previous = self.level.set(pressure);
+        if pressure == previous {
+            return;
+        }
+        if pressure > previous {
+            warn!(
+                event = "redis_memory_pressure",
+                pressure = %pressure,
+                used_bytes = usage.used_bytes,
+                limit_bytes = limit,
+                "Redis memory usage at {:.0}% of the limit",
+                ratio * 100.0
+            );
+        } else {
+            info!(
+                event = "redis_memory_pressure",
+                pressure = %pressure,
+                used_bytes = usage.used_bytes,
+                limit_bytes = limit,
+                "Redis memory usage back to {:.0}% of the limit",
+                ratio * 100.0
+            );
+        }
+
+        // NOTE: the switch to the bloom filter is not undone when the pressure drops: the URLs
+        // visited since then are only known to the filter.
+        if pressure >= MemoryPressure::Elevated && self.url_controller.switch_to_bloom() {
+            warn!("Tracking new visited URLs in the bloom filter to save Redis memory");
+        }
+    }
+}
@@ -0,0 +1,32 @@
+pub mod charset;
+pub mod fingerprint;
+pub mod html;
+pub mod language;
+pub mod license;
+pub mod pdf;
+pub mod readability;
+pub mod robots;
+pub mod routes;
+pub mod selectors;
+pub mod sitemap;
+pub mod structured;
+pub mod text;
+
+use crate::requests::error::RequestError;
+use html::ParsedHtml;
+use selectors::SelectorParser;
+
+// Extraction pipeline applied to every downloaded body: links, meta tags, usage rights and robots
+// directives from the HTML (plus the fields of the matching `selectors` profile), and
+// `X-Robots-Tag` directives from the response headers.
+pub fn extract(
+    url: &str,
+    headers: &[String],
+    body: &str,
+    selectors: Option<&SelectorParser>,
+) -> Result<ParsedHtml, RequestError> {
+    let mut parsed = html::parse_html(url, body, selectors)?;
+    license::apply_robots_headers(&mut parsed.usage, headers);
+    robots::apply_headers(&mut parsed.robots, headers);
+    Ok(parsed)
+}
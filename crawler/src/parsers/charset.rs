@@ -0,0 +1,51 @@
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+use once_cell::sync::Lazy;
+use regex::bytes::Regex;
+
+// Bytes of the body searched for a `<meta>` charset declaration, as browsers do.
+const META_PRESCAN_BYTES: usize = 1024;
+
+// `<meta charset="...">` and `<meta http-equiv="Content-Type" content="text/html; charset=...">`.
+static META_CHARSET: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?i)<meta\s[^>]*?charset\s*=\s*["']?\s*([a-z0-9_.:\-]+)"#).unwrap());
+
+// Decode a downloaded body to UTF-8 text. The encoding is taken from, in order: a byte order
+// mark, the `charset` parameter of the Content-Type header, a `<meta>` declaration at the start of
+// the body, and sniffing (UTF-8 when the body is valid UTF-8, else windows-1252, the default of
+// legacy Western pages). Returns the text and the encoding it was decoded from.
+pub fn decode(body: &[u8], content_type: Option<&str>) -> (String, &'static Encoding) {
+    let encoding = content_type
+        .and_then(header_charset)
+        .or_else(|| meta_charset(body))
+        .unwrap_or_else(|| sniff(body));
+    // `decode` gives precedence to a byte order mark and replaces malformed sequences.
+    let (text, encoding, _) = encoding.decode(body);
+    (text.into_owned(), encoding)
+}
+
+// Encoding named by the `charset` parameter of a Content-Type value.
+fn header_charset(content_type: &str) -> Option<&'static Encoding> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        if !name.trim().eq_ignore_ascii_case("charset") {
+            return None;
+        }
+        Encoding::for_label(value.trim().trim_matches(['"', '\'']).as_bytes())
+    })
+}
+
+// Encoding declared by a `<meta>` tag at the start of the body. As in browsers, UTF-16 labels mean
+// UTF-8 since a document readable as ASCII cannot be UTF-16.
+fn meta_charset(body: &[u8]) -> Option<&'static Encoding> {
+    let prefix = &body[..body.len().min(META_PRESCAN_BYTES)];
+    let label = META_CHARSET.captures(prefix)?.get(1)?.as_bytes();
+    Encoding::for_label(label).map(Encoding::output_encoding)
+}
+
+fn sniff(body: &[u8]) -> &'static Encoding {
+    if std::str::from_utf8(body).is_ok() {
+        UTF_8
+    } else {
+        WINDOWS_1252
+    }
+}
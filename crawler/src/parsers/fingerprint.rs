@@ -0,0 +1,58 @@
+use models::ContentFingerprint;
+use scraper::Html;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    bloom::{fnv1a, splitmix64},
+    parsers::text,
+};
+
+// Number of consecutive words hashed together into a SimHash feature.
+const SHINGLE_WORDS: usize = 3;
+
+// Fingerprint of the visible text of a document, or None for pages without text. The text is
+// cleaned before hashing (markup, scripts and styles removed, whitespace collapsed, lowercased),
+// so pages rendered from the same content with different markup share a content hash.
+pub fn fingerprint(document: &Html) -> Option<ContentFingerprint> {
+    let text = visible_text(document);
+    if text.is_empty() {
+        return None;
+    }
+    let content_hash = Sha256::digest(text.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    Some(ContentFingerprint {
+        content_hash,
+        simhash: simhash(&text),
+    })
+}
+
+// Lowercased words of the visible text of the document (see `text::paragraphs`), separated by
+// one space.
+pub fn visible_text(document: &Html) -> String {
+    text::paragraphs(document.tree.root(), |_| false)
+        .iter()
+        .flat_map(|paragraph| paragraph.split(' '))
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// SimHash over word shingles: each shingle votes on every bit of the hash with the bits of its
+// own hash, so texts sharing most shingles end up with hashes differing in a few bits.
+fn simhash(text: &str) -> u64 {
+    let words: Vec<&str> = text.split(' ').collect();
+    let mut votes = [0i64; 64];
+    for shingle in words.windows(SHINGLE_WORDS.min(words.len())) {
+        let hash = splitmix64(fnv1a(shingle.join(" ").as_bytes()));
+        for (bit, vote) in votes.iter_mut().enumerate() {
+            *vote += if hash >> bit & 1 == 1 { 1 } else { -1 };
+        }
+    }
+    votes
+        .iter()
+        .enumerate()
+        .filter(|(_, vote)| **vote > 0)
+        .fold(0, |hash, (bit, _)| hash | 1 << bit)
+}
@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+
+use models::{ContentFingerprint, Link, RobotsDirectives, StructuredData, UsageRights};
+use once_cell::sync::Lazy;
+use scraper::Selector;
+use tracing::{debug, instrument, warn};
+
+use crate::{
+    parsers::{
+        fingerprint, language, license, robots, routes, selectors::SelectorParser,
+        structured::StructuredDataExtractor,
+    },
+    requests::error::RequestError,
+    validators,
+};
+
+static LINKS: Lazy<Selector> = Lazy::new(|| Selector::parse("a[href]").expect("valid selector"));
+static NAMED_META: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("meta[name]").expect("valid selector"));
+static CHARSET_META: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("meta[charset]").expect("valid selector"));
+static REL_LINKS: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("link[rel][href]").expect("valid selector"));
+static OG_URL: Lazy<Selector> =
+    Lazy::new(|| Selector::parse(r#"meta[property="og:url"][content]"#).expect("valid selector"));
+
+// Links, meta tags, usage rights, robots directives, the canonical URL, the content fingerprint,
+// the language and the structured data extracted from an HTML document.
+#[derive(Debug, Clone, Default)]
+pub struct ParsedHtml {
+    pub links: Vec<Link>,
+    pub meta: Vec<String>,
+    pub usage: UsageRights,
+    pub robots: RobotsDirectives,
+    // URL the page declares as its canonical version (`<link rel="canonical">`, else `og:url`).
+    pub canonical_url: Option<String>,
+    // Fingerprint of the visible text, to detect pages served at several URLs.
+    pub fingerprint: Option<ContentFingerprint>,
+    // ISO 639-3 code of the language of the visible text, when reliably detected.
+    pub language: Option<String>,
+    // JSON-LD, OpenGraph/Twitter card and microdata metadata, if any.
+    pub structured_data: Option<StructuredData>,
+    // Fields extracted by the matching site-specific profile (see `SelectorParser`).
+    pub custom_fields: HashMap<String, String>,
+    // Client-side routes of single-page applications (see `routes::discover_routes`).
+    pub routes: Vec<String>,
+}
+
+// Element matched by a CSS selector.
+#[derive(Debug, Clone)]
+pub struct SelectorMatch {
+    pub text: String,
+    pub html: String,
+}
+
+// Run a CSS `selector` against the HTML `body` and return every matching element.
+#[instrument(skip(body), fields(body_len = body.len()))]
+pub fn select(body: &str, selector: &str) -> Result<Vec<SelectorMatch>, RequestError> {
+    let document = scraper::Html::parse_document(body);
+    let selector = scraper::Selector::parse(selector)
+        .map_err(|e| RequestError::Parse(format!("Selector parse error: {e}")))?;
+
+    Ok(document
+        .select(&selector)
+        .map(|element| SelectorMatch {
+            text: element
+                .text()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" "),
+            html: element.html(),
+        })
+        .collect())
+}
+
+// Extract links and meta tags from the HTML `body` fetched from `target`, plus the fields of the
+// `selectors` profile matching it.
+#[instrument(skip(body, selectors), fields(body_len = body.len()))]
+pub fn parse_html(
+    target: &str,
+    body: &str,
+    selectors: Option<&SelectorParser>,
+) -> Result<ParsedHtml, RequestError> {
+    // Parse the HTML body using the scraper crate.
+    debug!("Parsing HTML body");
+    let document = scraper::Html::parse_document(body);
+
+    // Extract all links from anchor tags (<a href="...">).
+    let mut links: Vec<Link> = document
+        .select(&LINKS)
+        .enumerate()
+        .filter_map(|(position, element)| {
+            let href = element.value().attr("href")?.to_string();
+            let rel = element
+                .value()
+                .attr("rel")
+                .map(|rel| rel.split_whitespace().map(str::to_lowercase).collect())
+                .unwrap_or_default();
+            Some(Link::new(href, anchor_text(&element), rel, position))
+        })
+        .collect();
+    debug!("Found {} links", links.len());
+
+    // If links start with a slash, prepend the domain.
+    let url = url::Url::parse(target)
+        .map_err(|e| RequestError::InvalidUrl(format!("Error parsing target URL: {e}")))?;
+
+    for link in links.iter_mut() {
+        if link.href.starts_with('/') {
+            link.href = format!("{}{}", url.origin().ascii_serialization(), link.href);
+        }
+    }
+
+    // Now, only keep links that are valid URLs.
+    links.retain(|link| {
+        let is_ok = validators::validate_url(&link.href).is_ok();
+        if !is_ok {
+            warn!("Invalid link found and removed: {}", link.href);
+        }
+        is_ok
+    });
+
+    // Extract meta tags with a name attribute.
+    let mut meta: Vec<String> = document
+        .select(&NAMED_META)
+        .filter_map(|element| {
+            let name = element.value().attr("name")?;
+            let content = element.value().attr("content")?;
+            Some(format!("{name}: {content}"))
+        })
+        .collect();
+
+    // Also extract meta tags with a charset attribute.
+    meta.extend(document.select(&CHARSET_META).filter_map(|element| {
+        element
+            .value()
+            .attr("charset")
+            .map(|charset| format!("charset: {charset}"))
+    }));
+    debug!("Found {} meta tags", meta.len());
+
+    // Extract license signals and AI-usage directives.
+    let usage = license::extract_usage_rights(&document, &url);
+
+    // Extract the noindex/nofollow directives of robots meta tags.
+    let robots = robots::extract_directives(&document);
+
+    // Extract the canonical URL of the page.
+    let canonical_url = canonical_url(&document, &url);
+    if let Some(canonical_url) = &canonical_url {
+        debug!("Canonical URL: {}", canonical_url);
+    }
+
+    // Fingerprint the visible text of the page.
+    let fingerprint = fingerprint::fingerprint(&document);
+
+    // Detect the language of the visible text.
+    let language = language::detect(&document);
+    debug!("Detected language: {:?}", language);
+
+    // Extract JSON-LD, OpenGraph/Twitter card and microdata metadata.
+    let structured_data = StructuredDataExtractor::new(&url).extract(&document);
+
+    // Extract the fields of the site-specific profile of the page.
+    let custom_fields = selectors
+        .map(|selectors| selectors.extract(&url, &document))
+        .unwrap_or_default();
+    debug!("Extracted {} custom fields", custom_fields.len());
+
+    // Discover client-side routes, followed when SPA route discovery is enabled.
+    let routes = routes::discover_routes(&document, &url);
+    debug!("Found {} client-side routes", routes.len());
+
+    Ok(ParsedHtml {
+        links,
+        meta,
+        usage,
+        robots,
+        canonical_url,
+        fingerprint,
+        language,
+        structured_data,
+        custom_fields,
+        routes,
+    })
+}
+
+// Canonical URL declared by `<link rel="canonical">`, falling back to the Open Graph `og:url`,
+// resolved against `base`. Invalid URLs are ignored.
+fn canonical_url(document: &scraper::Html, base: &url::Url) -> Option<String> {
+    let links = document.select(&REL_LINKS).filter_map(|element| {
+        let is_canonical = element.value().attr("rel").is_some_and(|rel| {
+            rel.split_whitespace()
+                .any(|r| r.eq_ignore_ascii_case("canonical"))
+        });
+        is_canonical.then(|| element.value().attr("href")).flatten()
+    });
+    let og_urls = document
+        .select(&OG_URL)
+        .filter_map(|element| element.value().attr("content"));
+
+    links.chain(og_urls).find_map(|href| {
+        let mut url = base.join(href.trim()).ok()?;
+        url.set_fragment(None);
+        validators::validate_url(url.as_str()).ok()?;
+        Some(url.to_string())
+    })
+}
+
+// Visible text of an anchor, or its `aria-label`/`title` when it has none (e.g. image links).
+fn anchor_text(element: &scraper::ElementRef) -> String {
+    let text = element.text().collect::<Vec<_>>().join(" ");
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if !text.is_empty() {
+        return text;
+    }
+    ["aria-label", "title"]
+        .iter()
+        .find_map(|attribute| element.value().attr(attribute))
+        .map(|label| label.split_whitespace().collect::<Vec<_>>().join(" "))
+        .unwrap_or_default()
+}
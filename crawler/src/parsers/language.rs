@@ -0,0 +1,49 @@
+use scraper::Html;
+use whatlang::Lang;
+
+use crate::{config::LanguageFilterMode, parsers::fingerprint};
+
+// Characters of visible text the language is detected from; more does not improve detection of
+// whole pages but slows it down.
+const DETECTION_CHARS: usize = 4096;
+
+// ISO 639-3 code (`eng`, `deu`, `jpn`, ...) of the language of the visible text of a document,
+// when it can be reliably detected.
+pub fn detect(document: &Html) -> Option<String> {
+    let text = fingerprint::visible_text(document);
+    let end = text
+        .char_indices()
+        .nth(DETECTION_CHARS)
+        .map_or(text.len(), |(i, _)| i);
+    whatlang::detect(&text[..end])
+        .filter(|info| info.is_reliable())
+        .map(|info| info.lang().code().to_string())
+}
+
+// Languages a crawl is restricted to. Pages whose language could not be detected are allowed.
+#[derive(Debug, Clone)]
+pub struct LanguagePolicy {
+    languages: Vec<String>,
+    pub mode: LanguageFilterMode,
+}
+
+impl LanguagePolicy {
+    // Policy restricting the crawl to `languages` (ISO 639-3 codes), or None when empty.
+    pub fn new(languages: &[String], mode: LanguageFilterMode) -> Result<Option<Self>, String> {
+        let languages = languages
+            .iter()
+            .map(|code| {
+                Lang::from_code(code.to_lowercase())
+                    .map(|lang| lang.code().to_string())
+                    .ok_or_else(|| {
+                        format!("Unknown language '{code}' (expected an ISO 639-3 code)")
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((!languages.is_empty()).then_some(LanguagePolicy { languages, mode }))
+    }
+
+    pub fn allows(&self, language: Option<&str>) -> bool {
+        language.is_none_or(|language| self.languages.iter().any(|l| l == language))
+    }
+}
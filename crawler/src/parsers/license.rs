@@ -0,0 +1,171 @@
+use models::UsageRights;
+use once_cell::sync::Lazy;
+use scraper::{Html, Selector};
+use tracing::debug;
+use url::Url;
+
+// Meta tag names that carry a license or rights statement.
+const LICENSE_META_NAMES: &[&str] = &[
+    "license",
+    "dc.license",
+    "dc.rights",
+    "dcterms.license",
+    "dcterms.rights",
+];
+
+static REL_LINKS: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("a[rel][href], link[rel][href]").expect("valid selector"));
+static NAMED_META: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("meta[name][content]").expect("valid selector"));
+static JSON_LD: Lazy<Selector> =
+    Lazy::new(|| Selector::parse(r#"script[type="application/ld+json"]"#).expect("valid selector"));
+
+// Collect license signals and AI-usage directives declared in an HTML document. Relative license
+// URLs are resolved against `base`.
+pub fn extract_usage_rights(document: &Html, base: &Url) -> UsageRights {
+    let mut usage = UsageRights::default();
+
+    // <a rel="license" href="..."> and <link rel="license" href="...">
+    for element in document.select(&REL_LINKS) {
+        let is_license = element.value().attr("rel").is_some_and(|rel| {
+            rel.split_whitespace()
+                .any(|r| r.eq_ignore_ascii_case("license"))
+        });
+        if is_license {
+            if let Some(href) = element.value().attr("href") {
+                push_license(&mut usage, resolve(base, href));
+            }
+        }
+    }
+
+    // <meta name="license" content="..."> and Dublin Core rights, plus robots directives
+    for element in document.select(&NAMED_META) {
+        let name = element
+            .value()
+            .attr("name")
+            .unwrap_or_default()
+            .to_lowercase();
+        let content = element.value().attr("content").unwrap_or_default();
+        if LICENSE_META_NAMES.contains(&name.as_str()) {
+            push_license(&mut usage, resolve(base, content));
+        } else if name == "robots" || name.ends_with("bot") {
+            apply_directives(&mut usage, content);
+        }
+    }
+
+    // "license" properties of JSON-LD structured data
+    for element in document.select(&JSON_LD) {
+        let text = element.text().collect::<String>();
+        match serde_json::from_str::<serde_json::Value>(&text) {
+            Ok(value) => {
+                let mut found = Vec::new();
+                collect_json_ld_licenses(&value, &mut found);
+                for license in found {
+                    push_license(&mut usage, resolve(base, &license));
+                }
+            }
+            Err(e) => debug!("Skipping invalid JSON-LD block: {}", e),
+        }
+    }
+
+    usage.creative_commons = usage.licenses.iter().find_map(|l| creative_commons(l));
+    usage
+}
+
+// Apply `X-Robots-Tag` directives from response headers ("Key: Value" strings).
+pub fn apply_robots_headers(usage: &mut UsageRights, headers: &[String]) {
+    for header in headers {
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("x-robots-tag") {
+                apply_directives(usage, value);
+            }
+        }
+    }
+}
+
+// Record `noai`/`noimageai` from a comma-separated directive list. Values may be prefixed with a
+// user agent (`somebot: noai`), which is ignored.
+fn apply_directives(usage: &mut UsageRights, directives: &str) {
+    for directive in directives.split([',', ' ', ':']) {
+        match directive.trim().to_lowercase().as_str() {
+            "noai" => usage.noai = true,
+            "noimageai" => usage.noimageai = true,
+            _ => {}
+        }
+    }
+}
+
+fn push_license(usage: &mut UsageRights, license: String) {
+    if !license.is_empty() && !usage.licenses.contains(&license) {
+        usage.licenses.push(license);
+    }
+}
+
+// Resolve relative license URLs; plain identifiers (e.g. "CC-BY-4.0") are kept as they are.
+fn resolve(base: &Url, value: &str) -> String {
+    let value = value.trim();
+    if value.starts_with('/') || value.starts_with("./") || value.starts_with("../") {
+        if let Ok(url) = base.join(value) {
+            return url.to_string();
+        }
+    }
+    value.to_string()
+}
+
+// Walk a JSON-LD value and collect every "license" property (strings or `{"@id"|"url": ...}`).
+fn collect_json_ld_licenses(value: &serde_json::Value, found: &mut Vec<String>) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map {
+                if key == "license" {
+                    collect_license_value(value, found);
+                } else {
+                    collect_json_ld_licenses(value, found);
+                }
+            }
+        }
+        serde_json::Value::Array(values) => {
+            values
+                .iter()
+                .for_each(|v| collect_json_ld_licenses(v, found));
+        }
+        _ => {}
+    }
+}
+
+fn collect_license_value(value: &serde_json::Value, found: &mut Vec<String>) {
+    match value {
+        serde_json::Value::String(s) => found.push(s.clone()),
+        serde_json::Value::Array(values) => {
+            values.iter().for_each(|v| collect_license_value(v, found));
+        }
+        serde_json::Value::Object(map) => {
+            if let Some(id) = map
+                .get("@id")
+                .or_else(|| map.get("url"))
+                .and_then(|v| v.as_str())
+            {
+                found.push(id.to_string());
+            }
+        }
+        _ => {}
+    }
+}
+
+// Normalize a Creative Commons license URL, e.g.
+// `https://creativecommons.org/licenses/by-sa/4.0/` -> `CC BY-SA 4.0`.
+fn creative_commons(license: &str) -> Option<String> {
+    let url = Url::parse(license).ok()?;
+    if !url.host_str()?.ends_with("creativecommons.org") {
+        return None;
+    }
+
+    let segments: Vec<&str> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+    match segments.as_slice() {
+        ["licenses", code, version, ..] => Some(format!("CC {} {}", code.to_uppercase(), version)),
+        ["licenses", code] => Some(format!("CC {}", code.to_uppercase())),
+        ["publicdomain", "zero", version, ..] => Some(format!("CC0 {version}")),
+        ["publicdomain", "mark", ..] => Some("Public Domain Mark".to_string()),
+        _ => None,
+    }
+}
@@ -0,0 +1,22 @@
+// Media type of the documents whose text can be extracted.
+pub const PDF_MEDIA_TYPE: &str = "application/pdf";
+
+// Text of a PDF document, one line per line of text and pages separated by blank lines. Malformed
+// documents can make the extractor panic: run it on a blocking task (see `extract_text_blocking`).
+#[cfg(feature = "pdf")]
+pub fn extract_text(document: &[u8]) -> Result<String, String> {
+    pdf_extract::extract_text_from_mem(document).map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "pdf"))]
+pub fn extract_text(_document: &[u8]) -> Result<String, String> {
+    Err("the crawler was built without the `pdf` feature".to_string())
+}
+
+// Extract the text of a PDF document off the async runtime, turning panics of the extractor into
+// errors.
+pub async fn extract_text_blocking(document: Vec<u8>) -> Result<String, String> {
+    tokio::task::spawn_blocking(move || extract_text(&document))
+        .await
+        .map_err(|e| format!("PDF extraction failed: {e}"))?
+}
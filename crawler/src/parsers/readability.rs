@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+
+use ego_tree::NodeId;
+use models::MainContent;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+
+use crate::parsers::text;
+
+// Class/id hints of boilerplate containers: navigation, banners, comments, cookie notices, ...
+static UNLIKELY: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)-ad-|ai2html|banner|breadcrumbs|combx|comment|community|consent|cookie|cover-wrap|disqus|extra|footer|gdpr|header|legends|menu|modal|nav|newsletter|pager|pagination|popup|related|remark|replies|rss|share|shoutbox|sidebar|skyscraper|social|sponsor|subscribe|supplemental|yom-remote",
+    )
+    .unwrap()
+});
+// Hints of content containers, keeping elements that also look like boilerplate.
+static MAYBE_CONTENT: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)and|article|body|column|content|main|shadow").unwrap());
+static POSITIVE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)article|blog|body|content|entry|h-entry|hentry|main|page|post|story|text")
+        .unwrap()
+});
+static NEGATIVE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)-ad-|banner|combx|comment|com-|contact|footer|gdpr|masthead|media|meta|outbrain|promo|related|scroll|share|shopping|shoutbox|sidebar|skyscraper|sponsor|tags|widget",
+    )
+    .unwrap()
+});
+
+static PARAGRAPHS: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("p, pre, td, blockquote").expect("valid selector"));
+static LINKS: Lazy<Selector> = Lazy::new(|| Selector::parse("a").expect("valid selector"));
+static OG_TITLE: Lazy<Selector> =
+    Lazy::new(|| Selector::parse(r#"meta[property="og:title"][content]"#).expect("valid selector"));
+static H1: Lazy<Selector> = Lazy::new(|| Selector::parse("h1").expect("valid selector"));
+static TITLE: Lazy<Selector> = Lazy::new(|| Selector::parse("title").expect("valid selector"));
+
+// Elements never part of the main content, besides the hidden ones (see `text::HIDDEN_ELEMENTS`).
+const BOILERPLATE_ELEMENTS: &[&str] = &[
+    "aside", "button", "footer", "form", "header", "iframe", "nav", "svg",
+];
+// Paragraphs shorter than this (in characters) do not vote for their container.
+const MIN_PARAGRAPH_CHARS: usize = 25;
+
+// Main content of an HTML page (the article text and its title), without navigation, banners,
+// cookie notices and footers, in the spirit of Arc90's Readability: paragraphs vote for their
+// parent and grandparent, weighted by their length and commas; containers are favoured or
+// penalised by their tag and class/id hints and by the share of their text in links. The best
+// container is kept along with the siblings scoring close to it. None when the page has no
+// paragraph long enough to find its content.
+pub fn extract(body: &str) -> Option<MainContent> {
+    let document = Html::parse_document(body);
+
+    let mut scores: HashMap<NodeId, f64> = HashMap::new();
+    for paragraph in document.select(&PARAGRAPHS) {
+        if is_boilerplate(paragraph) {
+            continue;
+        }
+        let text = normalized_text(paragraph);
+        let chars = text.chars().count();
+        if chars < MIN_PARAGRAPH_CHARS {
+            continue;
+        }
+        let score = 1.0 + text.matches(',').count() as f64 + (chars / 100).min(3) as f64;
+        // the parent gets the whole score, the grandparent half of it
+        for (level, ancestor) in paragraph
+            .ancestors()
+            .filter_map(ElementRef::wrap)
+            .take(2)
+            .enumerate()
+        {
+            *scores
+                .entry(ancestor.id())
+                .or_insert_with(|| initial_score(ancestor)) += score / (level + 1) as f64;
+        }
+    }
+
+    let final_score = |element: ElementRef| {
+        scores
+            .get(&element.id())
+            .map(|score| score * (1.0 - link_density(element)))
+    };
+    let (top, top_score) = scores
+        .keys()
+        .filter_map(|id| ElementRef::wrap(document.tree.get(*id)?))
+        .filter_map(|element| Some((element, final_score(element)?)))
+        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+
+    // Articles split into several containers: keep the siblings scoring close to the top
+    // candidate, and long paragraphs with few links.
+    let threshold = (top_score * 0.2).max(10.0);
+    let parts: Vec<ElementRef> = match top.parent().and_then(ElementRef::wrap) {
+        Some(parent) => parent
+            .children()
+            .filter_map(ElementRef::wrap)
+            .filter(|sibling| {
+                sibling.id() == top.id()
+                    || final_score(*sibling).is_some_and(|score| score >= threshold)
+                    || (sibling.value().name() == "p"
+                        && normalized_text(*sibling).chars().count() > 80
+                        && link_density(*sibling) < 0.25)
+            })
+            .collect(),
+        None => vec![top],
+    };
+
+    let text = parts
+        .into_iter()
+        .map(block_text)
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    if text.is_empty() {
+        return None;
+    }
+    Some(MainContent {
+        title: title(&document),
+        text,
+    })
+}
+
+// Title of the article: its Open Graph title, else its first `<h1>`, else the document title.
+fn title(document: &Html) -> Option<String> {
+    let og_title = document
+        .select(&OG_TITLE)
+        .filter_map(|element| element.value().attr("content"))
+        .map(|title| title.split_whitespace().collect::<Vec<_>>().join(" "));
+    let headings = document.select(&H1).map(normalized_text);
+    let titles = document.select(&TITLE).map(normalized_text);
+    og_title
+        .chain(headings)
+        .chain(titles)
+        .find(|title| !title.is_empty())
+}
+
+// Score of a container before its paragraphs vote, from its tag and class/id hints.
+fn initial_score(element: ElementRef) -> f64 {
+    let tag = match element.value().name() {
+        "div" | "article" | "main" => 5.0,
+        "pre" | "td" | "blockquote" => 3.0,
+        "address" | "ol" | "ul" | "dl" | "dd" | "dt" | "li" | "form" => -3.0,
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "th" => -5.0,
+        _ => 0.0,
+    };
+    let hints = hints(element);
+    let mut weight = 0.0;
+    if NEGATIVE.is_match(&hints) {
+        weight -= 25.0;
+    }
+    if POSITIVE.is_match(&hints) {
+        weight += 25.0;
+    }
+    tag + weight
+}
+
+// Class and id of an element, matched against the content hints.
+fn hints(element: ElementRef) -> String {
+    let value = element.value();
+    format!(
+        "{} {}",
+        value.attr("class").unwrap_or_default(),
+        value.id().unwrap_or_default()
+    )
+}
+
+// Whether an element is boilerplate itself (see `BOILERPLATE_ELEMENTS` and `UNLIKELY`).
+fn is_excluded(element: ElementRef) -> bool {
+    let name = element.value().name();
+    if BOILERPLATE_ELEMENTS.contains(&name) || text::HIDDEN_ELEMENTS.contains(&name) {
+        return true;
+    }
+    if matches!(name, "html" | "body" | "article" | "main") {
+        return false;
+    }
+    let hints = hints(element);
+    UNLIKELY.is_match(&hints) && !MAYBE_CONTENT.is_match(&hints)
+}
+
+// Whether an element or one of its ancestors is boilerplate.
+fn is_boilerplate(element: ElementRef) -> bool {
+    is_excluded(element)
+        || element
+            .ancestors()
+            .filter_map(ElementRef::wrap)
+            .any(is_excluded)
+}
+
+// Share of the text of an element inside links.
+fn link_density(element: ElementRef) -> f64 {
+    let chars = normalized_text(element).chars().count();
+    if chars == 0 {
+        return 0.0;
+    }
+    let link_chars: usize = element
+        .select(&LINKS)
+        .map(|link| normalized_text(link).chars().count())
+        .sum();
+    link_chars as f64 / chars as f64
+}
+
+// Visible text of an element on one line.
+fn normalized_text(element: ElementRef) -> String {
+    text::paragraphs(*element, |_| false).join(" ")
+}
+
+// Text of an element without its boilerplate descendants, one paragraph per block element
+// separated by a blank line.
+fn block_text(element: ElementRef) -> String {
+    text::text(*element, is_excluded)
+}
@@ -0,0 +1,66 @@
+use models::RobotsDirectives;
+use once_cell::sync::Lazy;
+use scraper::{Html, Selector};
+
+// Directives that take a value (`max-snippet: 20`), so that a `name:` prefix in `X-Robots-Tag`
+// is not mistaken for a user agent.
+const VALUED_DIRECTIVES: &[&str] = &[
+    "unavailable_after",
+    "max-snippet",
+    "max-image-preview",
+    "max-video-preview",
+];
+
+static NAMED_META: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("meta[name][content]").expect("valid selector"));
+
+// Indexing directives of `<meta name="robots">` tags. Tags addressed to a specific crawler
+// (`<meta name="googlebot">`) are ignored.
+pub fn extract_directives(document: &Html) -> RobotsDirectives {
+    let mut directives = RobotsDirectives::default();
+    for element in document.select(&NAMED_META) {
+        let name = element.value().attr("name").unwrap_or_default();
+        if name.trim().eq_ignore_ascii_case("robots") {
+            apply(
+                &mut directives,
+                element.value().attr("content").unwrap_or_default(),
+            );
+        }
+    }
+    directives
+}
+
+// Apply the `X-Robots-Tag` headers ("Key: Value" strings). Values prefixed with a user agent
+// (`X-Robots-Tag: googlebot: noindex`) are ignored.
+pub fn apply_headers(directives: &mut RobotsDirectives, headers: &[String]) {
+    for header in headers {
+        let Some((name, value)) = header.split_once(':') else {
+            continue;
+        };
+        if !name.trim().eq_ignore_ascii_case("x-robots-tag") {
+            continue;
+        }
+        let addressed_to_agent = value.split_once(':').is_some_and(|(prefix, _)| {
+            let prefix = prefix.trim().to_lowercase();
+            !prefix.contains(',') && !VALUED_DIRECTIVES.contains(&prefix.as_str())
+        });
+        if !addressed_to_agent {
+            apply(directives, value);
+        }
+    }
+}
+
+// Record `noindex`/`nofollow`/`none` from a comma-separated directive list.
+fn apply(directives: &mut RobotsDirectives, list: &str) {
+    for directive in list.split(',') {
+        match directive.trim().to_lowercase().as_str() {
+            "noindex" => directives.noindex = true,
+            "nofollow" => directives.nofollow = true,
+            "none" => {
+                directives.noindex = true;
+                directives.nofollow = true;
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use scraper::{Html, Selector};
+use url::Url;
+
+use crate::validators;
+
+// Navigation calls whose first string argument is a route: `location.assign('/a')`,
+// `router.push("/a")`, `navigate('/a')`, `window.open('/a')`...
+static NAVIGATE_CALL: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?:location\.(?:assign|replace)|router\.(?:push|replace)|navigate(?:To|ByUrl)?|window\.open)\s*\(\s*['"`]([^'"`\s]+)['"`]"#,
+    )
+    .unwrap()
+});
+// History API navigation: `history.pushState(state, title, '/a')`.
+static HISTORY_CALL: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"history\.(?:pushState|replaceState)\s*\([^,]*,[^,]*,\s*['"`]([^'"`\s]+)['"`]"#)
+        .unwrap()
+});
+// Assignments to the location: `location.href = '/a'`, `window.location = "/a"`.
+static LOCATION_ASSIGNMENT: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"location(?:\.href)?\s*=\s*['"`]([^'"`\s]+)['"`]"#).unwrap());
+
+// Attributes used by client-side routers and widgets to hold the target of a link.
+const ROUTE_ATTRIBUTES: [&str; 6] = [
+    "data-href",
+    "data-url",
+    "data-route",
+    "data-link",
+    "routerlink",
+    "ng-href",
+];
+
+static CLICKABLE: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("[onclick]").expect("valid selector"));
+// Elements with one of `ROUTE_ATTRIBUTES`.
+static ROUTED: Lazy<Selector> = Lazy::new(|| {
+    let attributes = ROUTE_ATTRIBUTES
+        .iter()
+        .map(|attribute| format!("[{attribute}]"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    Selector::parse(&attributes).expect("valid selector")
+});
+// Vue/React router links rendered without an `href`.
+static ROUTER_LINKS: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("router-link[to], a[to]:not([href])").expect("valid selector"));
+static INLINE_SCRIPTS: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("script:not([src])").expect("valid selector"));
+
+// Discover the client-side routes of a single-page application in `document`: targets of
+// elements clicked through JavaScript (`onclick` handlers, `<a>` without `href` but with a router
+// attribute) and History API navigation in inline scripts. Routes are resolved against `base` and
+// kept only on its origin, since they only exist within the application.
+pub fn discover_routes(document: &Html, base: &Url) -> Vec<String> {
+    let mut candidates = Vec::new();
+
+    for element in document.select(&CLICKABLE) {
+        if let Some(handler) = element.value().attr("onclick") {
+            candidates.extend(navigation_targets(handler));
+        }
+    }
+
+    for element in document.select(&ROUTED) {
+        candidates.extend(
+            ROUTE_ATTRIBUTES
+                .iter()
+                .filter_map(|attribute| element.value().attr(attribute))
+                .map(str::to_string),
+        );
+    }
+
+    for element in document.select(&ROUTER_LINKS) {
+        if let Some(to) = element.value().attr("to") {
+            candidates.push(to.to_string());
+        }
+    }
+
+    for script in document.select(&INLINE_SCRIPTS) {
+        candidates.extend(navigation_targets(&script.text().collect::<String>()));
+    }
+
+    let mut routes: Vec<String> = Vec::new();
+    for candidate in candidates {
+        let Some(route) = resolve(base, &candidate) else {
+            continue;
+        };
+        if !routes.contains(&route) {
+            routes.push(route);
+        }
+    }
+    routes
+}
+
+// Targets of the navigation calls and location assignments in a piece of JavaScript.
+fn navigation_targets(script: &str) -> Vec<String> {
+    [&*NAVIGATE_CALL, &*HISTORY_CALL, &*LOCATION_ASSIGNMENT]
+        .iter()
+        .flat_map(|pattern| pattern.captures_iter(script))
+        .filter_map(|captures| captures.get(1))
+        .map(|target| target.as_str().to_string())
+        .collect()
+}
+
+// Absolute URL of a route on the origin of `base`, if it is one. Hash routes are dropped: without
+// executing the application they fetch the same document as `base`.
+fn resolve(base: &Url, route: &str) -> Option<String> {
+    if route.starts_with("javascript:") || route.starts_with('#') {
+        return None;
+    }
+    let mut url = base.join(route).ok()?;
+    url.set_fragment(None);
+    if url.origin() != base.origin() || url.as_str() == base.as_str() {
+        return None;
+    }
+    validators::validate_url(url.as_str()).ok()?;
+    Some(url.to_string())
+}
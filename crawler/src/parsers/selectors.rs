@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+
+use scraper::{Html, Selector};
+use url::Url;
+
+use crate::{config::ExtractionConfig, parsers::text, rules::RuleTarget};
+
+// Named field of an extraction profile: the first element matching `selector`, read from its
+// `attribute` or else its text.
+#[derive(Debug)]
+struct Field {
+    name: String,
+    selector: Selector,
+    attribute: Option<String>,
+}
+
+#[derive(Debug)]
+struct SelectorProfile {
+    target: RuleTarget,
+    fields: Vec<Field>,
+}
+
+// Site-specific scraping: extracts named fields (prices, authors, dates, ...) with the CSS
+// selectors of the first profile matching the page URL, by domain or URL pattern.
+#[derive(Debug)]
+pub struct SelectorParser {
+    profiles: Vec<SelectorProfile>,
+}
+
+impl SelectorParser {
+    // Parser of the configured profiles, or None without profiles.
+    pub fn from_config(config: &ExtractionConfig) -> Result<Option<Self>, String> {
+        let profiles = config
+            .profiles
+            .iter()
+            .map(|profile| {
+                let target = RuleTarget::from_config(
+                    profile.domain.as_deref(),
+                    profile.pattern.as_deref(),
+                    "extraction",
+                )?;
+                let fields = profile
+                    .fields
+                    .iter()
+                    .map(|(name, rule)| {
+                        // `@` cannot end a CSS selector, so the last one starts the attribute.
+                        let (selector, attribute) = match rule.rsplit_once('@') {
+                            Some((selector, attribute)) if !attribute.contains([']', ' ']) => {
+                                (selector, Some(attribute.trim().to_string()))
+                            }
+                            _ => (rule.as_str(), None),
+                        };
+                        let selector = Selector::parse(selector.trim()).map_err(|e| {
+                            format!("Invalid selector '{selector}' of field '{name}': {e}")
+                        })?;
+                        Ok(Field {
+                            name: name.clone(),
+                            selector,
+                            attribute,
+                        })
+                    })
+                    .collect::<Result<_, String>>()?;
+                Ok(SelectorProfile { target, fields })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok((!profiles.is_empty()).then_some(SelectorParser { profiles }))
+    }
+
+    // Fields of the profile matching `url` found in `document`. Fields without a match, or with
+    // an empty value, are left out.
+    pub fn extract(&self, url: &Url, document: &Html) -> HashMap<String, String> {
+        let Some(profile) = self.profiles.iter().find(|p| p.target.matches(url)) else {
+            return HashMap::new();
+        };
+        profile
+            .fields
+            .iter()
+            .filter_map(|field| {
+                let element = document.select(&field.selector).next()?;
+                let value = match &field.attribute {
+                    Some(attribute) => element.value().attr(attribute)?.trim().to_string(),
+                    None => text::paragraphs(*element, |_| false).join(" "),
+                };
+                (!value.is_empty()).then(|| (field.name.clone(), value))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,102 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+static ENTRY: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)<(url|sitemap)(?:\s[^>]*)?>(.*?)</(?:url|sitemap)>").unwrap());
+static LOC: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<loc>\s*(.*?)\s*</loc>").unwrap());
+static LASTMOD: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)<lastmod>\s*(.*?)\s*</lastmod>").unwrap());
+
+#[derive(Debug, Clone)]
+pub struct SitemapEntry {
+    pub loc: String,
+    // `lastmod` as a unix timestamp (seconds), if present and valid.
+    pub lastmod: Option<u64>,
+}
+
+// Content of a sitemap (https://www.sitemaps.org/protocol.html): page URLs of a `urlset`, or
+// nested sitemaps of a `sitemapindex`.
+#[derive(Debug, Default)]
+pub struct ParsedSitemap {
+    pub urls: Vec<SitemapEntry>,
+    pub sitemaps: Vec<SitemapEntry>,
+}
+
+pub fn parse_sitemap(xml: &str) -> ParsedSitemap {
+    let mut sitemap = ParsedSitemap::default();
+    for entry in ENTRY.captures_iter(xml) {
+        let Some(loc) = LOC.captures(&entry[2]).map(|c| unescape(&c[1])) else {
+            continue;
+        };
+        let lastmod = LASTMOD
+            .captures(&entry[2])
+            .and_then(|c| parse_w3c_datetime(&c[1]));
+        let parsed = SitemapEntry { loc, lastmod };
+        match &entry[1] {
+            "url" => sitemap.urls.push(parsed),
+            _ => sitemap.sitemaps.push(parsed),
+        }
+    }
+    sitemap
+}
+
+// Parse a W3C datetime as used by `lastmod` (`2024-05-01`, `2024-05-01T10:30:00+02:00`, ...)
+// into a unix timestamp (seconds).
+pub fn parse_w3c_datetime(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let (date, time) = match value.split_once('T') {
+        Some((date, time)) => (date, Some(time)),
+        None => (value, None),
+    };
+
+    let mut parts = date.split('-');
+    let year = parts.next()?.parse::<i64>().ok()?;
+    let month = parts.next().map_or(Some(1), |m| m.parse::<i64>().ok())?;
+    let day = parts.next().map_or(Some(1), |d| d.parse::<i64>().ok())?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    let mut seconds = days_from_civil(year, month, day) * 86_400;
+    if let Some(time) = time {
+        // Split the time zone designator off: `Z`, `+hh:mm` or `-hh:mm`.
+        let (clock, offset) = if let Some(clock) = time.strip_suffix('Z') {
+            (clock, 0)
+        } else if let Some(pos) = time.rfind(['+', '-']) {
+            let (hours, minutes) = time[pos + 1..].split_once(':')?;
+            let offset = hours.parse::<i64>().ok()? * 3600 + minutes.parse::<i64>().ok()? * 60;
+            let sign = if time.as_bytes()[pos] == b'-' { -1 } else { 1 };
+            (&time[..pos], sign * offset)
+        } else {
+            (time, 0)
+        };
+
+        let mut fields = clock.split(':');
+        let hours = fields.next()?.parse::<i64>().ok()?;
+        let minutes = fields.next()?.parse::<i64>().ok()?;
+        let secs = fields.next().map_or(Some(0.0), |s| s.parse::<f64>().ok())? as i64;
+        seconds += hours * 3600 + minutes * 60 + secs - offset;
+    }
+
+    u64::try_from(seconds).ok()
+}
+
+// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
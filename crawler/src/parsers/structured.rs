@@ -0,0 +1,166 @@
+use std::collections::BTreeMap;
+
+use models::{MicrodataItem, MicrodataValue, StructuredData};
+use once_cell::sync::Lazy;
+use scraper::{ElementRef, Html, Selector};
+use tracing::debug;
+use url::Url;
+
+use crate::parsers::text;
+
+static JSON_LD: Lazy<Selector> =
+    Lazy::new(|| Selector::parse(r#"script[type="application/ld+json"]"#).expect("valid selector"));
+static PROPERTY_META: Lazy<Selector> = Lazy::new(|| {
+    Selector::parse("meta[property][content], meta[name][content]").expect("valid selector")
+});
+static TOP_LEVEL_ITEMS: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("[itemscope]:not([itemprop])").expect("valid selector"));
+
+// Prefixes of the OpenGraph properties: `og:` and the object types of the protocol.
+const OPEN_GRAPH_PREFIXES: &[&str] = &[
+    "og:", "article:", "book:", "books:", "music:", "product:", "profile:", "video:",
+];
+// Nesting of microdata items beyond which nested items are ignored.
+const MAX_ITEM_DEPTH: usize = 16;
+// Elements whose microdata value is a URL attribute, resolved against the page URL.
+const URL_ATTRIBUTES: &[(&str, &str)] = &[
+    ("a", "href"),
+    ("area", "href"),
+    ("audio", "src"),
+    ("embed", "src"),
+    ("iframe", "src"),
+    ("img", "src"),
+    ("link", "href"),
+    ("object", "data"),
+    ("source", "src"),
+    ("track", "src"),
+    ("video", "src"),
+];
+
+// Extracts the structured data of HTML documents: JSON-LD blocks, OpenGraph and Twitter card meta
+// tags, and schema.org microdata. URLs of microdata properties are resolved against `base`.
+pub struct StructuredDataExtractor<'a> {
+    base: &'a Url,
+}
+
+impl<'a> StructuredDataExtractor<'a> {
+    pub fn new(base: &'a Url) -> Self {
+        StructuredDataExtractor { base }
+    }
+
+    // Structured data of `document`, or None when it has none.
+    pub fn extract(&self, document: &Html) -> Option<StructuredData> {
+        let mut data = StructuredData {
+            json_ld: json_ld(document),
+            microdata: document
+                .select(&TOP_LEVEL_ITEMS)
+                .map(|element| self.item(element, 0))
+                .collect(),
+            ..StructuredData::default()
+        };
+        for element in document.select(&PROPERTY_META) {
+            let value = element.value();
+            // OpenGraph uses `property`, Twitter cards `name` (some sites mix them up).
+            let Some(name) = value.attr("property").or(value.attr("name")) else {
+                continue;
+            };
+            let name = name.trim().to_lowercase();
+            let content = value.attr("content").unwrap_or_default().trim().to_string();
+            let properties = if name.starts_with("twitter:") {
+                &mut data.twitter
+            } else if OPEN_GRAPH_PREFIXES.iter().any(|p| name.starts_with(p)) {
+                &mut data.open_graph
+            } else {
+                continue;
+            };
+            properties.entry(name).or_default().push(content);
+        }
+        (!data.is_empty()).then_some(data)
+    }
+
+    // Microdata item of an `itemscope` element with the properties of its descendants, not
+    // descending into nested items (they are properties themselves, or ignored without
+    // `itemprop`).
+    fn item(&self, element: ElementRef, depth: usize) -> MicrodataItem {
+        let value = element.value();
+        let mut item = MicrodataItem {
+            types: value
+                .attr("itemtype")
+                .map(|types| types.split_whitespace().map(str::to_string).collect())
+                .unwrap_or_default(),
+            id: value.attr("itemid").map(|id| self.resolve(id)),
+            properties: BTreeMap::new(),
+        };
+
+        let mut stack: Vec<ElementRef> = element.children().filter_map(ElementRef::wrap).collect();
+        stack.reverse();
+        while let Some(element) = stack.pop() {
+            let value = element.value();
+            let is_item = value.attr("itemscope").is_some();
+            if let Some(names) = value.attr("itemprop") {
+                let property = if is_item {
+                    (depth < MAX_ITEM_DEPTH)
+                        .then(|| MicrodataValue::Item(self.item(element, depth + 1)))
+                } else {
+                    Some(MicrodataValue::Text(self.property_value(element)))
+                };
+                if let Some(property) = property {
+                    for name in names.split_whitespace() {
+                        item.properties
+                            .entry(name.to_string())
+                            .or_default()
+                            .push(property.clone());
+                    }
+                }
+            }
+            if !is_item {
+                // children are pushed in reverse so that they are visited in document order
+                stack.extend(element.children().filter_map(ElementRef::wrap).rev());
+            }
+        }
+        item
+    }
+
+    // Value of a non-item microdata property, from the attribute its element carries it in or
+    // its text.
+    fn property_value(&self, element: ElementRef) -> String {
+        let value = element.value();
+        let name = value.name();
+        if let Some((_, attribute)) = URL_ATTRIBUTES.iter().find(|(tag, _)| *tag == name) {
+            return value
+                .attr(attribute)
+                .map(|url| self.resolve(url))
+                .unwrap_or_default();
+        }
+        let attribute = match name {
+            "meta" => value.attr("content"),
+            "data" | "meter" => value.attr("value"),
+            "time" => value.attr("datetime"),
+            _ => None,
+        };
+        match attribute {
+            Some(attribute) => attribute.trim().to_string(),
+            None => text::paragraphs(*element, |_| false).join(" "),
+        }
+    }
+
+    fn resolve(&self, url: &str) -> String {
+        self.base
+            .join(url.trim())
+            .map(|url| url.to_string())
+            .unwrap_or_else(|_| url.trim().to_string())
+    }
+}
+
+// Parsed JSON-LD blocks. Invalid blocks are skipped.
+fn json_ld(document: &Html) -> Vec<serde_json::Value> {
+    document
+        .select(&JSON_LD)
+        .filter_map(|element| {
+            let text = element.text().collect::<String>();
+            serde_json::from_str(&text)
+                .inspect_err(|e| debug!("Skipping invalid JSON-LD block: {}", e))
+                .ok()
+        })
+        .collect()
+}
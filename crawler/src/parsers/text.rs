@@ -0,0 +1,100 @@
+use ego_tree::NodeRef;
+use scraper::{ElementRef, Node};
+
+// Elements whose text is not visible content.
+pub const HIDDEN_ELEMENTS: &[&str] = &["head", "noscript", "script", "style", "template"];
+// Elements starting a new paragraph of text.
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "br",
+    "dd",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "summary",
+    "table",
+    "td",
+    "th",
+    "tr",
+    "ul",
+];
+
+// Visible text under `root` (a document or an element), walking its text nodes in document order
+// and leaving out `HIDDEN_ELEMENTS` and the elements for which `skip` is true, with their
+// descendants. Block elements end the current paragraph; whitespace is collapsed within
+// paragraphs and empty ones are dropped.
+pub fn paragraphs<'a>(root: NodeRef<'a, Node>, skip: impl Fn(ElementRef) -> bool) -> Vec<String> {
+    enum Step<'a> {
+        Enter(NodeRef<'a, Node>),
+        // End of a block element.
+        Break,
+    }
+
+    let mut paragraphs: Vec<String> = Vec::new();
+    let mut words: Vec<&str> = Vec::new();
+    let mut steps = vec![Step::Enter(root)];
+    while let Some(step) = steps.pop() {
+        let node = match step {
+            Step::Enter(node) => node,
+            Step::Break => {
+                flush(&mut paragraphs, &mut words);
+                continue;
+            }
+        };
+        match node.value() {
+            Node::Text(text) => words.extend(text.split_whitespace()),
+            Node::Element(element) => {
+                if HIDDEN_ELEMENTS.contains(&element.name())
+                    || ElementRef::wrap(node).is_some_and(&skip)
+                {
+                    continue;
+                }
+                if BLOCK_ELEMENTS.contains(&element.name()) {
+                    flush(&mut paragraphs, &mut words);
+                    steps.push(Step::Break);
+                }
+            }
+            _ => {}
+        }
+        // children are pushed in reverse so that they are visited in document order
+        steps.extend(node.children().rev().map(Step::Enter));
+    }
+    flush(&mut paragraphs, &mut words);
+    paragraphs
+}
+
+// Visible text under `root` (see `paragraphs`), with paragraphs separated by a blank line.
+pub fn text(root: NodeRef<'_, Node>, skip: impl Fn(ElementRef) -> bool) -> String {
+    paragraphs(root, skip).join("\n\n")
+}
+
+// End the current paragraph, if it has any text.
+fn flush(paragraphs: &mut Vec<String>, words: &mut Vec<&str>) {
+    if !words.is_empty() {
+        paragraphs.push(words.join(" "));
+        words.clear();
+    }
+}
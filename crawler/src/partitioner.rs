@@ -0,0 +1,256 @@
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use sha2::{Digest, Sha256};
+use tokio::sync::mpsc;
+use url::Url;
+
+use crate::requests::http::HttpRequest;
+
+// Points of each agent on the hash ring; more points spread domains more evenly.
+const VIRTUAL_NODES: usize = 64;
+
+// Assigns whole domains to agents with consistent hashing, so that every URL of a domain is
+// crawled by the same agent (keeping its robots.txt cache and per-domain delays local) and most
+// domains keep their agent when the number of agents changes between runs. Seeds and checkpoint
+// entries are split with `partition`; the links discovered while crawling go through a
+// `LinkRouter`.
+pub struct DomainPartitioner {
+    ring: BTreeMap<u64, usize>,
+    n_agents: usize,
+}
+
+impl DomainPartitioner {
+    pub fn new(n_agents: usize) -> Self {
+        let n_agents = n_agents.max(1);
+        let ring = (0..n_agents)
+            .flat_map(|agent| {
+                (0..VIRTUAL_NODES).map(move |node| (hash(&format!("agent-{agent}#{node}")), agent))
+            })
+            .collect();
+        DomainPartitioner { ring, n_agents }
+    }
+
+    // Index (0-based) of the agent responsible for the domain of `url`.
+    pub fn agent_for(&self, url: &Url) -> usize {
+        let point = hash(&domain_of(url));
+        self.ring
+            .range(point..)
+            .next()
+            .or_else(|| self.ring.iter().next())
+            .map(|(_, agent)| *agent)
+            .unwrap_or_default()
+    }
+
+    // Split `items` into one list per agent, keeping their order. Items without a valid URL go to
+    // the first agent.
+    pub fn partition<T>(&self, items: Vec<T>, url_of: impl Fn(&T) -> Option<Url>) -> Vec<Vec<T>> {
+        let mut partitions: Vec<Vec<T>> = (0..self.n_agents).map(|_| Vec::new()).collect();
+        for item in items {
+            let agent = url_of(&item).map_or(0, |url| self.agent_for(&url));
+            partitions[agent].push(item);
+        }
+        partitions
+    }
+}
+
+// Hands the links discovered by an agent to the agent owning their domain (see
+// `DomainPartitioner`), through one channel per agent.
+#[derive(Clone)]
+pub struct LinkRouter {
+    partitioner: Arc<DomainPartitioner>,
+    senders: Arc<Vec<mpsc::UnboundedSender<HttpRequest>>>,
+    // Agents with requests left, which may still route links to the others.
+    busy: Arc<AtomicUsize>,
+}
+
+impl LinkRouter {
+    // Router of `partitioner`, with the receiving end of the channel of each agent (by index).
+    pub fn new(
+        partitioner: DomainPartitioner,
+    ) -> (Self, Vec<mpsc::UnboundedReceiver<HttpRequest>>) {
+        let (senders, receivers) = (0..partitioner.n_agents)
+            .map(|_| mpsc::unbounded_channel())
+            .unzip();
+        let router = LinkRouter {
+            partitioner: Arc::new(partitioner),
+            senders: Arc::new(senders),
+            busy: Arc::default(),
+        };
+        (router, receivers)
+    }
+
+    // Inbox of agent `agent`, receiving on `receiver`. The agent counts as busy until it reports
+    // otherwise.
+    pub fn inbox(
+        &self,
+        agent: usize,
+        receiver: mpsc::UnboundedReceiver<HttpRequest>,
+    ) -> AgentInbox {
+        self.busy.fetch_add(1, Ordering::SeqCst);
+        AgentInbox {
+            agent,
+            router: self.clone(),
+            receiver,
+            idle: false,
+        }
+    }
+}
+
+// Links routed to an agent by the others, and the routing of the links it discovers.
+pub struct AgentInbox {
+    agent: usize,
+    router: LinkRouter,
+    receiver: mpsc::UnboundedReceiver<HttpRequest>,
+    idle: bool,
+}
+
+impl AgentInbox {
+    // Send `req` to the agent owning its domain. Returns the request when it belongs to this
+    // agent, or when its owner stopped (it is then crawled here rather than lost).
+    pub fn route(&self, req: HttpRequest) -> Option<HttpRequest> {
+        let Ok(url) = Url::parse(&req.target) else {
+            return Some(req);
+        };
+        let owner = self.router.partitioner.agent_for(&url);
+        if owner == self.agent {
+            return Some(req);
+        }
+        match self.router.senders.get(owner) {
+            Some(sender) => sender.send(req).err().map(|err| err.0),
+            None => Some(req),
+        }
+    }
+
+    // Requests routed to the agent since the last call.
+    pub fn receive(&mut self) -> Vec<HttpRequest> {
+        let mut requests = Vec::new();
+        while let Ok(req) = self.receiver.try_recv() {
+            requests.push(req);
+        }
+        requests
+    }
+
+    // Report whether the agent ran out of requests. Returns whether another agent is still busy,
+    // i.e. may route links to this one.
+    pub fn set_idle(&mut self, idle: bool) -> bool {
+        if idle != self.idle {
+            self.idle = idle;
+            if idle {
+                self.router.busy.fetch_sub(1, Ordering::SeqCst);
+            } else {
+                self.router.busy.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+        self.router.busy.load(Ordering::SeqCst) > usize::from(!self.idle)
+    }
+
+    // Stop receiving, once the agent is done: the agents routing links to it from then on keep
+    // them. Returns the requests routed in the meantime.
+    pub fn close(&mut self) -> Vec<HttpRequest> {
+        self.receiver.close();
+        self.receive()
+    }
+}
+
+impl Drop for AgentInbox {
+    fn drop(&mut self) {
+        if !self.idle {
+            self.router.busy.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+// Domain used as the partitioning key: the lowercased host without `www.`.
+fn domain_of(url: &Url) -> String {
+    let host = url.host_str().unwrap_or_default().to_lowercase();
+    host.strip_prefix("www.").unwrap_or(&host).to_string()
+}
+
+// Stable across processes and Rust versions, unlike `DefaultHasher`.
+fn hash(key: &str) -> u64 {
+    let digest = Sha256::digest(key.as_bytes());
+    u64::from_be_bytes(digest[..8].try_into().expect("8 bytes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::requests::request::Request;
+
+    fn domains(n: usize) -> Vec<Url> {
+        (0..n)
+            .map(|i| Url::parse(&format!("https://site-{i}.example/page")).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn a_domain_always_maps_to_the_same_agent() {
+        let partitioner = DomainPartitioner::new(8);
+        let again = DomainPartitioner::new(8);
+        for url in domains(200) {
+            let agent = partitioner.agent_for(&url);
+            assert!(agent < 8);
+            assert_eq!(agent, partitioner.agent_for(&url));
+            assert_eq!(agent, again.agent_for(&url));
+            let other_page = url.join("/other?page=2").unwrap();
+            assert_eq!(agent, partitioner.agent_for(&other_page));
+        }
+        let www = Url::parse("https://www.site-1.example/").unwrap();
+        let bare = Url::parse("https://SITE-1.example/about").unwrap();
+        assert_eq!(partitioner.agent_for(&www), partitioner.agent_for(&bare));
+    }
+
+    #[test]
+    fn adding_an_agent_moves_few_domains() {
+        let (before, after) = (DomainPartitioner::new(4), DomainPartitioner::new(5));
+        let urls = domains(2000);
+        let moved = urls
+            .iter()
+            .filter(|url| before.agent_for(url) != after.agent_for(url))
+            .count();
+        // Ideally a fifth of the domains move to the new agent, and only to it.
+        assert!(moved < urls.len() * 3 / 10, "{moved} domains moved");
+        for url in &urls {
+            let agent = after.agent_for(url);
+            assert!(agent == before.agent_for(url) || agent == 4);
+        }
+    }
+
+    #[tokio::test]
+    async fn discovered_links_are_routed_to_the_owner_of_their_domain() {
+        let partitioner = DomainPartitioner::new(2);
+        let urls = domains(50);
+        let owned = |agent: usize| {
+            urls.iter()
+                .find(|url| partitioner.agent_for(url) == agent)
+                .cloned()
+                .unwrap()
+        };
+        let (first_url, second_url) = (owned(0), owned(1));
+        let (router, mut receivers) = LinkRouter::new(partitioner);
+        let mut second = router.inbox(1, receivers.pop().unwrap());
+        let first = router.inbox(0, receivers.pop().unwrap());
+
+        assert!(first
+            .route(HttpRequest::new(first_url.as_str(), 1))
+            .is_some());
+        assert!(first
+            .route(HttpRequest::new(second_url.as_str(), 1))
+            .is_none());
+        let received = second.receive();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].target, second_url.as_str());
+
+        // Once the owner stopped, its links stay with the agent that found them.
+        assert!(second.close().is_empty());
+        assert!(first
+            .route(HttpRequest::new(second_url.as_str(), 1))
+            .is_some());
+    }
+}
@@ -0,0 +1,267 @@
+use std::{
+    io::IsTerminal,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use serde::Serialize;
+use tokio::{sync::watch, task::JoinHandle};
+use tracing::info;
+
+// How crawl progress is reported on stderr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ProgressMode {
+    // Progress bars when stderr is a terminal, nothing otherwise
+    Auto,
+    // One progress bar per agent
+    Bars,
+    // One JSON line per interval with the counters of every agent
+    Json,
+    // No progress output
+    Off,
+}
+
+// Counters updated by a crawler agent as it works through its queue.
+#[derive(Debug, Default)]
+pub struct AgentProgress {
+    // Requests executed successfully (published or not).
+    pub processed: AtomicU64,
+    // Requests that failed (and were retried, parked or dead-lettered).
+    pub failed: AtomicU64,
+    // Pages published to the message bus.
+    pub published: AtomicU64,
+    // Requests waiting in the agent queue.
+    pub queued: AtomicU64,
+    // URLs claimed (marked visited) by the agent.
+    pub visited: AtomicU64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+struct AgentSnapshot {
+    agent: String,
+    processed: u64,
+    failed: u64,
+    published: u64,
+    queued: u64,
+    visited: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct ProgressLine<'a> {
+    elapsed_secs: u64,
+    processed: u64,
+    failed: u64,
+    published: u64,
+    queued: u64,
+    agents: &'a [AgentSnapshot],
+}
+
+pub struct ProgressReporter {
+    mode: ProgressMode,
+    interval: Duration,
+    // Interval of the `crawl_status` log events (disabled when unset).
+    status_interval: Option<Duration>,
+    agents: Vec<(String, Arc<AgentProgress>)>,
+}
+
+// Running reporter, stopped with `finish`.
+pub struct ProgressHandle {
+    done: watch::Sender<bool>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl ProgressReporter {
+    // `Auto` resolves to `Bars` on terminals and `Off` otherwise.
+    pub fn new(mode: ProgressMode, interval: Duration) -> Self {
+        let mode = match mode {
+            ProgressMode::Auto if std::io::stderr().is_terminal() => ProgressMode::Bars,
+            ProgressMode::Auto => ProgressMode::Off,
+            mode => mode,
+        };
+        ProgressReporter {
+            mode,
+            interval,
+            status_interval: None,
+            agents: Vec::new(),
+        }
+    }
+
+    // Also log the status of the crawl as a `crawl_status` event every `interval`, whatever the
+    // progress mode, so that crawls running without a terminal stay observable in their logs.
+    pub fn log_status(&mut self, interval: Duration) {
+        self.status_interval = Some(interval);
+    }
+
+    // Counters for the agent `name`, to hand over to `Crawler::report_progress`.
+    pub fn register(&mut self, name: &str) -> Arc<AgentProgress> {
+        let progress = Arc::new(AgentProgress::default());
+        self.agents.push((name.to_string(), Arc::clone(&progress)));
+        progress
+    }
+
+    // Render progress every interval until `finish` is called on the returned handle.
+    pub fn spawn(self) -> ProgressHandle {
+        let (done, done_rx) = watch::channel(false);
+        let reporter = Arc::new(self);
+        let mut tasks = vec![tokio::task::spawn(
+            Arc::clone(&reporter).render(done_rx.clone()),
+        )];
+        if let Some(interval) = reporter.status_interval {
+            tasks.push(tokio::task::spawn(reporter.log(interval, done_rx)));
+        }
+        ProgressHandle { done, tasks }
+    }
+
+    // Render the progress bars or JSON lines every interval.
+    async fn render(self: Arc<Self>, mut done_rx: watch::Receiver<bool>) {
+        if self.mode == ProgressMode::Off {
+            return;
+        }
+
+        let started = Instant::now();
+        let bars = (self.mode == ProgressMode::Bars).then(|| self.bars());
+        let mut interval = tokio::time::interval(self.interval);
+        loop {
+            let finished = tokio::select! {
+                _ = interval.tick() => false,
+                _ = done_rx.changed() => true,
+            };
+
+            let snapshots = self.snapshots();
+            match &bars {
+                Some(bars) => render_bars(bars, &snapshots, finished),
+                None => render_json(&snapshots, started.elapsed()),
+            }
+            if finished {
+                break;
+            }
+        }
+    }
+
+    // Log a `crawl_status` event every interval with the frontier size, the visited URLs, the
+    // fetch and error rates over the interval and the status of every agent.
+    async fn log(self: Arc<Self>, interval: Duration, mut done_rx: watch::Receiver<bool>) {
+        let mut ticker = tokio::time::interval(interval.max(Duration::from_secs(1)));
+        ticker.tick().await;
+        let mut previous = self.snapshots();
+        let mut last = Instant::now();
+        loop {
+            let finished = tokio::select! {
+                _ = ticker.tick() => false,
+                _ = done_rx.changed() => true,
+            };
+
+            let snapshots = self.snapshots();
+            let elapsed = last.elapsed().as_secs_f64().max(0.001);
+            last = Instant::now();
+            let mut fetched = 0;
+            let mut failed = 0;
+            let mut agents = Vec::with_capacity(snapshots.len());
+            for (snapshot, before) in snapshots.iter().zip(&previous) {
+                let agent_fetched = (snapshot.processed + snapshot.failed)
+                    .saturating_sub(before.processed + before.failed);
+                fetched += agent_fetched;
+                failed += snapshot.failed.saturating_sub(before.failed);
+                let status = match (agent_fetched, snapshot.queued) {
+                    (0, 0) => "idle",
+                    (0, _) => "stalled",
+                    _ => "active",
+                };
+                agents.push(format!(
+                    "{}={}({} queued)",
+                    snapshot.agent, status, snapshot.queued
+                ));
+            }
+            info!(
+                event = "crawl_status",
+                frontier = snapshots.iter().map(|s| s.queued).sum::<u64>(),
+                visited = snapshots.iter().map(|s| s.visited).sum::<u64>(),
+                published = snapshots.iter().map(|s| s.published).sum::<u64>(),
+                fetch_rate = format!("{:.2}", fetched as f64 / elapsed),
+                error_rate = format!("{:.3}", failed as f64 / fetched.max(1) as f64),
+                agents = %agents.join(" "),
+                "Crawl status"
+            );
+            previous = snapshots;
+            if finished {
+                break;
+            }
+        }
+    }
+
+    fn bars(&self) -> Vec<ProgressBar> {
+        let multi = MultiProgress::with_draw_target(ProgressDrawTarget::stderr());
+        let style = ProgressStyle::with_template(
+            "{prefix:>20} [{bar:30.cyan/blue}] {pos}/{len} {msg} ({elapsed})",
+        )
+        .expect("valid progress template")
+        .progress_chars("=> ");
+
+        self.agents
+            .iter()
+            .map(|(name, _)| {
+                let bar = multi.add(ProgressBar::new(0));
+                bar.set_style(style.clone());
+                bar.set_prefix(name.clone());
+                bar
+            })
+            .collect()
+    }
+
+    fn snapshots(&self) -> Vec<AgentSnapshot> {
+        self.agents
+            .iter()
+            .map(|(name, progress)| AgentSnapshot {
+                agent: name.clone(),
+                processed: progress.processed.load(Ordering::Relaxed),
+                failed: progress.failed.load(Ordering::Relaxed),
+                published: progress.published.load(Ordering::Relaxed),
+                queued: progress.queued.load(Ordering::Relaxed),
+                visited: progress.visited.load(Ordering::Relaxed),
+            })
+            .collect()
+    }
+}
+
+impl ProgressHandle {
+    // Render the final state and stop reporting.
+    pub async fn finish(self) {
+        let _ = self.done.send(true);
+        for task in self.tasks {
+            let _ = task.await;
+        }
+    }
+}
+
+fn render_bars(bars: &[ProgressBar], snapshots: &[AgentSnapshot], finished: bool) {
+    for (bar, snapshot) in bars.iter().zip(snapshots) {
+        let done = snapshot.processed + snapshot.failed;
+        bar.set_length(done + snapshot.queued);
+        bar.set_position(done);
+        bar.set_message(format!(
+            "published {}, failed {}",
+            snapshot.published, snapshot.failed
+        ));
+        if finished {
+            bar.finish();
+        }
+    }
+}
+
+fn render_json(snapshots: &[AgentSnapshot], elapsed: Duration) {
+    let line = ProgressLine {
+        elapsed_secs: elapsed.as_secs(),
+        processed: snapshots.iter().map(|s| s.processed).sum(),
+        failed: snapshots.iter().map(|s| s.failed).sum(),
+        published: snapshots.iter().map(|s| s.published).sum(),
+        queued: snapshots.iter().map(|s| s.queued).sum(),
+        agents: snapshots,
+    };
+    if let Ok(json) = serde_json::to_string(&line) {
+        eprintln!("{json}");
+    }
+}
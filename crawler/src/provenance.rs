@@ -0,0 +1,55 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::config::{
+    CrawlerConfig, DedupConfig, FetchConfig, FrontierConfig, PolitenessConfig, ScopeConfig,
+};
+
+// Settings that change what a crawl produces. They are hashed into the provenance of every
+// published page so that corpora produced by differently configured deployments can be told apart.
+#[derive(Serialize)]
+struct Fingerprint<'a> {
+    crawler_type: &'a str,
+    max_depth: u32,
+    politeness: &'a PolitenessConfig,
+    scope: &'a ScopeConfig,
+    frontier: &'a FrontierConfig,
+    fetch: &'a FetchConfig,
+    dedup: &'a DedupConfig,
+    incremental: bool,
+    recrawl: bool,
+    message_bus: &'a str,
+}
+
+// Version of the crawler crate, recorded in the provenance of every published page.
+pub const CRAWLER_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// Short SHA-256 of the crawl-affecting settings.
+pub fn config_hash(config: &CrawlerConfig) -> String {
+    let fingerprint = Fingerprint {
+        crawler_type: &config.crawler_type,
+        max_depth: config.max_depth,
+        politeness: &config.politeness,
+        scope: &config.scope,
+        frontier: &config.frontier,
+        fetch: &config.fetch,
+        dedup: &config.dedup,
+        incremental: config.incremental.enabled,
+        recrawl: config.recrawl.enabled,
+        message_bus: &config.bus.kind,
+    };
+    let json = serde_json::to_string(&fingerprint).unwrap_or_default();
+    Sha256::digest(json.as_bytes())[..8]
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+// Identifier of the current crawl job: the configured job id if set, otherwise the crawler type and
+// the start time of the process.
+pub fn job_id(config: &CrawlerConfig, started_at: u64) -> String {
+    config
+        .job_id
+        .clone()
+        .unwrap_or_else(|| format!("{}-{started_at}", config.crawler_type))
+}
@@ -0,0 +1,207 @@
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+use drivers::errors::DriverError;
+use tracing::{debug, error, info, instrument, warn};
+use url::Url;
+
+use crate::{
+    config::RecrawlConfig,
+    controllers::{
+        checkpointcontroller::CheckpointControllerTrait,
+        crawlrecordcontroller::CrawlRecordControllerTrait, leasecontroller::LeaseControllerTrait,
+        CheckpointController, CrawlRecordController, LeaseController,
+    },
+    crawler::unix_timestamp,
+    frontier::Frontier,
+    repositories::CheckpointEntry,
+    requests::http::HttpRequest,
+    rules::RuleTarget,
+};
+
+// Width of the time buckets recrawls are filed under: `<key>:<due_at / BUCKET_SECS>`.
+const BUCKET_SECS: u64 = 60;
+
+struct RecrawlRule {
+    target: RuleTarget,
+    ttl: Duration,
+}
+
+// Freshness TTL of the crawled pages: how long a page is considered fresh before it is fetched
+// again. The first rule matching the URL (by domain or URL pattern) applies, then the default.
+pub struct RecrawlPolicy {
+    default_ttl: Option<Duration>,
+    rules: Vec<RecrawlRule>,
+}
+
+impl RecrawlPolicy {
+    pub fn from_config(config: &RecrawlConfig) -> Result<Self, String> {
+        let rules = config
+            .rules
+            .iter()
+            .map(|rule| {
+                let target = RuleTarget::from_config(
+                    rule.domain.as_deref(),
+                    rule.pattern.as_deref(),
+                    "recrawl",
+                )?;
+                Ok(RecrawlRule {
+                    target,
+                    ttl: Duration::from_secs(rule.ttl_secs),
+                })
+            })
+            .collect::<Result<_, String>>()?;
+
+        Ok(RecrawlPolicy {
+            default_ttl: (config.default_ttl_secs > 0)
+                .then(|| Duration::from_secs(config.default_ttl_secs)),
+            rules,
+        })
+    }
+
+    // Time after which `url` should be crawled again, if ever. A rule with a TTL of 0 disables
+    // recrawls of its pages.
+    pub fn ttl(&self, url: &Url) -> Option<Duration> {
+        let rule = self.rules.iter().find(|rule| rule.target.matches(url));
+        match rule {
+            Some(rule) => (!rule.ttl.is_zero()).then_some(rule.ttl),
+            None => self.default_ttl,
+        }
+    }
+}
+
+// Key of the list holding the recrawls due at `due_at`, under the prefix `key`.
+pub fn due_key(key: &str, due_at: u64) -> String {
+    format!("{key}:{}", due_at / BUCKET_SECS)
+}
+
+// Hands the pages whose freshness TTL expired back to the running crawl. Crawled pages are filed
+// in Redis lists by due time (see `due_key`); each interval the lists that are due are consumed
+// and their pages refreshed through the frontier, unless they were crawled again in the meantime.
+//
+// Every process of the deployment runs the task, but each interval only the process that wins
+// the lease of the interval schedules the recrawls.
+pub struct RecrawlScheduler {
+    checkpoint_controller: Arc<CheckpointController>,
+    crawl_record_controller: Arc<CrawlRecordController>,
+    lease_controller: LeaseController,
+    frontier: Frontier,
+    key: String,
+    interval: Duration,
+}
+
+impl RecrawlScheduler {
+    pub fn new(
+        checkpoint_controller: Arc<CheckpointController>,
+        crawl_record_controller: Arc<CrawlRecordController>,
+        lease_controller: LeaseController,
+        frontier: Frontier,
+        key: String,
+        interval: Duration,
+    ) -> Self {
+        RecrawlScheduler {
+            checkpoint_controller,
+            crawl_record_controller,
+            lease_controller,
+            frontier,
+            key,
+            interval,
+        }
+    }
+
+    // Schedule the due recrawls every interval, for as long as the process runs.
+    pub async fn run(self) {
+        let period = self.interval.as_secs().max(1);
+        let mut interval = tokio::time::interval(Duration::from_secs(period));
+        loop {
+            interval.tick().await;
+            match self
+                .lease_controller
+                .acquire(&self.key, unix_timestamp() / period)
+                .await
+            {
+                Ok(true) => {}
+                Ok(false) => {
+                    debug!("Recrawls are scheduled by another process");
+                    continue;
+                }
+                Err(e) => {
+                    error!("Failed to acquire the recrawl lease: {}", e);
+                    continue;
+                }
+            }
+            match self.schedule().await {
+                Ok(0) => debug!("No pages due for a recrawl"),
+                Ok(scheduled) => info!(
+                    event = "recrawl_scheduled",
+                    pages = scheduled,
+                    "Scheduled {} recrawls",
+                    scheduled
+                ),
+                Err(e) => error!("Failed to schedule recrawls: {}", e),
+            }
+        }
+    }
+
+    // Refresh the pages of every due list. Returns the number of pages scheduled.
+    #[instrument(skip(self), fields(key = %self.key))]
+    pub async fn schedule(&self) -> Result<usize, DriverError> {
+        let now = unix_timestamp();
+        let current_bucket = now / BUCKET_SECS;
+        let mut seen = HashSet::new();
+        let mut scheduled = 0;
+
+        for key in self
+            .checkpoint_controller
+            .keys(&format!("{}:*", self.key))
+            .await?
+        {
+            let is_due = key
+                .rsplit_once(':')
+                .and_then(|(_, bucket)| bucket.parse::<u64>().ok())
+                .is_some_and(|bucket| bucket <= current_bucket);
+            if !is_due {
+                continue;
+            }
+
+            for entry in self.checkpoint_controller.restore(&key).await? {
+                if !seen.insert(entry.url.clone()) {
+                    continue;
+                }
+                // Pages crawled again since they were filed have a later due time.
+                let record = self.crawl_record_controller.find(&entry.url).await?;
+                if record.is_some_and(|record| record.recrawl_at.is_some_and(|at| at > now)) {
+                    continue;
+                }
+                match self
+                    .frontier
+                    .refresh(&entry.url, entry.depth, entry.origin_seed, entry.path)
+                {
+                    Ok(_) => scheduled += 1,
+                    Err(e) => warn!("Not recrawling {}: {}", entry.url, e),
+                }
+            }
+        }
+        Ok(scheduled)
+    }
+}
+
+// File the page of `req` for a recrawl at `due_at` under the prefix `key`.
+pub async fn file(
+    checkpoint_controller: &CheckpointController,
+    key: &str,
+    req: &HttpRequest,
+    due_at: u64,
+) -> Result<(), DriverError> {
+    let entry = CheckpointEntry {
+        url: req.target.clone(),
+        depth: req.depth,
+        attempts: 0,
+        refresh: true,
+        origin_seed: req.origin_seed.clone(),
+        path: req.path.clone(),
+        enqueued_at: Some(unix_timestamp()),
+    };
+    checkpoint_controller
+        .save(&due_key(key, due_at), vec![entry])
+        .await
+}
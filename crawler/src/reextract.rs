@@ -0,0 +1,208 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use models::{CrawlMetadata, PageData, Provenance, SchemaVersion};
+use serde::Deserialize;
+use tracing::{info, instrument, warn};
+
+use crate::{
+    controllers::{pagecontroller::PageControllerTrait, PageController},
+    parsers::{self, selectors::SelectorParser},
+    repositories::StoredPage,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReextractSource {
+    // Raw responses stored in Redis by DEBUG_STORE_PAGES
+    Redis,
+    // PageData records with their body, one JSON object per line (e.g. a consumer `jsonl` sink)
+    Jsonl,
+}
+
+// Fields of a stored PageData needed to extract it again. Other fields are recomputed.
+#[derive(Debug, Deserialize)]
+struct CorpusRecord {
+    url: String,
+    #[serde(default)]
+    status_code: u16,
+    #[serde(default)]
+    headers: Vec<String>,
+    #[serde(default)]
+    body: String,
+    #[serde(default)]
+    crawl: CrawlMetadata,
+}
+
+#[derive(Debug, Default)]
+pub struct ReextractStats {
+    pub extracted: usize,
+    pub skipped: usize,
+}
+
+// Run the current extraction pipeline over stored bodies and write the resulting PageData as JSON
+// lines to the file `output`, with the fields of the `selectors` profiles. Pages are stamped with
+// `provenance`, so re-extracted records can be told apart from the original crawl.
+#[instrument(
+    name = "Re-extract pages",
+    skip(page_controller, provenance, selectors)
+)]
+pub async fn reextract(
+    source: ReextractSource,
+    page_controller: Option<&PageController>,
+    path: Option<&str>,
+    host: Option<&str>,
+    output: &str,
+    provenance: &Provenance,
+    selectors: Option<&SelectorParser>,
+) -> Result<ReextractStats, String> {
+    if let Some(parent) = Path::new(output).parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+    }
+    let file = File::create(output).map_err(|e| format!("Failed to create {output}: {e}"))?;
+    let mut writer = BufWriter::new(file);
+    let mut stats = ReextractStats::default();
+
+    match source {
+        ReextractSource::Redis => {
+            let page_controller = page_controller.ok_or("No page store configured")?;
+            let urls = page_controller
+                .stored_urls(host)
+                .await
+                .map_err(|e| format!("Failed to list stored pages: {e}"))?;
+            info!(count = urls.len(), "Loaded stored pages");
+
+            for url in urls {
+                match page_controller.find(&url).await {
+                    Ok(Some(StoredPage {
+                        url,
+                        status_code,
+                        headers,
+                        body,
+                        fetched_at,
+                    })) => {
+                        let record = CorpusRecord {
+                            url,
+                            status_code,
+                            headers,
+                            body,
+                            crawl: CrawlMetadata {
+                                crawled_at: fetched_at,
+                                ..CrawlMetadata::default()
+                            },
+                        };
+                        emit(&mut writer, record, provenance, selectors, &mut stats)?;
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        warn!("Failed to load stored page {}: {}", url, e);
+                        stats.skipped += 1;
+                    }
+                }
+            }
+        }
+        ReextractSource::Jsonl => {
+            let path = path.ok_or("--path is required to re-extract a JSONL corpus")?;
+            let file = File::open(path).map_err(|e| format!("Failed to open {path}: {e}"))?;
+            for (n, line) in BufReader::new(file).lines().enumerate() {
+                let line = line.map_err(|e| format!("Failed to read {path}: {e}"))?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str::<CorpusRecord>(&line) {
+                    Ok(record) => emit(&mut writer, record, provenance, selectors, &mut stats)?,
+                    Err(e) => {
+                        warn!("Skipping line {} of {}: {}", n + 1, path, e);
+                        stats.skipped += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    writer
+        .flush()
+        .map_err(|e| format!("Failed to write {output}: {e}"))?;
+    Ok(stats)
+}
+
+// Extract a single record and write it out. Records without a body are skipped.
+fn emit(
+    writer: &mut impl Write,
+    record: CorpusRecord,
+    provenance: &Provenance,
+    selectors: Option<&SelectorParser>,
+    stats: &mut ReextractStats,
+) -> Result<(), String> {
+    if record.body.is_empty() {
+        warn!("Skipping {}: no stored body", record.url);
+        stats.skipped += 1;
+        return Ok(());
+    }
+
+    let parsed = match parsers::extract(&record.url, &record.headers, &record.body, selectors) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            warn!("Skipping {}: {}", record.url, e);
+            stats.skipped += 1;
+            return Ok(());
+        }
+    };
+
+    let title = record
+        .headers
+        .iter()
+        .find_map(|header| {
+            let (key, value) = header.split_once(':')?;
+            key.trim()
+                .eq_ignore_ascii_case("title")
+                .then(|| value.trim())
+        })
+        .unwrap_or("No title")
+        .to_string();
+
+    // Same robots handling as live crawls (see `HttpRequest::execute`).
+    let links = if parsed.robots.nofollow {
+        Vec::new()
+    } else {
+        parsed.links
+    };
+    let body = if parsed.robots.noindex {
+        String::new()
+    } else {
+        record.body
+    };
+
+    let page = PageData {
+        schema_version: SchemaVersion::CURRENT,
+        url: record.url,
+        title,
+        status_code: record.status_code,
+        headers: record.headers,
+        meta: parsed.meta,
+        links,
+        body,
+        usage: parsed.usage,
+        robots: parsed.robots,
+        canonical_url: parsed.canonical_url,
+        fingerprint: parsed.fingerprint,
+        language: parsed.language,
+        main_content: None,
+        structured_data: parsed.structured_data,
+        custom_fields: parsed.custom_fields,
+        crawl: record.crawl,
+        provenance: provenance.clone(),
+        skipped: None,
+    };
+
+    let mut line = serde_json::to_vec(&page).map_err(|e| e.to_string())?;
+    line.push(b'\n');
+    writer
+        .write_all(&line)
+        .map_err(|e| format!("Failed to write page: {e}"))?;
+    stats.extracted += 1;
+    Ok(())
+}
@@ -1,5 +1,19 @@
+pub mod blockrepository;
+pub mod bloomrepository;
+pub mod budgetrepository;
+pub mod checkpointrepository;
+pub mod contentrepository;
+pub mod crawlrecordrepository;
+pub mod deadletterrepository;
+pub mod externaldomainrepository;
+pub mod leaserepository;
+pub mod pagerepository;
+pub mod refusalrepository;
+pub mod robotsrepository;
 pub mod seedrepository;
+pub mod sharedfrontierrepository;
 pub mod urlrepository;
+pub mod workerrepository;
 
 use async_trait::async_trait;
 use drivers::errors::DriverError;
@@ -19,5 +33,19 @@ where
 }
 
 // re-export all repositories here
-pub use seedrepository::{load_default_seeds, load_seeds_from_dir};
+pub use blockrepository::{BlockRepository, BlockedDomain};
+pub use bloomrepository::BloomRepository;
+pub use budgetrepository::BudgetRepository;
+pub use checkpointrepository::{CheckpointEntry, CheckpointRepository};
+pub use contentrepository::{ContentRecord, ContentRepository};
+pub use crawlrecordrepository::{CrawlRecord, CrawlRecordRepository};
+pub use deadletterrepository::{DeadLetterEntry, DeadLetterRepository};
+pub use externaldomainrepository::ExternalDomainRepository;
+pub use leaserepository::LeaseRepository;
+pub use pagerepository::{PageRepository, StoredPage};
+pub use refusalrepository::{RefusalRepository, RefusalWindow};
+pub use robotsrepository::{RobotsRecord, RobotsRepository};
+pub use seedrepository::{load_default_seeds, load_seeds_from_dir, load_seeds_from_file};
+pub use sharedfrontierrepository::{FrontierEntry, FrontierLease, SharedFrontierRepository};
 pub use urlrepository::UrlRepository;
+pub use workerrepository::{WorkerHeartbeat, WorkerRepository};
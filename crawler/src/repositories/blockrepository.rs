@@ -0,0 +1,65 @@
+use std::sync::Arc;
+
+use crate::blocking::BlockStrategy;
+use crate::repositories::Repository;
+use async_trait::async_trait;
+use drivers::errors::DriverError;
+use drivers::CacheDriver;
+use models::WorkerIdentity;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+// State of a domain that served bot-blocking responses.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockedDomain {
+    pub domain: String,
+    pub reason: String,
+    pub strategy: BlockStrategy,
+    // Number of blocking responses seen so far.
+    pub count: u32,
+    // Delay to wait before each request (`SlowDown` strategy).
+    pub delay_ms: u64,
+    // Index of the fallback proxy to use (`SwitchProxy` strategy).
+    pub proxy_index: usize,
+    // Unix timestamp (seconds) until which requests to the domain are paused (`Cooldown`
+    // strategy).
+    #[serde(default)]
+    pub cooldown_until: u64,
+    // Unix timestamp (seconds) of the last detection.
+    pub detected_at: u64,
+    // Worker that saw the last blocking response.
+    #[serde(default)]
+    pub detected_by: WorkerIdentity,
+}
+
+pub struct BlockRepository {
+    driver: Arc<Mutex<dyn CacheDriver<str, BlockedDomain>>>,
+}
+
+impl BlockRepository {
+    pub fn new(driver: Arc<Mutex<dyn CacheDriver<str, BlockedDomain>>>) -> Self {
+        BlockRepository { driver }
+    }
+}
+
+#[async_trait]
+impl<K> Repository<K, BlockedDomain> for BlockRepository
+where
+    K: AsRef<str> + Send + Sync + 'static,
+{
+    async fn set(&self, key: K, value: BlockedDomain) -> Result<(), DriverError> {
+        self.driver.lock().await.set(key.as_ref(), &value)
+    }
+
+    async fn get(&self, key: K) -> Result<BlockedDomain, DriverError> {
+        self.driver.lock().await.get(key.as_ref())
+    }
+
+    async fn remove(&self, key: K) -> Result<(), DriverError> {
+        self.driver.lock().await.remove(key.as_ref())
+    }
+
+    async fn exists(&self, key: K) -> Result<bool, DriverError> {
+        self.driver.lock().await.exists(key.as_ref())
+    }
+}
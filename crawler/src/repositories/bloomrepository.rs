@@ -0,0 +1,40 @@
+use std::sync::Arc;
+
+use crate::bloom::BloomFilter;
+use crate::repositories::Repository;
+use async_trait::async_trait;
+use drivers::errors::DriverError;
+use drivers::CacheDriver;
+use tokio::sync::Mutex;
+
+pub struct BloomRepository {
+    driver: Arc<Mutex<dyn CacheDriver<str, BloomFilter>>>,
+}
+
+impl BloomRepository {
+    pub fn new(driver: Arc<Mutex<dyn CacheDriver<str, BloomFilter>>>) -> Self {
+        BloomRepository { driver }
+    }
+}
+
+#[async_trait]
+impl<K> Repository<K, BloomFilter> for BloomRepository
+where
+    K: AsRef<str> + Send + Sync + 'static,
+{
+    async fn set(&self, key: K, value: BloomFilter) -> Result<(), DriverError> {
+        self.driver.lock().await.set(key.as_ref(), &value)
+    }
+
+    async fn get(&self, key: K) -> Result<BloomFilter, DriverError> {
+        self.driver.lock().await.get(key.as_ref())
+    }
+
+    async fn remove(&self, key: K) -> Result<(), DriverError> {
+        self.driver.lock().await.remove(key.as_ref())
+    }
+
+    async fn exists(&self, key: K) -> Result<bool, DriverError> {
+        self.driver.lock().await.exists(key.as_ref())
+    }
+}
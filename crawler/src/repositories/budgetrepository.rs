@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use crate::repositories::Repository;
+use async_trait::async_trait;
+use drivers::errors::DriverError;
+use drivers::CacheDriver;
+use tokio::sync::Mutex;
+
+pub struct BudgetRepository {
+    driver: Arc<Mutex<dyn CacheDriver<str, u64>>>,
+}
+
+impl BudgetRepository {
+    pub fn new(driver: Arc<Mutex<dyn CacheDriver<str, u64>>>) -> Self {
+        BudgetRepository { driver }
+    }
+
+    pub async fn increment(&self, key: &str, delta: u64) -> Result<u64, DriverError> {
+        let delta = i64::try_from(delta)
+            .map_err(|_| DriverError::InvalidInput(format!("Increment too large: {delta}")))?;
+        let total = self.driver.lock().await.increment(key, delta)?;
+        Ok(total.max(0) as u64)
+    }
+}
+
+#[async_trait]
+impl<K> Repository<K, u64> for BudgetRepository
+where
+    K: AsRef<str> + Send + Sync + 'static,
+{
+    async fn set(&self, key: K, value: u64) -> Result<(), DriverError> {
+        self.driver.lock().await.set(key.as_ref(), &value)
+    }
+
+    async fn get(&self, key: K) -> Result<u64, DriverError> {
+        self.driver.lock().await.get(key.as_ref())
+    }
+
+    async fn remove(&self, key: K) -> Result<(), DriverError> {
+        self.driver.lock().await.remove(key.as_ref())
+    }
+
+    async fn exists(&self, key: K) -> Result<bool, DriverError> {
+        self.driver.lock().await.exists(key.as_ref())
+    }
+}
@@ -0,0 +1,112 @@
+use std::{collections::HashSet, sync::Arc};
+
+use crate::repositories::Repository;
+use async_trait::async_trait;
+use drivers::errors::DriverError;
+use drivers::CacheDriver;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+// Serializable snapshot of a queued request, used to persist the crawler queue across restarts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckpointEntry {
+    pub url: String,
+    pub depth: u32,
+    #[serde(default)]
+    pub attempts: u32,
+    // Recrawl even if the URL was already visited (incremental crawls).
+    #[serde(default)]
+    pub refresh: bool,
+    // Seed the request was discovered from and pages followed from it (see `HttpRequest`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin_seed: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path: Vec<String>,
+    // Unix timestamp at which the entry was persisted, used to expire stale entries.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enqueued_at: Option<u64>,
+}
+
+pub struct CheckpointRepository {
+    driver: Arc<Mutex<dyn CacheDriver<str, Vec<CheckpointEntry>>>>,
+}
+
+impl CheckpointRepository {
+    pub fn new(driver: Arc<Mutex<dyn CacheDriver<str, Vec<CheckpointEntry>>>>) -> Self {
+        CheckpointRepository { driver }
+    }
+
+    // Append entries to the checkpoint stored at `key`. The driver lock is held for the whole
+    // read-modify-write so that agents shutting down concurrently do not overwrite each other.
+    pub async fn append(
+        &self,
+        key: &str,
+        entries: Vec<CheckpointEntry>,
+    ) -> Result<(), DriverError> {
+        let mut driver = self.driver.lock().await;
+        let mut stored = if driver.exists(key)? {
+            driver.get(key)?
+        } else {
+            Vec::new()
+        };
+        stored.extend(entries);
+        driver.set(key, &stored)
+    }
+
+    pub async fn keys(&self, pattern: &str) -> Result<Vec<String>, DriverError> {
+        self.driver.lock().await.keys(pattern)
+    }
+
+    // Remove from the list at `key` the entries whose URL is in `drop`, the entries persisted
+    // before `expires_before`, and repeated URLs (the first entry is kept). The list is re-read
+    // under the driver lock, so entries appended since it was last read are kept. Returns the
+    // removed entries.
+    pub async fn compact(
+        &self,
+        key: &str,
+        drop: &HashSet<String>,
+        expires_before: Option<u64>,
+    ) -> Result<Vec<CheckpointEntry>, DriverError> {
+        let mut driver = self.driver.lock().await;
+        if !driver.exists(key)? {
+            return Ok(Vec::new());
+        }
+        let mut seen = HashSet::new();
+        let (kept, removed): (Vec<CheckpointEntry>, Vec<CheckpointEntry>) =
+            driver.get(key)?.into_iter().partition(|entry| {
+                let expired = matches!(
+                    (entry.enqueued_at, expires_before),
+                    (Some(at), Some(before)) if at < before
+                );
+                !drop.contains(&entry.url) && !expired && seen.insert(entry.url.clone())
+            });
+        if kept.is_empty() {
+            driver.remove(key)?;
+        } else if !removed.is_empty() {
+            driver.set(key, &kept)?;
+        }
+        Ok(removed)
+    }
+}
+
+#[async_trait]
+impl<K> Repository<K, Vec<CheckpointEntry>> for CheckpointRepository
+where
+    K: AsRef<str> + Send + Sync + 'static,
+{
+    async fn set(&self, key: K, value: Vec<CheckpointEntry>) -> Result<(), DriverError> {
+        self.driver.lock().await.set(key.as_ref(), &value)
+    }
+
+    async fn get(&self, key: K) -> Result<Vec<CheckpointEntry>, DriverError> {
+        self.driver.lock().await.get(key.as_ref())
+    }
+
+    async fn remove(&self, key: K) -> Result<(), DriverError> {
+        self.driver.lock().await.remove(key.as_ref())
+    }
+
+    async fn exists(&self, key: K) -> Result<bool, DriverError> {
+        self.driver.lock().await.exists(key.as_ref())
+    }
+}
@@ -0,0 +1,84 @@
+use std::sync::Arc;
+
+use crate::repositories::Repository;
+use async_trait::async_trait;
+use drivers::errors::DriverError;
+use drivers::CacheDriver;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+// Page whose content was seen first, stored in the buckets of its fingerprint.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContentRecord {
+    pub url: String,
+    pub content_hash: String,
+    pub simhash: u64,
+}
+
+pub struct ContentRepository {
+    driver: Arc<Mutex<dyn CacheDriver<str, Vec<ContentRecord>>>>,
+}
+
+impl ContentRepository {
+    pub fn new(driver: Arc<Mutex<dyn CacheDriver<str, Vec<ContentRecord>>>>) -> Self {
+        ContentRepository { driver }
+    }
+
+    // Return the first record of the `keys` buckets matching `is_match`. When none does, `record`
+    // is appended to every bucket (unless already there), which keeps at most `max_records`
+    // records, dropping the oldest. The buckets are read and updated under a single lock.
+    pub async fn find_or_insert(
+        &self,
+        keys: &[String],
+        record: ContentRecord,
+        is_match: impl Fn(&ContentRecord) -> bool,
+        max_records: usize,
+    ) -> Result<Option<ContentRecord>, DriverError> {
+        let mut driver = self.driver.lock().await;
+        let mut buckets = Vec::with_capacity(keys.len());
+        for key in keys {
+            let bucket = if driver.exists(key)? {
+                driver.get(key)?
+            } else {
+                Vec::new()
+            };
+            if let Some(found) = bucket.iter().find(|r| is_match(r)) {
+                return Ok(Some(found.clone()));
+            }
+            buckets.push(bucket);
+        }
+
+        for (key, mut bucket) in keys.iter().zip(buckets) {
+            if bucket.iter().any(|r| r.url == record.url) {
+                continue;
+            }
+            bucket.push(record.clone());
+            let excess = bucket.len().saturating_sub(max_records);
+            bucket.drain(..excess);
+            driver.set(key, &bucket)?;
+        }
+        Ok(None)
+    }
+}
+
+#[async_trait]
+impl<K> Repository<K, Vec<ContentRecord>> for ContentRepository
+where
+    K: AsRef<str> + Send + Sync + 'static,
+{
+    async fn set(&self, key: K, value: Vec<ContentRecord>) -> Result<(), DriverError> {
+        self.driver.lock().await.set(key.as_ref(), &value)
+    }
+
+    async fn get(&self, key: K) -> Result<Vec<ContentRecord>, DriverError> {
+        self.driver.lock().await.get(key.as_ref())
+    }
+
+    async fn remove(&self, key: K) -> Result<(), DriverError> {
+        self.driver.lock().await.remove(key.as_ref())
+    }
+
+    async fn exists(&self, key: K) -> Result<bool, DriverError> {
+        self.driver.lock().await.exists(key.as_ref())
+    }
+}
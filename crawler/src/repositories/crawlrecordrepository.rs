@@ -0,0 +1,60 @@
+use std::sync::Arc;
+
+use crate::repositories::Repository;
+use async_trait::async_trait;
+use drivers::errors::DriverError;
+use drivers::CacheDriver;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+// Last successful crawl of a URL, used to only recrawl pages that changed since.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrawlRecord {
+    pub url: String,
+    pub status_code: u16,
+    // Unix timestamp (seconds) of the fetch.
+    pub crawled_at: u64,
+    // SHA-256 of the body (hex), telling whether a recrawled page changed.
+    #[serde(default)]
+    pub content_hash: Option<String>,
+    // Unix timestamp at which the page is due for a recrawl, when its freshness TTL expires.
+    #[serde(default)]
+    pub recrawl_at: Option<u64>,
+    // `ETag` and `Last-Modified` headers of the page, sent back when it is recrawled.
+    #[serde(default)]
+    pub etag: Option<String>,
+    #[serde(default)]
+    pub last_modified: Option<String>,
+}
+
+pub struct CrawlRecordRepository {
+    driver: Arc<Mutex<dyn CacheDriver<str, CrawlRecord>>>,
+}
+
+impl CrawlRecordRepository {
+    pub fn new(driver: Arc<Mutex<dyn CacheDriver<str, CrawlRecord>>>) -> Self {
+        CrawlRecordRepository { driver }
+    }
+}
+
+#[async_trait]
+impl<K> Repository<K, CrawlRecord> for CrawlRecordRepository
+where
+    K: AsRef<str> + Send + Sync + 'static,
+{
+    async fn set(&self, key: K, value: CrawlRecord) -> Result<(), DriverError> {
+        self.driver.lock().await.set(key.as_ref(), &value)
+    }
+
+    async fn get(&self, key: K) -> Result<CrawlRecord, DriverError> {
+        self.driver.lock().await.get(key.as_ref())
+    }
+
+    async fn remove(&self, key: K) -> Result<(), DriverError> {
+        self.driver.lock().await.remove(key.as_ref())
+    }
+
+    async fn exists(&self, key: K) -> Result<bool, DriverError> {
+        self.driver.lock().await.exists(key.as_ref())
+    }
+}
@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use drivers::errors::DriverError;
+use drivers::CacheDriver;
+use models::WorkerIdentity;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+// Record of a request that permanently failed, kept for later inspection.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetterEntry {
+    pub url: String,
+    pub depth: u32,
+    pub attempts: u32,
+    pub error: String,
+    // Unix timestamp (seconds) of the last failed attempt.
+    pub failed_at: u64,
+    // Worker that gave up on the URL.
+    #[serde(default)]
+    pub worker: WorkerIdentity,
+}
+
+pub struct DeadLetterRepository {
+    driver: Arc<Mutex<dyn CacheDriver<str, DeadLetterEntry>>>,
+}
+
+impl DeadLetterRepository {
+    pub fn new(driver: Arc<Mutex<dyn CacheDriver<str, DeadLetterEntry>>>) -> Self {
+        DeadLetterRepository { driver }
+    }
+
+    // Append an entry to the dead-letter list stored at `key` (a Redis list), dropping its oldest
+    // entries beyond `max_entries` (0 = unlimited).
+    pub async fn append(
+        &self,
+        key: &str,
+        entry: DeadLetterEntry,
+        max_entries: usize,
+    ) -> Result<(), DriverError> {
+        self.driver
+            .lock()
+            .await
+            .push_capped(key, &entry, max_entries)
+    }
+
+    // Latest `limit` entries of the list stored at `key`, oldest first (every entry when `limit`
+    // is 0).
+    pub async fn latest(
+        &self,
+        key: &str,
+        limit: usize,
+    ) -> Result<Vec<DeadLetterEntry>, DriverError> {
+        let start = if limit == 0 { 0 } else { -(limit as i64) };
+        self.driver.lock().await.list_range(key, start, -1)
+    }
+}
@@ -0,0 +1,44 @@
+use std::sync::Arc;
+
+use crate::repositories::Repository;
+use async_trait::async_trait;
+use drivers::errors::DriverError;
+use drivers::CacheDriver;
+use tokio::sync::Mutex;
+
+pub struct LeaseRepository {
+    driver: Arc<Mutex<dyn CacheDriver<str, u64>>>,
+}
+
+impl LeaseRepository {
+    pub fn new(driver: Arc<Mutex<dyn CacheDriver<str, u64>>>) -> Self {
+        LeaseRepository { driver }
+    }
+
+    pub async fn increment(&self, key: &str) -> Result<u64, DriverError> {
+        let total = self.driver.lock().await.increment(key, 1)?;
+        Ok(total.max(0) as u64)
+    }
+}
+
+#[async_trait]
+impl<K> Repository<K, u64> for LeaseRepository
+where
+    K: AsRef<str> + Send + Sync + 'static,
+{
+    async fn set(&self, key: K, value: u64) -> Result<(), DriverError> {
+        self.driver.lock().await.set(key.as_ref(), &value)
+    }
+
+    async fn get(&self, key: K) -> Result<u64, DriverError> {
+        self.driver.lock().await.get(key.as_ref())
+    }
+
+    async fn remove(&self, key: K) -> Result<(), DriverError> {
+        self.driver.lock().await.remove(key.as_ref())
+    }
+
+    async fn exists(&self, key: K) -> Result<bool, DriverError> {
+        self.driver.lock().await.exists(key.as_ref())
+    }
+}
@@ -0,0 +1,55 @@
+use std::sync::Arc;
+
+use crate::repositories::Repository;
+use async_trait::async_trait;
+use drivers::errors::DriverError;
+use drivers::CacheDriver;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+// Raw response stored for debugging purposes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredPage {
+    pub url: String,
+    pub status_code: u16,
+    pub headers: Vec<String>,
+    pub body: String,
+    // Unix timestamp (seconds) of the fetch.
+    pub fetched_at: u64,
+}
+
+pub struct PageRepository {
+    driver: Arc<Mutex<dyn CacheDriver<str, StoredPage>>>,
+}
+
+impl PageRepository {
+    pub fn new(driver: Arc<Mutex<dyn CacheDriver<str, StoredPage>>>) -> Self {
+        PageRepository { driver }
+    }
+
+    pub async fn keys(&self, pattern: &str) -> Result<Vec<String>, DriverError> {
+        self.driver.lock().await.keys(pattern)
+    }
+}
+
+#[async_trait]
+impl<K> Repository<K, StoredPage> for PageRepository
+where
+    K: AsRef<str> + Send + Sync + 'static,
+{
+    async fn set(&self, key: K, value: StoredPage) -> Result<(), DriverError> {
+        self.driver.lock().await.set(key.as_ref(), &value)
+    }
+
+    async fn get(&self, key: K) -> Result<StoredPage, DriverError> {
+        self.driver.lock().await.get(key.as_ref())
+    }
+
+    async fn remove(&self, key: K) -> Result<(), DriverError> {
+        self.driver.lock().await.remove(key.as_ref())
+    }
+
+    async fn exists(&self, key: K) -> Result<bool, DriverError> {
+        self.driver.lock().await.exists(key.as_ref())
+    }
+}
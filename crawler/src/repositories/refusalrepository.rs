@@ -0,0 +1,58 @@
+use std::{sync::Arc, time::Duration};
+
+use crate::repositories::Repository;
+use async_trait::async_trait;
+use drivers::errors::DriverError;
+use drivers::CacheDriver;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+// Bare 403/429 responses served by a domain within the current storm window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefusalWindow {
+    // Unix timestamp (seconds) of the first refusal of the window.
+    pub started_at: u64,
+    pub count: u32,
+}
+
+pub struct RefusalRepository {
+    driver: Arc<Mutex<dyn CacheDriver<str, RefusalWindow>>>,
+}
+
+impl RefusalRepository {
+    pub fn new(driver: Arc<Mutex<dyn CacheDriver<str, RefusalWindow>>>) -> Self {
+        RefusalRepository { driver }
+    }
+
+    // Store the window of `key`, expiring after `ttl`.
+    pub async fn set_with_ttl(
+        &self,
+        key: &str,
+        value: RefusalWindow,
+        ttl: Duration,
+    ) -> Result<(), DriverError> {
+        self.driver.lock().await.set_with_ttl(key, &value, ttl)
+    }
+}
+
+#[async_trait]
+impl<K> Repository<K, RefusalWindow> for RefusalRepository
+where
+    K: AsRef<str> + Send + Sync + 'static,
+{
+    async fn set(&self, key: K, value: RefusalWindow) -> Result<(), DriverError> {
+        self.driver.lock().await.set(key.as_ref(), &value)
+    }
+
+    async fn get(&self, key: K) -> Result<RefusalWindow, DriverError> {
+        self.driver.lock().await.get(key.as_ref())
+    }
+
+    async fn remove(&self, key: K) -> Result<(), DriverError> {
+        self.driver.lock().await.remove(key.as_ref())
+    }
+
+    async fn exists(&self, key: K) -> Result<bool, DriverError> {
+        self.driver.lock().await.exists(key.as_ref())
+    }
+}
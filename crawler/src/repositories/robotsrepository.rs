@@ -0,0 +1,60 @@
+use std::{sync::Arc, time::Duration};
+
+use crate::repositories::Repository;
+use async_trait::async_trait;
+use drivers::errors::DriverError;
+use drivers::CacheDriver;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+// Cached robots.txt of a host, shared by the agents of every process.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RobotsRecord {
+    // Body of the robots.txt, or `None` when the host has none (4xx responses), in which case
+    // every URL of the host is allowed.
+    pub body: Option<String>,
+    // Unix timestamp at which the robots.txt was fetched.
+    pub fetched_at: u64,
+}
+
+pub struct RobotsRepository {
+    driver: Arc<Mutex<dyn CacheDriver<str, RobotsRecord>>>,
+}
+
+impl RobotsRepository {
+    pub fn new(driver: Arc<Mutex<dyn CacheDriver<str, RobotsRecord>>>) -> Self {
+        RobotsRepository { driver }
+    }
+
+    // Store the record of `key`, expiring after `ttl`.
+    pub async fn set_with_ttl(
+        &self,
+        key: &str,
+        value: RobotsRecord,
+        ttl: Duration,
+    ) -> Result<(), DriverError> {
+        self.driver.lock().await.set_with_ttl(key, &value, ttl)
+    }
+}
+
+#[async_trait]
+impl<K> Repository<K, RobotsRecord> for RobotsRepository
+where
+    K: AsRef<str> + Send + Sync + 'static,
+{
+    async fn set(&self, key: K, value: RobotsRecord) -> Result<(), DriverError> {
+        self.driver.lock().await.set(key.as_ref(), &value)
+    }
+
+    async fn get(&self, key: K) -> Result<RobotsRecord, DriverError> {
+        self.driver.lock().await.get(key.as_ref())
+    }
+
+    async fn remove(&self, key: K) -> Result<(), DriverError> {
+        self.driver.lock().await.remove(key.as_ref())
+    }
+
+    async fn exists(&self, key: K) -> Result<bool, DriverError> {
+        self.driver.lock().await.exists(key.as_ref())
+    }
+}
@@ -1,76 +1,194 @@
+use serde::Deserialize;
 use std::{fs, fs::read_to_string, path::Path};
 use tracing::{debug, info, instrument, trace, warn};
 use url::Url;
 
+// A seed URL. Seeds with a higher priority are crawled first by their agent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Seed {
+    pub url: Url,
+    pub priority: u8,
+}
+
+impl Seed {
+    fn new(url: Url) -> Self {
+        Seed { url, priority: 0 }
+    }
+}
+
+// Entries of JSON seed files: either plain URLs or `{"url": "...", "priority": 3}` objects.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonSeed {
+    Url(String),
+    Entry {
+        url: String,
+        #[serde(default)]
+        priority: u8,
+    },
+}
+
 #[instrument(name = "Load seeds from directory", level = "info", skip_all, fields(dir = %dir_path))]
-pub async fn load_seeds_from_dir(dir_path: &str) -> Result<Vec<Url>, String> {
+pub async fn load_seeds_from_dir(dir_path: &str) -> Result<Vec<Seed>, String> {
     info!("Scanning directory");
     let mut seeds = Vec::new();
 
     let entries = fs::read_dir(Path::new(dir_path))
         .map_err(|e| format!("Failed to read dir '{dir_path}': {e}"))?;
 
-    for entry in entries {
-        let entry = match entry {
-            Ok(e) => e,
+    // Read files in a stable order so that seeds keep their order between runs.
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| match entry {
+            Ok(e) => Some(e.path()),
             Err(e) => {
                 warn!("Skipping unreadable dir entry: {e}");
-                continue;
+                None
             }
-        };
+        })
+        .collect();
+    paths.sort();
 
-        let path = entry.path();
+    for path in paths {
         if !path.is_file() {
             trace!("Skipping non-file: {:?}", path);
             continue;
         }
+        match read_seed_file(&path) {
+            Ok(file_seeds) => seeds.extend(file_seeds),
+            Err(e) => warn!("Skipping {}", e),
+        }
+    }
+
+    sort_by_priority(&mut seeds);
+    info!(count = seeds.len(), "Loaded seeds");
+    Ok(seeds)
+}
 
-        let fname = path
-            .file_name()
-            .and_then(|s| s.to_str())
-            .unwrap_or("unknown");
-        debug!("Reading file '{}'", fname);
+#[instrument(name = "Load seeds from file", level = "info", skip_all, fields(file = %file_path))]
+pub async fn load_seeds_from_file(file_path: &str) -> Result<Vec<Seed>, String> {
+    let mut seeds = read_seed_file(Path::new(file_path))?;
+    sort_by_priority(&mut seeds);
+    info!(count = seeds.len(), "Loaded seeds");
+    Ok(seeds)
+}
 
-        let content = match read_to_string(&path) {
-            Ok(c) => c,
-            Err(e) => {
-                warn!("Skipping unreadable file '{}': {}", fname, e);
-                continue;
+// Parse a seed file according to its extension: `.csv` (`url,priority` rows), `.json` (an array
+// of URLs or `{url, priority}` objects), and plain text with one URL per line otherwise.
+fn read_seed_file(path: &Path) -> Result<Vec<Seed>, String> {
+    let fname = path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or("unknown");
+    debug!("Reading file '{}'", fname);
+
+    let content = read_to_string(path).map_err(|e| format!("unreadable file '{fname}': {e}"))?;
+
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_lowercase);
+    match extension.as_deref() {
+        Some("csv") => Ok(parse_csv(fname, &content)),
+        Some("json") => parse_json(fname, &content),
+        _ => Ok(parse_text(fname, &content)),
+    }
+}
+
+fn parse_text(fname: &str, content: &str) -> Vec<Seed> {
+    let mut seeds = Vec::new();
+    for (ln, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match Url::parse(line) {
+            Ok(u) => {
+                trace!("Accepted URL at {}:{} -> '{}'", fname, ln + 1, line);
+                seeds.push(Seed::new(u))
             }
-        };
+            Err(e) => warn!("Invalid URL at {}:{} -> '{}': {}", fname, ln + 1, line, e),
+        }
+    }
+    seeds
+}
+
+// Rows are `url` or `url,priority`; a header row (`url,priority`) is skipped.
+fn parse_csv(fname: &str, content: &str) -> Vec<Seed> {
+    let mut seeds = Vec::new();
+    for (ln, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
 
-        for (ln, line) in content.lines().enumerate() {
-            match Url::parse(line) {
-                Ok(u) => {
-                    trace!("Accepted URL at {}:{} -> '{}'", fname, ln + 1, line);
-                    seeds.push(u)
-                }
-                Err(e) => warn!("Invalid URL at {}:{} -> '{}': {}", fname, ln + 1, line, e),
+        // URLs may contain commas: only a trailing numeric column is read as the priority.
+        let (url, priority) = match line.rsplit_once(',') {
+            Some((url, priority)) => match unquote(priority).parse::<u8>() {
+                Ok(priority) => (unquote(url), priority),
+                Err(_) if ln == 0 => continue,
+                Err(_) => (unquote(line), 0),
+            },
+            None => (unquote(line), 0),
+        };
+        match Url::parse(url) {
+            Ok(u) => {
+                trace!("Accepted URL at {}:{} -> '{}'", fname, ln + 1, url);
+                seeds.push(Seed { url: u, priority })
             }
+            Err(_) if ln == 0 => trace!("Skipping header of {}", fname),
+            Err(e) => warn!("Invalid URL at {}:{} -> '{}': {}", fname, ln + 1, url, e),
         }
     }
+    seeds
+}
 
-    info!(count = seeds.len(), "Loaded seeds");
+fn parse_json(fname: &str, content: &str) -> Result<Vec<Seed>, String> {
+    let entries: Vec<JsonSeed> =
+        serde_json::from_str(content).map_err(|e| format!("invalid JSON in '{fname}': {e}"))?;
+
+    let mut seeds = Vec::new();
+    for (i, entry) in entries.into_iter().enumerate() {
+        let (url, priority) = match entry {
+            JsonSeed::Url(url) => (url, 0),
+            JsonSeed::Entry { url, priority } => (url, priority),
+        };
+        match Url::parse(&url) {
+            Ok(u) => seeds.push(Seed { url: u, priority }),
+            Err(e) => warn!("Invalid URL at {}[{}] -> '{}': {}", fname, i, url, e),
+        }
+    }
     Ok(seeds)
 }
 
+fn unquote(value: &str) -> &str {
+    value.trim().trim_matches('"').trim()
+}
+
+// Highest priority first, keeping the file order otherwise.
+fn sort_by_priority(seeds: &mut [Seed]) {
+    seeds.sort_by_key(|seed| std::cmp::Reverse(seed.priority));
+}
+
 #[instrument(name = "Load default seeds", level = "debug")]
-pub fn load_default_seeds() -> Vec<Url> {
-    let seeds = vec![
-        Url::parse("https://en.wikipedia.org/wiki/Main_Page").unwrap(),
-        Url::parse("https://www.bbc.com").unwrap(),
-        Url::parse("https://news.ycombinator.com/").unwrap(),
-        Url::parse("https://arxiv.org/").unwrap(),
-        Url::parse("https://scholar.google.com/").unwrap(),
-        Url::parse("https://data.gov/").unwrap(),
-        Url::parse("https://github.com/trending").unwrap(),
-        Url::parse("https://stackoverflow.com/").unwrap(),
-        Url::parse("https://www.producthunt.com/").unwrap(),
-        Url::parse("https://www.reddit.com/r/technology/").unwrap(),
-        Url::parse("https://medium.com/").unwrap(),
-        Url::parse("https://www.amazon.com/").unwrap(),
-        Url::parse("https://www.ebay.com/").unwrap(),
-    ];
+pub fn load_default_seeds() -> Vec<Seed> {
+    let seeds: Vec<Seed> = [
+        "https://en.wikipedia.org/wiki/Main_Page",
+        "https://www.bbc.com",
+        "https://news.ycombinator.com/",
+        "https://arxiv.org/",
+        "https://scholar.google.com/",
+        "https://data.gov/",
+        "https://github.com/trending",
+        "https://stackoverflow.com/",
+        "https://www.producthunt.com/",
+        "https://www.reddit.com/r/technology/",
+        "https://medium.com/",
+        "https://www.amazon.com/",
+        "https://www.ebay.com/",
+    ]
+    .into_iter()
+    .map(|url| Seed::new(Url::parse(url).unwrap()))
+    .collect();
     debug!(count = seeds.len(), "Default seeds prepared");
     seeds
 }
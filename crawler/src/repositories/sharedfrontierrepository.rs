@@ -0,0 +1,114 @@
+use std::sync::Arc;
+
+use crate::repositories::Repository;
+use async_trait::async_trait;
+use drivers::errors::DriverError;
+use drivers::CacheDriver;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+// Request waiting in the frontier shared by the crawler processes of a deployment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrontierEntry {
+    pub url: String,
+    pub depth: u32,
+    #[serde(default)]
+    pub attempts: u32,
+    // Recrawl even if the URL was already visited.
+    #[serde(default)]
+    pub refresh: bool,
+    // Runtime job the request belongs to, if any.
+    #[serde(default)]
+    pub job: Option<String>,
+    // Seed the request was discovered from and pages followed from it (see `HttpRequest`).
+    #[serde(default)]
+    pub origin_seed: Option<String>,
+    #[serde(default)]
+    pub path: Vec<String>,
+    // Unix timestamp at which the entry was (re)queued.
+    pub pushed_at: u64,
+}
+
+// Claim of a shared frontier entry by a worker. Entries whose lease expired are handed out again,
+// so the requests of crashed workers are not lost.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrontierLease {
+    pub worker: String,
+    // Unix timestamp after which the entry may be claimed by another worker.
+    pub expires_at: u64,
+}
+
+pub struct SharedFrontierRepository {
+    driver: Arc<Mutex<dyn CacheDriver<str, FrontierEntry>>>,
+    lease_driver: Arc<Mutex<dyn CacheDriver<str, FrontierLease>>>,
+    counter_driver: Arc<Mutex<dyn CacheDriver<str, i64>>>,
+}
+
+impl SharedFrontierRepository {
+    pub fn new(
+        driver: Arc<Mutex<dyn CacheDriver<str, FrontierEntry>>>,
+        lease_driver: Arc<Mutex<dyn CacheDriver<str, FrontierLease>>>,
+        counter_driver: Arc<Mutex<dyn CacheDriver<str, i64>>>,
+    ) -> Self {
+        SharedFrontierRepository {
+            driver,
+            lease_driver,
+            counter_driver,
+        }
+    }
+
+    pub async fn increment(&self, key: &str, delta: i64) -> Result<i64, DriverError> {
+        self.counter_driver.lock().await.increment(key, delta)
+    }
+
+    // Value of the counter at `key` (0 when it was never incremented).
+    pub async fn counter(&self, key: &str) -> Result<i64, DriverError> {
+        let mut driver = self.counter_driver.lock().await;
+        if !driver.exists(key)? {
+            return Ok(0);
+        }
+        driver.get(key)
+    }
+
+    pub async fn keys(&self, pattern: &str) -> Result<Vec<String>, DriverError> {
+        self.driver.lock().await.keys(pattern)
+    }
+
+    pub async fn find_lease(&self, key: &str) -> Result<Option<FrontierLease>, DriverError> {
+        let mut driver = self.lease_driver.lock().await;
+        if !driver.exists(key)? {
+            return Ok(None);
+        }
+        driver.get(key).map(Some)
+    }
+
+    pub async fn set_lease(&self, key: &str, lease: FrontierLease) -> Result<(), DriverError> {
+        self.lease_driver.lock().await.set(key, &lease)
+    }
+
+    pub async fn remove_lease(&self, key: &str) -> Result<(), DriverError> {
+        self.lease_driver.lock().await.remove(key)
+    }
+}
+
+#[async_trait]
+impl<K> Repository<K, FrontierEntry> for SharedFrontierRepository
+where
+    K: AsRef<str> + Send + Sync + 'static,
+{
+    async fn set(&self, key: K, value: FrontierEntry) -> Result<(), DriverError> {
+        self.driver.lock().await.set(key.as_ref(), &value)
+    }
+
+    async fn get(&self, key: K) -> Result<FrontierEntry, DriverError> {
+        self.driver.lock().await.get(key.as_ref())
+    }
+
+    async fn remove(&self, key: K) -> Result<(), DriverError> {
+        self.driver.lock().await.remove(key.as_ref())
+    }
+
+    async fn exists(&self, key: K) -> Result<bool, DriverError> {
+        self.driver.lock().await.exists(key.as_ref())
+    }
+}
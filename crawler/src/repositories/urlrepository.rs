@@ -15,6 +15,15 @@ impl UrlRepository {
     pub fn new(driver: Arc<Mutex<dyn CacheDriver<str, url::Url>>>) -> Self {
         UrlRepository { driver }
     }
+
+    // Mark `url` as visited unless it already was; returns whether it was marked by this call.
+    pub async fn set_if_absent(&self, url: Url) -> Result<bool, DriverError> {
+        self.driver.lock().await.set_if_absent(url.as_str(), &url)
+    }
+
+    pub async fn keys(&self, pattern: &str) -> Result<Vec<String>, DriverError> {
+        self.driver.lock().await.keys(pattern)
+    }
 }
 
 #[async_trait]
@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use crate::repositories::Repository;
+use async_trait::async_trait;
+use drivers::errors::DriverError;
+use drivers::CacheDriver;
+use models::WorkerIdentity;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+// Latest heartbeat of a crawler agent, refreshed every heartbeat interval while the agent runs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerHeartbeat {
+    // Unique id of the agent in the deployment: `<hostname>/<agent>`.
+    pub id: String,
+    // Name of the agent in its process (e.g. `crawler-generic-1`).
+    pub agent: String,
+    pub worker: WorkerIdentity,
+    pub crawler_type: String,
+    pub job: String,
+    // Requests executed per second since the previous heartbeat.
+    pub pages_per_sec: f64,
+    // Requests waiting in the agent queue.
+    pub queue_depth: u64,
+    // Requests executed since the agent started.
+    pub processed: u64,
+    // Unix timestamp of the heartbeat.
+    pub last_seen: u64,
+}
+
+impl WorkerHeartbeat {
+    // Whether the agent sent a heartbeat less than `stale_after_secs` before `now`.
+    pub fn is_alive(&self, now: u64, stale_after_secs: u64) -> bool {
+        now.saturating_sub(self.last_seen) < stale_after_secs
+    }
+}
+
+pub struct WorkerRepository {
+    driver: Arc<Mutex<dyn CacheDriver<str, WorkerHeartbeat>>>,
+}
+
+impl WorkerRepository {
+    pub fn new(driver: Arc<Mutex<dyn CacheDriver<str, WorkerHeartbeat>>>) -> Self {
+        WorkerRepository { driver }
+    }
+
+    pub async fn keys(&self, pattern: &str) -> Result<Vec<String>, DriverError> {
+        self.driver.lock().await.keys(pattern)
+    }
+}
+
+#[async_trait]
+impl<K> Repository<K, WorkerHeartbeat> for WorkerRepository
+where
+    K: AsRef<str> + Send + Sync + 'static,
+{
+    async fn set(&self, key: K, value: WorkerHeartbeat) -> Result<(), DriverError> {
+        self.driver.lock().await.set(key.as_ref(), &value)
+    }
+
+    async fn get(&self, key: K) -> Result<WorkerHeartbeat, DriverError> {
+        self.driver.lock().await.get(key.as_ref())
+    }
+
+    async fn remove(&self, key: K) -> Result<(), DriverError> {
+        self.driver.lock().await.remove(key.as_ref())
+    }
+
+    async fn exists(&self, key: K) -> Result<bool, DriverError> {
+        self.driver.lock().await.exists(key.as_ref())
+    }
+}
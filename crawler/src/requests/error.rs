@@ -0,0 +1,102 @@
+use std::fmt::{Display, Formatter};
+
+use crate::{
+    clients::http::{BodyTooLarge, RedirectRejected},
+    validators::{AddressError, AddressRejected},
+};
+
+#[derive(Debug)]
+pub enum RequestError {
+    InvalidUrl(String),
+    Timeout,
+    Connection(String),
+    Status(u16),
+    Http(String),
+    Parse(String),
+    // The response body exceeded the maximum size (in bytes).
+    BodyTooLarge(u64),
+    // A redirect was not followed (too many hops, loop, target out of scope).
+    Redirect(String),
+    // The host resolves to an address the crawl may not connect to (see `AddressPolicy`).
+    AddressRejected(String),
+}
+
+impl RequestError {
+    // Transient failures that are worth retrying: timeouts, connection errors, 5xx and 429 responses.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            RequestError::Timeout | RequestError::Connection(_) => true,
+            RequestError::Status(code) => *code >= 500 || *code == 429,
+            _ => false,
+        }
+    }
+}
+
+// Classify errors returned by the HTTP client
+impl From<Box<dyn std::error::Error + Send + Sync>> for RequestError {
+    fn from(err: Box<dyn std::error::Error + Send + Sync>) -> Self {
+        if let Some(e) = err.downcast_ref::<BodyTooLarge>() {
+            return RequestError::BodyTooLarge(e.limit);
+        }
+        if let Some(e) = err.downcast_ref::<RedirectRejected>() {
+            return RequestError::Redirect(e.0.clone());
+        }
+        if let Some(AddressError::Unresolved(msg)) = err.downcast_ref::<AddressError>() {
+            return RequestError::Connection(msg.clone());
+        }
+        // Addresses refused by the resolver come wrapped in the connection error.
+        let mut source: Option<&(dyn std::error::Error + 'static)> = Some(err.as_ref());
+        while let Some(e) = source {
+            if let Some(e) = e.downcast_ref::<AddressRejected>() {
+                return RequestError::AddressRejected(e.0.clone());
+            }
+            source = e.source();
+        }
+        if let Some(e) = err.downcast_ref::<reqwest::Error>() {
+            if e.is_timeout() {
+                return RequestError::Timeout;
+            }
+            if e.is_connect() {
+                return RequestError::Connection(e.to_string());
+            }
+        }
+        if let Some(e) = err.downcast_ref::<std::io::Error>() {
+            if e.kind() == std::io::ErrorKind::TimedOut {
+                return RequestError::Timeout;
+            }
+        }
+        RequestError::Http(err.to_string())
+    }
+}
+
+// Hosts that do not resolve may resolve later, so they fail like connection errors (retried).
+impl From<AddressError> for RequestError {
+    fn from(err: AddressError) -> Self {
+        match err {
+            AddressError::Rejected(e) => RequestError::AddressRejected(e.0),
+            AddressError::Unresolved(msg) => RequestError::Connection(msg),
+        }
+    }
+}
+
+impl From<reqwest::Error> for RequestError {
+    fn from(err: reqwest::Error) -> Self {
+        RequestError::from(Box::new(err) as Box<dyn std::error::Error + Send + Sync>)
+    }
+}
+
+impl Display for RequestError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RequestError::InvalidUrl(msg) => write!(f, "Invalid URL: {msg}"),
+            RequestError::Timeout => write!(f, "Request timed out"),
+            RequestError::Connection(msg) => write!(f, "Connection error: {msg}"),
+            RequestError::Status(code) => write!(f, "Unexpected status code: {code}"),
+            RequestError::Http(msg) => write!(f, "HTTP error: {msg}"),
+            RequestError::Parse(msg) => write!(f, "Parse error: {msg}"),
+            RequestError::BodyTooLarge(limit) => write!(f, "Response body exceeds {limit} bytes"),
+            RequestError::Redirect(msg) => write!(f, "Redirect not followed: {msg}"),
+            RequestError::AddressRejected(msg) => write!(f, "Address not allowed: {msg}"),
+        }
+    }
+}
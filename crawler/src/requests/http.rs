@@ -1,32 +1,274 @@
+use std::{collections::HashMap, sync::Arc, time::Instant};
+
+use models::{
+    ContentFingerprint, Link, MainContent, RobotsDirectives, SkippedReason, StructuredData,
+    UsageRights,
+};
+use reqwest::header::{HeaderMap, HeaderValue, IF_MODIFIED_SINCE, IF_NONE_MATCH};
 use serde::{Deserialize, Serialize};
-use tracing::{debug, error, info, instrument, warn};
+use tracing::{debug, error, info, instrument};
 
 use crate::{
-    clients::http::{get_default_http_client, HttpClient},
-    requests::request::Request,
+    clients::{
+        backend::{FetchBackend, FetchOptions, FetchedBody, FetchedPage},
+        http::{self, HttpClient, RedirectPolicy},
+    },
+    crawler::unix_timestamp,
+    parsers::{
+        self, charset, html::ParsedHtml, license::apply_robots_headers, pdf, readability, robots,
+        selectors::SelectorParser,
+    },
+    requests::{error::RequestError, request::Request},
     validators,
 };
 
 #[derive(Debug)]
 pub struct HttpRequest {
     pub target: String,
-    pub client: Option<HttpClient>,
+    pub client: Option<Arc<HttpClient>>,
+    // Backend fetching the page instead of `client` (e.g. a headless browser), with the options of
+    // the render rule matching it.
+    pub backend: Option<(Arc<dyn FetchBackend>, FetchOptions)>,
     pub depth: u32,
+    // Number of attempts already made for this request.
+    pub attempts: u32,
+    // Earliest instant at which the request may be retried.
+    pub retry_at: Option<tokio::time::Instant>,
+    // Media types whose body is downloaded; other responses are skipped. `None` accepts any.
+    pub allowed_content_types: Option<Arc<Vec<String>>>,
+    // Also extract the main content of HTML pages (see `readability::extract`).
+    pub main_content: bool,
+    // Site-specific extraction profiles applied to the page, if any.
+    pub selectors: Option<Arc<SelectorParser>>,
+    // Extract the text of PDF documents (see `pdf::extract_text`) instead of skipping them.
+    pub extract_pdf: bool,
+    // Maximum size of the PDF documents whose text is extracted (`None` = unlimited).
+    pub max_pdf_bytes: Option<u64>,
+    // How the HTTP client follows redirects (hop limit, scope of the targets).
+    pub redirects: Arc<RedirectPolicy>,
+    // Recrawl and publish the page even if it was already visited (incremental crawls).
+    pub refresh: bool,
+    // Job submitted at runtime this request belongs to (see `JobManager`), inherited by the
+    // links discovered on its page. `None` for the crawl started with the process.
+    pub job: Option<String>,
+    // Priority given by the external URL scorer, if any (see `ExternalScorer`).
+    pub score: Option<f64>,
+    // Relevance of the link it was discovered from, if scored (see `RelevanceModel`).
+    pub relevance: Option<f64>,
+    // Seed the request was discovered from, unset for seeds (see `origin_seed`).
+    pub origin_seed: Option<String>,
+    // Pages followed from the seed to reach the request, starting with the seed.
+    pub path: Vec<String>,
+    // Validators of the previous crawl of the page, sent to only download it if it changed.
+    pub validators: Option<CacheValidators>,
+    // Shared frontier entry the request was claimed from, acknowledged once the request is done
+    // (see `SharedFrontierController`).
+    pub claim: Option<u64>,
+}
+
+// Validators of the previously crawled version of a page, sent as `If-None-Match` and
+// `If-Modified-Since` headers. Servers answer 304 Not Modified when the page did not change.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CacheValidators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl CacheValidators {
+    // Conditional request headers of the validators.
+    fn headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        let validators = [
+            (IF_NONE_MATCH, &self.etag),
+            (IF_MODIFIED_SINCE, &self.last_modified),
+        ];
+        for (name, value) in validators {
+            if let Some(value) = value.as_deref().and_then(|v| HeaderValue::from_str(v).ok()) {
+                headers.insert(name, value);
+            }
+        }
+        headers
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExtraHttpResponseFields {
-    pub links: Vec<String>,
+    pub links: Vec<Link>,
+    // Client-side routes discovered on single-page applications; not published with the page.
+    #[serde(default)]
+    pub routes: Vec<String>,
+    // Body decoded to UTF-8.
     pub body: String,
+    // Encoding the body was decoded from (e.g. `Shift_JIS`).
+    #[serde(default)]
+    pub encoding: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HttpResponse {
     pub title: String,
+    // URL the response was served from, after following redirects.
+    pub final_url: String,
+    // URLs that redirected the request, starting with the requested URL (empty without redirects).
+    #[serde(default)]
+    pub redirect_chain: Vec<String>,
+    // Unix timestamp (seconds) at which the fetch started.
+    #[serde(default)]
+    pub fetched_at: u64,
+    // Time taken to fetch the page, from sending the request to reading the body.
+    #[serde(default)]
+    pub fetch_duration_ms: u64,
     pub status_code: u16,
     pub headers: Vec<String>,
     pub meta: Vec<String>,
+    // License and AI-usage signals from the body and the `X-Robots-Tag` header.
+    pub usage: UsageRights,
+    // `noindex`/`nofollow` directives from robots meta tags and the `X-Robots-Tag` header.
+    #[serde(default)]
+    pub robots: RobotsDirectives,
+    // Canonical URL declared by the page, if any.
+    #[serde(default)]
+    pub canonical_url: Option<String>,
+    // Fingerprint of the visible text of the page, if any.
+    #[serde(default)]
+    pub fingerprint: Option<ContentFingerprint>,
+    // ISO 639-3 code of the language of the page, when reliably detected.
+    #[serde(default)]
+    pub language: Option<String>,
+    // Article text and title of the page, when requested and found.
+    #[serde(default)]
+    pub main_content: Option<MainContent>,
+    // JSON-LD, OpenGraph/Twitter card and microdata metadata of the page, if any.
+    #[serde(default)]
+    pub structured_data: Option<StructuredData>,
+    // Fields extracted by the site-specific profile matching the page (see `SelectorParser`).
+    #[serde(default)]
+    pub custom_fields: HashMap<String, String>,
     pub extra: Option<ExtraHttpResponseFields>,
+    // Set when the body was not downloaded.
+    pub skipped: Option<SkippedReason>,
+}
+
+impl HttpResponse {
+    // Response whose body was not downloaded, keeping the status and headers.
+    fn skipped(
+        title: String,
+        final_url: String,
+        status_code: u16,
+        headers: Vec<String>,
+        reason: SkippedReason,
+    ) -> Self {
+        HttpResponse {
+            skipped: Some(reason),
+            ..Self::without_body(title, final_url, status_code, headers)
+        }
+    }
+
+    // Response without a body: skipped, or not modified since the previous crawl.
+    fn without_body(
+        title: String,
+        final_url: String,
+        status_code: u16,
+        headers: Vec<String>,
+    ) -> Self {
+        let mut usage = UsageRights::default();
+        apply_robots_headers(&mut usage, &headers);
+        let mut robots = RobotsDirectives::default();
+        robots::apply_headers(&mut robots, &headers);
+        HttpResponse {
+            title,
+            final_url,
+            redirect_chain: Vec::new(),
+            fetched_at: 0,
+            fetch_duration_ms: 0,
+            status_code,
+            headers,
+            meta: Vec::new(),
+            usage,
+            robots,
+            canonical_url: None,
+            fingerprint: None,
+            language: None,
+            main_content: None,
+            structured_data: None,
+            custom_fields: HashMap::new(),
+            extra: None,
+            skipped: None,
+        }
+    }
+
+    // Whether the server answered that the page did not change since its previous crawl (see
+    // `CacheValidators`).
+    pub fn not_modified(&self) -> bool {
+        self.status_code == 304
+    }
+
+    // Value of the first header named `name` (case-insensitive).
+    pub fn header(&self, name: &str) -> Option<&str> {
+        header(&self.headers, name)
+    }
+}
+
+impl HttpRequest {
+    // Seed the request was discovered from: the target itself for seeds.
+    pub fn origin_seed(&self) -> &str {
+        self.origin_seed.as_deref().unwrap_or(&self.target)
+    }
+
+    // Path from the seed to the links found on the target.
+    pub fn path_to_links(&self) -> Vec<String> {
+        let mut path = self.path.clone();
+        path.push(self.target.clone());
+        path
+    }
+
+    // Collect only HEAD-level metadata (status code and headers) for the target, without
+    // downloading or parsing the body.
+    #[instrument(skip(self), fields(url = %self.target))]
+    pub async fn head(&self) -> Result<HttpResponse, RequestError> {
+        validators::validate_url(&self.target).map_err(RequestError::InvalidUrl)?;
+        let client = self.client()?;
+        client.validate_address(&self.target).await?;
+
+        info!("Performing HTTP HEAD request");
+        let fetched_at = unix_timestamp();
+        let started = Instant::now();
+        let response = client
+            .head_with_redirects(&self.target, &self.redirects)
+            .await?;
+
+        let status_code = response.status().as_u16();
+        debug!("Response status code: {}", status_code);
+        let final_url = response.url().to_string();
+        let redirect_chain = http::redirect_chain(&response);
+        let headers = http::header_lines(&response);
+
+        let mut usage = UsageRights::default();
+        apply_robots_headers(&mut usage, &headers);
+        let mut robots = RobotsDirectives::default();
+        robots::apply_headers(&mut robots, &headers);
+
+        Ok(HttpResponse {
+            title: "No title".to_string(),
+            final_url,
+            redirect_chain,
+            fetched_at,
+            fetch_duration_ms: started.elapsed().as_millis() as u64,
+            status_code,
+            headers,
+            meta: Vec::new(),
+            usage,
+            robots,
+            canonical_url: None,
+            fingerprint: None,
+            language: None,
+            main_content: None,
+            structured_data: None,
+            custom_fields: HashMap::new(),
+            extra: None,
+            skipped: None,
+        })
+    }
 }
 
 impl Request for HttpRequest {
@@ -40,125 +282,351 @@ impl Request for HttpRequest {
         );
         HttpRequest {
             target: String::from(target),
-            client: Some(get_default_http_client()),
+            // Set by the crawls to the client built from their settings (see `Crawler::prepare`).
+            client: None,
+            backend: None,
             depth,
+            attempts: 0,
+            retry_at: None,
+            allowed_content_types: None,
+            main_content: false,
+            selectors: None,
+            extract_pdf: false,
+            max_pdf_bytes: None,
+            redirects: Arc::default(),
+            refresh: false,
+            job: None,
+            score: None,
+            relevance: None,
+            origin_seed: None,
+            path: Vec::new(),
+            validators: None,
+            claim: None,
         }
     }
 
     #[instrument(skip(self), fields(url = %self.target))]
-    async fn execute(&self) -> Result<HttpResponse, String> {
+    async fn execute(&self) -> Result<HttpResponse, RequestError> {
+        let fetched_at = unix_timestamp();
+        let started = Instant::now();
+        let mut response = self.fetch().await?;
+        response.fetched_at = fetched_at;
+        response.fetch_duration_ms = started.elapsed().as_millis() as u64;
+        debug!("Fetched in {} ms", response.fetch_duration_ms);
+        Ok(response)
+    }
+}
+
+impl HttpRequest {
+    // GET the target and extract the page (see `execute`).
+    async fn fetch(&self) -> Result<HttpResponse, RequestError> {
         // ensure url is valid
         debug!("Validating URL");
         match validators::validate_url(&self.target) {
             Ok(_) => (),
             Err(e) => {
                 error!("URL validation failed: {}", e);
-                return Err(e);
+                return Err(RequestError::InvalidUrl(e));
             }
         }
+        // ensure the host is not a private address (the backends may resolve it elsewhere)
+        if let Some(client) = &self.client {
+            client.validate_address(&self.target).await?;
+        }
 
-        // Perform HTTP GET request.
-        info!("Performing HTTP GET request");
-        let response = self
-            .client
-            .as_ref()
-            .unwrap()
-            .get(&self.target)
-            .await
-            .map_err(|e| format!("HTTP request error: {e}"))?;
-
-        // Get status code.
-        let status_code = response.status().as_u16();
+        // Download the page with the backend of its render rule, else the HTTP client. Requests
+        // are conditional if the page was crawled before.
+        let mut options = FetchOptions {
+            headers: self
+                .validators
+                .as_ref()
+                .map(CacheValidators::headers)
+                .unwrap_or_default(),
+            allowed_content_types: self.allowed_content_types.clone(),
+            extract_pdf: self.extract_pdf,
+            max_pdf_bytes: self.max_pdf_bytes,
+            redirects: Arc::clone(&self.redirects),
+            ..FetchOptions::default()
+        };
+        let backend: &dyn FetchBackend = match &self.backend {
+            Some((backend, render)) => {
+                options.wait_for = render.wait_for.clone();
+                options.timeout = render.timeout;
+                backend.as_ref()
+            }
+            None => self.client()?,
+        };
+        info!("Fetching page through the {}", backend.name());
+        let page = backend.fetch(&self.target, &options).await?;
+        self.parse(page).await
+    }
+
+    // HTTP client of the request, unset for requests that only go through a backend.
+    fn client(&self) -> Result<&HttpClient, RequestError> {
+        self.client
+            .as_deref()
+            .ok_or_else(|| RequestError::Http(format!("No HTTP client to fetch {}", self.target)))
+    }
+
+    // Extract the page downloaded by a backend.
+    async fn parse(&self, page: FetchedPage) -> Result<HttpResponse, RequestError> {
+        let FetchedPage {
+            final_url,
+            redirect_chain,
+            status_code,
+            headers,
+            body,
+        } = page;
         debug!("Response status code: {}", status_code);
+        if final_url != self.target {
+            debug!("Redirected to {} through {:?}", final_url, redirect_chain);
+        }
 
         // Get the title of the HTML page.
-        let title = response
-            .headers()
-            .get("title")
-            .and_then(|value| value.to_str().ok())
-            .unwrap_or("No title")
-            .to_string();
+        let title = header(&headers, "title").unwrap_or("No title").to_string();
         debug!("Response title: {}", title);
 
-        // Collect headers as "Key: Value" strings.
-        let headers: Vec<String> = response
-            .headers()
-            .iter()
-            .map(|(name, value)| format!("{}: {}", name, value.to_str().unwrap_or("")))
-            .collect();
-
-        // Read the response body as text.
-        debug!("Reading response body");
-        let body = response
-            .text()
-            .await
-            .map_err(|e| format!("Error reading body: {e}"))?;
-
-        // Parse the HTML body using the scraper crate.
-        debug!("Parsing HTML body");
-        let document = scraper::Html::parse_document(&body);
-
-        // Extract all links from anchor tags (<a href="...">).
-        let link_selector = scraper::Selector::parse("a[href]")
-            .map_err(|e| format!("Selector parse error: {e}"))?;
-
-        let mut links: Vec<String> = document
-            .select(&link_selector)
-            .filter_map(|element| element.value().attr("href").map(|s| s.to_string()))
-            .collect();
-        debug!("Found {} links", links.len());
-
-        // If links start with a slash, prepend the domain.
-        let url = url::Url::parse(&self.target).map_err(|_| "Error parsing target URL")?;
-
-        for link in links.iter_mut() {
-            if link.starts_with('/') {
-                *link = format!("{}{}", url.origin().ascii_serialization(), link);
+        let content_type = header(&headers, "content-type").map(str::to_string);
+        let (body, encoding) = match body {
+            FetchedBody::Skipped(reason) => {
+                info!("Skipped body: {}", reason);
+                return Ok(HttpResponse {
+                    redirect_chain,
+                    ..HttpResponse::skipped(title, final_url, status_code, headers, reason)
+                });
             }
-        }
+            // Unchanged pages come without a body.
+            _ if status_code == 304 => {
+                info!("Page not modified since its previous crawl");
+                return Ok(HttpResponse {
+                    redirect_chain,
+                    ..HttpResponse::without_body(title, final_url, status_code, headers)
+                });
+            }
+            // Publish the text of PDF documents, if enabled.
+            FetchedBody::Bytes(document)
+                if self.extract_pdf
+                    && content_type
+                        .as_deref()
+                        .is_some_and(|c| validators::media_type(c) == pdf::PDF_MEDIA_TYPE) =>
+            {
+                let size = document.len();
+                let text = pdf::extract_text_blocking(document)
+                    .await
+                    .map_err(RequestError::Parse)?;
+                debug!(
+                    "Extracted {} characters from {} bytes of PDF",
+                    text.len(),
+                    size
+                );
 
-        // Now, only keep links that are valid URLs.
-        links.retain(|link| {
-            let is_ok = validators::validate_url(link).is_ok();
-            if !is_ok {
-                warn!("Invalid link found and removed: {}", link);
+                let response = HttpResponse::without_body(title, final_url, status_code, headers);
+                // PDF documents only carry robots directives in the `X-Robots-Tag` header.
+                let body = if response.robots.noindex {
+                    debug!("Document is noindex, dropping its text");
+                    String::new()
+                } else {
+                    text
+                };
+                return Ok(HttpResponse {
+                    redirect_chain,
+                    extra: Some(ExtraHttpResponseFields {
+                        links: Vec::new(),
+                        routes: Vec::new(),
+                        body,
+                        encoding: None,
+                    }),
+                    ..response
+                });
             }
-            is_ok
-        });
-
-        // Extract meta tags with a name attribute.
-        let meta_selector = scraper::Selector::parse("meta[name]")
-            .map_err(|e| format!("Selector parse error: {e}"))?;
-        let mut meta: Vec<String> = document
-            .select(&meta_selector)
-            .filter_map(|element| {
-                let name = element.value().attr("name")?;
-                let content = element.value().attr("content")?;
-                Some(format!("{name}: {content}"))
-            })
-            .collect();
-
-        // Also extract meta tags with a charset attribute.
-        let meta_charset_selector = scraper::Selector::parse("meta[charset]")
-            .map_err(|e| format!("Selector parse error: {e}"))?;
-        meta.extend(
-            document
-                .select(&meta_charset_selector)
-                .filter_map(|element| {
-                    element
-                        .value()
-                        .attr("charset")
-                        .map(|charset| format!("charset: {charset}"))
-                }),
-        );
-        debug!("Found {} meta tags", meta.len());
+            // Decode the body to UTF-8.
+            FetchedBody::Bytes(body) => {
+                let (body, encoding) = charset::decode(&body, content_type.as_deref());
+                debug!("Decoded response body from {}", encoding.name());
+                (body, Some(encoding.name().to_string()))
+            }
+            FetchedBody::Text(body) => (body, None),
+        };
+
+        // Extract links, meta tags, usage rights, robots directives, the canonical URL, the content
+        // fingerprint, the language and the structured data from the HTML body.
+        let ParsedHtml {
+            mut links,
+            meta,
+            usage,
+            robots,
+            canonical_url,
+            fingerprint,
+            language,
+            structured_data,
+            custom_fields,
+            mut routes,
+        } = parsers::extract(&final_url, &headers, &body, self.selectors.as_deref())?;
+
+        // Honour the robots directives: the links of nofollow pages are not followed and the body
+        // of noindex pages is not published.
+        if robots.nofollow {
+            debug!("Page is nofollow, dropping {} links", links.len());
+            links.clear();
+            routes.clear();
+        }
+        let body = if robots.noindex {
+            debug!("Page is noindex, dropping its body");
+            String::new()
+        } else {
+            body
+        };
+
+        // Extract the article of the page, without its boilerplate, if requested.
+        let main_content = if self.main_content && !body.is_empty() {
+            readability::extract(&body)
+        } else {
+            None
+        };
 
         Ok(HttpResponse {
             title,
+            final_url,
+            redirect_chain,
+            fetched_at: 0,
+            fetch_duration_ms: 0,
             status_code,
             headers,
             meta,
-            extra: Some(ExtraHttpResponseFields { links, body }),
+            usage,
+            robots,
+            canonical_url,
+            fingerprint,
+            language,
+            main_content,
+            structured_data,
+            custom_fields,
+            extra: Some(ExtraHttpResponseFields {
+                links,
+                routes,
+                body,
+                encoding,
+            }),
+            skipped: None,
         })
     }
 }
+
+// Value of the first of `headers` ("Key: Value" strings) named `name` (case-insensitive).
+fn header<'a>(headers: &'a [String], name: &str) -> Option<&'a str> {
+    headers.iter().find_map(|header| {
+        let (key, value) = header.split_once(':')?;
+        key.trim().eq_ignore_ascii_case(name).then(|| value.trim())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+
+    use super::*;
+
+    // Backend answering every request with the same page.
+    #[derive(Debug)]
+    struct CannedBackend {
+        headers: Vec<String>,
+        body: Vec<u8>,
+    }
+
+    #[async_trait]
+    impl FetchBackend for CannedBackend {
+        fn name(&self) -> &'static str {
+            "canned backend"
+        }
+
+        async fn fetch(
+            &self,
+            url: &str,
+            _options: &FetchOptions,
+        ) -> Result<FetchedPage, Box<dyn std::error::Error + Send + Sync>> {
+            Ok(FetchedPage {
+                final_url: url.to_string(),
+                redirect_chain: Vec::new(),
+                status_code: 200,
+                headers: self.headers.clone(),
+                body: FetchedBody::Bytes(self.body.clone()),
+            })
+        }
+    }
+
+    async fn fetch_canned(headers: &[&str], body: &[u8]) -> HttpResponse {
+        let mut req = HttpRequest::new("https://example.com/docs/", 0);
+        let backend = CannedBackend {
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            body: body.to_vec(),
+        };
+        req.backend = Some((Arc::new(backend), FetchOptions::default()));
+        req.execute().await.unwrap()
+    }
+
+    const PAGE: &str = r#"<html><head><title>Docs</title></head><body>
+        <a href="/guide">Guide</a>
+        <a href="https://other.example/" rel="nofollow">Other</a>
+        </body></html>"#;
+
+    #[tokio::test]
+    async fn links_are_extracted_and_resolved() {
+        let res = fetch_canned(&["Content-Type: text/html"], PAGE.as_bytes()).await;
+        let links = res.extra.unwrap().links;
+        let hrefs: Vec<_> = links.iter().map(|link| link.href.as_str()).collect();
+        assert_eq!(
+            hrefs,
+            ["https://example.com/guide", "https://other.example/"]
+        );
+        assert_eq!(links[0].anchor_text, "Guide");
+        assert!(links[1].is_nofollow);
+    }
+
+    #[tokio::test]
+    async fn title_is_read_from_the_response() {
+        let res = fetch_canned(&["Content-Type: text/html", "Title: Docs"], PAGE.as_bytes()).await;
+        assert_eq!(res.title, "Docs");
+        let res = fetch_canned(&["Content-Type: text/html"], PAGE.as_bytes()).await;
+        assert_eq!(res.title, "No title");
+    }
+
+    #[tokio::test]
+    async fn noindex_pages_drop_their_body() {
+        let page = r#"<html><head><meta name="robots" content="noindex"></head>
+            <body><a href="/guide">Guide</a></body></html>"#;
+        let res = fetch_canned(&["Content-Type: text/html"], page.as_bytes()).await;
+        assert!(res.robots.noindex);
+        let extra = res.extra.unwrap();
+        assert!(extra.body.is_empty());
+        assert_eq!(extra.links.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn nofollow_pages_drop_their_links() {
+        let res = fetch_canned(
+            &["Content-Type: text/html", "X-Robots-Tag: nofollow"],
+            PAGE.as_bytes(),
+        )
+        .await;
+        assert!(res.robots.nofollow);
+        let extra = res.extra.unwrap();
+        assert!(extra.links.is_empty());
+        assert!(!extra.body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn body_is_decoded_from_its_declared_charset() {
+        // "café" in ISO-8859-1.
+        let body = b"<html><body><p>caf\xe9</p></body></html>";
+        let res = fetch_canned(&["Content-Type: text/html; charset=iso-8859-1"], body).await;
+        let extra = res.extra.unwrap();
+        assert!(extra.body.contains("café"));
+        assert_eq!(extra.encoding.as_deref(), Some("windows-1252"));
+    }
+
+    #[tokio::test]
+    async fn requests_without_client_or_backend_fail() {
+        let req = HttpRequest::new("https://example.com/", 0);
+        assert!(matches!(req.execute().await, Err(RequestError::Http(_))));
+    }
+}
@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
 
+use crate::requests::error::RequestError;
+
 pub trait Request {
     type Output: std::fmt::Debug + Serialize + Deserialize<'static>;
     fn new(target: &str, depth: u32) -> Self;
-    async fn execute(&self) -> Result<Self::Output, String>;
+    async fn execute(&self) -> Result<Self::Output, RequestError>;
 }
@@ -0,0 +1,126 @@
+use std::time::{Duration, SystemTime};
+
+use rand::Rng;
+
+use crate::requests::error::RequestError;
+
+// Retry policy applied to failed HTTP requests.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    // Total number of attempts per request, including the first one.
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    // Upper bound on delays requested by servers through `Retry-After`.
+    pub max_retry_after: Duration,
+}
+
+impl RetryPolicy {
+    pub fn new(
+        max_attempts: u32,
+        base_delay: Duration,
+        max_delay: Duration,
+        max_retry_after: Duration,
+    ) -> Self {
+        RetryPolicy {
+            max_attempts,
+            base_delay,
+            max_delay,
+            max_retry_after,
+        }
+    }
+
+    // Whether a request that already made `attempts` attempts and failed with `err` should be
+    // tried again.
+    pub fn should_retry(&self, attempts: u32, err: &RequestError) -> bool {
+        err.is_retryable() && attempts < self.max_attempts
+    }
+
+    // Exponential backoff with full jitter: a random delay in [0, min(max, base * 2^(attempt-1))].
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let ceiling = self.base_delay.saturating_mul(factor).min(self.max_delay);
+        let millis = rand::rng().random_range(0..=ceiling.as_millis() as u64);
+        Duration::from_millis(millis)
+    }
+}
+
+// Parse a `Retry-After` header value, either delay-seconds or an HTTP-date.
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy::new(
+            5,
+            Duration::from_millis(500),
+            Duration::from_secs(10),
+            Duration::from_secs(60),
+        )
+    }
+
+    proptest! {
+        // Full jitter: the delay is anywhere between zero and the exponential ceiling.
+        #[test]
+        fn backoff_stays_within_the_exponential_ceiling(attempt in 1u32..5) {
+            let policy = policy();
+            let ceiling = policy.base_delay * 2u32.pow(attempt - 1);
+            prop_assert!(policy.backoff(attempt) <= ceiling);
+        }
+
+        #[test]
+        fn backoff_is_capped_at_the_maximum_delay(attempt in 6u32..=u32::MAX) {
+            let policy = policy();
+            prop_assert!(policy.backoff(attempt) <= policy.max_delay);
+        }
+    }
+
+    #[test]
+    fn backoff_is_jittered() {
+        let policy = policy();
+        let delays: Vec<_> = (0..50).map(|_| policy.backoff(10)).collect();
+        assert!(delays.iter().any(|delay| *delay != delays[0]));
+    }
+
+    #[test]
+    fn retry_after_accepts_delay_seconds() {
+        assert_eq!(parse_retry_after("120"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_retry_after(" 0 "), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn retry_after_accepts_http_dates() {
+        let date = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(300));
+        let delay = parse_retry_after(&date).unwrap();
+        assert!(delay > Duration::from_secs(290) && delay <= Duration::from_secs(300));
+    }
+
+    #[test]
+    fn retry_after_in_the_past_is_immediate() {
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn garbage_retry_after_is_ignored() {
+        for value in ["", "soon", "-5", "1.5", "Wed, 32 Oct 2015 07:28:00 GMT"] {
+            assert_eq!(parse_retry_after(value), None, "{value}");
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use models::PageData;
+use serde_json::Value;
+use url::Url;
+
+use crate::{
+    config::{CategoryRuleConfig, RoutingConfig},
+    rules::RuleTarget,
+};
+
+// Category of the pages not matching any rule.
+const OTHER: &str = "other";
+
+// Topic routing keys of the published pages, `page.<type>.<language>.<category>` (e.g.
+// `page.html.en.news`), so that consumer pools bind their queue to the slices they handle
+// (`page.html.*.news`, `page.pdf.#`, ...). Unknown content types and languages are `unknown`.
+pub struct RoutingPolicy {
+    categories: Vec<CategoryRule>,
+}
+
+struct CategoryRule {
+    name: String,
+    target: Option<RuleTarget>,
+    // Normalized types (see `normalize_type`).
+    types: Vec<String>,
+}
+
+impl RoutingPolicy {
+    pub fn from_config(config: &RoutingConfig) -> Result<Self, String> {
+        let categories = config
+            .categories
+            .iter()
+            .map(CategoryRule::from_config)
+            .collect::<Result<_, String>>()?;
+        Ok(RoutingPolicy { categories })
+    }
+
+    pub fn routing_key(&self, page: &PageData) -> String {
+        let content_type = page
+            .crawl
+            .content_type
+            .as_deref()
+            .map(|media_type| media_type.rsplit('/').next().unwrap_or(media_type));
+        format!(
+            "page.{}.{}.{}",
+            segment(content_type),
+            segment(page.language.as_deref()),
+            segment(Some(self.category(page)))
+        )
+    }
+
+    // Name of the first category rule matching the page.
+    pub fn category(&self, page: &PageData) -> &str {
+        let url = Url::parse(&page.url).ok();
+        let types = page_types(page);
+        self.categories
+            .iter()
+            .find(|rule| {
+                let in_target = match (&rule.target, &url) {
+                    (Some(target), Some(url)) => target.matches(url),
+                    (Some(_), None) => false,
+                    (None, _) => true,
+                };
+                in_target && (rule.types.is_empty() || rule.types.iter().any(|t| types.contains(t)))
+            })
+            .map_or(OTHER, |rule| rule.name.as_str())
+    }
+}
+
+impl CategoryRule {
+    fn from_config(config: &CategoryRuleConfig) -> Result<Self, String> {
+        if config.name.trim().is_empty() {
+            return Err("Each routing category needs a name".to_string());
+        }
+        let target = match (&config.domain, &config.pattern) {
+            (None, None) if config.types.is_empty() => {
+                return Err(format!(
+                    "Routing category '{}' needs a domain, a pattern or types",
+                    config.name
+                ));
+            }
+            (None, None) => None,
+            (domain, pattern) => Some(RuleTarget::from_config(
+                domain.as_deref(),
+                pattern.as_deref(),
+                "routing category",
+            )?),
+        };
+        Ok(CategoryRule {
+            name: config.name.trim().to_string(),
+            target,
+            types: config.types.iter().map(|t| normalize_type(t)).collect(),
+        })
+    }
+}
+
+// Types declared by the page: `@type` of its JSON-LD blocks (and their `@graph`), `itemtype` of
+// its microdata items and its `og:type`.
+fn page_types(page: &PageData) -> Vec<String> {
+    let Some(structured) = &page.structured_data else {
+        return Vec::new();
+    };
+    let mut types = Vec::new();
+    let mut add_json_ld = |node: &Value| match node.get("@type") {
+        Some(Value::String(t)) => types.push(normalize_type(t)),
+        Some(Value::Array(values)) => {
+            types.extend(values.iter().filter_map(Value::as_str).map(normalize_type))
+        }
+        _ => {}
+    };
+    for block in &structured.json_ld {
+        add_json_ld(block);
+        for node in block
+            .get("@graph")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            add_json_ld(node);
+        }
+        if let Some(blocks) = block.as_array() {
+            blocks.iter().for_each(&mut add_json_ld);
+        }
+    }
+    for item in &structured.microdata {
+        types.extend(item.types.iter().map(|t| normalize_type(t)));
+    }
+    if let Some(og_types) = structured.open_graph.get("og:type") {
+        types.extend(og_types.iter().map(|t| normalize_type(t)));
+    }
+    types
+}
+
+// `https://schema.org/NewsArticle` and `NewsArticle` are both `newsarticle`.
+fn normalize_type(value: &str) -> String {
+    let value = value.trim().trim_end_matches('/');
+    value.rsplit('/').next().unwrap_or(value).to_lowercase()
+}
+
+// Word of a routing key: lowercase, without the `.` separating words.
+fn segment(value: Option<&str>) -> String {
+    let value = value
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .unwrap_or("unknown");
+    value
+        .to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,45 @@
+use regex::Regex;
+use url::Url;
+
+// URLs a per-site rule applies to (recrawl TTLs, extraction profiles).
+#[derive(Debug)]
+pub enum RuleTarget {
+    // A domain and its subdomains.
+    Domain(String),
+    Pattern(Regex),
+}
+
+impl RuleTarget {
+    // Target of a rule configured with either a `domain` or a `pattern`; `kind` names the rule in
+    // errors.
+    pub fn from_config(
+        domain: Option<&str>,
+        pattern: Option<&str>,
+        kind: &str,
+    ) -> Result<Self, String> {
+        match (domain, pattern) {
+            (Some(domain), None) => Ok(RuleTarget::Domain(
+                domain.trim().trim_start_matches('.').to_lowercase(),
+            )),
+            (None, Some(pattern)) => Regex::new(pattern)
+                .map(RuleTarget::Pattern)
+                .map_err(|e| format!("Invalid {kind} pattern '{pattern}': {e}")),
+            _ => Err(format!(
+                "Each {kind} rule needs either a domain or a pattern"
+            )),
+        }
+    }
+
+    pub fn matches(&self, url: &Url) -> bool {
+        match self {
+            RuleTarget::Domain(domain) => {
+                let host = url.host_str().unwrap_or_default();
+                host == domain
+                    || host
+                        .strip_suffix(domain.as_str())
+                        .is_some_and(|sub| sub.ends_with('.'))
+            }
+            RuleTarget::Pattern(pattern) => pattern.is_match(url.as_str()),
+        }
+    }
+}
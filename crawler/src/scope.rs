@@ -0,0 +1,335 @@
+use std::{
+    collections::HashSet,
+    str::FromStr,
+    sync::{Arc, RwLock},
+};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+// Which discovered links may be crawled, relative to the seeds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScopeMode {
+    // Hosts of the seed domains and their subdomains (`www.` is ignored).
+    SeedDomain,
+    // Exactly the hosts of the seeds.
+    SeedHost,
+    // Any host.
+    Unrestricted,
+}
+
+impl FromStr for ScopeMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "seed_domain" => Ok(ScopeMode::SeedDomain),
+            "seed_host" => Ok(ScopeMode::SeedHost),
+            "unrestricted" => Ok(ScopeMode::Unrestricted),
+            other => Err(format!(
+                "Unknown scope mode '{other}' (expected seed_domain, seed_host or unrestricted)"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScopePolicy {
+    pub mode: ScopeMode,
+    // When not empty, links must match at least one of these patterns.
+    pub include: Vec<Regex>,
+    // Links matching any of these patterns are never crawled.
+    pub exclude: Vec<Regex>,
+    // Maximum number of distinct external domains enqueued from a single page.
+    pub max_external_domains_per_page: Option<usize>,
+    // Drop the links marked `rel="nofollow"` (or `ugc`/`sponsored`).
+    pub skip_nofollow: bool,
+    // Hosts of the seeds, lowercased. Shared by the clones of the policy, so that seeds submitted
+    // at runtime extend the scope of every agent.
+    seed_hosts: Arc<RwLock<HashSet<String>>>,
+}
+
+impl ScopePolicy {
+    pub fn new(mode: ScopeMode, include: Vec<Regex>, exclude: Vec<Regex>, seeds: &[Url]) -> Self {
+        ScopePolicy {
+            mode,
+            include,
+            exclude,
+            max_external_domains_per_page: None,
+            skip_nofollow: false,
+            seed_hosts: Arc::new(RwLock::new(
+                seeds
+                    .iter()
+                    .filter_map(|seed| seed.host_str().map(str::to_lowercase))
+                    .collect(),
+            )),
+        }
+    }
+
+    // Extend the scope with a seed submitted while the crawl runs.
+    pub fn add_seed(&self, seed: &Url) {
+        if let Some(host) = seed.host_str() {
+            self.seed_hosts
+                .write()
+                .expect("scope lock poisoned")
+                .insert(host.to_lowercase());
+        }
+    }
+
+    // Whether a discovered link should be enqueued.
+    pub fn allows(&self, url: &Url) -> bool {
+        let in_mode = match self.mode {
+            ScopeMode::Unrestricted => true,
+            ScopeMode::SeedHost => url.host_str().is_some_and(|host| {
+                self.seed_hosts
+                    .read()
+                    .expect("scope lock poisoned")
+                    .contains(&host.to_lowercase())
+            }),
+            ScopeMode::SeedDomain => !self.is_external(url),
+        };
+        if !in_mode {
+            return false;
+        }
+
+        let url = url.as_str();
+        (self.include.is_empty() || self.include.iter().any(|re| re.is_match(url)))
+            && !self.exclude.iter().any(|re| re.is_match(url))
+    }
+
+    // Whether the link is outside the seed domains (and their subdomains).
+    pub fn is_external(&self, url: &Url) -> bool {
+        let Some(host) = url.host_str() else {
+            return true;
+        };
+        let host = host.to_lowercase();
+        let host = host.strip_prefix("www.").unwrap_or(&host);
+        let seed_hosts = self.seed_hosts.read().expect("scope lock poisoned");
+        !seed_hosts.iter().any(|seed| {
+            let domain = seed.strip_prefix("www.").unwrap_or(seed);
+            host == domain || host.ends_with(&format!(".{domain}"))
+        })
+    }
+}
+
+// Compile a list of regular expressions.
+pub fn parse_patterns(patterns: &[String]) -> Result<Vec<Regex>, String> {
+    patterns
+        .iter()
+        .map(|pattern| Regex::new(pattern).map_err(|e| format!("Invalid pattern '{pattern}': {e}")))
+        .collect()
+}
+
+// URLs are normalized by parsing them (`Url::parse`: lowercased scheme and host, default port
+// removed, dot-segments resolved, percent-encoding of unsafe characters), and the serialized form
+// is the visited-set key. These properties check that normalization is stable and that the scope
+// decision of a URL only depends on that key.
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    // Hostnames of 1 to 3 labels under a fixed TLD, lowercase.
+    fn host() -> impl Strategy<Value = String> {
+        (
+            prop::collection::vec("[a-z0-9]{1,8}", 1..=3),
+            prop::sample::select(vec!["com", "org", "example"]),
+        )
+            .prop_map(|(labels, tld)| format!("{}.{tld}", labels.join(".")))
+    }
+
+    // Path segments mixing plain, encoded, non-ASCII, dot and empty segments.
+    fn segments() -> impl Strategy<Value = Vec<String>> {
+        prop::collection::vec(
+            prop_oneof![
+                "[a-zA-Z0-9_~-]{1,6}",
+                Just("%41".to_string()),
+                Just("%2e".to_string()),
+                Just("%2E%2e".to_string()),
+                Just("caf%C3%A9".to_string()),
+                Just("café".to_string()),
+                Just("sp ace".to_string()),
+                Just("a%2Fb".to_string()),
+                Just("private".to_string()),
+                Just(".".to_string()),
+                Just("..".to_string()),
+                Just(String::new()),
+            ],
+            0..6,
+        )
+    }
+
+    // Randomly upper-case the characters of `s`.
+    fn mixed_case(s: &str, mask: u64) -> String {
+        s.chars()
+            .enumerate()
+            .map(|(i, c)| {
+                if mask >> (i % 64) & 1 == 1 {
+                    c.to_ascii_uppercase()
+                } else {
+                    c
+                }
+            })
+            .collect()
+    }
+
+    // Messy absolute URLs: mixed-case scheme and host, explicit or odd ports, dot-segments, weird
+    // encodings, queries and fragments.
+    fn messy_url() -> impl Strategy<Value = String> {
+        (
+            prop::sample::select(vec!["http", "https"]),
+            host(),
+            any::<u64>(),
+            prop_oneof![
+                Just(None),
+                Just(Some(80u16)),
+                Just(Some(443)),
+                any::<u16>().prop_map(Some)
+            ],
+            segments(),
+            prop::option::of("[a-zA-Z0-9=&%]{0,12}"),
+            prop::option::of("[a-z]{0,6}"),
+        )
+            .prop_map(|(scheme, host, mask, port, segments, query, fragment)| {
+                let mut url = format!(
+                    "{}://{}",
+                    mixed_case(scheme, mask),
+                    mixed_case(&host, mask.rotate_left(7))
+                );
+                if let Some(port) = port {
+                    url.push_str(&format!(":{port}"));
+                }
+                url.push('/');
+                url.push_str(&segments.join("/"));
+                if let Some(query) = query {
+                    url.push_str(&format!("?{query}"));
+                }
+                if let Some(fragment) = fragment {
+                    url.push_str(&format!("#{fragment}"));
+                }
+                url
+            })
+    }
+
+    fn policy(mode: ScopeMode, seed: &str) -> ScopePolicy {
+        let exclude = parse_patterns(&["/private(/|$)".to_string()]).unwrap();
+        let seed = Url::parse(&format!("https://{seed}/")).unwrap();
+        ScopePolicy::new(mode, Vec::new(), exclude, &[seed])
+    }
+
+    fn mode() -> impl Strategy<Value = ScopeMode> {
+        prop::sample::select(vec![
+            ScopeMode::SeedDomain,
+            ScopeMode::SeedHost,
+            ScopeMode::Unrestricted,
+        ])
+    }
+
+    proptest! {
+        #[test]
+        fn normalization_is_idempotent(raw in messy_url()) {
+            let Ok(url) = Url::parse(&raw) else {
+                return Ok(());
+            };
+            let again = Url::parse(url.as_str()).unwrap();
+            prop_assert_eq!(url.as_str(), again.as_str());
+        }
+
+        #[test]
+        fn normalized_urls_are_valid(raw in messy_url()) {
+            if let Ok(url) = Url::parse(&raw) {
+                prop_assert!(crate::validators::validate_url(url.as_str()).is_ok());
+                prop_assert!(url.host_str().is_some_and(|h| h == h.to_lowercase()));
+                prop_assert!(!url.path().split('/').any(|s| s == "." || s == ".."));
+            }
+        }
+
+        // A URL and its normalized form are one visited-set entry, so they must get the same scope
+        // decision.
+        #[test]
+        fn scope_is_decided_on_the_dedup_key(
+            raw in messy_url(),
+            seed in host(),
+            mode in mode(),
+        ) {
+            let Ok(url) = Url::parse(&raw) else {
+                return Ok(());
+            };
+            let key = Url::parse(url.as_str()).unwrap();
+            let policy = policy(mode, &seed);
+            prop_assert_eq!(policy.allows(&url), policy.allows(&key));
+            prop_assert_eq!(policy.is_external(&url), policy.is_external(&key));
+        }
+
+        // Spellings of the same page (host case, explicit default port, dot-segments) share a
+        // dedup key, and therefore a scope decision.
+        #[test]
+        fn equivalent_spellings_share_key_and_scope(
+            host in host(),
+            mask in any::<u64>(),
+            segments in prop::collection::vec("[a-z0-9]{1,6}", 1..5),
+            detour in "[a-z]{1,4}",
+            https in any::<bool>(),
+            mode in mode(),
+        ) {
+            let (scheme, default_port) = if https { ("https", 443) } else { ("http", 80) };
+            let path = segments.join("/");
+            let plain = format!("{scheme}://{host}/{path}");
+            let messy = format!(
+                "{}://{}:{default_port}/./{detour}/../{path}",
+                mixed_case(scheme, mask),
+                mixed_case(&host, mask.rotate_left(13)),
+            );
+            let plain = Url::parse(&plain).unwrap();
+            let messy = Url::parse(&messy).unwrap();
+            prop_assert_eq!(plain.as_str(), messy.as_str());
+
+            let policy = policy(mode, &host);
+            prop_assert_eq!(policy.allows(&plain), policy.allows(&messy));
+        }
+
+        // Subdomains and the `www.` prefix of a seed are internal regardless of case; other hosts
+        // are external.
+        #[test]
+        fn seed_domain_scope_ignores_case_and_www(
+            seed in host(),
+            sub in "[a-z0-9]{1,8}",
+            mask in any::<u64>(),
+            other in host(),
+        ) {
+            prop_assume!(!seed.starts_with("www."));
+            let policy = policy(ScopeMode::SeedDomain, &seed);
+            for candidate in [seed.clone(), format!("www.{seed}"), format!("{sub}.{seed}")] {
+                let url = Url::parse(&format!("https://{}/", mixed_case(&candidate, mask))).unwrap();
+                prop_assert!(!policy.is_external(&url));
+                prop_assert!(policy.allows(&url));
+            }
+
+            let other_url = Url::parse(&format!("https://{other}/")).unwrap();
+            let related = other == seed || other.ends_with(&format!(".{seed}"));
+            prop_assert_eq!(policy.is_external(&other_url), !related);
+        }
+
+        // Excluded paths stay excluded whatever the spelling of the URL.
+        #[test]
+        fn exclusions_apply_to_the_normalized_url(
+            host in host(),
+            detour in "[a-z]{1,4}",
+            mode in mode(),
+        ) {
+            let policy = policy(mode, &host);
+            for raw in [
+                format!("https://{host}/private"),
+                format!("https://{host}/{detour}/../private/page"),
+                format!("https://{host}/./private/"),
+                format!("https://{host}/%2e/private"),
+            ] {
+                let url = Url::parse(&raw).unwrap();
+                prop_assert!(!policy.allows(&url), "{} allowed as {}", raw, url);
+            }
+        }
+    }
+}
@@ -1 +1,13 @@
+pub mod blockservice;
+pub mod budgetservice;
+pub mod checkpointservice;
+pub mod contentservice;
+pub mod crawlrecordservice;
+pub mod deadletterservice;
+pub mod externaldomainservice;
+pub mod leaseservice;
+pub mod pageservice;
+pub mod robotsservice;
+pub mod sharedfrontierservice;
 pub mod urlservice;
+pub mod workerservice;
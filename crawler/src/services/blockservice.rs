@@ -0,0 +1,123 @@
+use std::{sync::Arc, time::Duration};
+
+use drivers::{errors::DriverError, CacheDriver};
+use models::WorkerIdentity;
+use tokio::sync::Mutex;
+
+use crate::{
+    blocking::{BlockPolicy, BlockStrategy},
+    repositories::{BlockRepository, BlockedDomain, RefusalRepository, RefusalWindow, Repository},
+};
+
+pub trait BlockServiceTrait {
+    async fn find(&self, domain: &str) -> Result<Option<BlockedDomain>, DriverError>;
+    async fn record(
+        &self,
+        domain: &str,
+        reason: String,
+        detected_at: u64,
+        detected_by: &WorkerIdentity,
+    ) -> Result<BlockedDomain, DriverError>;
+    async fn record_refusal(&self, domain: &str, at: u64) -> Result<Option<u32>, DriverError>;
+}
+
+pub struct BlockService {
+    repository: BlockRepository,
+    refusals: RefusalRepository,
+    policy: BlockPolicy,
+}
+
+impl BlockService {
+    // constructor method
+    pub fn new(
+        client: Arc<Mutex<dyn CacheDriver<str, BlockedDomain>>>,
+        refusals: Arc<Mutex<dyn CacheDriver<str, RefusalWindow>>>,
+        policy: BlockPolicy,
+    ) -> Self {
+        BlockService {
+            repository: BlockRepository::new(client),
+            refusals: RefusalRepository::new(refusals),
+            policy,
+        }
+    }
+
+    fn key(domain: &str) -> String {
+        format!("blocked:{domain}")
+    }
+
+    fn refusals_key(domain: &str) -> String {
+        format!("refusals:{domain}")
+    }
+}
+
+impl BlockServiceTrait for BlockService {
+    async fn find(&self, domain: &str) -> Result<Option<BlockedDomain>, DriverError> {
+        let key = Self::key(domain);
+        if !self.repository.exists(key.clone()).await? {
+            return Ok(None);
+        }
+        self.repository.get(key).await.map(Some)
+    }
+
+    // Register a new blocking response for `domain`, escalating the fallback strategy state.
+    async fn record(
+        &self,
+        domain: &str,
+        reason: String,
+        detected_at: u64,
+        detected_by: &WorkerIdentity,
+    ) -> Result<BlockedDomain, DriverError> {
+        let count = self.find(domain).await?.map(|d| d.count).unwrap_or(0) + 1;
+        let state = BlockedDomain {
+            domain: domain.to_string(),
+            reason,
+            strategy: self.policy.strategy,
+            count,
+            delay_ms: self.policy.delay(count).as_millis() as u64,
+            proxy_index: (count as usize - 1) % self.policy.proxies.len().max(1),
+            cooldown_until: match self.policy.strategy {
+                BlockStrategy::Cooldown => detected_at + self.policy.cooldown.as_secs(),
+                _ => 0,
+            },
+            detected_at,
+            detected_by: detected_by.clone(),
+        };
+        self.repository
+            .set(Self::key(domain), state.clone())
+            .await?;
+        Ok(state)
+    }
+
+    // Count a bare 403/429 response of `domain`. Returns the number of refusals once they reach
+    // the storm threshold within the storm window, starting a new window.
+    async fn record_refusal(&self, domain: &str, at: u64) -> Result<Option<u32>, DriverError> {
+        let key = Self::refusals_key(domain);
+        let window = if self.refusals.exists(key.clone()).await? {
+            Some(self.refusals.get(key.clone()).await?)
+        } else {
+            None
+        };
+        let window = match window {
+            Some(window) if at < window.started_at + self.policy.storm_window.as_secs() => {
+                RefusalWindow {
+                    count: window.count + 1,
+                    ..window
+                }
+            }
+            _ => RefusalWindow {
+                started_at: at,
+                count: 1,
+            },
+        };
+        if window.count >= self.policy.storm_threshold {
+            self.refusals.remove(key).await?;
+            return Ok(Some(window.count));
+        }
+        // The window expires with the storm window, so quiet domains leave no key behind.
+        let remaining = (window.started_at + self.policy.storm_window.as_secs()).saturating_sub(at);
+        self.refusals
+            .set_with_ttl(&key, window, Duration::from_secs(remaining.max(1)))
+            .await?;
+        Ok(None)
+    }
+}
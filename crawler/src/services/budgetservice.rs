@@ -0,0 +1,55 @@
+use std::sync::Arc;
+
+use drivers::{errors::DriverError, CacheDriver};
+use tokio::sync::Mutex;
+
+use crate::repositories::{BudgetRepository, Repository};
+
+pub trait BudgetServiceTrait {
+    async fn is_exhausted(&self, domain: &str) -> Result<bool, DriverError>;
+    async fn consume(&self, domain: &str, bytes: u64) -> Result<u64, DriverError>;
+}
+
+pub struct BudgetService {
+    repository: BudgetRepository,
+    // Maximum number of body bytes downloaded per domain. `None` disables the budget.
+    max_bytes_per_domain: Option<u64>,
+}
+
+impl BudgetService {
+    // constructor method
+    pub fn new(
+        client: Arc<Mutex<dyn CacheDriver<str, u64>>>,
+        max_bytes_per_domain: Option<u64>,
+    ) -> Self {
+        BudgetService {
+            repository: BudgetRepository::new(client),
+            max_bytes_per_domain,
+        }
+    }
+
+    fn key(domain: &str) -> String {
+        format!("budget:bytes:{domain}")
+    }
+}
+
+impl BudgetServiceTrait for BudgetService {
+    async fn is_exhausted(&self, domain: &str) -> Result<bool, DriverError> {
+        let Some(limit) = self.max_bytes_per_domain else {
+            return Ok(false);
+        };
+
+        let key = Self::key(domain);
+        if !self.repository.exists(key.clone()).await? {
+            return Ok(false);
+        }
+        Ok(self.repository.get(key).await? >= limit)
+    }
+
+    async fn consume(&self, domain: &str, bytes: u64) -> Result<u64, DriverError> {
+        if self.max_bytes_per_domain.is_none() {
+            return Ok(0);
+        }
+        self.repository.increment(&Self::key(domain), bytes).await
+    }
+}
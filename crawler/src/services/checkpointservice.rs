@@ -0,0 +1,74 @@
+use std::{collections::HashSet, sync::Arc};
+
+use drivers::{errors::DriverError, CacheDriver};
+use tokio::sync::Mutex;
+
+use crate::repositories::{CheckpointEntry, CheckpointRepository, Repository};
+
+pub trait CheckpointServiceTrait {
+    async fn save(&self, key: &str, entries: Vec<CheckpointEntry>) -> Result<(), DriverError>;
+    async fn restore(&self, key: &str) -> Result<Vec<CheckpointEntry>, DriverError>;
+    async fn keys(&self, pattern: &str) -> Result<Vec<String>, DriverError>;
+    async fn peek(&self, key: &str) -> Result<Vec<CheckpointEntry>, DriverError>;
+    async fn compact(
+        &self,
+        key: &str,
+        drop: &HashSet<String>,
+        expires_before: Option<u64>,
+    ) -> Result<Vec<CheckpointEntry>, DriverError>;
+}
+
+pub struct CheckpointService {
+    repository: CheckpointRepository,
+}
+
+impl CheckpointService {
+    // constructor method
+    pub fn new(client: Arc<Mutex<dyn CacheDriver<str, Vec<CheckpointEntry>>>>) -> Self {
+        CheckpointService {
+            repository: CheckpointRepository::new(client),
+        }
+    }
+}
+
+impl CheckpointServiceTrait for CheckpointService {
+    async fn save(&self, key: &str, entries: Vec<CheckpointEntry>) -> Result<(), DriverError> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        self.repository.append(key, entries).await
+    }
+
+    async fn restore(&self, key: &str) -> Result<Vec<CheckpointEntry>, DriverError> {
+        if !self.repository.exists(key.to_string()).await? {
+            return Ok(Vec::new());
+        }
+
+        // NOTE: the checkpoint is consumed on restore, so a crash after resuming does not replay
+        // the same queue twice.
+        let entries = self.repository.get(key.to_string()).await?;
+        self.repository.remove(key.to_string()).await?;
+        Ok(entries)
+    }
+
+    async fn keys(&self, pattern: &str) -> Result<Vec<String>, DriverError> {
+        self.repository.keys(pattern).await
+    }
+
+    // Read the entries at `key` without consuming them.
+    async fn peek(&self, key: &str) -> Result<Vec<CheckpointEntry>, DriverError> {
+        if !self.repository.exists(key.to_string()).await? {
+            return Ok(Vec::new());
+        }
+        self.repository.get(key.to_string()).await
+    }
+
+    async fn compact(
+        &self,
+        key: &str,
+        drop: &HashSet<String>,
+        expires_before: Option<u64>,
+    ) -> Result<Vec<CheckpointEntry>, DriverError> {
+        self.repository.compact(key, drop, expires_before).await
+    }
+}
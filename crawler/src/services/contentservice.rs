@@ -0,0 +1,93 @@
+use std::sync::Arc;
+
+use drivers::{errors::DriverError, CacheDriver};
+use models::ContentFingerprint;
+use tokio::sync::Mutex;
+
+use crate::repositories::{ContentRecord, ContentRepository};
+
+// Largest SimHash distance accepted for near-duplicates: the hash is split into distance + 1
+// bands, which get too narrow (and their buckets too crowded) beyond this.
+const MAX_NEAR_DUPLICATE_DISTANCE: u32 = 7;
+// Records kept per bucket.
+const MAX_BUCKET_RECORDS: usize = 64;
+
+pub trait ContentServiceTrait {
+    async fn claim(
+        &self,
+        url: &str,
+        fingerprint: &ContentFingerprint,
+    ) -> Result<Option<String>, DriverError>;
+}
+
+pub struct ContentService {
+    repository: ContentRepository,
+    // SimHash distance up to which pages are near-duplicates (0 only detects exact duplicates).
+    max_distance: u32,
+}
+
+impl ContentService {
+    // constructor method
+    pub fn new(
+        client: Arc<Mutex<dyn CacheDriver<str, Vec<ContentRecord>>>>,
+        max_distance: u32,
+    ) -> Self {
+        ContentService {
+            repository: ContentRepository::new(client),
+            max_distance: max_distance.min(MAX_NEAR_DUPLICATE_DISTANCE),
+        }
+    }
+
+    // Buckets a fingerprint is stored in. Exact duplicates share the bucket of their content
+    // hash. For near-duplicates the SimHash is split into `max_distance + 1` bands: two hashes
+    // differing in at most `max_distance` bits have at least one identical band, so they share
+    // at least one band bucket.
+    fn bucket_keys(&self, fingerprint: &ContentFingerprint) -> Vec<String> {
+        if self.max_distance == 0 {
+            return vec![format!("content:{}", fingerprint.content_hash)];
+        }
+        let bands = (self.max_distance + 1) as u64;
+        let width = 64 / bands;
+        (0..bands)
+            .map(|band| {
+                let shift = band * width;
+                // the last band takes the remaining bits
+                let bits = if band == bands - 1 { 64 - shift } else { width };
+                let value = (fingerprint.simhash >> shift) & (u64::MAX >> (64 - bits));
+                format!("simhash:{bands}:{band}:{value:x}")
+            })
+            .collect()
+    }
+}
+
+impl ContentServiceTrait for ContentService {
+    // URL of another page already seen with the same (or, with near-duplicate detection, a
+    // similar) content. When there is none, `url` is recorded as the first page with this
+    // content.
+    async fn claim(
+        &self,
+        url: &str,
+        fingerprint: &ContentFingerprint,
+    ) -> Result<Option<String>, DriverError> {
+        let record = ContentRecord {
+            url: url.to_string(),
+            content_hash: fingerprint.content_hash.clone(),
+            simhash: fingerprint.simhash,
+        };
+        let is_match = |other: &ContentRecord| {
+            other.url != url
+                && (other.content_hash == fingerprint.content_hash
+                    || (other.simhash ^ fingerprint.simhash).count_ones() <= self.max_distance)
+        };
+        let found = self
+            .repository
+            .find_or_insert(
+                &self.bucket_keys(fingerprint),
+                record,
+                is_match,
+                MAX_BUCKET_RECORDS,
+            )
+            .await?;
+        Ok(found.map(|record| record.url))
+    }
+}
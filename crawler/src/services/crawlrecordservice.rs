@@ -0,0 +1,45 @@
+use std::sync::Arc;
+
+use drivers::{errors::DriverError, CacheDriver};
+use tokio::sync::Mutex;
+
+use crate::repositories::{CrawlRecord, CrawlRecordRepository, Repository};
+
+pub trait CrawlRecordServiceTrait {
+    async fn find(&self, url: &str) -> Result<Option<CrawlRecord>, DriverError>;
+    async fn record(&self, record: CrawlRecord) -> Result<Option<CrawlRecord>, DriverError>;
+}
+
+pub struct CrawlRecordService {
+    repository: CrawlRecordRepository,
+}
+
+impl CrawlRecordService {
+    // constructor method
+    pub fn new(client: Arc<Mutex<dyn CacheDriver<str, CrawlRecord>>>) -> Self {
+        CrawlRecordService {
+            repository: CrawlRecordRepository::new(client),
+        }
+    }
+
+    fn key(url: &str) -> String {
+        format!("crawl:{url}")
+    }
+}
+
+impl CrawlRecordServiceTrait for CrawlRecordService {
+    async fn find(&self, url: &str) -> Result<Option<CrawlRecord>, DriverError> {
+        let key = Self::key(url);
+        if !self.repository.exists(key.clone()).await? {
+            return Ok(None);
+        }
+        self.repository.get(key).await.map(Some)
+    }
+
+    // Store the record of the latest crawl of a URL, returning the previous one.
+    async fn record(&self, record: CrawlRecord) -> Result<Option<CrawlRecord>, DriverError> {
+        let previous = self.find(&record.url).await?;
+        self.repository.set(Self::key(&record.url), record).await?;
+        Ok(previous)
+    }
+}
@@ -0,0 +1,40 @@
+use std::sync::Arc;
+
+use drivers::{errors::DriverError, CacheDriver};
+use tokio::sync::Mutex;
+
+use crate::repositories::{DeadLetterEntry, DeadLetterRepository};
+
+pub trait DeadLetterServiceTrait {
+    async fn record(&self, key: &str, entry: DeadLetterEntry) -> Result<(), DriverError>;
+    async fn list(&self, key: &str, limit: usize) -> Result<Vec<DeadLetterEntry>, DriverError>;
+}
+
+pub struct DeadLetterService {
+    repository: DeadLetterRepository,
+    // Entries kept per dead-letter list, the oldest being dropped (0 = unlimited).
+    max_entries: usize,
+}
+
+impl DeadLetterService {
+    // constructor method
+    pub fn new(
+        client: Arc<Mutex<dyn CacheDriver<str, DeadLetterEntry>>>,
+        max_entries: usize,
+    ) -> Self {
+        DeadLetterService {
+            repository: DeadLetterRepository::new(client),
+            max_entries,
+        }
+    }
+}
+
+impl DeadLetterServiceTrait for DeadLetterService {
+    async fn record(&self, key: &str, entry: DeadLetterEntry) -> Result<(), DriverError> {
+        self.repository.append(key, entry, self.max_entries).await
+    }
+
+    async fn list(&self, key: &str, limit: usize) -> Result<Vec<DeadLetterEntry>, DriverError> {
+        self.repository.latest(key, limit).await
+    }
+}
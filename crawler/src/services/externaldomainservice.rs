@@ -0,0 +1,63 @@
+use std::sync::Arc;
+
+use drivers::{errors::DriverError, CacheDriver};
+use tokio::sync::Mutex;
+
+use crate::repositories::{ExternalDomainRepository, Repository};
+
+pub trait ExternalDomainServiceTrait {
+    async fn admit(&self, domain: &str) -> Result<bool, DriverError>;
+}
+
+pub struct ExternalDomainService {
+    repository: ExternalDomainRepository,
+    // Job the quota applies to; processes sharing a job id share the quota.
+    job: String,
+    // Maximum number of external domains admitted per job. `None` disables the quota.
+    max_domains_per_job: Option<u64>,
+}
+
+impl ExternalDomainService {
+    // constructor method
+    pub fn new(
+        client: Arc<Mutex<dyn CacheDriver<str, u64>>>,
+        job: String,
+        max_domains_per_job: Option<u64>,
+    ) -> Self {
+        ExternalDomainService {
+            repository: ExternalDomainRepository::new(client),
+            job,
+            max_domains_per_job,
+        }
+    }
+
+    fn domain_key(&self, domain: &str) -> String {
+        format!("external:{}:domain:{domain}", self.job)
+    }
+
+    fn count_key(&self) -> String {
+        format!("external:{}:count", self.job)
+    }
+}
+
+impl ExternalDomainServiceTrait for ExternalDomainService {
+    // Whether links to the external `domain` may enter the frontier. Domains admitted once stay
+    // admitted; new domains are admitted while the job quota lasts.
+    async fn admit(&self, domain: &str) -> Result<bool, DriverError> {
+        let Some(limit) = self.max_domains_per_job else {
+            return Ok(true);
+        };
+
+        let key = self.domain_key(domain);
+        if self.repository.exists(key.clone()).await? {
+            return Ok(true);
+        }
+        // NOTE: concurrent agents may both count the same new domain, which only makes the quota
+        // slightly stricter.
+        if self.repository.increment(&self.count_key()).await? > limit {
+            return Ok(false);
+        }
+        self.repository.set(key, 1).await?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use drivers::{errors::DriverError, CacheDriver};
+use tokio::sync::Mutex;
+
+use crate::repositories::{LeaseRepository, Repository};
+
+pub trait LeaseServiceTrait {
+    async fn acquire(&self, name: &str, epoch: u64) -> Result<bool, DriverError>;
+}
+
+pub struct LeaseService {
+    repository: LeaseRepository,
+}
+
+impl LeaseService {
+    // constructor method
+    pub fn new(client: Arc<Mutex<dyn CacheDriver<str, u64>>>) -> Self {
+        LeaseService {
+            repository: LeaseRepository::new(client),
+        }
+    }
+
+    fn lease_key(name: &str, epoch: u64) -> String {
+        format!("lease:{name}:{epoch}")
+    }
+}
+
+impl LeaseServiceTrait for LeaseService {
+    // Whether this process holds the lease `name` for `epoch`: the first process to ask for an
+    // epoch gets it, every other process sharing the Redis instance is turned down.
+    async fn acquire(&self, name: &str, epoch: u64) -> Result<bool, DriverError> {
+        let acquired = self
+            .repository
+            .increment(&Self::lease_key(name, epoch))
+            .await?
+            == 1;
+        if acquired && epoch > 0 {
+            // The lease of the previous epoch is no longer needed.
+            self.repository
+                .remove(Self::lease_key(name, epoch - 1))
+                .await?;
+        }
+        Ok(acquired)
+    }
+}
@@ -0,0 +1,55 @@
+use std::sync::Arc;
+
+use drivers::{errors::DriverError, CacheDriver};
+use tokio::sync::Mutex;
+
+use crate::repositories::{PageRepository, Repository, StoredPage};
+
+pub trait PageServiceTrait {
+    async fn store(&self, page: StoredPage) -> Result<(), DriverError>;
+    async fn find(&self, url: &str) -> Result<Option<StoredPage>, DriverError>;
+    async fn stored_urls(&self, host: Option<&str>) -> Result<Vec<String>, DriverError>;
+}
+
+pub struct PageService {
+    repository: PageRepository,
+}
+
+impl PageService {
+    // constructor method
+    pub fn new(client: Arc<Mutex<dyn CacheDriver<str, StoredPage>>>) -> Self {
+        PageService {
+            repository: PageRepository::new(client),
+        }
+    }
+
+    fn key(url: &str) -> String {
+        format!("page:{url}")
+    }
+}
+
+impl PageServiceTrait for PageService {
+    async fn store(&self, page: StoredPage) -> Result<(), DriverError> {
+        self.repository.set(Self::key(&page.url), page).await
+    }
+
+    async fn find(&self, url: &str) -> Result<Option<StoredPage>, DriverError> {
+        let key = Self::key(url);
+        if !self.repository.exists(key.clone()).await? {
+            return Ok(None);
+        }
+        self.repository.get(key).await.map(Some)
+    }
+
+    async fn stored_urls(&self, host: Option<&str>) -> Result<Vec<String>, DriverError> {
+        let pattern = Self::key(&format!("http*://{}/*", host.unwrap_or("*")));
+        let keys = self.repository.keys(&pattern).await?;
+        Ok(keys
+            .into_iter()
+            .filter_map(|key| key.strip_prefix("page:").map(str::to_string))
+            .filter(|url| {
+                host.is_none_or(|h| url::Url::parse(url).is_ok_and(|u| u.host_str() == Some(h)))
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,59 @@
+use std::{sync::Arc, time::Duration};
+
+use drivers::{errors::DriverError, CacheDriver};
+use tokio::sync::Mutex;
+
+use crate::repositories::{Repository, RobotsRecord, RobotsRepository};
+
+pub trait RobotsServiceTrait {
+    async fn find(&self, host: &str) -> Result<Option<RobotsRecord>, DriverError>;
+    async fn save(
+        &self,
+        host: &str,
+        record: RobotsRecord,
+        ttl: Duration,
+    ) -> Result<(), DriverError>;
+}
+
+pub struct RobotsService {
+    repository: RobotsRepository,
+}
+
+impl RobotsService {
+    // constructor method
+    pub fn new(client: Arc<Mutex<dyn CacheDriver<str, RobotsRecord>>>) -> Self {
+        RobotsService {
+            repository: RobotsRepository::new(client),
+        }
+    }
+
+    fn key(host: &str) -> String {
+        format!("robots:{host}")
+    }
+}
+
+impl RobotsServiceTrait for RobotsService {
+    async fn find(&self, host: &str) -> Result<Option<RobotsRecord>, DriverError> {
+        let key = Self::key(host);
+        if !self.repository.exists(key.clone()).await? {
+            return Ok(None);
+        }
+        self.repository.get(key).await.map(Some)
+    }
+
+    // Cache the record of `host` for `ttl` (forever if zero), so stale files do not pile up in
+    // Redis.
+    async fn save(
+        &self,
+        host: &str,
+        record: RobotsRecord,
+        ttl: Duration,
+    ) -> Result<(), DriverError> {
+        if ttl.is_zero() {
+            return self.repository.set(Self::key(host), record).await;
+        }
+        self.repository
+            .set_with_ttl(&Self::key(host), record, ttl)
+            .await
+    }
+}
@@ -0,0 +1,184 @@
+use std::{sync::Arc, time::Duration};
+
+use drivers::{errors::DriverError, CacheDriver};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::{
+    crawler::unix_timestamp,
+    repositories::{FrontierEntry, FrontierLease, Repository, SharedFrontierRepository},
+};
+
+pub trait SharedFrontierServiceTrait {
+    async fn push(&self, entries: Vec<FrontierEntry>) -> Result<(), DriverError>;
+    async fn claim(
+        &self,
+        worker: &str,
+        max: usize,
+    ) -> Result<Vec<(u64, FrontierEntry)>, DriverError>;
+    async fn ack(&self, claim: u64) -> Result<(), DriverError>;
+    async fn pending(&self) -> Result<u64, DriverError>;
+    async fn reap(&self) -> Result<usize, DriverError>;
+}
+
+// Frontier shared by the crawler processes of a deployment, stored in Redis under `<key>:*`.
+// Entries are numbered by a tail counter when pushed and handed out in order through a head
+// counter; a claimed entry gets a lease and stays in Redis until acknowledged, so a request is
+// crawled at least once even if its worker crashes (see `reap`).
+pub struct SharedFrontierService {
+    repository: SharedFrontierRepository,
+    key: String,
+    lease: Duration,
+}
+
+impl SharedFrontierService {
+    pub fn new(
+        client: Arc<Mutex<dyn CacheDriver<str, FrontierEntry>>>,
+        lease_client: Arc<Mutex<dyn CacheDriver<str, FrontierLease>>>,
+        counter_client: Arc<Mutex<dyn CacheDriver<str, i64>>>,
+        key: String,
+        lease: Duration,
+    ) -> Self {
+        SharedFrontierService {
+            repository: SharedFrontierRepository::new(client, lease_client, counter_client),
+            key,
+            lease,
+        }
+    }
+
+    fn entry_key(&self, index: u64) -> String {
+        format!("{}:entry:{index}", self.key)
+    }
+
+    fn lease_key(&self, index: u64) -> String {
+        format!("{}:lease:{index}", self.key)
+    }
+
+    fn counter_key(&self, name: &str) -> String {
+        format!("{}:{name}", self.key)
+    }
+}
+
+impl SharedFrontierServiceTrait for SharedFrontierService {
+    async fn push(&self, entries: Vec<FrontierEntry>) -> Result<(), DriverError> {
+        for entry in entries {
+            // Counted as pending before it is written, so the frontier never looks drained while
+            // an entry exists.
+            self.repository
+                .increment(&self.counter_key("pending"), 1)
+                .await?;
+            let index = self
+                .repository
+                .increment(&self.counter_key("tail"), 1)
+                .await?;
+            self.repository
+                .set(self.entry_key(index.max(0) as u64), entry)
+                .await?;
+        }
+        Ok(())
+    }
+
+    // Claim up to `max` entries for `worker`, in push order. Returns them with their index, to be
+    // acknowledged once crawled.
+    async fn claim(
+        &self,
+        worker: &str,
+        max: usize,
+    ) -> Result<Vec<(u64, FrontierEntry)>, DriverError> {
+        let mut claimed = Vec::new();
+        while claimed.len() < max {
+            let head = self.repository.counter(&self.counter_key("head")).await?;
+            let tail = self.repository.counter(&self.counter_key("tail")).await?;
+            if head >= tail {
+                break;
+            }
+            // NOTE: concurrent workers may move the head past entries that are still being
+            // written; such entries are left unleased and handed out again by `reap`.
+            let index = self
+                .repository
+                .increment(&self.counter_key("head"), 1)
+                .await?;
+            let index = index.max(0) as u64;
+            let key = self.entry_key(index);
+            if !self.repository.exists(key.clone()).await? {
+                debug!("Shared frontier entry {} is not written yet", index);
+                continue;
+            }
+            let entry = self.repository.get(key).await?;
+            let lease = FrontierLease {
+                worker: worker.to_string(),
+                expires_at: unix_timestamp() + self.lease.as_secs(),
+            };
+            self.repository
+                .set_lease(&self.lease_key(index), lease)
+                .await?;
+            claimed.push((index, entry));
+        }
+        Ok(claimed)
+    }
+
+    // Remove a crawled entry. Entries handed out again since they were claimed are left alone.
+    async fn ack(&self, claim: u64) -> Result<(), DriverError> {
+        let key = self.entry_key(claim);
+        if !self.repository.exists(key.clone()).await? {
+            return Ok(());
+        }
+        self.repository.remove(key).await?;
+        self.repository.remove_lease(&self.lease_key(claim)).await?;
+        self.repository
+            .increment(&self.counter_key("pending"), -1)
+            .await?;
+        Ok(())
+    }
+
+    // Entries pushed and not acknowledged yet, claimed or not.
+    async fn pending(&self) -> Result<u64, DriverError> {
+        let pending = self
+            .repository
+            .counter(&self.counter_key("pending"))
+            .await?;
+        Ok(pending.max(0) as u64)
+    }
+
+    // Queue again the entries handed out whose lease expired (their worker crashed or stalled),
+    // and the entries skipped by the head counter that were never leased. Returns the number of
+    // entries queued again.
+    async fn reap(&self) -> Result<usize, DriverError> {
+        let now = unix_timestamp();
+        let head = self.repository.counter(&self.counter_key("head")).await?;
+        let prefix = format!("{}:entry:", self.key);
+        let mut reaped = 0;
+
+        for key in self.repository.keys(&format!("{prefix}*")).await? {
+            let Some(index) = key
+                .strip_prefix(&prefix)
+                .and_then(|index| index.parse::<u64>().ok())
+            else {
+                continue;
+            };
+            // Entries after the head were not handed out yet.
+            if index as i64 > head {
+                continue;
+            }
+            let mut entry = self.repository.get(key.clone()).await?;
+            let expired = match self.repository.find_lease(&self.lease_key(index)).await? {
+                Some(lease) => lease.expires_at <= now,
+                // Unleased entries get a lease period of grace, as they may be being claimed.
+                None => entry.pushed_at + self.lease.as_secs() <= now,
+            };
+            if !expired {
+                continue;
+            }
+
+            debug!(
+                "Queuing shared frontier entry {} again: {}",
+                index, entry.url
+            );
+            entry.pushed_at = now;
+            self.push(vec![entry]).await?;
+            self.ack(index).await?;
+            reaped += 1;
+        }
+        Ok(reaped)
+    }
+}
@@ -1,18 +1,46 @@
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
 
 use drivers::{errors::DriverError, CacheDriver};
 use tokio::sync::Mutex;
+use tracing::{debug, warn};
 
-use crate::repositories::{Repository, UrlRepository};
+use crate::{
+    bloom::BloomFilter,
+    repositories::{BloomRepository, Repository, UrlRepository},
+};
+
+// Redis key under which the shared bloom filter is stored.
+const BLOOM_KEY: &str = "bloom:visited";
 
 pub trait UrlServiceTrait {
     // Define service methods here, e.g., create, read, update, delete URLs
     async fn is_visited(&self, url: url::Url) -> Result<bool, DriverError>;
-    async fn mark_visited(&self, url: url::Url) -> Result<(), DriverError>;
+    async fn mark_visited(&self, url: url::Url) -> Result<bool, DriverError>;
+    async fn claim(&self, url: url::Url) -> Result<bool, DriverError>;
+    async fn visited_urls(&self, host: Option<&str>) -> Result<Vec<url::Url>, DriverError>;
+    async fn sync(&self) -> Result<(), DriverError>;
+    fn switch_to_bloom(&self) -> bool;
+}
+
+// Probabilistic dedup state: an in-process filter periodically merged with the copy in Redis.
+struct BloomState {
+    filter: Mutex<BloomFilter>,
+    repository: BloomRepository,
 }
 
 pub struct UrlService {
     repository: UrlRepository,
+    // When set (and in use), visited URLs are tracked in a bloom filter instead of one Redis key
+    // per URL.
+    bloom: Option<BloomState>,
+    // Whether URLs are looked up in the Redis keys of the exact mode.
+    exact: bool,
+    // Whether new URLs go to the bloom filter. Exact mode can switch to it under memory pressure
+    // (see `switch_to_bloom`), keeping the keys written so far.
+    uses_bloom: AtomicBool,
 }
 
 impl UrlService {
@@ -20,18 +48,172 @@ impl UrlService {
     pub fn new(client: Arc<Mutex<dyn CacheDriver<str, url::Url>>>) -> Self {
         UrlService {
             repository: UrlRepository::new(client),
+            bloom: None,
+            exact: true,
+            uses_bloom: AtomicBool::new(false),
+        }
+    }
+
+    // constructor method for the bloom-filter dedup mode
+    pub fn new_with_bloom(
+        client: Arc<Mutex<dyn CacheDriver<str, url::Url>>>,
+        bloom_client: Arc<Mutex<dyn CacheDriver<str, BloomFilter>>>,
+        filter: BloomFilter,
+    ) -> Self {
+        UrlService {
+            repository: UrlRepository::new(client),
+            bloom: Some(BloomState {
+                filter: Mutex::new(filter),
+                repository: BloomRepository::new(bloom_client),
+            }),
+            exact: false,
+            uses_bloom: AtomicBool::new(true),
         }
     }
+
+    // constructor method for the exact mode, with a bloom filter to switch to under memory
+    // pressure
+    pub fn new_with_bloom_fallback(
+        client: Arc<Mutex<dyn CacheDriver<str, url::Url>>>,
+        bloom_client: Arc<Mutex<dyn CacheDriver<str, BloomFilter>>>,
+        filter: BloomFilter,
+    ) -> Self {
+        UrlService {
+            exact: true,
+            uses_bloom: AtomicBool::new(false),
+            ..Self::new_with_bloom(client, bloom_client, filter)
+        }
+    }
+
+    fn active_bloom(&self) -> Option<&BloomState> {
+        self.bloom
+            .as_ref()
+            .filter(|_| self.uses_bloom.load(Ordering::Relaxed))
+    }
 }
 
 impl UrlServiceTrait for UrlService {
     async fn is_visited(&self, url: url::Url) -> Result<bool, DriverError> {
-        self.repository.get(url).await.map(|_| true)
+        if let Some(bloom) = self.active_bloom() {
+            if bloom.filter.lock().await.contains(url.as_str()) {
+                return Ok(true);
+            }
+            if !self.exact {
+                return Ok(false);
+            }
+        }
+        self.repository.exists(url).await
     }
 
-    async fn mark_visited(&self, url: url::Url) -> Result<(), DriverError> {
+    // Mark `url` as visited. Returns whether it was not visited yet: marking is atomic (SETNX in
+    // exact mode, under the filter lock in bloom mode), so exactly one of several agents marking
+    // the same URL gets `true`.
+    async fn mark_visited(&self, url: url::Url) -> Result<bool, DriverError> {
+        if let Some(bloom) = self.active_bloom() {
+            let mut filter = bloom.filter.lock().await;
+            if filter.contains(url.as_str()) {
+                return Ok(false);
+            }
+            // URLs marked before switching to the bloom filter are still in Redis.
+            if self.exact && self.repository.exists(url.clone()).await? {
+                return Ok(false);
+            }
+            filter.insert(url.as_str());
+            return Ok(true);
+        }
+
         // NOTE: we set the URL as both key and value for simplicity. We just need to track
         // visited.
-        self.repository.set(url.clone(), url).await
+        self.repository.set_if_absent(url).await
+    }
+
+    // Mark `url` as visited unless it already was, before crawling it. Returns whether the caller
+    // claimed the URL; callers that did not must not crawl it.
+    async fn claim(&self, url: url::Url) -> Result<bool, DriverError> {
+        self.mark_visited(url).await
+    }
+
+    async fn visited_urls(&self, host: Option<&str>) -> Result<Vec<url::Url>, DriverError> {
+        // Visited URLs are stored with the URL itself as key, so they can be listed by pattern.
+        let pattern = format!("http*://{}/*", host.unwrap_or("*"));
+        let keys = self.repository.keys(&pattern).await?;
+        Ok(keys
+            .iter()
+            .filter_map(|key| url::Url::parse(key).ok())
+            .filter(|url| host.is_none_or(|h| url.host_str() == Some(h)))
+            .collect())
+    }
+
+    // Merge the in-process bloom filter with the one stored in Redis and write the union back, so
+    // that processes sharing the same Redis converge on the same set of visited URLs. No-op in
+    // exact mode.
+    async fn sync(&self) -> Result<(), DriverError> {
+        let Some(bloom) = self.active_bloom() else {
+            return Ok(());
+        };
+
+        let mut filter = bloom.filter.lock().await;
+        if bloom.repository.exists(BLOOM_KEY).await? {
+            let remote = bloom.repository.get(BLOOM_KEY).await?;
+            if !filter.merge(&remote) {
+                warn!("Stored bloom filter has a different size, overwriting it");
+            }
+        }
+        bloom.repository.set(BLOOM_KEY, filter.clone()).await?;
+        debug!("Synced bloom filter ({} bytes)", filter.size_bytes());
+        Ok(())
+    }
+
+    // Track new visited URLs in the bloom filter instead of Redis keys. Returns whether the
+    // service switched (it needs a fallback filter and only switches once).
+    fn switch_to_bloom(&self) -> bool {
+        self.bloom.is_some() && !self.uses_bloom.swap(true, Ordering::Relaxed)
+    }
+}
+
+// The dedup modes run against the in-memory cache driver, so that no Redis is needed.
+#[cfg(test)]
+mod tests {
+    use drivers::memory::MemoryDriver;
+
+    use super::*;
+
+    fn url(s: &str) -> url::Url {
+        url::Url::parse(s).unwrap()
+    }
+
+    #[tokio::test]
+    async fn exact_mode_claims_a_url_once() {
+        let service = UrlService::new(Arc::new(Mutex::new(MemoryDriver::new())));
+        let page = url("https://example.com/a");
+
+        assert!(!service.is_visited(page.clone()).await.unwrap());
+        assert!(service.claim(page.clone()).await.unwrap());
+        assert!(!service.claim(page.clone()).await.unwrap());
+        assert!(service.is_visited(page).await.unwrap());
+        assert!(!service
+            .is_visited(url("https://example.com/b"))
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn fallback_keeps_the_urls_claimed_before_the_switch() {
+        let driver = MemoryDriver::new();
+        let service = UrlService::new_with_bloom_fallback(
+            Arc::new(Mutex::new(driver.clone())),
+            Arc::new(Mutex::new(driver)),
+            BloomFilter::new(1000, 0.01),
+        );
+        let before = url("https://example.com/before");
+        let after = url("https://example.com/after");
+
+        assert!(service.claim(before.clone()).await.unwrap());
+        assert!(service.switch_to_bloom());
+        assert!(!service.claim(before.clone()).await.unwrap());
+        assert!(service.claim(after.clone()).await.unwrap());
+        assert!(!service.claim(after.clone()).await.unwrap());
+        assert!(service.is_visited(before).await.unwrap());
+        assert!(service.is_visited(after).await.unwrap());
     }
 }
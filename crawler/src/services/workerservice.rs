@@ -0,0 +1,57 @@
+use std::sync::Arc;
+
+use drivers::{errors::DriverError, CacheDriver};
+use tokio::sync::Mutex;
+
+use crate::repositories::{Repository, WorkerHeartbeat, WorkerRepository};
+
+pub trait WorkerServiceTrait {
+    async fn beat(&self, heartbeat: WorkerHeartbeat) -> Result<(), DriverError>;
+    async fn remove(&self, crawler_type: &str, id: &str) -> Result<(), DriverError>;
+    async fn list(&self, crawler_type: Option<&str>) -> Result<Vec<WorkerHeartbeat>, DriverError>;
+}
+
+// Registry of the crawler agents of every deployment sharing the Redis instance, stored as
+// `worker:<crawler_type>:<id>` keys.
+pub struct WorkerService {
+    repository: WorkerRepository,
+}
+
+impl WorkerService {
+    // constructor method
+    pub fn new(client: Arc<Mutex<dyn CacheDriver<str, WorkerHeartbeat>>>) -> Self {
+        WorkerService {
+            repository: WorkerRepository::new(client),
+        }
+    }
+
+    fn key(crawler_type: &str, id: &str) -> String {
+        format!("worker:{crawler_type}:{id}")
+    }
+}
+
+impl WorkerServiceTrait for WorkerService {
+    async fn beat(&self, heartbeat: WorkerHeartbeat) -> Result<(), DriverError> {
+        let key = Self::key(&heartbeat.crawler_type, &heartbeat.id);
+        self.repository.set(key, heartbeat).await
+    }
+
+    async fn remove(&self, crawler_type: &str, id: &str) -> Result<(), DriverError> {
+        self.repository.remove(Self::key(crawler_type, id)).await
+    }
+
+    // Heartbeats of the agents of `crawler_type` (or of every crawler type), sorted by id.
+    async fn list(&self, crawler_type: Option<&str>) -> Result<Vec<WorkerHeartbeat>, DriverError> {
+        let pattern = format!("worker:{}:*", crawler_type.unwrap_or("*"));
+        let mut heartbeats = Vec::new();
+        for key in self.repository.keys(&pattern).await? {
+            // Agents may stop between listing and reading their key.
+            if !self.repository.exists(key.clone()).await? {
+                continue;
+            }
+            heartbeats.push(self.repository.get(key).await?);
+        }
+        heartbeats.sort_by(|a, b| (&a.crawler_type, &a.id).cmp(&(&b.crawler_type, &b.id)));
+        Ok(heartbeats)
+    }
+}
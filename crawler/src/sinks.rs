@@ -0,0 +1,272 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use drivers::{config::BusConfig, warc::WarcWriter, EnqueueOptions, QueueDriver};
+use models::PageData;
+use serde_json::Value;
+use tokio::{sync::mpsc, task::JoinHandle};
+use tracing::{error, info, warn};
+
+use crate::{
+    config::{OutputConfig, SinkKind, WarcSinkConfig},
+    writer::CrawlerWriter,
+};
+
+// Destination of the published pages.
+#[async_trait]
+pub trait Sink: Send + Sync {
+    // Name of the sink in logs and errors.
+    fn name(&self) -> &'static str;
+    // Deliver a page. `options` carries the message headers, only the message bus uses them.
+    async fn send(&self, page: &PageData, options: &EnqueueOptions) -> Result<(), String>;
+    // Flush and release the sink once every agent is done.
+    async fn close(&self) -> Result<(), String>;
+    // Messages waiting to be consumed downstream, for sinks feeding a queue (see
+    // `BackpressureController`).
+    async fn backlog(&self) -> Result<Option<u64>, String> {
+        Ok(None)
+    }
+}
+
+// Publishes the pages to the message bus (RabbitMQ or Kafka).
+pub struct BusSink(Arc<dyn QueueDriver>);
+
+impl BusSink {
+    pub async fn connect(config: &BusConfig) -> Result<Self, String> {
+        drivers::connect_queue_driver_with(config)
+            .await
+            .map(BusSink)
+            .map_err(|e| format!("Failed to build message bus client: {e}"))
+    }
+}
+
+#[async_trait]
+impl Sink for BusSink {
+    fn name(&self) -> &'static str {
+        "bus"
+    }
+
+    async fn send(&self, page: &PageData, options: &EnqueueOptions) -> Result<(), String> {
+        self.0
+            .enqueue(page, options)
+            .await
+            .map_err(|e| format!("Message bus enqueue error: {e}"))
+    }
+
+    async fn close(&self) -> Result<(), String> {
+        self.0.close().await.map_err(|e| e.to_string())
+    }
+
+    async fn backlog(&self) -> Result<Option<u64>, String> {
+        self.0
+            .backlog()
+            .await
+            .map_err(|e| format!("Message bus queue depth error: {e}"))
+    }
+}
+
+// Local sink written by a background task, fed through a bounded channel: a slow disk makes the
+// agents wait (see `send_timeout_ms`) instead of buffering pages without limit.
+pub struct ChannelSink {
+    name: &'static str,
+    sender: Mutex<Option<mpsc::Sender<PageData>>>,
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl ChannelSink {
+    // `task` must end once `sender` (and its clones) is dropped.
+    pub fn new(name: &'static str, sender: mpsc::Sender<PageData>, task: JoinHandle<()>) -> Self {
+        ChannelSink {
+            name,
+            sender: Mutex::new(Some(sender)),
+            task: Mutex::new(Some(task)),
+        }
+    }
+
+    // JSON lines appended to `path` (the `file` sink, see `CrawlerWriter`).
+    pub fn file(writer: CrawlerWriter, buffer: usize) -> Self {
+        let (sender, task) = writer.spawn(buffer);
+        ChannelSink::new("file", sender, task)
+    }
+
+    // WARC records written to the files of `config.path` (the `warc` sink, see `WarcWriter`).
+    pub fn warc(config: &WarcSinkConfig, buffer: usize) -> Result<Self, String> {
+        let writer = WarcWriter::open(&config.path, &config.prefix, config.max_bytes, config.gzip)?;
+        let (sender, mut receiver) = mpsc::channel::<PageData>(buffer.max(1));
+        // WARC files are written with blocking I/O, off the async workers.
+        let task = tokio::task::spawn_blocking(move || {
+            while let Some(page) = receiver.blocking_recv() {
+                let written = match serde_json::to_value(&page) {
+                    Ok(Value::Object(record)) => writer.write(&record),
+                    Ok(_) => Err("page is not a JSON object".to_string()),
+                    Err(e) => Err(e.to_string()),
+                };
+                if let Err(e) = written {
+                    error!("Failed to write {} as WARC: {}", page.url, e);
+                }
+            }
+        });
+        Ok(ChannelSink::new("warc", sender, task))
+    }
+}
+
+#[async_trait]
+impl Sink for ChannelSink {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    async fn send(&self, page: &PageData, _options: &EnqueueOptions) -> Result<(), String> {
+        let sender = self.sender.lock().unwrap().clone();
+        let sender = sender.ok_or_else(|| format!("{} sink is closed", self.name))?;
+        sender
+            .send(page.clone())
+            .await
+            .map_err(|_| format!("{} sink writer stopped", self.name))
+    }
+
+    async fn close(&self) -> Result<(), String> {
+        // the writer drains the channel and ends once the last sender is gone
+        self.sender.lock().unwrap().take();
+        let task = self.task.lock().unwrap().take();
+        match task {
+            Some(task) => task
+                .await
+                .map_err(|e| format!("{} sink failed: {e}", self.name)),
+            None => Ok(()),
+        }
+    }
+}
+
+// Sends every page to several sinks, e.g. the message bus and a local WARC archive. Sinks are
+// isolated from each other: a sink failing to take a page is logged and the others still get
+// it, and the page only fails (and is retried) when no sink took it. With `send_timeout`, a sink
+// that does not take a page in time (its buffer is full, or the broker is slow to confirm)
+// misses that page instead of stalling the crawl.
+pub struct SinkMulticast {
+    sinks: Vec<Box<dyn Sink>>,
+    send_timeout: Option<Duration>,
+    // Pages each sink missed, in the order of `sinks`.
+    missed: Vec<AtomicU64>,
+}
+
+impl SinkMulticast {
+    pub fn new(sinks: Vec<Box<dyn Sink>>, send_timeout: Option<Duration>) -> Self {
+        let missed = sinks.iter().map(|_| AtomicU64::new(0)).collect();
+        SinkMulticast {
+            sinks,
+            send_timeout,
+            missed,
+        }
+    }
+
+    // The sinks listed in `output`. The message bus is skipped unless `publish` is set.
+    pub async fn from_config(
+        output: &OutputConfig,
+        bus: &BusConfig,
+        publish: bool,
+    ) -> Result<Self, String> {
+        let mut sinks: Vec<Box<dyn Sink>> = Vec::new();
+        for kind in &output.sinks {
+            if sinks.iter().any(|sink| sink.name() == kind.name()) {
+                warn!(
+                    "Sink {} is listed twice, ignoring the duplicate",
+                    kind.name()
+                );
+                continue;
+            }
+            match kind {
+                SinkKind::Bus if !publish => info!("Pages are not published to the message bus"),
+                SinkKind::Bus => sinks.push(Box::new(BusSink::connect(bus).await?)),
+                SinkKind::File => {
+                    let writer = CrawlerWriter::from_config(&output.file);
+                    sinks.push(Box::new(ChannelSink::file(writer, output.buffer)));
+                }
+                SinkKind::Warc => {
+                    sinks.push(Box::new(ChannelSink::warc(&output.warc, output.buffer)?));
+                }
+            }
+        }
+        let send_timeout =
+            (output.send_timeout_ms > 0).then(|| Duration::from_millis(output.send_timeout_ms));
+        Ok(SinkMulticast::new(sinks, send_timeout))
+    }
+
+    // Add a sink, e.g. one of an embedding program.
+    pub fn push(&mut self, sink: Box<dyn Sink>) {
+        self.sinks.push(sink);
+        self.missed.push(AtomicU64::new(0));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sinks.is_empty()
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.sinks.iter().map(|sink| sink.name()).collect()
+    }
+}
+
+#[async_trait]
+impl Sink for SinkMulticast {
+    fn name(&self) -> &'static str {
+        "multicast"
+    }
+
+    async fn send(&self, page: &PageData, options: &EnqueueOptions) -> Result<(), String> {
+        let mut errors = Vec::new();
+        for (sink, missed) in self.sinks.iter().zip(&self.missed) {
+            let sent = match self.send_timeout {
+                Some(timeout) => tokio::time::timeout(timeout, sink.send(page, options))
+                    .await
+                    .unwrap_or_else(|_| Err(format!("{} sink timed out", sink.name()))),
+                None => sink.send(page, options).await,
+            };
+            if let Err(e) = sent {
+                missed.fetch_add(1, Ordering::Relaxed);
+                warn!("Sink {} missed {}: {}", sink.name(), page.url, e);
+                errors.push(e);
+            }
+        }
+        if !self.sinks.is_empty() && errors.len() == self.sinks.len() {
+            return Err(errors.join("; "));
+        }
+        Ok(())
+    }
+
+    async fn close(&self) -> Result<(), String> {
+        let mut errors = Vec::new();
+        for (sink, missed) in self.sinks.iter().zip(&self.missed) {
+            let missed = missed.load(Ordering::Relaxed);
+            if missed > 0 {
+                warn!("Sink {} missed {} pages", sink.name(), missed);
+            }
+            if let Err(e) = sink.close().await {
+                error!("Failed to close sink {}: {}", sink.name(), e);
+                errors.push(e);
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+
+    // The deepest backlog of the sinks.
+    async fn backlog(&self) -> Result<Option<u64>, String> {
+        let mut deepest = None;
+        for sink in &self.sinks {
+            if let Some(backlog) = sink.backlog().await? {
+                deepest = deepest.max(Some(backlog));
+            }
+        }
+        Ok(deepest)
+    }
+}
@@ -0,0 +1,192 @@
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use tokio::time::Instant;
+use tracing::{debug, info, warn};
+
+use crate::{
+    config::AdaptiveThrottleConfig,
+    requests::{error::RequestError, http::HttpResponse},
+};
+
+// How often the domains back to the minimum delay are dropped.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+// Delay between the fetches of each domain, adapting to how its server copes with the crawl
+// (AIMD): a response slower than `slow_response`, a 5xx/429 response, a timeout or a connection
+// error multiplies the delay of the domain by `backoff_factor` (adding at least `step`), up to
+// `max_delay`; each healthy response takes `step` off again, down to `min_delay`. Shared by the
+// agents of the process. Each slowdown, and each domain back to `min_delay`, is logged as an
+// `adaptive_throttle` event.
+pub struct AdaptiveThrottle {
+    min_delay: Duration,
+    max_delay: Duration,
+    step: Duration,
+    backoff_factor: f64,
+    slow_response: Duration,
+    domains: Mutex<Domains>,
+}
+
+// Domains fetched recently or slowed down. Those back to `min_delay` whose next slot has passed
+// are dropped after their fetch and every `SWEEP_INTERVAL`, so the map does not grow with the crawl.
+struct Domains {
+    throttles: HashMap<String, DomainThrottle>,
+    swept_at: Instant,
+}
+
+struct DomainThrottle {
+    delay: Duration,
+    // When the next fetch of the domain may start.
+    next_at: Instant,
+}
+
+impl AdaptiveThrottle {
+    pub fn from_config(config: &AdaptiveThrottleConfig) -> Self {
+        let min_delay = Duration::from_millis(config.min_delay_ms);
+        AdaptiveThrottle {
+            min_delay,
+            max_delay: Duration::from_millis(config.max_delay_ms).max(min_delay),
+            step: Duration::from_millis(config.step_ms),
+            // NOTE: a factor under 1 would speed up the overloaded domains.
+            backoff_factor: config.backoff_factor.max(1.0),
+            slow_response: Duration::from_millis(config.slow_response_ms),
+            domains: Mutex::new(Domains {
+                throttles: HashMap::new(),
+                swept_at: Instant::now(),
+            }),
+        }
+    }
+
+    // Whether a domain may be forgotten: it is back to `min_delay` and its next slot has passed.
+    fn is_idle(&self, state: &DomainThrottle, now: Instant) -> bool {
+        state.delay <= self.min_delay && state.next_at <= now
+    }
+
+    fn sweep(&self, domains: &mut Domains, now: Instant) {
+        if now.duration_since(domains.swept_at) < SWEEP_INTERVAL {
+            return;
+        }
+        domains.swept_at = now;
+        domains
+            .throttles
+            .retain(|_, state| !self.is_idle(state, now));
+    }
+
+    // Wait for the next slot of the domain: fetches of a domain start at least its delay apart,
+    // even when several are in flight.
+    pub async fn wait(&self, domain: &str) {
+        let start = {
+            let now = Instant::now();
+            let mut domains = self.domains.lock().unwrap();
+            self.sweep(&mut domains, now);
+            let state = domains
+                .throttles
+                .entry(domain.to_string())
+                .or_insert_with(|| DomainThrottle {
+                    delay: self.min_delay,
+                    next_at: now,
+                });
+            let start = state.next_at.max(now);
+            state.next_at = start + state.delay;
+            start
+        };
+        tokio::time::sleep_until(start).await;
+    }
+
+    // Adjust the delay of the domain to the outcome of one of its fetches.
+    pub fn record(&self, domain: &str, result: &Result<HttpResponse, RequestError>) {
+        let overload = match result {
+            Ok(res) if res.status_code == 429 || res.status_code >= 500 => {
+                format!("{} response", res.status_code)
+            }
+            Ok(res) if res.fetch_duration_ms > self.slow_response.as_millis() as u64 => {
+                format!("response in {}ms", res.fetch_duration_ms)
+            }
+            Ok(_) => {
+                self.recover(domain);
+                return;
+            }
+            Err(RequestError::Status(code)) if *code == 429 || *code >= 500 => {
+                format!("{code} response")
+            }
+            Err(RequestError::Timeout) => "timeout".to_string(),
+            Err(RequestError::Connection(_)) => "connection error".to_string(),
+            // other failures (invalid URLs, rejected redirects, ...) say nothing about the server
+            Err(_) => {
+                self.forget_if_idle(domain);
+                return;
+            }
+        };
+
+        let now = Instant::now();
+        let mut domains = self.domains.lock().unwrap();
+        self.sweep(&mut domains, now);
+        let state = domains
+            .throttles
+            .entry(domain.to_string())
+            .or_insert_with(|| DomainThrottle {
+                delay: self.min_delay,
+                next_at: now,
+            });
+        let previous = state.delay;
+        state.delay = Duration::try_from_secs_f64(previous.as_secs_f64() * self.backoff_factor)
+            .unwrap_or(self.max_delay)
+            .max(previous + self.step)
+            .min(self.max_delay);
+        if state.delay > previous {
+            warn!(
+                event = "adaptive_throttle",
+                domain,
+                delay_ms = state.delay.as_millis() as u64,
+                reason = %overload,
+                "Slowing down {} to one fetch every {:?}: {}",
+                domain,
+                state.delay,
+                overload
+            );
+        }
+    }
+
+    // Additive decrease of the delay of a domain that answered normally.
+    fn recover(&self, domain: &str) {
+        let mut domains = self.domains.lock().unwrap();
+        let Some(state) = domains.throttles.get_mut(domain) else {
+            return;
+        };
+        let previous = state.delay;
+        state.delay = previous.saturating_sub(self.step).max(self.min_delay);
+        if state.delay < previous {
+            if state.delay == self.min_delay {
+                info!(
+                    event = "adaptive_throttle",
+                    domain,
+                    delay_ms = state.delay.as_millis() as u64,
+                    "{} recovered, back to one fetch every {:?}",
+                    domain,
+                    state.delay
+                );
+            } else {
+                debug!(
+                    "Speeding up {} to one fetch every {:?}",
+                    domain, state.delay
+                );
+            }
+        }
+        drop(domains);
+        self.forget_if_idle(domain);
+    }
+
+    // Drop the domain once idle (see `is_idle`), along with the other idle domains when a sweep
+    // is due.
+    fn forget_if_idle(&self, domain: &str) {
+        let now = Instant::now();
+        let mut domains = self.domains.lock().unwrap();
+        if domains
+            .throttles
+            .get(domain)
+            .is_some_and(|state| self.is_idle(state, now))
+        {
+            domains.throttles.remove(domain);
+        }
+        self.sweep(&mut domains, now);
+    }
+}
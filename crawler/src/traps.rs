@@ -0,0 +1,201 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    hash::{Hash, Hasher},
+    sync::Mutex,
+};
+
+use regex::Regex;
+use serde::Serialize;
+use tracing::{debug, info, warn};
+use url::Url;
+
+use crate::config::TrapConfig;
+
+// Kind of infinite URL space detected on a domain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrapKind {
+    // Endless combinations of query parameters on the same path (faceted search, sorting filters).
+    QueryString,
+    // Endless paths of the same shape differing by numbers (calendars, pagination).
+    PathPattern,
+    // Session ids in the URLs, giving every visit new URLs for the same pages.
+    SessionId,
+}
+
+// Deny pattern added to a domain once a trap was detected, as reported by `GET /traps`.
+#[derive(Debug, Clone, Serialize)]
+pub struct DetectedTrap {
+    pub domain: String,
+    pub kind: TrapKind,
+    pub pattern: String,
+}
+
+#[derive(Debug, Default)]
+struct DomainTraps {
+    // Hashes of the distinct members (URLs, or parameter names for query strings) seen per pattern,
+    // up to the threshold.
+    urls: HashMap<(TrapKind, String), HashSet<u64>>,
+    deny: Vec<(DetectedTrap, Regex)>,
+}
+
+// Detects spider traps from the links discovered on every domain. Links are grouped into patterns:
+// the shape of their path with its numbers replaced, and whether they carry a session id. Once
+// `threshold` distinct links share a pattern, or a path is linked with `max_query_combinations`
+// different sets of query parameters, a deny pattern is added for the domain and the links
+// matching it are no longer followed.
+#[derive(Debug)]
+pub struct TrapDetector {
+    threshold: usize,
+    max_query_combinations: usize,
+    session_params: HashSet<String>,
+    domains: Mutex<HashMap<String, DomainTraps>>,
+}
+
+impl TrapDetector {
+    pub fn from_config(config: &TrapConfig) -> Option<Self> {
+        if !config.enabled || config.threshold == 0 {
+            return None;
+        }
+        Some(TrapDetector {
+            threshold: config.threshold,
+            max_query_combinations: config.max_query_combinations,
+            session_params: config
+                .session_params
+                .iter()
+                .map(|param| param.trim().to_lowercase())
+                .collect(),
+            domains: Mutex::new(HashMap::new()),
+        })
+    }
+
+    // Record the link `url` and tell whether it is part of a detected trap.
+    pub fn is_trap(&self, url: &Url) -> bool {
+        let Some(domain) = url.host_str().map(str::to_lowercase) else {
+            return false;
+        };
+        let mut domains = self.domains.lock().unwrap();
+        let traps = domains.entry(domain.clone()).or_default();
+        if let Some((trap, _)) = traps.deny.iter().find(|(_, re)| re.is_match(url.as_str())) {
+            debug!("Link {} matches trap pattern {}", url, trap.pattern);
+            return true;
+        }
+
+        for (kind, key, member) in self.patterns(url) {
+            let threshold = match kind {
+                TrapKind::QueryString => self.max_query_combinations,
+                _ => self.threshold,
+            };
+            let seen = traps.urls.entry((kind, key.clone())).or_default();
+            seen.insert(member);
+            if threshold == 0 || seen.len() < threshold {
+                continue;
+            }
+            traps.urls.remove(&(kind, key.clone()));
+            let pattern = self.deny_pattern(kind, &key);
+            let Ok(re) = Regex::new(&pattern) else {
+                warn!("Invalid trap pattern {}", pattern);
+                continue;
+            };
+            info!(
+                event = "trap_detected",
+                domain = %domain,
+                kind = ?kind,
+                pattern = %pattern,
+                "Spider trap detected, links matching the pattern are no longer followed"
+            );
+            let trap = DetectedTrap {
+                domain: domain.clone(),
+                kind,
+                pattern,
+            };
+            traps.deny.push((trap, re));
+            return true;
+        }
+        false
+    }
+
+    // Traps detected so far, on every domain.
+    pub fn detected(&self) -> Vec<DetectedTrap> {
+        self.domains
+            .lock()
+            .unwrap()
+            .values()
+            .flat_map(|traps| traps.deny.iter().map(|(trap, _)| trap.clone()))
+            .collect()
+    }
+
+    // Patterns the link belongs to, with the key identifying each of them on its domain and the
+    // member counted for the link.
+    fn patterns(&self, url: &Url) -> Vec<(TrapKind, String, u64)> {
+        let mut patterns = Vec::new();
+        let origin = url.origin().ascii_serialization();
+        let url_hash = hash(url.as_str());
+        // Many values of the same parameters are fine (`?t=123`), many combinations of parameters
+        // are not.
+        if url.query().is_some() {
+            let mut names: Vec<_> = url.query_pairs().map(|(name, _)| name).collect();
+            names.sort();
+            names.dedup();
+            let key = format!("{origin}{}", url.path());
+            patterns.push((TrapKind::QueryString, key, hash(&names.join("&"))));
+        }
+        // A single number is usually an id (`/post/123`), two or more a date or nested paging.
+        let shape = path_shape(url.path());
+        if shape.matches("{n}").count() >= 2 {
+            patterns.push((TrapKind::PathPattern, format!("{origin}{shape}"), url_hash));
+        }
+        let has_session = url
+            .query_pairs()
+            .any(|(name, _)| self.session_params.contains(&name.to_lowercase()))
+            || url.path().to_lowercase().contains(";jsessionid=");
+        if has_session {
+            patterns.push((TrapKind::SessionId, origin, url_hash));
+        }
+        patterns
+    }
+
+    // Regex matching the links of a detected trap.
+    fn deny_pattern(&self, kind: TrapKind, key: &str) -> String {
+        match kind {
+            TrapKind::QueryString => format!(r"^{}\?", regex::escape(key)),
+            TrapKind::PathPattern => format!(
+                "^{}(?:[?#]|$)",
+                regex::escape(key).replace(r"\{n\}", r"\d+")
+            ),
+            TrapKind::SessionId => {
+                let mut params: Vec<_> = self
+                    .session_params
+                    .iter()
+                    .map(|p| regex::escape(p))
+                    .collect();
+                params.sort();
+                format!(r"^{}/.*[?&;](?i:{})=", regex::escape(key), params.join("|"))
+            }
+        }
+    }
+}
+
+fn hash(value: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+// Path with its runs of digits replaced by `{n}` (`/calendar/2024/05` is `/calendar/{n}/{n}`).
+fn path_shape(path: &str) -> String {
+    let mut shape = String::with_capacity(path.len());
+    let mut in_digits = false;
+    for c in path.chars() {
+        if c.is_ascii_digit() {
+            if !in_digits {
+                shape.push_str("{n}");
+            }
+            in_digits = true;
+        } else {
+            shape.push(c);
+            in_digits = false;
+        }
+    }
+    shape
+}
@@ -1,5 +1,19 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{Display, Formatter},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    str::FromStr,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use serde::Serialize;
 use tracing::{debug, instrument};
-use url::Url;
+use url::{Host, Url};
+
+use crate::{
+    clients::dns::DnsCache,
+    config::{FetchConfig, UrlFilterConfig},
+};
 
 #[instrument]
 pub fn validate_url(s: &str) -> Result<(), String> {
@@ -13,3 +27,429 @@ pub fn validate_url(s: &str) -> Result<(), String> {
         scheme => Err(format!("Invalid URL scheme: {scheme}")),
     }
 }
+
+// Rule of the `UrlFilter` a link was dropped by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UrlRule {
+    InvalidUrl,
+    Extension,
+    Length,
+    QueryParams,
+    RepeatedSegments,
+}
+
+impl UrlRule {
+    const ALL: [UrlRule; 5] = [
+        UrlRule::InvalidUrl,
+        UrlRule::Extension,
+        UrlRule::Length,
+        UrlRule::QueryParams,
+        UrlRule::RepeatedSegments,
+    ];
+}
+
+impl Display for UrlRule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            UrlRule::InvalidUrl => "invalid URL",
+            UrlRule::Extension => "non-crawlable extension",
+            UrlRule::Length => "URL too long",
+            UrlRule::QueryParams => "too many query parameters",
+            UrlRule::RepeatedSegments => "repeated path segments",
+        })
+    }
+}
+
+// Links worth following: `http`/`https` URLs that are not binary files (by extension), not longer
+// than `max_length`, without more than `max_query_params` query parameters, and not repeating a
+// path segment more than `max_repeated_segments` times (calendar and relative-link traps). Counts
+// the links dropped by every rule, reported by `GET /url-filter`.
+#[derive(Debug)]
+pub struct UrlFilter {
+    skip_extensions: HashSet<String>,
+    max_length: usize,
+    max_query_params: usize,
+    max_repeated_segments: usize,
+    dropped: [AtomicU64; UrlRule::ALL.len()],
+}
+
+impl UrlFilter {
+    pub fn from_config(config: &UrlFilterConfig) -> Self {
+        UrlFilter {
+            skip_extensions: config
+                .skip_extensions
+                .iter()
+                .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+                .collect(),
+            max_length: config.max_length,
+            max_query_params: config.max_query_params,
+            max_repeated_segments: config.max_repeated_segments,
+            dropped: Default::default(),
+        }
+    }
+
+    // Rule dropping `url`, if any, counted in the metrics.
+    pub fn check(&self, url: &Url) -> Result<(), UrlRule> {
+        self.rule(url).map_err(|rule| {
+            self.dropped[rule as usize].fetch_add(1, Ordering::Relaxed);
+            rule
+        })
+    }
+
+    fn rule(&self, url: &Url) -> Result<(), UrlRule> {
+        validate_url(url.as_str()).map_err(|_| UrlRule::InvalidUrl)?;
+        if self.max_length > 0 && url.as_str().len() > self.max_length {
+            return Err(UrlRule::Length);
+        }
+        let segments: Vec<&str> = url
+            .path_segments()
+            .map(|segments| segments.filter(|s| !s.is_empty()).collect())
+            .unwrap_or_default();
+        let extension = segments
+            .last()
+            .and_then(|last| last.rsplit_once('.'))
+            .map(|(_, ext)| ext.to_lowercase());
+        if extension.is_some_and(|ext| self.skip_extensions.contains(&ext)) {
+            return Err(UrlRule::Extension);
+        }
+        if self.max_query_params > 0 && url.query_pairs().count() > self.max_query_params {
+            return Err(UrlRule::QueryParams);
+        }
+        if self.max_repeated_segments > 0 {
+            let mut occurrences: HashMap<&str, usize> = HashMap::new();
+            for segment in &segments {
+                let count = occurrences.entry(segment).or_default();
+                *count += 1;
+                if *count > self.max_repeated_segments {
+                    return Err(UrlRule::RepeatedSegments);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Links dropped by every rule so far.
+    pub fn dropped(&self) -> HashMap<UrlRule, u64> {
+        UrlRule::ALL
+            .into_iter()
+            .map(|rule| (rule, self.dropped[rule as usize].load(Ordering::Relaxed)))
+            .collect()
+    }
+}
+
+// Whether a `Content-Type` header value is in the `allowed` media types. Parameters such as
+// `charset` are ignored, `type/*` entries match a whole type, and responses without a content
+// type are allowed.
+pub fn is_allowed_content_type(content_type: Option<&str>, allowed: &[String]) -> bool {
+    let Some(content_type) = content_type else {
+        return true;
+    };
+    let media_type = media_type(content_type);
+
+    allowed.iter().any(|entry| match entry.strip_suffix("/*") {
+        Some(prefix) => media_type
+            .split_once('/')
+            .is_some_and(|(kind, _)| kind == prefix),
+        None => *entry == media_type,
+    })
+}
+
+// Media type of a `Content-Type` header value (`text/html; charset=utf-8` is `text/html`).
+pub fn media_type(content_type: &str) -> String {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase()
+}
+
+// Error returned for URLs whose host resolves to an address the crawl may not connect to.
+#[derive(Debug)]
+pub struct AddressRejected(pub String);
+
+impl Display for AddressRejected {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for AddressRejected {}
+
+// Error returned by `validate_address`.
+#[derive(Debug)]
+pub enum AddressError {
+    // The host resolves to an address the crawl may not connect to.
+    Rejected(AddressRejected),
+    // The host did not resolve, so its addresses could not be checked.
+    Unresolved(String),
+}
+
+impl Display for AddressError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AddressError::Rejected(e) => e.fmt(f),
+            AddressError::Unresolved(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for AddressError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AddressError::Rejected(e) => Some(e),
+            AddressError::Unresolved(_) => None,
+        }
+    }
+}
+
+// Range of IP addresses in CIDR notation (`10.0.0.0/8`, `fd00::/8`); a bare address is a range of
+// one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpNetwork {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpNetwork {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                prefix_matches(&net.octets(), &ip.octets(), self.prefix)
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                prefix_matches(&net.octets(), &ip.octets(), self.prefix)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpNetwork {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix) = s.trim().split_once('/').unwrap_or((s.trim(), ""));
+        let addr: IpAddr = addr
+            .parse()
+            .map_err(|e| format!("Invalid network {s}: {e}"))?;
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            "" => max,
+            prefix => prefix
+                .parse()
+                .ok()
+                .filter(|p| *p <= max)
+                .ok_or_else(|| format!("Invalid prefix length in network {s}"))?,
+        };
+        Ok(IpNetwork { addr, prefix })
+    }
+}
+
+// Whether the first `prefix` bits of `a` and `b` are equal.
+fn prefix_matches(a: &[u8], b: &[u8], prefix: u8) -> bool {
+    let (bytes, bits) = ((prefix / 8) as usize, prefix % 8);
+    if a[..bytes] != b[..bytes] {
+        return false;
+    }
+    bits == 0 || (a[bytes] ^ b[bytes]) >> (8 - bits) == 0
+}
+
+// Which addresses the crawler may connect to. Loopback, private, link-local, shared (CGNAT),
+// multicast and unspecified addresses are refused so that links to `http://localhost:8080/admin`
+// or to a cloud metadata endpoint (`169.254.169.254`) are not fetched, unless they are in
+// `allowlist` or `allow_private` is set.
+#[derive(Debug, Clone, Default)]
+pub struct AddressPolicy {
+    pub allow_private: bool,
+    pub allowlist: Vec<IpNetwork>,
+}
+
+impl AddressPolicy {
+    pub fn from_config(config: &FetchConfig) -> Result<Self, String> {
+        Ok(AddressPolicy {
+            allow_private: config.allow_private_addresses,
+            allowlist: config
+                .address_allowlist
+                .iter()
+                .map(|network| network.parse())
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    pub fn allows(&self, ip: IpAddr) -> bool {
+        self.allow_private
+            || is_public_address(ip)
+            || self.allowlist.iter().any(|network| network.contains(ip))
+    }
+}
+
+// Whether `ip` is a globally routable address.
+pub fn is_public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_ipv4(ip),
+            None => is_public_ipv6(ip),
+        },
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    !(ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        // "This network" (0.0.0.0/8) and shared address space (100.64.0.0/10).
+        || a == 0
+        || (a == 100 && (b & 0xc0) == 64))
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    !(ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        // Unique local (fc00::/7) and link-local (fe80::/10) addresses.
+        || (first & 0xfe00) == 0xfc00
+        || (first & 0xffc0) == 0xfe80)
+}
+
+// Check that the host of `s` does not resolve to an address refused by `policy`, before fetching
+// it. Hosts are resolved through `dns` when set. Hosts that fail to resolve are refused too, since
+// their addresses cannot be checked. The clients resolve hosts again when connecting (see
+// `PolicyResolver`), so this only fails the requests early.
+#[instrument(skip(dns))]
+pub async fn validate_address(
+    s: &str,
+    policy: &AddressPolicy,
+    dns: Option<&DnsCache>,
+) -> Result<(), AddressError> {
+    if policy.allow_private {
+        return Ok(());
+    }
+    let Ok(url) = Url::parse(s) else {
+        return Ok(());
+    };
+    let ips = match url.host() {
+        Some(Host::Ipv4(ip)) => vec![IpAddr::V4(ip)],
+        Some(Host::Ipv6(ip)) => vec![IpAddr::V6(ip)],
+        Some(Host::Domain(domain)) => resolve(domain, dns)
+            .await
+            .map_err(|e| AddressError::Unresolved(format!("Failed to resolve {domain}: {e}")))?,
+        None => Vec::new(),
+    };
+    match ips.into_iter().find(|ip| !policy.allows(*ip)) {
+        Some(ip) => Err(AddressError::Rejected(AddressRejected(format!(
+            "{} resolves to non-public address {ip}",
+            url.host_str().unwrap_or_default()
+        )))),
+        None => Ok(()),
+    }
+}
+
+// Addresses of `host`, through `dns` when set, else through the system resolver.
+pub async fn resolve(
+    host: &str,
+    dns: Option<&DnsCache>,
+) -> Result<Vec<IpAddr>, Box<dyn std::error::Error + Send + Sync>> {
+    let ips: Vec<_> = match dns {
+        Some(dns) => dns.lookup(host).await?,
+        None => tokio::net::lookup_host((host, 0))
+            .await?
+            .map(|addr| addr.ip())
+            .collect(),
+    };
+    if ips.is_empty() {
+        return Err(format!("No address for {host}").into());
+    }
+    Ok(ips)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(allowlist: &[&str]) -> AddressPolicy {
+        AddressPolicy {
+            allow_private: false,
+            allowlist: allowlist.iter().map(|n| n.parse().unwrap()).collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn loopback_addresses_are_rejected() {
+        for url in [
+            "http://127.0.0.1:8080/admin",
+            "http://[::1]/",
+            "http://localhost/",
+        ] {
+            assert!(matches!(
+                validate_address(url, &policy(&[]), None).await,
+                Err(AddressError::Rejected(_))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn link_local_addresses_are_rejected() {
+        for url in [
+            "http://169.254.169.254/latest/meta-data/",
+            "http://[fe80::1]/",
+        ] {
+            assert!(matches!(
+                validate_address(url, &policy(&[]), None).await,
+                Err(AddressError::Rejected(_))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn public_addresses_are_allowed() {
+        assert!(
+            validate_address("http://93.184.216.34/", &policy(&[]), None)
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn the_allowlist_lets_private_networks_through() {
+        let policy = policy(&["10.1.0.0/16", "127.0.0.1"]);
+        assert!(validate_address("http://10.1.2.3/", &policy, None)
+            .await
+            .is_ok());
+        assert!(validate_address("http://127.0.0.1/", &policy, None)
+            .await
+            .is_ok());
+        assert!(matches!(
+            validate_address("http://10.2.0.1/", &policy, None).await,
+            Err(AddressError::Rejected(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn hosts_that_do_not_resolve_are_refused() {
+        // `.invalid` names never resolve (RFC 6761).
+        assert!(matches!(
+            validate_address("http://nonexistent.invalid/", &policy(&[]), None).await,
+            Err(AddressError::Unresolved(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn allow_private_skips_the_check() {
+        let policy = AddressPolicy {
+            allow_private: true,
+            allowlist: Vec::new(),
+        };
+        assert!(validate_address("http://127.0.0.1/", &policy, None)
+            .await
+            .is_ok());
+    }
+}
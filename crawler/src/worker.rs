@@ -0,0 +1,23 @@
+use models::WorkerIdentity;
+
+use crate::config::WorkerConfig;
+
+// Resolve the identity of this worker: the configured hostname (else the system hostname) and
+// region label.
+pub fn identity(config: &WorkerConfig) -> WorkerIdentity {
+    let hostname = config
+        .hostname
+        .clone()
+        .filter(|h| !h.trim().is_empty())
+        .unwrap_or_else(|| gethostname::gethostname().to_string_lossy().into_owned());
+    let region = config
+        .region
+        .as_ref()
+        .map(|r| r.trim().to_string())
+        .filter(|r| !r.is_empty());
+
+    WorkerIdentity {
+        hostname: hostname.trim().to_string(),
+        region,
+    }
+}
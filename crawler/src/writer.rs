@@ -0,0 +1,137 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use models::PageData;
+use tokio::{
+    fs::{File, OpenOptions},
+    io::{AsyncWriteExt, BufWriter},
+    sync::mpsc,
+    task::JoinHandle,
+};
+use tracing::{error, info};
+
+use crate::{config::FileSinkConfig, crawler::unix_timestamp};
+
+// Writes the pages published by every agent as JSON lines to a local file (the `file` sink). The
+// agents send their pages through a channel to a single task, so lines are never interleaved.
+// The file is rotated to `<path>.<unix timestamp>` once it would grow past `max_bytes` or was
+// opened `rotate_secs` ago, like the `jsonl` sink of the consumers.
+pub struct CrawlerWriter {
+    path: PathBuf,
+    max_bytes: Option<u64>,
+    rotate_every: Option<Duration>,
+    // Open file, its size and when it was opened.
+    file: Option<(BufWriter<File>, u64, Instant)>,
+}
+
+impl CrawlerWriter {
+    pub fn from_config(config: &FileSinkConfig) -> Self {
+        CrawlerWriter {
+            path: PathBuf::from(&config.path),
+            max_bytes: (config.max_bytes > 0).then_some(config.max_bytes),
+            rotate_every: (config.rotate_secs > 0).then(|| Duration::from_secs(config.rotate_secs)),
+            file: None,
+        }
+    }
+
+    // Start the writer task, buffering up to `buffer` pages. It ends, flushing the file, once
+    // every sender is dropped.
+    pub fn spawn(self, buffer: usize) -> (mpsc::Sender<PageData>, JoinHandle<()>) {
+        let (sender, receiver) = mpsc::channel(buffer.max(1));
+        info!("Writing pages to {}", self.path.display());
+        (sender, tokio::task::spawn(self.run(receiver)))
+    }
+
+    async fn run(mut self, mut receiver: mpsc::Receiver<PageData>) {
+        while let Some(page) = receiver.recv().await {
+            if let Err(e) = self.write(&page).await {
+                error!(
+                    "Failed to write {} to {}: {}",
+                    page.url,
+                    self.path.display(),
+                    e
+                );
+            }
+            // flush once the agents are not waiting for the writer
+            if receiver.is_empty() {
+                self.flush().await;
+            }
+        }
+        self.flush().await;
+    }
+
+    async fn write(&mut self, page: &PageData) -> Result<(), String> {
+        let mut line = serde_json::to_vec(page).map_err(|e| e.to_string())?;
+        line.push(b'\n');
+        let len = line.len() as u64;
+
+        if self.should_rotate(len) {
+            self.rotate().await?;
+        }
+        if self.file.is_none() {
+            self.file = Some(self.open().await?);
+        }
+        let (file, size, _) = self.file.as_mut().expect("file opened above");
+        file.write_all(&line).await.map_err(|e| e.to_string())?;
+        *size += len;
+        Ok(())
+    }
+
+    fn should_rotate(&self, len: u64) -> bool {
+        let Some((_, size, opened_at)) = &self.file else {
+            return false;
+        };
+        let full = self
+            .max_bytes
+            .is_some_and(|max| *size > 0 && size + len > max);
+        let expired = self
+            .rotate_every
+            .is_some_and(|every| *size > 0 && opened_at.elapsed() >= every);
+        full || expired
+    }
+
+    async fn open(&self) -> Result<(BufWriter<File>, u64, Instant), String> {
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(dir)
+                .await
+                .map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .map_err(|e| format!("Failed to open {}: {e}", self.path.display()))?;
+        let size = file.metadata().await.map(|m| m.len()).unwrap_or_default();
+        Ok((BufWriter::new(file), size, Instant::now()))
+    }
+
+    // Close the current file and move it aside; the next page opens a new one.
+    async fn rotate(&mut self) -> Result<(), String> {
+        self.flush().await;
+        self.file = None;
+        let base = format!("{}.{}", self.path.display(), unix_timestamp());
+        let mut rotated = PathBuf::from(&base);
+        // several rotations within a second
+        let mut n = 1;
+        while tokio::fs::try_exists(&rotated).await.unwrap_or(false) {
+            rotated = PathBuf::from(format!("{base}-{n}"));
+            n += 1;
+        }
+        tokio::fs::rename(&self.path, &rotated)
+            .await
+            .map_err(|e| format!("Failed to rotate {}: {e}", self.path.display()))?;
+        info!("Rotated {} to {}", self.path.display(), rotated.display());
+        Ok(())
+    }
+
+    async fn flush(&mut self) {
+        if let Some((file, _, _)) = &mut self.file {
+            if let Err(e) = file.flush().await {
+                error!("Failed to flush {}: {}", self.path.display(), e);
+            }
+        }
+    }
+}
@@ -0,0 +1,9 @@
+-- Key/value entries of the SqlDriver. Values are JSON documents (as in Redis), and `expires_at`
+-- is a Unix timestamp in milliseconds after which the entry is ignored and purged.
+CREATE TABLE IF NOT EXISTS cache_entries (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL,
+    expires_at BIGINT
+);
+
+CREATE INDEX IF NOT EXISTS cache_entries_expires_at ON cache_entries (expires_at);
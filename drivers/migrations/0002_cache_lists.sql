@@ -0,0 +1,8 @@
+-- Lists of the SqlDriver, one row per value: `position` orders the values of a list (oldest
+-- first). Values are JSON documents, like in `cache_entries`.
+CREATE TABLE IF NOT EXISTS cache_lists (
+    key TEXT NOT NULL,
+    position BIGINT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (key, position)
+);
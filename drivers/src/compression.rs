@@ -0,0 +1,88 @@
+use std::{
+    fmt::Display,
+    io::{Read, Write},
+    str::FromStr,
+};
+
+use flate2::{Compression as GzipLevel, read::GzDecoder, write::GzEncoder};
+use serde::Deserialize;
+
+use crate::errors::DriverError;
+
+// zstd level used for payloads: fast, with most of the gain on HTML.
+const ZSTD_LEVEL: i32 = 3;
+
+// Compression of published payloads, announced to consumers through the `content-encoding`
+// message property.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    // `content-encoding` of payloads compressed this way.
+    pub fn encoding(&self) -> Option<&'static str> {
+        match self {
+            Compression::None => None,
+            Compression::Gzip => Some("gzip"),
+            Compression::Zstd => Some("zstd"),
+        }
+    }
+
+    pub fn compress(&self, data: &[u8]) -> Result<Vec<u8>, DriverError> {
+        let compressed = match self {
+            Compression::None => Ok(data.to_vec()),
+            Compression::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), GzipLevel::default());
+                encoder.write_all(data).and_then(|_| encoder.finish())
+            }
+            Compression::Zstd => zstd::encode_all(data, ZSTD_LEVEL),
+        };
+        compressed.map_err(|e| DriverError::SerializationError(format!("Compression failed: {e}")))
+    }
+}
+
+impl FromStr for Compression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "" | "none" => Ok(Compression::None),
+            "gzip" => Ok(Compression::Gzip),
+            "zstd" => Ok(Compression::Zstd),
+            other => Err(format!(
+                "unknown compression '{other}' (expected none, gzip or zstd)"
+            )),
+        }
+    }
+}
+
+impl Display for Compression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.encoding().unwrap_or("none"))
+    }
+}
+
+// Decode a payload published with the `content-encoding` `encoding` (uncompressed when unset).
+pub fn decompress(encoding: Option<&str>, data: Vec<u8>) -> Result<Vec<u8>, DriverError> {
+    let decoded = match encoding.map(|e| e.trim().to_lowercase()).as_deref() {
+        None | Some("") | Some("identity") => return Ok(data),
+        Some("gzip") => {
+            let mut decoded = Vec::new();
+            GzDecoder::new(&data[..])
+                .read_to_end(&mut decoded)
+                .map(|_| decoded)
+        }
+        Some("zstd") => zstd::decode_all(&data[..]),
+        Some(other) => {
+            return Err(DriverError::SerializationError(format!(
+                "Unsupported content encoding '{other}'"
+            )));
+        }
+    };
+    decoded.map_err(|e| DriverError::SerializationError(format!("Decompression failed: {e}")))
+}
@@ -0,0 +1,36 @@
+// UTC calendar dates of Unix timestamps, without pulling in a date crate.
+
+// Civil date and time (UTC) of a Unix timestamp: (year, month, day, hour, minute, second).
+fn civil(secs: u64) -> (i64, u64, u64, u64, u64, u64) {
+    let days = (secs / 86400) as i64;
+    let rem = secs % 86400;
+    // Days to civil date, from Howard Hinnant's algorithm.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u64;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u64;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day, rem / 3600, rem % 3600 / 60, rem % 60)
+}
+
+// `2024-05-01T12:00:00Z`, the format of `WARC-Date`.
+pub fn iso8601(secs: u64) -> String {
+    let (year, month, day, hour, minute, second) = civil(secs);
+    format!("{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}Z")
+}
+
+// `20240501120000`, used in file names.
+pub fn compact_timestamp(secs: u64) -> String {
+    let (year, month, day, hour, minute, second) = civil(secs);
+    format!("{year:04}{month:02}{day:02}{hour:02}{minute:02}{second:02}")
+}
+
+// `2024-05-01`, the day of a Unix timestamp.
+pub fn day(secs: u64) -> String {
+    let (year, month, day, ..) = civil(secs);
+    format!("{year:04}-{month:02}-{day:02}")
+}
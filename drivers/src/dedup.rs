@@ -0,0 +1,248 @@
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+use crate::{
+    CacheDriver, MessageHandler,
+    errors::{ConsumeError, DriverError},
+    message_handler, message_id,
+};
+
+// How often a duplicate of a message being handled by another consumer checks whether it is done.
+const BUSY_POLL_INTERVAL: Duration = Duration::from_secs(1);
+// How long such a duplicate is held before it is requeued.
+const BUSY_MAX_HOLD: Duration = Duration::from_secs(30);
+
+// Entry of a message id in the seen-set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum SeenState {
+    // A consumer is handling the message since `since` (unix seconds).
+    Processing { since: u64 },
+    // The message was handled.
+    Done,
+}
+
+// Outcome of `SeenSet::claim`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Claim {
+    // First delivery of the message: the caller handles it.
+    Won,
+    // The message was already handled.
+    Done,
+    // Another consumer is handling the message.
+    Busy,
+}
+
+// Message ids handled by the consumers of a queue, kept in the crawl state store (Redis) so that
+// every consumer of the queue shares them. Under at-least-once delivery a message can be
+// delivered again after its handler completed (a consumer died before its ack reached the broker,
+// a publish was replayed after a reconnect); the seen-set lets the handler skip it.
+//
+// Ids are remembered for `ttl`. A claim that is not completed within `lease` (the consumer died
+// while handling the message) is taken over by the next delivery. Delivery is exactly-once-ish: a
+// consumer still handling its message after `lease` sees it handled a second time.
+pub struct SeenSet {
+    driver: Arc<Mutex<dyn CacheDriver<str, SeenState>>>,
+    namespace: String,
+    ttl: Duration,
+    lease: Duration,
+}
+
+impl SeenSet {
+    pub fn new(
+        driver: Arc<Mutex<dyn CacheDriver<str, SeenState>>>,
+        namespace: &str,
+        ttl: Duration,
+        lease: Duration,
+    ) -> Self {
+        SeenSet {
+            driver,
+            namespace: namespace.to_string(),
+            ttl,
+            lease,
+        }
+    }
+
+    fn key(&self, id: &str) -> String {
+        format!("seen:{}:{}", self.namespace, id)
+    }
+
+    // Mark `id` as being handled by the caller, unless it was already handled or another consumer
+    // holds an unexpired claim on it.
+    pub async fn claim(&self, id: &str) -> Result<Claim, DriverError> {
+        let key = self.key(id);
+        let now = unix_now();
+        let claim = SeenState::Processing { since: now };
+        let mut driver = self.driver.lock().await;
+        if driver.set_if_absent(&key, &claim)? {
+            driver.expire(&key, self.lease)?;
+            return Ok(Claim::Won);
+        }
+        let stale_since = match driver.get(&key) {
+            Ok(SeenState::Done) => return Ok(Claim::Done),
+            Ok(SeenState::Processing { since })
+                if now.saturating_sub(since) < self.lease.as_secs() =>
+            {
+                return Ok(Claim::Busy);
+            }
+            Ok(SeenState::Processing { since }) => since,
+            // the claim expired since `set_if_absent` (Redis reads missing keys as invalid
+            // values); connection errors fail the next call as well
+            Err(_) => {
+                if driver.set_if_absent(&key, &claim)? {
+                    driver.expire(&key, self.lease)?;
+                    return Ok(Claim::Won);
+                }
+                return Ok(Claim::Busy);
+            }
+        };
+
+        // Consumers of other processes may find the same stale claim: only the first one to
+        // record its takeover (keyed by the start of the stale claim) handles the message.
+        let takeover = format!("{key}:takeover:{stale_since}");
+        if !driver.set_if_absent(&takeover, &claim)? {
+            return Ok(Claim::Busy);
+        }
+        driver.expire(&takeover, self.lease)?;
+        driver.set_with_ttl(&key, &claim, self.lease)?;
+        Ok(Claim::Won)
+    }
+
+    // Remember `id` as handled.
+    pub async fn complete(&self, id: &str) -> Result<(), DriverError> {
+        let key = self.key(id);
+        self.driver
+            .lock()
+            .await
+            .set_with_ttl(&key, &SeenState::Done, self.ttl)
+    }
+
+    // Drop the claim on `id`, so that its next delivery is handled again.
+    pub async fn release(&self, id: &str) -> Result<(), DriverError> {
+        let key = self.key(id);
+        self.driver.lock().await.remove(&key)
+    }
+}
+
+// Wrap `handler` so that each message id is handled once: duplicates of a handled message are
+// acknowledged without calling `handler`. Duplicates of a message being handled by another
+// consumer are held until it is done (then acknowledged), its claim is released or goes stale
+// (then handled), or for `BUSY_MAX_HOLD` at most (then requeued, retryable), so that they are not
+// redelivered in a tight loop while the other consumer works. A failed message is released so that its redelivery (or a
+// replay from the dead-letter queue) is handled again. Messages without an id, and every message
+// while the seen-set is unavailable, are handled as usual.
+pub fn deduplicated(seen: Arc<SeenSet>, handler: MessageHandler) -> MessageHandler {
+    message_handler(move |data| {
+        let seen = seen.clone();
+        let handler = handler.clone();
+        async move {
+            let Some(id) = message_id() else {
+                return handler(data).await;
+            };
+            let mut held = Duration::ZERO;
+            loop {
+                match seen.claim(&id).await {
+                    Ok(Claim::Won) => break,
+                    Ok(Claim::Done) => {
+                        debug!("Skipping duplicate message {}", id);
+                        return Ok(());
+                    }
+                    Ok(Claim::Busy) if held < BUSY_MAX_HOLD => {
+                        tokio::time::sleep(BUSY_POLL_INTERVAL).await;
+                        held += BUSY_POLL_INTERVAL;
+                    }
+                    Ok(Claim::Busy) => {
+                        return Err(ConsumeError::Retryable(format!(
+                            "Message {id} is being handled by another consumer"
+                        )));
+                    }
+                    Err(e) => {
+                        warn!("Seen-set unavailable, handling {} anyway: {}", id, e);
+                        return handler(data).await;
+                    }
+                }
+            }
+
+            let outcome = handler(data).await;
+            let recorded = match outcome {
+                Ok(()) => seen.complete(&id).await,
+                Err(_) => seen.release(&id).await,
+            };
+            if let Err(e) = recorded {
+                warn!("Failed to update the seen-set for {}: {}", id, e);
+            }
+            outcome
+        }
+    })
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::memory::MemoryDriver;
+
+    use super::*;
+
+    const TTL: Duration = Duration::from_secs(3600);
+    const LEASE: Duration = Duration::from_secs(300);
+
+    fn seen_set(driver: &MemoryDriver) -> SeenSet {
+        SeenSet::new(Arc::new(Mutex::new(driver.clone())), "pages", TTL, LEASE)
+    }
+
+    #[tokio::test]
+    async fn claims_a_message_once() {
+        let seen = seen_set(&MemoryDriver::new());
+
+        assert_eq!(seen.claim("a").await.unwrap(), Claim::Won);
+        assert_eq!(seen.claim("a").await.unwrap(), Claim::Busy);
+        assert_eq!(seen.claim("b").await.unwrap(), Claim::Won);
+        seen.complete("a").await.unwrap();
+        assert_eq!(seen.claim("a").await.unwrap(), Claim::Done);
+    }
+
+    #[tokio::test]
+    async fn released_messages_are_claimed_again() {
+        let seen = seen_set(&MemoryDriver::new());
+
+        assert_eq!(seen.claim("a").await.unwrap(), Claim::Won);
+        seen.release("a").await.unwrap();
+        assert_eq!(seen.claim("a").await.unwrap(), Claim::Won);
+    }
+
+    #[tokio::test]
+    async fn stale_claims_are_taken_over_once() {
+        let mut driver = MemoryDriver::new();
+        let seen = seen_set(&driver);
+        // a consumer died while handling the message, long ago
+        driver
+            .set("seen:pages:a", &SeenState::Processing { since: 0 })
+            .unwrap();
+
+        assert_eq!(seen.claim("a").await.unwrap(), Claim::Won);
+        assert_eq!(seen.claim("a").await.unwrap(), Claim::Busy);
+        // another consumer that read the same stale claim loses the takeover
+        assert!(
+            !driver
+                .set_if_absent(
+                    "seen:pages:a:takeover:0",
+                    &SeenState::Processing { since: 1 }
+                )
+                .unwrap()
+        );
+        seen.complete("a").await.unwrap();
+        assert_eq!(seen.claim("a").await.unwrap(), Claim::Done);
+    }
+}
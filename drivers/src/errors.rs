@@ -7,6 +7,58 @@ pub enum DriverError {
     AlreadyExists(String),
     InvalidInput(String),
     InternalError(String),
+    // Message bus errors
+    PublishError(String),
+    ConsumeError(String),
+    SerializationError(String),
+    ConnectionLost(String),
+}
+
+impl DriverError {
+    // Whether retrying the operation (possibly after reconnecting) may succeed.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            DriverError::ConnectionError(_)
+                | DriverError::ConnectionLost(_)
+                | DriverError::PublishError(_)
+        )
+    }
+}
+
+// Error of a message handler, telling the driver what to do with the message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConsumeError {
+    // The message may be handled later (e.g. a sink is down): it is requeued.
+    Retryable(String),
+    // The message can never be handled (e.g. it does not deserialize): it is dead-lettered.
+    Fatal(String),
+}
+
+impl ConsumeError {
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, ConsumeError::Retryable(_))
+    }
+}
+
+impl Display for ConsumeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConsumeError::Retryable(msg) => write!(f, "Retryable error: {}", msg),
+            ConsumeError::Fatal(msg) => write!(f, "Fatal error: {}", msg),
+        }
+    }
+}
+
+// Transient driver errors (see `DriverError::is_transient`) are worth retrying.
+impl From<DriverError> for ConsumeError {
+    fn from(err: DriverError) -> Self {
+        if err.is_transient() {
+            ConsumeError::Retryable(err.to_string())
+        } else {
+            ConsumeError::Fatal(err.to_string())
+        }
+    }
 }
 
 // DriverError support for Redis driver
@@ -36,6 +88,10 @@ impl Display for DriverError {
             DriverError::AlreadyExists(msg) => write!(f, "Already exists: {}", msg),
             DriverError::InvalidInput(msg) => write!(f, "Invalid input: {}", msg),
             DriverError::InternalError(msg) => write!(f, "Internal error: {}", msg),
+            DriverError::PublishError(msg) => write!(f, "Publish error: {}", msg),
+            DriverError::ConsumeError(msg) => write!(f, "Consume error: {}", msg),
+            DriverError::SerializationError(msg) => write!(f, "Serialization error: {}", msg),
+            DriverError::ConnectionLost(msg) => write!(f, "Connection lost: {}", msg),
         }
     }
 }
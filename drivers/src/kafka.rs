@@ -0,0 +1,347 @@
+use async_trait::async_trait;
+use futures_lite::future;
+use rdkafka::ClientConfig;
+use rdkafka::consumer::{CommitMode, Consumer, StreamConsumer};
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+use rdkafka::message::{Header, Headers, Message, OwnedHeaders};
+use rdkafka::producer::{FutureProducer, FutureRecord, Producer};
+use rdkafka::util::Timeout;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::{Instrument, Level, debug, error, info, instrument, span, trace, warn};
+
+use crate::config::KafkaConfig;
+use crate::errors::{ConsumeError, DriverError};
+use crate::{EnqueueOptions, MESSAGE_ID_HEADER, MessageHandler, QueueDriver, with_message_headers};
+
+// How long `close` waits for in-flight messages to be delivered.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(10);
+// Attempts at handling a message failing with retryable errors, and the delay between them.
+const RETRY_ATTEMPTS: u32 = 3;
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+pub struct KafkaDriver {
+    producer: FutureProducer,
+    brokers: String,
+    topic: String,
+    group_id: String,
+    // Set by `stop_consuming`.
+    stop: watch::Sender<bool>,
+}
+
+impl KafkaDriver {
+    /// Build from environment. Defaults: brokers=127.0.0.1:9092, topic=default_queue, group=crawler-generic
+    pub fn new() -> Result<Self, DriverError> {
+        Self::with_topic(None)
+    }
+
+    /// Build from environment, using `topic` instead of KAFKA_TOPIC when given
+    pub fn with_topic(topic: Option<&str>) -> Result<Self, DriverError> {
+        let mut config = KafkaConfig::from_env()?;
+        if let Some(topic) = topic {
+            config.topic = topic.to_string();
+        }
+        Self::with_config(&config)
+    }
+
+    /// Build from explicit settings
+    #[instrument(
+        name = "Kafka Setup",
+        level = "info",
+        skip_all,
+        fields(kafka.brokers, kafka.topic, kafka.group_id)
+    )]
+    pub fn with_config(config: &KafkaConfig) -> Result<Self, DriverError> {
+        let brokers = config.brokers.clone();
+        let topic = config.topic.clone();
+        let group_id = config.effective_group_id();
+
+        // enrich span
+        let span = tracing::Span::current();
+        span.record("kafka.brokers", &brokers);
+        span.record("kafka.topic", &topic);
+        span.record("kafka.group_id", &group_id);
+
+        info!("Creating Kafka producer for {}", brokers);
+        let producer: FutureProducer = ClientConfig::new()
+            .set("bootstrap.servers", &brokers)
+            .set("acks", "all")
+            .set("message.timeout.ms", "30000")
+            .create()
+            .map_err(|e| {
+                error!("Producer creation failed: {}", e);
+                DriverError::ConnectionError(format!(
+                    "Failed to create Kafka producer for {brokers}: {e}"
+                ))
+            })?;
+        info!("Producer created");
+
+        Ok(KafkaDriver {
+            producer,
+            brokers,
+            topic,
+            group_id,
+            stop: watch::Sender::new(false),
+        })
+    }
+
+    // Hand a message to the producer queue without waiting for the broker acknowledgement.
+    fn send(
+        &self,
+        payload: &[u8],
+        options: &EnqueueOptions,
+    ) -> Result<rdkafka::producer::DeliveryFuture, DriverError> {
+        let mut record = FutureRecord::<(), [u8]>::to(&self.topic).payload(payload);
+        if let Some(headers) = headers(options) {
+            record = record.headers(headers);
+        }
+        self.producer.send_result(record).map_err(|(e, _)| {
+            error!("Publish send failed: {}", e);
+            classify(e, DriverError::PublishError, "Publish send failed")
+        })
+    }
+
+    // Wait for the broker to acknowledge a message handed to `send`.
+    async fn confirm(delivery: rdkafka::producer::DeliveryFuture) -> Result<(), DriverError> {
+        match delivery.await {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err((e, _))) => {
+                error!("Publish confirm failed: {}", e);
+                Err(classify(
+                    e,
+                    DriverError::PublishError,
+                    "Publish confirm failed",
+                ))
+            }
+            Err(_) => {
+                error!("Publish confirm failed: delivery canceled");
+                Err(DriverError::PublishError(
+                    "Publish confirm failed: delivery canceled".to_string(),
+                ))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl QueueDriver for KafkaDriver {
+    async fn publish(&self, payload: &[u8]) -> Result<(), DriverError> {
+        self.publish_with(payload, &EnqueueOptions::default()).await
+    }
+
+    #[instrument(
+        name = "Enqueue Message",
+        level = "info",
+        skip(self, payload, options),
+        fields(kafka.topic = %self.topic, msg.size = payload.len())
+    )]
+    async fn publish_with(
+        &self,
+        payload: &[u8],
+        options: &EnqueueOptions,
+    ) -> Result<(), DriverError> {
+        Self::confirm(self.send(payload, options)?).await?;
+
+        debug!("Message published to {}", self.topic);
+        Ok(())
+    }
+
+    #[instrument(
+        name = "Enqueue Batch",
+        level = "info",
+        skip(self, payloads),
+        fields(kafka.topic = %self.topic, batch.size = payloads.len())
+    )]
+    async fn publish_batch(&self, payloads: &[Vec<u8>]) -> Result<(), DriverError> {
+        // queue everything first so librdkafka can batch the requests, then wait for delivery
+        let deliveries = payloads
+            .iter()
+            .map(|payload| self.send(payload, &EnqueueOptions::default()))
+            .collect::<Result<Vec<_>, _>>()?;
+        for delivery in deliveries {
+            Self::confirm(delivery).await?;
+        }
+
+        debug!("{} messages published to {}", payloads.len(), self.topic);
+        Ok(())
+    }
+
+    #[instrument(name = "Close Connection", level = "info", skip(self))]
+    async fn close(&self) -> Result<(), DriverError> {
+        info!("Flushing producer");
+        self.producer
+            .flush(Timeout::After(FLUSH_TIMEOUT))
+            .map_err(|e| {
+                error!("Producer flush failed: {}", e);
+                classify(e, DriverError::PublishError, "Producer flush failed")
+            })?;
+        info!("Closed");
+        Ok(())
+    }
+
+    #[instrument(
+        name = "Consume Messages",
+        level = "info",
+        skip(self, on_message),
+        fields(kafka.topic = %self.topic, kafka.group_id = %self.group_id)
+    )]
+    async fn consume(&self, on_message: MessageHandler) -> Result<(), DriverError> {
+        info!("Starting consumer");
+        // offsets are committed manually once the handler has processed a message
+        let consumer: StreamConsumer = ClientConfig::new()
+            .set("bootstrap.servers", &self.brokers)
+            .set("group.id", &self.group_id)
+            .set("enable.auto.commit", "false")
+            .set("auto.offset.reset", "earliest")
+            .create()
+            .map_err(|e| {
+                error!("Failed to create consumer: {}", e);
+                DriverError::ConnectionError(format!("Failed to create consumer: {e}"))
+            })?;
+        consumer.subscribe(&[&self.topic]).map_err(|e| {
+            error!("Failed to subscribe to '{}': {}", self.topic, e);
+            classify(
+                e,
+                DriverError::ConsumeError,
+                &format!("Failed to subscribe to '{}'", self.topic),
+            )
+        })?;
+
+        info!("Consumer started, waiting for messages...");
+        let mut stop = self.stop.subscribe();
+        loop {
+            // messages are handled one at a time: once stopped, the last one is already committed
+            let received = future::or(
+                async {
+                    let _ = stop.wait_for(|stop| *stop).await;
+                    None
+                },
+                async { Some(consumer.recv().await) },
+            )
+            .await;
+            let Some(received) = received else {
+                info!("Consumer stopped");
+                if let Err(e) = consumer.commit_consumer_state(CommitMode::Sync) {
+                    warn!("Failed to commit offsets: {}", e);
+                }
+                return Ok(());
+            };
+            let message = received.map_err(|e| {
+                error!("Consumer yielded error: {}", e);
+                classify(e, DriverError::ConsumeError, "Consumer yielded error")
+            })?;
+
+            let partition = message.partition();
+            let offset = message.offset();
+            let data = message.payload().unwrap_or_default();
+            let headers: BTreeMap<String, String> = message
+                .headers()
+                .map(|headers| {
+                    headers
+                        .iter()
+                        .filter_map(|header| {
+                            let value = std::str::from_utf8(header.value?).ok()?;
+                            Some((header.key.to_string(), value.to_string()))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            let msg_span = span!(
+                Level::DEBUG,
+                "Handle Delivery",
+                kafka.partition = partition,
+                kafka.offset = offset,
+                size = data.len()
+            );
+
+            // Messages are handled one at a time, so that offsets are committed in order. Kafka
+            // has no per-message rejection: retryable errors are retried in place a few times,
+            // then the message is logged and skipped like after a fatal error, so the offset is
+            // committed either way to avoid redelivering it forever.
+            let handled = async {
+                debug!("Received message");
+                trace!("Payload size: {} bytes", data.len());
+                let mut attempt = 1;
+                loop {
+                    match with_message_headers(headers.clone(), on_message(data.to_vec())).await {
+                        Ok(()) => break,
+                        Err(ConsumeError::Retryable(e)) if attempt < RETRY_ATTEMPTS => {
+                            warn!(
+                                "Handler error at {}/{}: {} (retrying in {:?})",
+                                partition, offset, e, RETRY_DELAY
+                            );
+                            tokio::time::sleep(RETRY_DELAY).await;
+                            attempt += 1;
+                        }
+                        Err(handler_err) => {
+                            warn!(
+                                "Handler error at {}/{}: {} (skipping)",
+                                partition, offset, handler_err
+                            );
+                            break;
+                        }
+                    }
+                }
+            };
+            handled.instrument(msg_span.clone()).await;
+            msg_span.in_scope(|| {
+                consumer
+                    .commit_message(&message, CommitMode::Async)
+                    .map_err(|e| {
+                        error!("Commit failed at {}/{}: {}", partition, offset, e);
+                        classify(e, DriverError::ConsumeError, "Commit failed")
+                    })
+            })?;
+            debug!("Committed offset {}/{}", partition, offset);
+        }
+    }
+}
+
+// Record headers of a message published with `options`. Kafka has no message priority or
+// expiration, so those are ignored; the content type, correlation id and message id are sent as
+// headers.
+fn headers(options: &EnqueueOptions) -> Option<OwnedHeaders> {
+    let properties = [
+        ("content-type", options.content_type.as_deref()),
+        ("correlation-id", options.correlation_id.as_deref()),
+        (MESSAGE_ID_HEADER, options.message_id.as_deref()),
+    ];
+    let headers: Vec<(&str, &str)> = options
+        .headers
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .chain(
+            properties
+                .into_iter()
+                .filter_map(|(name, value)| Some((name, value?))),
+        )
+        .collect();
+    if headers.is_empty() {
+        return None;
+    }
+    Some(headers.into_iter().fold(
+        OwnedHeaders::new_with_capacity(options.headers.len() + 3),
+        |acc, (key, value)| {
+            acc.insert(Header {
+                key,
+                value: Some(value),
+            })
+        },
+    ))
+}
+
+// Map a Kafka error to a DriverError: unreachable brokers become `ConnectionLost` so callers can
+// retry later, anything else is wrapped with `variant`.
+fn classify(err: KafkaError, variant: fn(String) -> DriverError, context: &str) -> DriverError {
+    let message = format!("{context}: {err}");
+    match err.rdkafka_error_code() {
+        Some(
+            RDKafkaErrorCode::AllBrokersDown
+            | RDKafkaErrorCode::BrokerTransportFailure
+            | RDKafkaErrorCode::NetworkException,
+        ) => DriverError::ConnectionLost(message),
+        _ => variant(message),
+    }
+}
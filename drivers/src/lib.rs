@@ -1,10 +1,41 @@
+use std::{
+    collections::{BTreeMap, hash_map::RandomState},
+    future::Future,
+    hash::BuildHasher,
+    pin::Pin,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
 use serde::{Serialize, de::DeserializeOwned};
+use tracing::error;
 
-use crate::errors::DriverError;
+use crate::{
+    config::{BusConfig, RedisConfig},
+    errors::{ConsumeError, DriverError},
+    memory::MemoryDriver,
+    redis::{MemoryUsage, RedisDriver},
+};
 
+pub mod compression;
+pub mod config;
+pub mod dates;
+pub mod dedup;
 pub mod errors;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+pub mod memory;
+pub mod qdrant;
 pub mod rabbit;
 pub mod redis;
+#[cfg(feature = "sql")]
+pub mod sql;
+pub mod telemetry;
+pub mod warc;
 
 // General trait definitions
 // Here we define the common internface for different kinds of drivers.
@@ -15,7 +46,416 @@ where
     V: Serialize + DeserializeOwned + Send + Sync + 'static,
 {
     fn set(&mut self, key: &K, value: &V) -> Result<(), DriverError>;
+    // Atomically store `value` at `key` unless the key already exists. Returns whether the value
+    // was stored, so that exactly one of several concurrent callers wins the key.
+    fn set_if_absent(&mut self, key: &K, value: &V) -> Result<bool, DriverError>;
+    // Store `value` at `key`, removed by the driver once `ttl` elapsed.
+    fn set_with_ttl(&mut self, key: &K, value: &V, ttl: Duration) -> Result<(), DriverError>;
+    // Remove `key` once `ttl` elapsed from now. Returns whether the key exists.
+    fn expire(&mut self, key: &K, ttl: Duration) -> Result<bool, DriverError>;
     fn get(&mut self, key: &K) -> Result<V, DriverError>;
     fn remove(&mut self, key: &K) -> Result<(), DriverError>;
     fn exists(&mut self, key: &K) -> Result<bool, DriverError>;
+    // Atomically add `delta` to the integer stored at `key` (missing keys count as 0) and return
+    // the updated value.
+    fn increment(&mut self, key: &K, delta: i64) -> Result<i64, DriverError>;
+    // List all keys matching a glob-style `pattern` (e.g. `https://example.com/*`).
+    fn keys(&mut self, pattern: &K) -> Result<Vec<String>, DriverError>;
+    // Atomically append `value` to the list stored at `key`, keeping only its last `cap` values
+    // (0 = unlimited).
+    fn push_capped(&mut self, key: &K, value: &V, cap: usize) -> Result<(), DriverError>;
+    // Values of the list stored at `key` from `start` to `stop` (inclusive, oldest first);
+    // negative indexes count from the end of the list, like LRANGE. Missing keys are empty lists.
+    fn list_range(&mut self, key: &K, start: i64, stop: i64) -> Result<Vec<V>, DriverError>;
+}
+
+// Future returned by a `MessageHandler`.
+pub type HandlerFuture = Pin<Box<dyn Future<Output = Result<(), ConsumeError>> + Send>>;
+
+// Async callback invoked for every consumed message. Resolving to Ok(()) acknowledges (or
+// commits) the message; a `ConsumeError::Retryable` requeues it and a `ConsumeError::Fatal`
+// dead-letters it (see each driver for how). Drivers may run several handlers at the same time,
+// so messages can complete out of order.
+pub type MessageHandler = Arc<dyn Fn(Vec<u8>) -> HandlerFuture + Send + Sync>;
+
+tokio::task_local! {
+    static MESSAGE_HEADERS: BTreeMap<String, String>;
+}
+
+// Headers of the message being handled, when called from a `MessageHandler` (empty elsewhere, and
+// for drivers without message headers). Lets handlers read routing or tracing headers while the
+// handler itself only receives the payload.
+pub fn message_headers() -> BTreeMap<String, String> {
+    MESSAGE_HEADERS.try_with(Clone::clone).unwrap_or_default()
+}
+
+// Id of the message being handled (`EnqueueOptions::message_id`), when it was published with one.
+pub fn message_id() -> Option<String> {
+    MESSAGE_HEADERS
+        .try_with(|headers| headers.get(MESSAGE_ID_HEADER).cloned())
+        .ok()
+        .flatten()
+}
+
+// Header under which `message_headers` returns the message id. Kafka sends the id as a record
+// header of that name, RabbitMQ as the `message_id` property.
+pub const MESSAGE_ID_HEADER: &str = "message-id";
+
+// Random (version 4) UUID, e.g. `0b6f3c8e-5a1d-4c2e-9f7a-2d4b8e1c6a30`.
+pub fn random_uuid() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+    // RandomState is seeded randomly, so hashing a counter gives unpredictable bits.
+    let high = RandomState::new().hash_one(n);
+    let low = RandomState::new().hash_one(n);
+    let high = (high & 0xffff_ffff_ffff_0fff) | 0x0000_0000_0000_4000;
+    let low = (low & 0x3fff_ffff_ffff_ffff) | 0x8000_0000_0000_0000;
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        high >> 32,
+        (high >> 16) & 0xffff,
+        high & 0xffff,
+        low >> 48,
+        low & 0xffff_ffff_ffff
+    )
+}
+
+// Run `handled` (a handler call) with `headers` returned by `message_headers`.
+pub(crate) async fn with_message_headers<F: Future>(
+    headers: BTreeMap<String, String>,
+    handled: F,
+) -> F::Output {
+    MESSAGE_HEADERS.scope(headers, handled).await
+}
+
+// Wrap an async closure into a `MessageHandler`.
+pub fn message_handler<F, Fut>(on_message: F) -> MessageHandler
+where
+    F: Fn(Vec<u8>) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<(), ConsumeError>> + Send + 'static,
+{
+    Arc::new(move |data| Box::pin(on_message(data)))
+}
+
+// Per-message properties of a publish. Drivers map them onto what their broker supports:
+// RabbitMQ uses the AMQP properties (priority only applies to queues declared with
+// `x-max-priority`, the routing key only to publishes through an exchange), Kafka sends them as
+// record headers and has no priority, expiration or routing key.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnqueueOptions {
+    // Application headers (e.g. routing or encoding flags).
+    pub headers: BTreeMap<String, String>,
+    pub priority: Option<u8>,
+    // Time after which the broker may drop the message if it was not consumed.
+    pub expiration: Option<Duration>,
+    // Media type of the payload (e.g. `application/json`).
+    pub content_type: Option<String>,
+    // Identifier correlating the message with related messages or traces.
+    pub correlation_id: Option<String>,
+    // Unique id of the message (e.g. `random_uuid`), kept by redeliveries and replays of
+    // unconfirmed publishes so that consumers can drop duplicates (see `dedup`).
+    pub message_id: Option<String>,
+    // Topic routing key (e.g. `page.html.en.news`), deciding which queues bound to the exchange
+    // receive the message.
+    pub routing_key: Option<String>,
+}
+
+impl EnqueueOptions {
+    pub fn header(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.headers.insert(name.to_string(), value.into());
+        self
+    }
+}
+
+// B. QueueDriver trait defines the interface for message bus drivers. Payloads are raw bytes so
+// that drivers can be used as `Arc<dyn QueueDriver>`; see `enqueue` and `consume_json` below for
+// the typed JSON helpers.
+#[async_trait]
+pub trait QueueDriver: Send + Sync {
+    async fn publish(&self, payload: &[u8]) -> Result<(), DriverError>;
+    // Publish with per-message properties. Drivers without message properties ignore them.
+    async fn publish_with(
+        &self,
+        payload: &[u8],
+        options: &EnqueueOptions,
+    ) -> Result<(), DriverError> {
+        let _ = options;
+        self.publish(payload).await
+    }
+    async fn publish_batch(&self, payloads: &[Vec<u8>]) -> Result<(), DriverError> {
+        for payload in payloads {
+            self.publish(payload).await?;
+        }
+        Ok(())
+    }
+    async fn consume(&self, on_message: MessageHandler) -> Result<(), DriverError>;
+    // Make a running `consume` stop taking messages and return once the handlers in flight are
+    // done (their messages settled as usual). Messages fetched ahead but not handled are
+    // redelivered by the broker.
+    async fn stop_consuming(&self) -> Result<(), DriverError> {
+        Ok(())
+    }
+    // Messages waiting in the queue, when the broker reports it (`None` otherwise).
+    async fn backlog(&self) -> Result<Option<u64>, DriverError> {
+        Ok(None)
+    }
+    async fn close(&self) -> Result<(), DriverError>;
+}
+
+impl dyn QueueDriver {
+    // Serialize `payload` as JSON and publish it with `options` (the content type defaults to
+    // `application/json`).
+    pub async fn enqueue<T: Serialize + ?Sized>(
+        &self,
+        payload: &T,
+        options: &EnqueueOptions,
+    ) -> Result<(), DriverError> {
+        let data = serde_json::to_vec(payload).map_err(|e| {
+            error!("Serialization failed: {}", e);
+            DriverError::SerializationError(format!("Failed to serialize payload: {e}"))
+        })?;
+        if options.content_type.is_some() {
+            return self.publish_with(&data, options).await;
+        }
+        let options = EnqueueOptions {
+            content_type: Some("application/json".to_string()),
+            ..options.clone()
+        };
+        self.publish_with(&data, &options).await
+    }
+
+    // Consume messages, deserializing each JSON payload into `V` before handing it to
+    // `on_message`. Messages that fail to deserialize are dead-lettered as fatal errors, and the
+    // consumer carries on with the next message.
+    pub async fn consume_json<F, Fut, V>(&self, on_message: F) -> Result<(), DriverError>
+    where
+        F: Fn(V) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), ConsumeError>> + Send + 'static,
+        V: DeserializeOwned + 'static,
+    {
+        let on_message = Arc::new(on_message);
+        self.consume(message_handler(move |data| {
+            let on_message = on_message.clone();
+            async move {
+                let value = serde_json::from_slice::<V>(&data).map_err(|e| {
+                    ConsumeError::Fatal(format!("Failed to deserialize message: {e}"))
+                })?;
+                on_message(value).await
+            }
+        }))
+        .await
+    }
+}
+
+// A point of a vector store: an embedding with the metadata it was computed from.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VectorPoint {
+    // Stable identifier, so that upserting the same point again replaces it.
+    pub id: u64,
+    pub vector: Vec<f32>,
+    pub payload: serde_json::Map<String, serde_json::Value>,
+}
+
+// C. VectorStoreDriver trait defines the interface for vector databases (e.g. embeddings of the
+// crawled pages).
+#[async_trait]
+pub trait VectorStoreDriver: Send + Sync {
+    // Insert the points, replacing points with the same id.
+    async fn upsert(&self, points: &[VectorPoint]) -> Result<(), DriverError>;
+    // Remove every point whose payload `field` equals `value`.
+    async fn delete_matching(&self, field: &str, value: &str) -> Result<(), DriverError>;
+}
+
+// Connect to the message bus selected by `MESSAGE_BUS` (`rabbitmq` or `kafka`, default
+// `rabbitmq`).
+pub async fn connect_queue_driver() -> Result<Arc<dyn QueueDriver>, DriverError> {
+    connect_queue_driver_to(None).await
+}
+
+// Like `connect_queue_driver`, but publishing to / consuming from `queue` (the RabbitMQ queue or
+// Kafka topic) instead of the configured one when given.
+pub async fn connect_queue_driver_to(
+    queue: Option<&str>,
+) -> Result<Arc<dyn QueueDriver>, DriverError> {
+    let mut config = BusConfig::from_env()?;
+    if let Some(queue) = queue {
+        config.rabbit.queue = queue.to_string();
+        config.kafka.topic = queue.to_string();
+    }
+    connect_queue_driver_with(&config).await
+}
+
+// Connect to the message bus selected by `config.kind`.
+pub async fn connect_queue_driver_with(
+    config: &BusConfig,
+) -> Result<Arc<dyn QueueDriver>, DriverError> {
+    match config.kind.trim().to_lowercase().as_str() {
+        "rabbitmq" | "rabbit" => Ok(Arc::new(
+            rabbit::RabbitDriver::with_config(&config.rabbit).await?,
+        )),
+        #[cfg(feature = "kafka")]
+        "kafka" => Ok(Arc::new(kafka::KafkaDriver::with_config(&config.kafka)?)),
+        #[cfg(not(feature = "kafka"))]
+        "kafka" => Err(DriverError::InvalidInput(
+            "MESSAGE_BUS=kafka requires building with the `kafka` feature".to_string(),
+        )),
+        other => Err(DriverError::InvalidInput(format!(
+            "Unknown MESSAGE_BUS '{other}' (expected 'rabbitmq' or 'kafka')"
+        ))),
+    }
+}
+
+// Cache driver selected by `RedisConfig::backend`, so that callers can hold a single concrete type
+// whichever backend the process was started with.
+pub enum CacheStore {
+    Redis(RedisDriver),
+    Memory(MemoryDriver),
+    #[cfg(feature = "sql")]
+    Sql(sql::SqlDriver),
+}
+
+impl CacheStore {
+    // Memory usage of the Redis server; the in-memory backend has no limit to report.
+    pub fn memory_usage(&mut self) -> Result<MemoryUsage, DriverError> {
+        match self {
+            CacheStore::Redis(driver) => driver.memory_usage(),
+            _ => Err(DriverError::InvalidInput(
+                "memory usage is only reported by Redis".to_string(),
+            )),
+        }
+    }
+
+    pub fn is_memory(&self) -> bool {
+        matches!(self, CacheStore::Memory(_))
+    }
+
+    pub fn is_redis(&self) -> bool {
+        matches!(self, CacheStore::Redis(_))
+    }
+}
+
+// Build the cache driver selected by `config.backend` (`redis`, `sql` or `memory`).
+pub fn connect_cache_driver_with(config: &RedisConfig) -> Result<CacheStore, DriverError> {
+    match config.backend.trim().to_lowercase().as_str() {
+        "redis" => Ok(CacheStore::Redis(
+            RedisDriver::with_config(config).map_err(DriverError::ConnectionError)?,
+        )),
+        "memory" => Ok(CacheStore::Memory(MemoryDriver::new())),
+        #[cfg(feature = "sql")]
+        "sql" => Ok(CacheStore::Sql(sql::SqlDriver::with_config(&config.sql)?)),
+        #[cfg(not(feature = "sql"))]
+        "sql" => Err(DriverError::InvalidInput(
+            "CACHE_BACKEND=sql requires building with the `sql` feature".to_string(),
+        )),
+        other => Err(DriverError::InvalidInput(format!(
+            "Unknown CACHE_BACKEND '{other}' (expected 'redis', 'sql' or 'memory')"
+        ))),
+    }
+}
+
+impl<K, V> CacheDriver<K, V> for CacheStore
+where
+    K: AsRef<str> + ?Sized,
+    V: Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    fn set(&mut self, key: &K, value: &V) -> Result<(), DriverError> {
+        match self {
+            CacheStore::Redis(driver) => CacheDriver::<K, V>::set(driver, key, value),
+            CacheStore::Memory(driver) => CacheDriver::<K, V>::set(driver, key, value),
+            #[cfg(feature = "sql")]
+            CacheStore::Sql(driver) => CacheDriver::<K, V>::set(driver, key, value),
+        }
+    }
+
+    fn set_if_absent(&mut self, key: &K, value: &V) -> Result<bool, DriverError> {
+        match self {
+            CacheStore::Redis(driver) => CacheDriver::<K, V>::set_if_absent(driver, key, value),
+            CacheStore::Memory(driver) => CacheDriver::<K, V>::set_if_absent(driver, key, value),
+            #[cfg(feature = "sql")]
+            CacheStore::Sql(driver) => CacheDriver::<K, V>::set_if_absent(driver, key, value),
+        }
+    }
+
+    fn set_with_ttl(&mut self, key: &K, value: &V, ttl: Duration) -> Result<(), DriverError> {
+        match self {
+            CacheStore::Redis(driver) => CacheDriver::<K, V>::set_with_ttl(driver, key, value, ttl),
+            CacheStore::Memory(driver) => {
+                CacheDriver::<K, V>::set_with_ttl(driver, key, value, ttl)
+            }
+            #[cfg(feature = "sql")]
+            CacheStore::Sql(driver) => CacheDriver::<K, V>::set_with_ttl(driver, key, value, ttl),
+        }
+    }
+
+    fn expire(&mut self, key: &K, ttl: Duration) -> Result<bool, DriverError> {
+        match self {
+            CacheStore::Redis(driver) => CacheDriver::<K, V>::expire(driver, key, ttl),
+            CacheStore::Memory(driver) => CacheDriver::<K, V>::expire(driver, key, ttl),
+            #[cfg(feature = "sql")]
+            CacheStore::Sql(driver) => CacheDriver::<K, V>::expire(driver, key, ttl),
+        }
+    }
+
+    fn get(&mut self, key: &K) -> Result<V, DriverError> {
+        match self {
+            CacheStore::Redis(driver) => CacheDriver::<K, V>::get(driver, key),
+            CacheStore::Memory(driver) => CacheDriver::<K, V>::get(driver, key),
+            #[cfg(feature = "sql")]
+            CacheStore::Sql(driver) => CacheDriver::<K, V>::get(driver, key),
+        }
+    }
+
+    fn remove(&mut self, key: &K) -> Result<(), DriverError> {
+        match self {
+            CacheStore::Redis(driver) => CacheDriver::<K, V>::remove(driver, key),
+            CacheStore::Memory(driver) => CacheDriver::<K, V>::remove(driver, key),
+            #[cfg(feature = "sql")]
+            CacheStore::Sql(driver) => CacheDriver::<K, V>::remove(driver, key),
+        }
+    }
+
+    fn exists(&mut self, key: &K) -> Result<bool, DriverError> {
+        match self {
+            CacheStore::Redis(driver) => CacheDriver::<K, V>::exists(driver, key),
+            CacheStore::Memory(driver) => CacheDriver::<K, V>::exists(driver, key),
+            #[cfg(feature = "sql")]
+            CacheStore::Sql(driver) => CacheDriver::<K, V>::exists(driver, key),
+        }
+    }
+
+    fn increment(&mut self, key: &K, delta: i64) -> Result<i64, DriverError> {
+        match self {
+            CacheStore::Redis(driver) => CacheDriver::<K, V>::increment(driver, key, delta),
+            CacheStore::Memory(driver) => CacheDriver::<K, V>::increment(driver, key, delta),
+            #[cfg(feature = "sql")]
+            CacheStore::Sql(driver) => CacheDriver::<K, V>::increment(driver, key, delta),
+        }
+    }
+
+    fn keys(&mut self, pattern: &K) -> Result<Vec<String>, DriverError> {
+        match self {
+            CacheStore::Redis(driver) => CacheDriver::<K, V>::keys(driver, pattern),
+            CacheStore::Memory(driver) => CacheDriver::<K, V>::keys(driver, pattern),
+            #[cfg(feature = "sql")]
+            CacheStore::Sql(driver) => CacheDriver::<K, V>::keys(driver, pattern),
+        }
+    }
+
+    fn push_capped(&mut self, key: &K, value: &V, cap: usize) -> Result<(), DriverError> {
+        match self {
+            CacheStore::Redis(driver) => CacheDriver::<K, V>::push_capped(driver, key, value, cap),
+            CacheStore::Memory(driver) => CacheDriver::<K, V>::push_capped(driver, key, value, cap),
+            #[cfg(feature = "sql")]
+            CacheStore::Sql(driver) => CacheDriver::<K, V>::push_capped(driver, key, value, cap),
+        }
+    }
+
+    fn list_range(&mut self, key: &K, start: i64, stop: i64) -> Result<Vec<V>, DriverError> {
+        match self {
+            CacheStore::Redis(driver) => CacheDriver::<K, V>::list_range(driver, key, start, stop),
+            CacheStore::Memory(driver) => CacheDriver::<K, V>::list_range(driver, key, start, stop),
+            #[cfg(feature = "sql")]
+            CacheStore::Sql(driver) => CacheDriver::<K, V>::list_range(driver, key, start, stop),
+        }
+    }
 }
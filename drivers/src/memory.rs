@@ -0,0 +1,366 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use tracing::{debug, info, instrument, warn};
+
+use crate::errors::{ConsumeError, DriverError};
+use crate::{CacheDriver, MessageHandler, QueueDriver};
+
+// Value stored by the MemoryDriver, serialized as JSON like in Redis.
+struct MemoryEntry {
+    payload: Vec<u8>,
+    expires_at: Option<Instant>,
+}
+
+impl MemoryEntry {
+    fn is_live(&self, now: Instant) -> bool {
+        self.expires_at.is_none_or(|at| at > now)
+    }
+}
+
+// In-process CacheDriver backed by a HashMap, to run the crawler without Redis (local
+// development, tests). Clones share the same map, so a driver can be handed to several
+// controllers like a Redis connection. Values are stored as JSON like in Redis, so that
+// `increment` works on integers written by `set`. Expired keys are dropped when accessed.
+#[derive(Clone, Default)]
+pub struct MemoryDriver {
+    entries: Arc<Mutex<HashMap<String, MemoryEntry>>>,
+}
+
+impl MemoryDriver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Number of live keys.
+    pub fn len(&self) -> usize {
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, entry| entry.is_live(now));
+        entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Remove every key.
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    // Lock the map, dropping `key` first if it expired.
+    fn lock_live(&self, key: &str) -> std::sync::MutexGuard<'_, HashMap<String, MemoryEntry>> {
+        let mut entries = self.entries.lock().unwrap();
+        if entries
+            .get(key)
+            .is_some_and(|entry| !entry.is_live(Instant::now()))
+        {
+            entries.remove(key);
+        }
+        entries
+    }
+}
+
+fn to_payload<V: Serialize>(value: &V) -> Result<Vec<u8>, DriverError> {
+    serde_json::to_vec(value)
+        .map_err(|e| DriverError::InternalError(format!("Serialization error: {e}")))
+}
+
+impl<K, V> CacheDriver<K, V> for MemoryDriver
+where
+    K: AsRef<str> + ?Sized,
+    V: Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    fn set(&mut self, key: &K, value: &V) -> Result<(), DriverError> {
+        let payload = to_payload(value)?;
+        self.entries.lock().unwrap().insert(
+            key.as_ref().to_string(),
+            MemoryEntry {
+                payload,
+                expires_at: None,
+            },
+        );
+        Ok(())
+    }
+
+    fn set_if_absent(&mut self, key: &K, value: &V) -> Result<bool, DriverError> {
+        let payload = to_payload(value)?;
+        let mut entries = self.lock_live(key.as_ref());
+        if entries.contains_key(key.as_ref()) {
+            return Ok(false);
+        }
+        entries.insert(
+            key.as_ref().to_string(),
+            MemoryEntry {
+                payload,
+                expires_at: None,
+            },
+        );
+        Ok(true)
+    }
+
+    fn set_with_ttl(&mut self, key: &K, value: &V, ttl: Duration) -> Result<(), DriverError> {
+        let payload = to_payload(value)?;
+        self.entries.lock().unwrap().insert(
+            key.as_ref().to_string(),
+            MemoryEntry {
+                payload,
+                expires_at: Some(Instant::now() + ttl),
+            },
+        );
+        Ok(())
+    }
+
+    fn expire(&mut self, key: &K, ttl: Duration) -> Result<bool, DriverError> {
+        let mut entries = self.lock_live(key.as_ref());
+        match entries.get_mut(key.as_ref()) {
+            Some(entry) => {
+                entry.expires_at = Some(Instant::now() + ttl);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn get(&mut self, key: &K) -> Result<V, DriverError> {
+        let entries = self.lock_live(key.as_ref());
+        let entry = entries
+            .get(key.as_ref())
+            .ok_or_else(|| DriverError::NotFound(key.as_ref().to_string()))?;
+        serde_json::from_slice(&entry.payload)
+            .map_err(|e| DriverError::InternalError(format!("Deserialization error: {e}")))
+    }
+
+    fn remove(&mut self, key: &K) -> Result<(), DriverError> {
+        self.entries.lock().unwrap().remove(key.as_ref());
+        Ok(())
+    }
+
+    fn exists(&mut self, key: &K) -> Result<bool, DriverError> {
+        Ok(self.lock_live(key.as_ref()).contains_key(key.as_ref()))
+    }
+
+    fn increment(&mut self, key: &K, delta: i64) -> Result<i64, DriverError> {
+        let mut entries = self.lock_live(key.as_ref());
+        let current = match entries.get(key.as_ref()) {
+            Some(entry) => serde_json::from_slice::<i64>(&entry.payload).map_err(|_| {
+                DriverError::InvalidInput(format!("{} is not an integer", key.as_ref()))
+            })?,
+            None => 0,
+        };
+        let value = current
+            .checked_add(delta)
+            .ok_or_else(|| DriverError::InvalidInput(format!("{} overflows", key.as_ref())))?;
+        // Like INCRBY, the expiry of the key (if any) is kept.
+        let expires_at = entries.get(key.as_ref()).and_then(|entry| entry.expires_at);
+        entries.insert(
+            key.as_ref().to_string(),
+            MemoryEntry {
+                payload: to_payload(&value)?,
+                expires_at,
+            },
+        );
+        Ok(value)
+    }
+
+    fn keys(&mut self, pattern: &K) -> Result<Vec<String>, DriverError> {
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, entry| entry.is_live(now));
+        let pattern: Vec<char> = pattern.as_ref().chars().collect();
+        Ok(entries
+            .keys()
+            .filter(|key| glob_match(&pattern, &key.chars().collect::<Vec<_>>()))
+            .cloned()
+            .collect())
+    }
+
+    fn push_capped(&mut self, key: &K, value: &V, cap: usize) -> Result<(), DriverError> {
+        // NOTE: lists are stored as JSON arrays, updated under the lock of the map.
+        let mut entries = self.lock_live(key.as_ref());
+        let mut items = match entries.get(key.as_ref()) {
+            Some(entry) => list_items(key.as_ref(), &entry.payload)?,
+            None => Vec::new(),
+        };
+        items.push(
+            serde_json::to_value(value)
+                .map_err(|e| DriverError::InternalError(format!("Serialization error: {e}")))?,
+        );
+        if cap > 0 && items.len() > cap {
+            items.drain(..items.len() - cap);
+        }
+        // Like RPUSH, the expiry of the key (if any) is kept.
+        let expires_at = entries.get(key.as_ref()).and_then(|entry| entry.expires_at);
+        entries.insert(
+            key.as_ref().to_string(),
+            MemoryEntry {
+                payload: to_payload(&items)?,
+                expires_at,
+            },
+        );
+        Ok(())
+    }
+
+    fn list_range(&mut self, key: &K, start: i64, stop: i64) -> Result<Vec<V>, DriverError> {
+        let entries = self.lock_live(key.as_ref());
+        let Some(entry) = entries.get(key.as_ref()) else {
+            return Ok(Vec::new());
+        };
+        let items = list_items(key.as_ref(), &entry.payload)?;
+        let range = list_bounds(items.len(), start, stop);
+        items[range]
+            .iter()
+            .map(|item| {
+                V::deserialize(item)
+                    .map_err(|e| DriverError::InternalError(format!("Deserialization error: {e}")))
+            })
+            .collect()
+    }
+}
+
+// Values of the list stored in `payload`.
+fn list_items(key: &str, payload: &[u8]) -> Result<Vec<serde_json::Value>, DriverError> {
+    serde_json::from_slice(payload)
+        .map_err(|_| DriverError::InvalidInput(format!("{key} is not a list")))
+}
+
+// Indexes of a list of `len` values selected by the LRANGE-style `start` and `stop`.
+pub(crate) fn list_bounds(len: usize, start: i64, stop: i64) -> std::ops::Range<usize> {
+    let index = |i: i64| if i < 0 { len as i64 + i } else { i };
+    let start = index(start).max(0);
+    let stop = index(stop).min(len as i64 - 1);
+    if start > stop {
+        return 0..0;
+    }
+    start as usize..stop as usize + 1
+}
+
+// Match `text` against a Redis glob pattern: `*` matches any run of characters, `?` any single
+// character and `\` escapes the next one. Character classes are not supported.
+pub(crate) fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` in the pattern and of the text it was matched at, to backtrack.
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+                continue;
+            }
+            Some('?') => {
+                p += 1;
+                t += 1;
+                continue;
+            }
+            Some('\\') if pattern.get(p + 1) == Some(&text[t]) => {
+                p += 2;
+                t += 1;
+                continue;
+            }
+            Some(c) if *c != '\\' && *c == text[t] => {
+                p += 1;
+                t += 1;
+                continue;
+            }
+            _ => {}
+        }
+        match star {
+            Some((star_p, star_t)) => {
+                p = star_p + 1;
+                t = star_t + 1;
+                star = Some((star_p, star_t + 1));
+            }
+            None => return false,
+        }
+    }
+    pattern[p.min(pattern.len())..].iter().all(|c| *c == '*')
+}
+
+// In-process QueueDriver backed by a FIFO buffer. Useful to run crawler components without a
+// broker and to inspect what would have been published.
+#[derive(Default)]
+pub struct MemoryQueueDriver {
+    messages: Mutex<VecDeque<Vec<u8>>>,
+    // Set by `stop_consuming`.
+    stopped: AtomicBool,
+}
+
+impl MemoryQueueDriver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Number of messages currently buffered.
+    pub fn len(&self) -> usize {
+        self.messages.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Remove and return all buffered messages.
+    pub fn drain(&self) -> Vec<Vec<u8>> {
+        self.messages.lock().unwrap().drain(..).collect()
+    }
+}
+
+#[async_trait]
+impl QueueDriver for MemoryQueueDriver {
+    async fn publish(&self, payload: &[u8]) -> Result<(), DriverError> {
+        self.messages.lock().unwrap().push_back(payload.to_vec());
+        debug!("Message buffered in memory");
+        Ok(())
+    }
+
+    async fn publish_batch(&self, payloads: &[Vec<u8>]) -> Result<(), DriverError> {
+        self.messages
+            .lock()
+            .unwrap()
+            .extend(payloads.iter().cloned());
+        debug!("{} messages buffered in memory", payloads.len());
+        Ok(())
+    }
+
+    // Hand every buffered message to `on_message` and return once the buffer is empty. Messages
+    // failing with a retryable error go back to the end of the buffer, fatal ones are dropped.
+    #[instrument(name = "Consume Messages", level = "info", skip_all)]
+    async fn consume(&self, on_message: MessageHandler) -> Result<(), DriverError> {
+        while !self.stopped.load(Ordering::Relaxed) {
+            let Some(data) = self.messages.lock().unwrap().pop_front() else {
+                break;
+            };
+            match on_message(data.clone()).await {
+                Ok(()) => {}
+                Err(ConsumeError::Retryable(e)) => {
+                    warn!("Handler error: {} (requeueing message)", e);
+                    self.messages.lock().unwrap().push_back(data);
+                }
+                Err(ConsumeError::Fatal(e)) => warn!("Handler error: {} (dropping message)", e),
+            }
+        }
+
+        info!("Consumer stopped");
+        Ok(())
+    }
+
+    async fn stop_consuming(&self) -> Result<(), DriverError> {
+        self.stopped.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn backlog(&self) -> Result<Option<u64>, DriverError> {
+        Ok(Some(self.len() as u64))
+    }
+
+    async fn close(&self) -> Result<(), DriverError> {
+        info!("Closed with {} buffered messages", self.len());
+        Ok(())
+    }
+}
@@ -0,0 +1,116 @@
+use crate::config::QdrantConfig;
+use crate::errors::DriverError;
+use crate::{VectorPoint, VectorStoreDriver};
+use async_trait::async_trait;
+use reqwest::{Client, Method, RequestBuilder, StatusCode};
+use serde_json::{Value, json};
+use tokio::sync::OnceCell;
+use tracing::{info, instrument};
+
+// Qdrant vector store, over its REST API. The collection is created on the first upsert (sized
+// after the first vector, with cosine distance) unless it already exists.
+pub struct QdrantDriver {
+    client: Client,
+    url: String,
+    collection: String,
+    api_key: Option<String>,
+    ready: OnceCell<()>,
+}
+
+impl QdrantDriver {
+    /// Build from environment. Defaults: http://127.0.0.1:6333, collection=pages
+    pub fn new() -> Result<Self, DriverError> {
+        Ok(Self::with_config(&QdrantConfig::from_env()?))
+    }
+
+    /// Build from explicit settings
+    pub fn with_config(config: &QdrantConfig) -> Self {
+        QdrantDriver {
+            client: Client::new(),
+            url: config.url.trim_end_matches('/').to_string(),
+            collection: config.collection.clone(),
+            api_key: config.api_key.clone(),
+            ready: OnceCell::new(),
+        }
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let url = format!("{}/collections/{}{}", self.url, self.collection, path);
+        let request = self.client.request(method, url);
+        match &self.api_key {
+            Some(api_key) => request.header("api-key", api_key),
+            None => request,
+        }
+    }
+
+    // Send `request`, mapping transport failures and error statuses (except `allowed`) to errors.
+    async fn send(
+        &self,
+        request: RequestBuilder,
+        allowed: Option<StatusCode>,
+    ) -> Result<StatusCode, DriverError> {
+        let response = request.send().await.map_err(|e| {
+            DriverError::ConnectionError(format!("Qdrant request to {} failed: {e}", self.url))
+        })?;
+        let status = response.status();
+        if status.is_success() || Some(status) == allowed {
+            return Ok(status);
+        }
+        let body = response.text().await.unwrap_or_default();
+        Err(DriverError::InternalError(format!(
+            "Qdrant returned {status} for collection '{}': {body}",
+            self.collection
+        )))
+    }
+
+    #[instrument(name = "Qdrant Setup", level = "info", skip(self), fields(qdrant.collection = %self.collection))]
+    async fn ensure_collection(&self, size: usize) -> Result<(), DriverError> {
+        let status = self
+            .send(self.request(Method::GET, ""), Some(StatusCode::NOT_FOUND))
+            .await?;
+        if status != StatusCode::NOT_FOUND {
+            return Ok(());
+        }
+        let body = json!({ "vectors": { "size": size, "distance": "Cosine" } });
+        self.send(self.request(Method::PUT, "").json(&body), None)
+            .await?;
+        info!(
+            "Created Qdrant collection {} ({} dimensions)",
+            self.collection, size
+        );
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl VectorStoreDriver for QdrantDriver {
+    async fn upsert(&self, points: &[VectorPoint]) -> Result<(), DriverError> {
+        let Some(first) = points.first() else {
+            return Ok(());
+        };
+        self.ready
+            .get_or_try_init(|| self.ensure_collection(first.vector.len()))
+            .await?;
+        let body = json!({ "points": points });
+        self.send(
+            self.request(Method::PUT, "/points?wait=true").json(&body),
+            None,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_matching(&self, field: &str, value: &str) -> Result<(), DriverError> {
+        let body = json!({
+            "filter": { "must": [{ "key": field, "match": { "value": Value::from(value) } }] }
+        });
+        // A missing collection has nothing to delete.
+        self.send(
+            self.request(Method::POST, "/points/delete?wait=true")
+                .json(&body),
+            Some(StatusCode::NOT_FOUND),
+        )
+        .await?;
+        Ok(())
+    }
+}
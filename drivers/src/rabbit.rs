@@ -1,142 +1,445 @@
-use futures_lite::StreamExt;
+use crate::compression::{Compression, decompress};
+use crate::config::{RabbitConfig, default_client_name};
+use crate::errors::{ConsumeError, DriverError};
+use crate::{EnqueueOptions, MESSAGE_ID_HEADER, MessageHandler, QueueDriver, with_message_headers};
+use async_trait::async_trait;
+use futures_lite::{StreamExt, future};
+use lapin::message::Delivery;
 use lapin::options::{
-    BasicAckOptions, BasicConsumeOptions, BasicNackOptions, BasicPublishOptions,
+    BasicAckOptions, BasicCancelOptions, BasicConsumeOptions, BasicNackOptions,
+    BasicPublishOptions, BasicQosOptions, ExchangeDeclareOptions, QueueBindOptions,
     QueueDeclareOptions,
 };
-use lapin::types::FieldTable;
-use lapin::{BasicProperties, Channel, Connection, ConnectionProperties};
-use serde::Serialize;
-use std::env;
-use tracing::{Level, debug, error, info, instrument, span, trace, warn};
+use lapin::publisher_confirm::PublisherConfirm;
+use lapin::types::{AMQPValue, FieldTable};
+use lapin::{BasicProperties, Channel, Connection, ConnectionProperties, ErrorKind, ExchangeKind};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{RwLock, Semaphore, watch};
+use tracing::{Instrument, Level, debug, error, info, instrument, span, trace, warn};
 
-#[allow(dead_code)]
-pub struct RabbitDriver {
+// Header carrying the routing key of a message published through the exchange, so that it
+// survives replays and is visible to consumers.
+const ROUTING_KEY_HEADER: &str = "x-routing-key";
+
+// Live connection to the broker. `generation` is bumped on every reconnect so that concurrent
+// publishers hitting the same broken connection only reconnect once.
+struct Link {
     conn: Connection,
     channel: Channel,
+    generation: u64,
+}
+
+pub struct RabbitDriver {
+    link: RwLock<Link>,
+    // Messages whose publish was interrupted by a lost connection (with their properties),
+    // replayed after reconnecting.
+    unconfirmed: Mutex<VecDeque<(Vec<u8>, BasicProperties)>>,
+    max_unconfirmed: usize,
+    prefetch: u16,
+    consumer_concurrency: usize,
+    dead_letter_queue: Option<String>,
+    compression: Compression,
+    compression_threshold: usize,
+    max_priority: u8,
+    // Topic exchange messages are published to, and the binding keys of the queue on it.
+    exchange: Option<String>,
+    bindings: Vec<String>,
+    // Queues measured by `backlog` when publishing to `exchange`.
+    backlog_queues: Vec<String>,
+    reconnect_attempts: u32,
+    reconnect_delay: Duration,
+    addr: String,
+    conn_addr: String,
     queue_name: String,
     consumer_tag: String,
+    // Set by `stop_consuming`.
+    stop: watch::Sender<bool>,
 }
 
 impl RabbitDriver {
     /// Build from environment. Defaults: guest/guest@127.0.0.1:5672, queue=default_queue, crawler=generic
+    pub async fn new() -> Result<Self, DriverError> {
+        Self::with_queue(None).await
+    }
+
+    /// Build from environment, using `queue` instead of RABBIT_QUEUE when given
+    pub async fn with_queue(queue: Option<&str>) -> Result<Self, DriverError> {
+        let mut config = RabbitConfig::from_env()?;
+        if let Some(queue) = queue {
+            config.queue = queue.to_string();
+        }
+        Self::with_config(&config).await
+    }
+
+    /// Build from explicit settings
     #[instrument(
         name = "RabbitMQ Setup",
         level = "info",
         skip_all,
         fields(rabbit.host, rabbit.port, rabbit.queue, rabbit.addr, rabbit.consumer_tag)
     )]
-    pub async fn new() -> Result<Self, String> {
-        // env with defaults
-        let user = env::var("RABBIT_USER").unwrap_or_else(|_| "guest".to_string());
-        let password = env::var("RABBIT_PASSWORD").unwrap_or_else(|_| "guest".to_string());
-        let host = env::var("RABBIT_HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
-        let port = env::var("RABBIT_PORT").unwrap_or_else(|_| "5672".to_string());
-        let queue_name = env::var("RABBIT_QUEUE").unwrap_or_else(|_| "default_queue".to_string());
-        let crawler_type = env::var("CRAWLER_TYPE").unwrap_or_else(|_| "generic".to_string());
+    pub async fn with_config(config: &RabbitConfig) -> Result<Self, DriverError> {
+        let RabbitConfig {
+            user,
+            password,
+            host,
+            port,
+            queue: queue_name,
+            ..
+        } = config.clone();
+        let reconnect_attempts = config.reconnect_attempts;
+        let reconnect_delay = Duration::from_millis(config.reconnect_delay_ms);
+        let max_unconfirmed = config.max_unconfirmed;
 
         // never log credentials
         let addr = format!("amqp://{}:{}@{}:{}", user, password, host, port);
         let conn_addr = format!("amqp://{}:{}", host, port); // safe to log
-        let consumer_tag = format!("crawler-{}", crawler_type.trim());
+        let consumer_tag = config
+            .consumer_tag
+            .clone()
+            .unwrap_or_else(default_client_name);
 
         // enrich span
         let span = tracing::Span::current();
         span.record("rabbit.host", &host);
-        span.record("rabbit.port", &port);
+        span.record("rabbit.port", port);
         span.record("rabbit.queue", &queue_name);
         span.record("rabbit.addr", &conn_addr);
         span.record("rabbit.consumer_tag", &consumer_tag);
 
-        info!("Connecting to RabbitMQ at {}", conn_addr);
-        let conn = Connection::connect(&addr, ConnectionProperties::default())
-            .await
-            .map_err(|e| {
-                error!("Connection failed: {}", e);
-                format!("Failed to connect to RabbitMQ at {conn_addr}: {e}")
-            })?;
-        info!("Connection established");
+        let (conn, channel) = open(
+            &addr,
+            &conn_addr,
+            &queue_name,
+            config.max_priority,
+            config.exchange.as_deref(),
+            &consumer_tag,
+        )
+        .await?;
 
-        debug!("Creating channel");
-        let channel = conn.create_channel().await.map_err(|e| {
-            error!("Channel creation failed: {}", e);
-            format!("Failed to create channel: {e}")
-        })?;
+        Ok(RabbitDriver {
+            link: RwLock::new(Link {
+                conn,
+                channel,
+                generation: 0,
+            }),
+            unconfirmed: Mutex::new(VecDeque::new()),
+            max_unconfirmed,
+            prefetch: config.effective_prefetch(),
+            consumer_concurrency: config.consumer_concurrency,
+            dead_letter_queue: config.dead_letter_queue.clone(),
+            compression: config.compression,
+            compression_threshold: config.compression_threshold,
+            max_priority: config.max_priority,
+            exchange: config.exchange.clone(),
+            bindings: config.bindings.clone(),
+            backlog_queues: config.backlog_queues.clone(),
+            reconnect_attempts,
+            reconnect_delay,
+            addr,
+            conn_addr,
+            queue_name,
+            consumer_tag,
+            stop: watch::Sender::new(false),
+        })
+    }
 
-        let queue_span = span!(Level::DEBUG, "Queue Declaration", %consumer_tag, %queue_name);
-        let _enter = queue_span.enter();
+    // Current channel and its generation, reconnecting first if the connection is known to be down.
+    async fn channel(&self) -> Result<(Channel, u64), DriverError> {
+        let (channel, generation, healthy) = {
+            let link = self.link.read().await;
+            let healthy = link.conn.status().connected() && link.channel.status().connected();
+            (link.channel.clone(), link.generation, healthy)
+        };
+        if healthy {
+            return Ok((channel, generation));
+        }
 
-        debug!("Declaring durable queue");
-        let queue_options = QueueDeclareOptions {
-            durable: true,
-            exclusive: false,
-            auto_delete: false,
-            ..Default::default()
+        warn!("RabbitMQ connection is down, reconnecting");
+        self.reconnect(generation).await
+    }
+
+    // Re-create the connection and channel unless another task already did so since `seen`
+    // was observed. Retries with exponential backoff, then replays unconfirmed messages.
+    #[instrument(name = "RabbitMQ Reconnect", level = "info", skip(self), fields(rabbit.addr = %self.conn_addr))]
+    async fn reconnect(&self, seen: u64) -> Result<(Channel, u64), DriverError> {
+        let (channel, generation) = {
+            let mut link = self.link.write().await;
+            if link.generation == seen {
+                let mut attempt = 0;
+                let (conn, channel) = loop {
+                    attempt += 1;
+                    match open(
+                        &self.addr,
+                        &self.conn_addr,
+                        &self.queue_name,
+                        self.max_priority,
+                        self.exchange.as_deref(),
+                        &self.consumer_tag,
+                    )
+                    .await
+                    {
+                        Ok(opened) => break opened,
+                        Err(e) if attempt < self.reconnect_attempts => {
+                            let delay = self.reconnect_delay * 2u32.saturating_pow(attempt - 1);
+                            warn!(
+                                "Reconnect attempt {}/{} failed: {}. Retrying in {:?}",
+                                attempt, self.reconnect_attempts, e, delay
+                            );
+                            tokio::time::sleep(delay).await;
+                        }
+                        Err(e) => {
+                            error!("Giving up reconnecting after {} attempts", attempt);
+                            return Err(DriverError::ConnectionLost(format!(
+                                "Failed to reconnect to RabbitMQ after {attempt} attempts: {e}"
+                            )));
+                        }
+                    }
+                };
+                *link = Link {
+                    conn,
+                    channel,
+                    generation: seen + 1,
+                };
+                info!("Reconnected to RabbitMQ");
+            }
+            (link.channel.clone(), link.generation)
         };
 
-        channel
-            .queue_declare(&queue_name, queue_options, FieldTable::default())
-            .await
-            .map_err(|e| {
-                error!("Queue declare failed for '{}': {}", queue_name, e);
-                format!("Queue declare failed for '{}': {e}", queue_name)
-            })?;
-        info!("Queue declared: {}", queue_name);
+        self.replay_unconfirmed(&channel).await?;
+        Ok((channel, generation))
+    }
 
-        Ok(RabbitDriver {
-            conn,
-            channel,
-            queue_name,
-            consumer_tag,
-        })
+    // Keep a message whose publish was interrupted so that it is replayed after reconnecting. The
+    // buffer is bounded: when full, the oldest message is dropped.
+    fn buffer_unconfirmed(&self, payload: Vec<u8>, properties: BasicProperties) {
+        let mut unconfirmed = self.unconfirmed.lock().unwrap();
+        if unconfirmed.len() >= self.max_unconfirmed {
+            unconfirmed.pop_front();
+            error!(
+                "Unconfirmed message buffer full ({}), dropping oldest message",
+                self.max_unconfirmed
+            );
+        }
+        unconfirmed.push_back((payload, properties));
     }
 
-    #[instrument(
-        name = "Enqueue Message",
-        level = "info",
-        skip(self, payload),
-        fields(rabbit.queue = %self.queue_name, msg.size)
-    )]
+    // Republish buffered messages. Messages that fail again go back to the buffer.
+    async fn replay_unconfirmed(&self, channel: &Channel) -> Result<(), DriverError> {
+        let pending: Vec<(Vec<u8>, BasicProperties)> =
+            self.unconfirmed.lock().unwrap().drain(..).collect();
+        if pending.is_empty() {
+            return Ok(());
+        }
 
-    pub async fn enqueue<T: Serialize + Sized>(&self, payload: T) -> Result<(), String> {
-        // convert to string
-        let data = serde_json::to_string(&payload).map_err(|e| {
-            error!("Serialization failed: {}", e);
-            format!("Failed to serialize payload: {e}")
-        })?;
+        info!("Replaying {} unconfirmed messages", pending.len());
+        let mut pending = pending.into_iter();
+        while let Some((payload, properties)) = pending.next() {
+            if let Err(e) = self.publish_on(channel, &payload, properties.clone()).await {
+                warn!("Replay interrupted: {}", e);
+                self.buffer_unconfirmed(payload, properties);
+                pending.for_each(|(p, props)| self.buffer_unconfirmed(p, props));
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
 
-        self.channel
+    // Compress `payload` if compression is enabled and the payload reaches the threshold. The
+    // encoding is announced in the `content-encoding` property and the `x-compression` header.
+    fn encode<'a>(
+        &self,
+        payload: &'a [u8],
+        properties: BasicProperties,
+    ) -> Result<(Cow<'a, [u8]>, BasicProperties), DriverError> {
+        let Some(encoding) = self.compression.encoding() else {
+            return Ok((Cow::Borrowed(payload), properties));
+        };
+        if payload.len() < self.compression_threshold {
+            return Ok((Cow::Borrowed(payload), properties));
+        }
+
+        let compressed = self.compression.compress(payload)?;
+        trace!(
+            "Compressed payload with {}: {} -> {} bytes",
+            encoding,
+            payload.len(),
+            compressed.len()
+        );
+        let mut headers = properties.headers().clone().unwrap_or_default();
+        headers.insert(
+            "x-compression".into(),
+            AMQPValue::LongString(encoding.into()),
+        );
+        let properties = properties
+            .with_content_encoding(encoding.into())
+            .with_headers(headers);
+        Ok((Cow::Owned(compressed), properties))
+    }
+
+    // Publish a single message to the queue, or to the exchange with its routing key (the queue
+    // name when it has none), returning the pending broker confirmation.
+    async fn send(
+        &self,
+        channel: &Channel,
+        data: &[u8],
+        properties: BasicProperties,
+    ) -> Result<PublisherConfirm, DriverError> {
+        let (exchange, routing_key) = match &self.exchange {
+            Some(exchange) => (
+                exchange.as_str(),
+                delivery_headers(&properties)
+                    .remove(ROUTING_KEY_HEADER)
+                    .unwrap_or_else(|| self.queue_name.clone()),
+            ),
+            // empty exchange for default
+            None => ("", self.queue_name.clone()),
+        };
+        channel
             .basic_publish(
-                "", // empty exchange for default
-                &self.queue_name,
+                exchange,
+                &routing_key,
                 BasicPublishOptions::default(),
-                data.as_bytes(),
-                BasicProperties::default(),
+                data,
+                properties,
             )
             .await
             .map_err(|e| {
                 error!("Publish send failed: {}", e);
-                format!("Publish send failed: {e}")
-            })?
+                classify(e, DriverError::PublishError, "Publish send failed")
+            })
+    }
+
+    // Publish a single message and wait for the broker confirmation.
+    async fn publish_on(
+        &self,
+        channel: &Channel,
+        data: &[u8],
+        properties: BasicProperties,
+    ) -> Result<(), DriverError> {
+        self.send(channel, data, properties)
+            .await?
             .await
             .map_err(|e| {
                 error!("Publish confirm failed: {}", e);
-                format!("Publish confirm failed: {e}")
+                classify(e, DriverError::PublishError, "Publish confirm failed")
             })?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl QueueDriver for RabbitDriver {
+    async fn publish(&self, payload: &[u8]) -> Result<(), DriverError> {
+        self.publish_with(payload, &EnqueueOptions::default()).await
+    }
+
+    #[instrument(
+        name = "Enqueue Message",
+        level = "info",
+        skip(self, payload, options),
+        fields(rabbit.queue = %self.queue_name, msg.size = payload.len())
+    )]
+    async fn publish_with(
+        &self,
+        payload: &[u8],
+        options: &EnqueueOptions,
+    ) -> Result<(), DriverError> {
+        let (payload, properties) = self.encode(payload, properties(options))?;
+        let (channel, generation) = self.channel().await?;
+        match self
+            .publish_on(&channel, &payload, properties.clone())
+            .await
+        {
+            Ok(()) => {}
+            Err(DriverError::ConnectionLost(e)) => {
+                // the message is replayed with the rest of the buffer once the connection is back
+                warn!("Connection lost while publishing: {}", e);
+                self.buffer_unconfirmed(payload.into_owned(), properties);
+                self.reconnect(generation).await?;
+            }
+            Err(e) => return Err(e),
+        }
 
         debug!("Message published to {}", self.queue_name);
         Ok(())
     }
 
+    #[instrument(
+        name = "Enqueue Batch",
+        level = "info",
+        skip(self, payloads),
+        fields(rabbit.queue = %self.queue_name, batch.size = payloads.len())
+    )]
+    async fn publish_batch(&self, payloads: &[Vec<u8>]) -> Result<(), DriverError> {
+        let (channel, generation) = self.channel().await?;
+
+        let messages = payloads
+            .iter()
+            .map(|payload| self.encode(payload, BasicProperties::default()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // publish everything first, then wait for the broker to confirm the whole batch
+        let mut confirms = Vec::with_capacity(messages.len());
+        let mut lost = false;
+        for (payload, properties) in messages {
+            match self.send(&channel, &payload, properties.clone()).await {
+                Ok(confirm) => confirms.push((payload, properties, confirm)),
+                Err(DriverError::ConnectionLost(_)) => {
+                    lost = true;
+                    self.buffer_unconfirmed(payload.into_owned(), properties);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        for (payload, properties, confirm) in confirms {
+            match confirm.await {
+                Ok(_) => {}
+                Err(e) => match classify(e, DriverError::PublishError, "Publish confirm failed") {
+                    DriverError::ConnectionLost(_) => {
+                        lost = true;
+                        self.buffer_unconfirmed(payload.into_owned(), properties);
+                    }
+                    e => {
+                        error!("{}", e);
+                        return Err(e);
+                    }
+                },
+            }
+        }
+
+        if lost {
+            warn!("Connection lost while publishing batch");
+            self.reconnect(generation).await?;
+        }
+
+        debug!(
+            "{} messages published to {}",
+            payloads.len(),
+            self.queue_name
+        );
+        Ok(())
+    }
+
     #[instrument(name = "Close Connection", level = "info", skip(self))]
-    pub async fn close(self) -> Result<(), String> {
+    async fn close(&self) -> Result<(), DriverError> {
+        let pending = self.unconfirmed.lock().unwrap().len();
+        if pending > 0 {
+            warn!("Closing with {} unconfirmed messages", pending);
+        }
+
         info!("Closing channel and connection");
-        self.channel.close(200, "Goodbye").await.map_err(|e| {
+        let link = self.link.read().await;
+        link.channel.close(200, "Goodbye").await.map_err(|e| {
             error!("Channel close failed: {}", e);
-            format!("Channel close failed: {e}")
+            classify(e, DriverError::ConnectionError, "Channel close failed")
         })?;
-        self.conn.close(200, "Bye").await.map_err(|e| {
+        link.conn.close(200, "Bye").await.map_err(|e| {
             error!("Connection close failed: {}", e);
-            format!("Connection close failed: {e}")
+            classify(e, DriverError::ConnectionError, "Connection close failed")
         })?;
         info!("Closed");
         Ok(())
@@ -148,16 +451,110 @@ impl RabbitDriver {
         skip(self, on_message),
         fields(rabbit.queue = %self.queue_name, rabbit.consumer_tag = %self.consumer_tag)
     )]
-    pub async fn consume<F, V>(&self, on_message: F) -> Result<(), String>
-    where
-        // thread-safe function that receives the message payload, and returns Ok(()) on success or
-        // Err(String) on failure
-        F: Fn(V) -> Result<(), String> + Send + Sync + 'static,
-        V: serde::de::DeserializeOwned + 'static,
-    {
-        info!("Starting consumer");
-        let mut consumer = self
-            .channel
+    async fn consume(&self, on_message: MessageHandler) -> Result<(), DriverError> {
+        let (mut channel, mut generation) = self.channel().await?;
+
+        // (re)start the consumer every time the connection is re-established; unacked deliveries
+        // are redelivered by the broker
+        loop {
+            match self.consume_on(&channel, &on_message).await {
+                Err(DriverError::ConnectionLost(_)) if *self.stop.borrow() => return Ok(()),
+                Err(DriverError::ConnectionLost(e)) => {
+                    warn!("Connection lost while consuming: {}", e);
+                    (channel, generation) = self.reconnect(generation).await?;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn stop_consuming(&self) -> Result<(), DriverError> {
+        self.stop.send_replace(true);
+        Ok(())
+    }
+
+    // Ready messages of the queue, or of the deepest backlog queue when publishing to an
+    // exchange: the publisher never declares `queue` then, so it tells nothing about the
+    // consumers. Without backlog queues, an exchange publisher reports no backlog.
+    async fn backlog(&self) -> Result<Option<u64>, DriverError> {
+        let queues = match &self.exchange {
+            Some(exchange) if self.backlog_queues.is_empty() => {
+                warn!(
+                    "Cannot read the depth of the queues bound to exchange '{}', set RABBIT_BACKLOG_QUEUES",
+                    exchange
+                );
+                return Ok(None);
+            }
+            Some(_) => self.backlog_queues.as_slice(),
+            None => std::slice::from_ref(&self.queue_name),
+        };
+        let mut deepest = 0;
+        for queue in queues {
+            deepest = deepest.max(self.queue_depth(queue).await?);
+        }
+        Ok(Some(deepest))
+    }
+}
+
+impl RabbitDriver {
+    // Ready messages of `queue`, read with a passive declaration on a channel of its own (a
+    // failed declaration closes its channel).
+    async fn queue_depth(&self, queue: &str) -> Result<u64, DriverError> {
+        let channel = {
+            let link = self.link.read().await;
+            link.conn.create_channel().await.map_err(|e| {
+                classify(e, DriverError::ConnectionError, "Failed to create channel")
+            })?
+        };
+        let options = QueueDeclareOptions {
+            passive: true,
+            ..Default::default()
+        };
+        let declared = channel
+            .queue_declare(queue, options, FieldTable::default())
+            .await
+            .map_err(|e| {
+                classify(
+                    e,
+                    DriverError::ConsumeError,
+                    &format!("Failed to read the depth of '{queue}'"),
+                )
+            });
+        let _ = channel.close(200, "Goodbye").await;
+        Ok(u64::from(declared?.message_count()))
+    }
+
+    // Consume from `channel` until the consumer stops or fails. Up to `consumer_concurrency`
+    // deliveries are handled at the same time, each acked or nacked as soon as its handler
+    // completes.
+    async fn consume_on(
+        &self,
+        channel: &Channel,
+        on_message: &MessageHandler,
+    ) -> Result<(), DriverError> {
+        // the broker stops sending once `prefetch` deliveries are unacknowledged
+        channel
+            .basic_qos(self.prefetch, BasicQosOptions::default())
+            .await
+            .map_err(|e| {
+                error!("Failed to set prefetch count: {}", e);
+                classify(e, DriverError::ConsumeError, "Failed to set prefetch count")
+            })?;
+
+        if let Some(queue) = &self.dead_letter_queue {
+            declare(channel, queue, 0).await?;
+        }
+        // with an exchange, only consumers declare their queue, receiving the slices they bind
+        if let Some(exchange) = &self.exchange {
+            declare(channel, &self.queue_name, self.max_priority).await?;
+            bind(channel, &self.queue_name, exchange, &self.bindings).await?;
+        }
+
+        info!(
+            "Starting consumer (prefetch {}, concurrency {})",
+            self.prefetch, self.consumer_concurrency
+        );
+        let mut consumer = channel
             .basic_consume(
                 &self.queue_name,
                 &self.consumer_tag,
@@ -167,19 +564,53 @@ impl RabbitDriver {
             .await
             .map_err(|e| {
                 error!("Failed to start consumer: {}", e);
-                format!("Failed to start consumer: {e}")
+                classify(e, DriverError::ConsumeError, "Failed to start consumer")
             })?;
 
         info!("Consumer started, waiting for messages...");
-        while let Some(delivery) = consumer.next().await {
-            let delivery = match delivery {
-                Ok(d) => d,
-                Err(e) => {
+        let slots = self.consumer_concurrency.max(1);
+        let in_flight = Arc::new(Semaphore::new(slots));
+        let mut stop = self.stop.subscribe();
+        let result = loop {
+            // the stop request is checked first, so a busy queue cannot delay it
+            let next = future::or(
+                async {
+                    let _ = stop.wait_for(|stop| *stop).await;
+                    None
+                },
+                async { Some(consumer.next().await) },
+            )
+            .await;
+            let Some(next) = next else {
+                info!("Stopping consumer, finishing the deliveries being handled");
+                if let Err(e) = channel
+                    .basic_cancel(&self.consumer_tag, BasicCancelOptions::default())
+                    .await
+                {
+                    warn!("Failed to cancel consumer: {}", e);
+                }
+                break Ok(());
+            };
+            let delivery = match next {
+                Some(Ok(d)) => d,
+                Some(Err(e)) => {
                     error!("Consumer yielded error: {}", e);
-                    return Err(format!("Consumer yielded error: {e}"));
+                    break Err(classify(
+                        e,
+                        DriverError::ConsumeError,
+                        "Consumer yielded error",
+                    ));
                 }
+                None => break Ok(()),
             };
 
+            // wait for a free slot before handling the delivery
+            let permit = in_flight
+                .clone()
+                .acquire_owned()
+                .await
+                .expect("the semaphore is never closed");
+
             let tag = delivery.delivery_tag;
             let corr = delivery
                 .properties
@@ -187,56 +618,340 @@ impl RabbitDriver {
                 .as_ref()
                 .map(|c| String::from_utf8_lossy(c.as_str().as_bytes()).to_string())
                 .unwrap_or_default();
-
             let msg_span = span!(Level::DEBUG, "Handle Delivery", delivery.tag = %tag, correlation_id = %corr, size = delivery.data.len());
-            let _enter = msg_span.enter();
-
-            debug!("Received message");
-            trace!("Payload size: {} bytes", delivery.data.len());
-
-            // deserialize message to expected type
-            let actual_data: V = match serde_json::from_slice::<V>(&delivery.data) {
-                Ok(v) => v,
-                Err(e) => {
-                    error!("Deserialization failed for tag {}: {}", tag, e);
-                    if let Err(e2) = delivery
-                        .nack(BasicNackOptions {
-                            requeue: false,
-                            ..Default::default()
-                        })
-                        .await
-                    {
-                        error!("Nack failed after deserialization error '{}': {}", e, e2);
-                    }
-                    return Err(format!("Failed to deserialize message for tag {tag}: {e}"));
+
+            let on_message = on_message.clone();
+            let dead_letter = self
+                .dead_letter_queue
+                .clone()
+                .map(|queue| (channel.clone(), queue));
+            tokio::spawn(
+                async move {
+                    settle(delivery, &on_message, dead_letter).await;
+                    drop(permit);
                 }
-            };
+                .instrument(msg_span),
+            );
+        };
+
+        // let the deliveries being handled finish; acks of a lost connection fail and the broker
+        // redelivers those messages
+        let _ = in_flight.acquire_many(slots as u32).await;
+        if result.is_ok() {
+            info!("Consumer stopped");
+        }
+        result
+    }
+}
 
-            // check result of handler
-            match on_message(actual_data) {
-                Ok(_) => {
-                    delivery
-                        .ack(BasicAckOptions::default())
-                        .await
-                        .map_err(|e| {
-                            error!("Ack failed for tag {}: {}", tag, e);
-                            format!("Ack failed: {e}")
-                        })?;
-                    debug!("Acked tag {}", tag);
+// String headers of a delivery, as returned by `message_headers` to the handler, with its
+// `message_id` property under `MESSAGE_ID_HEADER`.
+fn delivery_headers(properties: &BasicProperties) -> BTreeMap<String, String> {
+    let mut headers: BTreeMap<String, String> = properties
+        .headers()
+        .iter()
+        .flat_map(|headers| headers.inner().iter())
+        .filter_map(|(name, value)| {
+            let value = match value {
+                AMQPValue::LongString(value) => {
+                    String::from_utf8_lossy(value.as_bytes()).into_owned()
                 }
-                Err(handler_err) => {
-                    warn!("Handler error for tag {}: {}", tag, handler_err);
-                    let opts = BasicNackOptions::default();
-                    delivery.nack(opts).await.map_err(|e2| {
-                        error!("Nack failed after handler error '{}': {}", handler_err, e2);
-                        format!("Nack failed after handler error '{handler_err}': {e2}")
-                    })?;
-                    debug!("Nacked tag {} (requeue=false)", tag);
+                AMQPValue::ShortString(value) => value.as_str().to_string(),
+                _ => return None,
+            };
+            Some((name.as_str().to_string(), value))
+        })
+        .collect();
+    if let Some(message_id) = properties.message_id() {
+        headers.insert(
+            MESSAGE_ID_HEADER.to_string(),
+            message_id.as_str().to_string(),
+        );
+    }
+    headers
+}
+
+// Decompress a delivery (see `RabbitDriver::encode`) and hand it to the handler, then settle it: ack on success, nack with requeue on a
+// retryable error, and on a fatal error move it to the dead-letter queue (`dead_letter`, with
+// its channel) or nack it without requeue. Settling failures are only logged: they mean the
+// channel is gone, which the consumer loop notices, and the broker redelivers the message.
+async fn settle(
+    mut delivery: Delivery,
+    on_message: &MessageHandler,
+    dead_letter: Option<(Channel, String)>,
+) {
+    let tag = delivery.delivery_tag;
+    debug!("Received message");
+    trace!("Payload size: {} bytes", delivery.data.len());
+
+    let data = std::mem::take(&mut delivery.data);
+    let payload = dead_letter.as_ref().map(|_| data.clone());
+    let encoding = delivery
+        .properties
+        .content_encoding()
+        .as_ref()
+        .map(|e| e.as_str().to_string());
+    let headers = delivery_headers(&delivery.properties);
+    let outcome = match decompress(encoding.as_deref(), data) {
+        Ok(data) => with_message_headers(headers, on_message(data)).await,
+        // a payload that cannot be decoded will never be handled
+        Err(e) => Err(ConsumeError::Fatal(e.to_string())),
+    };
+    let handler_err = match outcome {
+        Ok(()) => {
+            match delivery.ack(BasicAckOptions::default()).await {
+                Ok(_) => debug!("Acked tag {}", tag),
+                Err(e) => error!("Ack failed for tag {}: {}", tag, e),
+            }
+            return;
+        }
+        Err(e) => e,
+    };
+
+    warn!("Handler error for tag {}: {}", tag, handler_err);
+    if let (ConsumeError::Fatal(reason), Some((channel, queue)), Some(payload)) =
+        (&handler_err, &dead_letter, payload)
+    {
+        match dead_letter_publish(channel, queue, &payload, &delivery.properties, reason).await {
+            Ok(()) => {
+                match delivery.ack(BasicAckOptions::default()).await {
+                    Ok(_) => debug!("Moved tag {} to dead-letter queue {}", tag, queue),
+                    Err(e) => error!("Ack failed for dead-lettered tag {}: {}", tag, e),
                 }
+                return;
             }
+            Err(e) => error!("Failed to dead-letter tag {}: {}", tag, e),
         }
+    }
 
-        info!("Consumer stopped");
-        Ok(())
+    let requeue = handler_err.is_retryable();
+    let opts = BasicNackOptions {
+        requeue,
+        ..Default::default()
+    };
+    match delivery.nack(opts).await {
+        Ok(_) => debug!("Nacked tag {} (requeue={})", tag, requeue),
+        Err(e) => error!("Nack failed after handler error '{}': {}", handler_err, e),
+    }
+}
+
+// Publish a failed message to the dead-letter queue with its original properties and the error
+// in the `x-error` header, waiting for the broker confirmation.
+async fn dead_letter_publish(
+    channel: &Channel,
+    queue: &str,
+    payload: &[u8],
+    properties: &BasicProperties,
+    reason: &str,
+) -> Result<(), lapin::Error> {
+    let mut headers = properties.headers().clone().unwrap_or_default();
+    headers.insert("x-error".into(), AMQPValue::LongString(reason.into()));
+    channel
+        .basic_publish(
+            "",
+            queue,
+            BasicPublishOptions::default(),
+            payload,
+            properties.clone().with_headers(headers),
+        )
+        .await?
+        .await?;
+    Ok(())
+}
+
+// Connect to the broker, open a channel and declare the durable queue.
+async fn open(
+    addr: &str,
+    conn_addr: &str,
+    queue_name: &str,
+    max_priority: u8,
+    exchange: Option<&str>,
+    consumer_tag: &str,
+) -> Result<(Connection, Channel), DriverError> {
+    info!("Connecting to RabbitMQ at {}", conn_addr);
+    let conn = Connection::connect(addr, ConnectionProperties::default())
+        .await
+        .map_err(|e| {
+            error!("Connection failed: {}", e);
+            DriverError::ConnectionError(format!(
+                "Failed to connect to RabbitMQ at {conn_addr}: {e}"
+            ))
+        })?;
+    info!("Connection established");
+
+    // connection health monitoring: log as soon as the broker connection breaks
+    conn.on_error(|e| error!("RabbitMQ connection error: {}", e));
+
+    debug!("Creating channel");
+    let channel = conn.create_channel().await.map_err(|e| {
+        error!("Channel creation failed: {}", e);
+        DriverError::ConnectionError(format!("Failed to create channel: {e}"))
+    })?;
+
+    let queue_span = span!(Level::DEBUG, "Queue Declaration", %consumer_tag, %queue_name);
+    let _enter = queue_span.enter();
+
+    match exchange {
+        Some(exchange) => declare_exchange(&channel, exchange).await?,
+        None => declare(&channel, queue_name, max_priority).await?,
+    }
+    Ok((conn, channel))
+}
+
+// Declare the durable topic exchange `exchange` on `channel`.
+async fn declare_exchange(channel: &Channel, exchange: &str) -> Result<(), DriverError> {
+    let options = ExchangeDeclareOptions {
+        durable: true,
+        ..Default::default()
+    };
+    channel
+        .exchange_declare(
+            exchange,
+            ExchangeKind::Topic,
+            options,
+            FieldTable::default(),
+        )
+        .await
+        .map_err(|e| {
+            error!("Exchange declare failed for '{}': {}", exchange, e);
+            classify(
+                e,
+                DriverError::ConnectionError,
+                &format!("Exchange declare failed for '{exchange}'"),
+            )
+        })?;
+    info!("Topic exchange declared: {}", exchange);
+    Ok(())
+}
+
+// Bind `queue_name` to `exchange` with each of the binding keys `bindings`.
+async fn bind(
+    channel: &Channel,
+    queue_name: &str,
+    exchange: &str,
+    bindings: &[String],
+) -> Result<(), DriverError> {
+    declare_exchange(channel, exchange).await?;
+    for binding in bindings {
+        channel
+            .queue_bind(
+                queue_name,
+                exchange,
+                binding,
+                QueueBindOptions::default(),
+                FieldTable::default(),
+            )
+            .await
+            .map_err(|e| {
+                error!(
+                    "Queue bind failed for '{}' ({}): {}",
+                    queue_name, binding, e
+                );
+                classify(
+                    e,
+                    DriverError::ConnectionError,
+                    &format!("Queue bind failed for '{queue_name}'"),
+                )
+            })?;
+        info!(
+            "Queue {} bound to {} with {}",
+            queue_name, exchange, binding
+        );
+    }
+    Ok(())
+}
+
+// Declare the durable queue `queue_name` on `channel`, as a priority queue when `max_priority`
+// is set.
+async fn declare(channel: &Channel, queue_name: &str, max_priority: u8) -> Result<(), DriverError> {
+    debug!("Declaring durable queue");
+    let queue_options = QueueDeclareOptions {
+        durable: true,
+        exclusive: false,
+        auto_delete: false,
+        ..Default::default()
+    };
+
+    let mut arguments = FieldTable::default();
+    if max_priority > 0 {
+        arguments.insert(
+            "x-max-priority".into(),
+            AMQPValue::ShortShortUInt(max_priority),
+        );
+    }
+
+    channel
+        .queue_declare(queue_name, queue_options, arguments)
+        .await
+        .map_err(|e| {
+            error!("Queue declare failed for '{}': {}", queue_name, e);
+            classify(
+                e,
+                DriverError::ConnectionError,
+                &format!("Queue declare failed for '{queue_name}'"),
+            )
+        })?;
+    if max_priority > 0 {
+        info!(
+            "Queue declared: {} (priorities 0-{})",
+            queue_name, max_priority
+        );
+    } else {
+        info!("Queue declared: {}", queue_name);
+    }
+    Ok(())
+}
+
+// AMQP properties of a message published with `options`.
+fn properties(options: &EnqueueOptions) -> BasicProperties {
+    let mut properties = BasicProperties::default();
+    let mut headers = FieldTable::default();
+    for (name, value) in &options.headers {
+        headers.insert(
+            name.as_str().into(),
+            AMQPValue::LongString(value.as_str().into()),
+        );
+    }
+    if let Some(routing_key) = &options.routing_key {
+        headers.insert(
+            ROUTING_KEY_HEADER.into(),
+            AMQPValue::LongString(routing_key.as_str().into()),
+        );
+    }
+    if !headers.inner().is_empty() {
+        properties = properties.with_headers(headers);
+    }
+    if let Some(priority) = options.priority {
+        properties = properties.with_priority(priority);
+    }
+    if let Some(expiration) = options.expiration {
+        // AMQP expirations are milliseconds, as a string
+        properties = properties.with_expiration(expiration.as_millis().to_string().into());
+    }
+    if let Some(content_type) = &options.content_type {
+        properties = properties.with_content_type(content_type.as_str().into());
+    }
+    if let Some(correlation_id) = &options.correlation_id {
+        properties = properties.with_correlation_id(correlation_id.as_str().into());
+    }
+    if let Some(message_id) = &options.message_id {
+        properties = properties.with_message_id(message_id.as_str().into());
+    }
+    properties
+}
+
+// Map a lapin error to a DriverError: broken connections and channels become `ConnectionLost` so
+// callers can reconnect and retry, anything else is wrapped with `variant`.
+fn classify(err: lapin::Error, variant: fn(String) -> DriverError, context: &str) -> DriverError {
+    let message = format!("{context}: {err}");
+    match err.kind() {
+        ErrorKind::IOError(_)
+        | ErrorKind::InvalidConnectionState(_)
+        | ErrorKind::InvalidChannelState(..)
+        | ErrorKind::MissingHeartbeatError => DriverError::ConnectionLost(message),
+        ErrorKind::SerialisationError(_) | ErrorKind::ParsingError(_) => {
+            DriverError::SerializationError(message)
+        }
+        _ => variant(message),
     }
 }
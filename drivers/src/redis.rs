@@ -1,24 +1,48 @@
+use std::time::Duration;
+
 use redis::Commands;
 use serde::{Serialize, de::DeserializeOwned};
-use std::env;
 use tracing::{info, instrument};
 
-use crate::{CacheDriver, errors::DriverError};
+use crate::{CacheDriver, config::RedisConfig, errors::DriverError};
 
 pub struct RedisDriver {
     pub conn: redis::Connection,
 }
 
+// Memory figures of a Redis server, from `INFO memory`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryUsage {
+    pub used_bytes: u64,
+    // `maxmemory` limit of the server (unset when unlimited).
+    pub max_bytes: Option<u64>,
+}
+
+impl MemoryUsage {
+    // Parse the `field:value` lines of `INFO memory`.
+    pub fn parse(info: &str) -> Option<Self> {
+        let field = |name: &str| {
+            info.lines()
+                .filter_map(|line| line.trim().split_once(':'))
+                .find(|(key, _)| *key == name)
+                .and_then(|(_, value)| value.trim().parse::<u64>().ok())
+        };
+        Some(MemoryUsage {
+            used_bytes: field("used_memory")?,
+            max_bytes: field("maxmemory").filter(|max| *max > 0),
+        })
+    }
+}
+
 impl RedisDriver {
-    #[instrument]
+    // Build from environment. Defaults: 127.0.0.1:6379, database 0
     pub fn new() -> Result<Self, String> {
-        // Build the Redis URL from environment variables
-        let redis_url = format!(
-            "redis://{}:{}/{}",
-            env::var("REDIS_HOST").unwrap_or_else(|_| "127.0.0.1".to_string()),
-            env::var("REDIS_PORT").unwrap_or_else(|_| "6379".to_string()),
-            env::var("REDIS_DB").unwrap_or_else(|_| "0".to_string())
-        );
+        Self::with_config(&RedisConfig::from_env().map_err(|e| e.to_string())?)
+    }
+
+    #[instrument(skip_all)]
+    pub fn with_config(config: &RedisConfig) -> Result<Self, String> {
+        let redis_url = config.url();
         info!("Connecting to Redis at {}", redis_url);
 
         // Create a Redis client and establish a connection
@@ -32,6 +56,17 @@ impl RedisDriver {
         info!("Redis connection successful");
         Ok(RedisDriver { conn })
     }
+
+    // Current memory usage of the server.
+    pub fn memory_usage(&mut self) -> Result<MemoryUsage, DriverError> {
+        let info: String = redis::cmd("INFO")
+            .arg("memory")
+            .query(&mut self.conn)
+            .map_err(|e| DriverError::InternalError(format!("Redis info error: {e}")))?;
+        MemoryUsage::parse(&info).ok_or_else(|| {
+            DriverError::InternalError("Redis info error: used_memory is missing".to_string())
+        })
+    }
 }
 
 impl<K, V> CacheDriver<K, V> for RedisDriver
@@ -50,6 +85,30 @@ where
         Ok(())
     }
 
+    fn set_if_absent(&mut self, key: &K, value: &V) -> Result<bool, DriverError> {
+        // SETNX is atomic, so concurrent callers (other agents or processes) cannot both win.
+        let payload = serde_json::to_vec(value)
+            .map_err(|e| DriverError::InternalError(format!("Serialization error: {e}")))?;
+        self.conn
+            .set_nx::<&str, Vec<u8>, bool>(key.as_ref(), payload)
+            .map_err(|e| DriverError::InternalError(format!("Redis setnx error: {e}")))
+    }
+
+    fn set_with_ttl(&mut self, key: &K, value: &V, ttl: Duration) -> Result<(), DriverError> {
+        // NOTE: Redis rejects a zero expiry, so TTLs are rounded up to one millisecond.
+        let payload = serde_json::to_vec(value)
+            .map_err(|e| DriverError::InternalError(format!("Serialization error: {e}")))?;
+        self.conn
+            .pset_ex::<&str, Vec<u8>, ()>(key.as_ref(), payload, ttl_millis(ttl))
+            .map_err(|e| DriverError::InternalError(format!("Redis set error: {e}")))
+    }
+
+    fn expire(&mut self, key: &K, ttl: Duration) -> Result<bool, DriverError> {
+        self.conn
+            .pexpire(key.as_ref(), ttl_millis(ttl) as i64)
+            .map_err(|e| DriverError::InternalError(format!("Redis expire error: {e}")))
+    }
+
     fn get(&mut self, key: &K) -> Result<V, DriverError> {
         // fetch bytes from Redis + deserialize using serde_json
         let bytes = self
@@ -75,4 +134,54 @@ where
             .exists(key.as_ref())
             .map_err(|e| DriverError::InternalError(format!("Redis exists check error: {e}")))
     }
+
+    fn increment(&mut self, key: &K, delta: i64) -> Result<i64, DriverError> {
+        // NOTE: integers are serialized by serde_json as plain digits, so INCRBY works on values
+        // written by `set` and the result can be read back with `get`.
+        self.conn
+            .incr(key.as_ref(), delta)
+            .map_err(|e| DriverError::InternalError(format!("Redis increment error: {e}")))
+    }
+
+    fn keys(&mut self, pattern: &K) -> Result<Vec<String>, DriverError> {
+        // NOTE: SCAN is used instead of KEYS so that large databases are not blocked.
+        let keys = self
+            .conn
+            .scan_match::<&str, String>(pattern.as_ref())
+            .map_err(|e| DriverError::InternalError(format!("Redis scan error: {e}")))?
+            .collect();
+        Ok(keys)
+    }
+
+    fn push_capped(&mut self, key: &K, value: &V, cap: usize) -> Result<(), DriverError> {
+        let payload = serde_json::to_vec(value)
+            .map_err(|e| DriverError::InternalError(format!("Serialization error: {e}")))?;
+        // RPUSH and LTRIM run in a MULTI/EXEC transaction, so the list never exceeds `cap`.
+        let mut pipe = redis::pipe();
+        pipe.atomic().rpush(key.as_ref(), payload).ignore();
+        if cap > 0 {
+            pipe.ltrim(key.as_ref(), -(cap as isize), -1).ignore();
+        }
+        pipe.query::<()>(&mut self.conn)
+            .map_err(|e| DriverError::InternalError(format!("Redis push error: {e}")))
+    }
+
+    fn list_range(&mut self, key: &K, start: i64, stop: i64) -> Result<Vec<V>, DriverError> {
+        let items = self
+            .conn
+            .lrange::<&str, Vec<Vec<u8>>>(key.as_ref(), start as isize, stop as isize)
+            .map_err(|e| DriverError::InternalError(format!("Redis range error: {e}")))?;
+        items
+            .iter()
+            .map(|bytes| {
+                serde_json::from_slice(bytes)
+                    .map_err(|e| DriverError::InternalError(format!("Deserialization error: {e}")))
+            })
+            .collect()
+    }
+}
+
+// Milliseconds of `ttl`, at least one.
+fn ttl_millis(ttl: Duration) -> u64 {
+    (ttl.as_millis() as u64).max(1)
 }
@@ -0,0 +1,161 @@
+use std::collections::BTreeMap;
+
+use tracing::Span;
+use tracing_subscriber::{filter::LevelFilter, layer::SubscriberExt, util::SubscriberInitExt};
+
+#[cfg(feature = "otel")]
+use opentelemetry::{
+    global,
+    propagation::{Extractor, Injector},
+    trace::TracerProvider as _,
+};
+#[cfg(feature = "otel")]
+use opentelemetry_sdk::{Resource, propagation::TraceContextPropagator, trace::SdkTracerProvider};
+#[cfg(feature = "otel")]
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+// Tracing pipeline of a process, installed by `init`. Dropping it exports the spans still
+// buffered, so it must live until the process exits.
+pub struct Telemetry {
+    #[cfg(feature = "otel")]
+    provider: Option<SdkTracerProvider>,
+}
+
+// Install the global tracing subscriber: logs on stdout, as text or (with LOG_FORMAT=json) one
+// JSON object per line, and, when built with the `otel` feature and OTEL_EXPORTER_OTLP_ENDPOINT is
+// set, spans exported over OTLP/HTTP under `service_name` (unless OTEL_SERVICE_NAME is set).
+pub fn init(service_name: &str) -> Telemetry {
+    // JSON lines carry the fields of the event at the top level and those of the current span
+    // (agent, url, domain, depth...) under `span`, so log pipelines can index them as is.
+    let json = match std::env::var("LOG_FORMAT").map(|f| f.trim().to_lowercase()) {
+        Ok(format) if format == "json" => true,
+        Ok(format) if format == "text" || format.is_empty() => false,
+        Ok(format) => {
+            eprintln!("Unknown LOG_FORMAT '{format}' (expected 'text' or 'json'), using text");
+            false
+        }
+        Err(_) => false,
+    };
+    let text_layer = (!json).then(tracing_subscriber::fmt::layer);
+    let json_layer = json.then(|| {
+        tracing_subscriber::fmt::layer()
+            .json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .with_span_list(false)
+    });
+
+    #[cfg(feature = "otel")]
+    let provider = otlp_provider(service_name);
+    #[cfg(feature = "otel")]
+    let otel_layer = provider.as_ref().map(|provider| {
+        tracing_opentelemetry::layer().with_tracer(provider.tracer(service_name.to_string()))
+    });
+    #[cfg(not(feature = "otel"))]
+    let otel_layer: Option<tracing_subscriber::layer::Identity> = {
+        let _ = service_name;
+        None
+    };
+
+    tracing_subscriber::registry()
+        .with(LevelFilter::INFO)
+        .with(text_layer)
+        .with(json_layer)
+        .with(otel_layer)
+        .init();
+
+    Telemetry {
+        #[cfg(feature = "otel")]
+        provider,
+    }
+}
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        #[cfg(feature = "otel")]
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("Failed to export the remaining spans: {e}");
+            }
+        }
+    }
+}
+
+// Add the trace context of the current span (W3C `traceparent` and `tracestate`) to the headers
+// of a message, so that handling the message joins the trace that published it. No-op without
+// the `otel` feature.
+pub fn inject_context(headers: &mut BTreeMap<String, String>) {
+    #[cfg(feature = "otel")]
+    {
+        let context = Span::current().context();
+        global::get_text_map_propagator(|propagator| {
+            propagator.inject_context(&context, &mut HeaderInjector(headers))
+        });
+    }
+    #[cfg(not(feature = "otel"))]
+    let _ = headers;
+}
+
+// Make `span` a child of the trace context carried by the headers of a message (see
+// `inject_context`). No-op without the `otel` feature.
+pub fn set_parent(span: &Span, headers: &BTreeMap<String, String>) {
+    #[cfg(feature = "otel")]
+    {
+        let parent = global::get_text_map_propagator(|propagator| {
+            propagator.extract(&HeaderExtractor(headers))
+        });
+        let _ = span.set_parent(parent);
+    }
+    #[cfg(not(feature = "otel"))]
+    let _ = (span, headers);
+}
+
+// Tracer provider exporting to OTEL_EXPORTER_OTLP_ENDPOINT, when set.
+#[cfg(feature = "otel")]
+fn otlp_provider(service_name: &str) -> Option<SdkTracerProvider> {
+    // NOTE: the subscriber is not installed yet, so errors go to stderr.
+    let endpoint = std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok()?;
+    let exporter = match opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .build()
+    {
+        Ok(exporter) => exporter,
+        Err(e) => {
+            eprintln!("Failed to build the OTLP exporter for {endpoint}: {e}");
+            return None;
+        }
+    };
+    let service_name =
+        std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| service_name.to_string());
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name(service_name).build())
+        .build();
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    global::set_tracer_provider(provider.clone());
+    Some(provider)
+}
+
+#[cfg(feature = "otel")]
+struct HeaderInjector<'a>(&'a mut BTreeMap<String, String>);
+
+#[cfg(feature = "otel")]
+impl Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        self.0.insert(key.to_string(), value);
+    }
+}
+
+#[cfg(feature = "otel")]
+struct HeaderExtractor<'a>(&'a BTreeMap<String, String>);
+
+#[cfg(feature = "otel")]
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(String::as_str)
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(String::as_str).collect()
+    }
+}
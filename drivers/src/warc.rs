@@ -0,0 +1,325 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use flate2::{Compression, write::GzEncoder};
+use serde_json::{Map, Value};
+use tracing::info;
+
+use crate::{
+    dates::{compact_timestamp, iso8601},
+    random_uuid,
+};
+
+// Response headers describing the encoding of the original transfer. Bodies are published
+// decoded (and as UTF-8), so these no longer apply to the stored payload.
+const TRANSFER_HEADERS: [&str; 3] = ["content-encoding", "transfer-encoding", "content-length"];
+
+// Writes every page (a `PageData` as a JSON object) as WARC 1.1 records (`request`, `response` and `metadata`) to files of
+// `dir`, named `<prefix>-<timestamp>-<serial>.warc.gz` and rotated once they reach `max_bytes`.
+// With `gzip`, every record is a separate gzip member, as expected by web-archive tooling. Each
+// file starts with a `warcinfo` record.
+//
+// Pages only carry what the crawler published: the request record is rebuilt from the URL, and
+// the response body is the decoded body, so the headers about the transfer encoding are
+// replaced by the length of the stored body.
+pub struct WarcWriter {
+    dir: PathBuf,
+    prefix: String,
+    max_bytes: u64,
+    gzip: bool,
+    serial: AtomicU64,
+    // Open file, its path and its current size.
+    file: Mutex<(File, PathBuf, u64)>,
+}
+
+impl WarcWriter {
+    pub fn open(dir: &str, prefix: &str, max_bytes: u64, gzip: bool) -> Result<Self, String> {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {dir}: {e}"))?;
+        let dir = PathBuf::from(dir);
+        let serial = AtomicU64::new(0);
+        let file = create(&dir, prefix, gzip, &serial)?;
+        info!("Writing WARC records to {}", file.1.display());
+        Ok(WarcWriter {
+            dir,
+            prefix: prefix.to_string(),
+            max_bytes,
+            gzip,
+            serial,
+            file: Mutex::new(file),
+        })
+    }
+
+    // Append the records of a page, rotating the file first when it would grow past `max_bytes`.
+    // Pages can be written from several threads.
+    pub fn write(&self, record: &Map<String, Value>) -> Result<(), String> {
+        let records = self.page_records(record)?;
+
+        let mut file = self.file.lock().unwrap();
+        let full = self.max_bytes > 0 && file.2 + records.len() as u64 > self.max_bytes;
+        if full {
+            *file = create(&self.dir, &self.prefix, self.gzip, &self.serial)?;
+            info!("Rotated WARC output to {}", file.1.display());
+        }
+        let (file, path, size) = &mut *file;
+        file.write_all(&records)
+            .map_err(|e| format!("Failed to write to {}: {e}", path.display()))?;
+        *size += records.len() as u64;
+        Ok(())
+    }
+
+    // The request, response and metadata records of a page.
+    fn page_records(&self, record: &Map<String, Value>) -> Result<Vec<u8>, String> {
+        let url = record
+            .get("url")
+            .and_then(Value::as_str)
+            .ok_or("record has no url")?;
+        let crawl = record.get("crawl").and_then(Value::as_object);
+        let crawl_field = |name: &str| crawl.and_then(|crawl| crawl.get(name));
+        let date = crawl_field("crawled_at")
+            .and_then(Value::as_u64)
+            .filter(|at| *at > 0)
+            .unwrap_or_else(unix_now);
+        let target = crawl_field("final_url")
+            .and_then(Value::as_str)
+            .filter(|final_url| !final_url.is_empty())
+            .unwrap_or(url);
+        let target_uri = format!("<{target}>");
+        // The request and metadata records refer to the response record.
+        let response_id = record_id();
+
+        // Request: rebuilt from the URL, the crawler does not publish its request headers.
+        let (host, path) = split_target(target);
+        let request = format!("GET {path} HTTP/1.1\r\nHost: {host}\r\n\r\n");
+        let mut out = encode(
+            self.gzip,
+            "request",
+            &record_id(),
+            &[
+                ("WARC-Target-URI", target_uri.as_str()),
+                ("WARC-Concurrent-To", response_id.as_str()),
+            ],
+            "application/http; msgtype=request",
+            request.as_bytes(),
+            date,
+        )?;
+
+        // Response: status line, headers and the (decoded) body.
+        let status = record
+            .get("status_code")
+            .and_then(Value::as_u64)
+            .unwrap_or_default();
+        let body = record
+            .get("body")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let mut response = format!("HTTP/1.1 {status} {}\r\n", reason(status));
+        for header in record
+            .get("headers")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+        {
+            let name = header.split(':').next().unwrap_or_default().trim();
+            if !TRANSFER_HEADERS.contains(&name.to_lowercase().as_str()) {
+                response.push_str(header.trim());
+                response.push_str("\r\n");
+            }
+        }
+        response.push_str(&format!("Content-Length: {}\r\n\r\n", body.len()));
+        let mut response = response.into_bytes();
+        response.extend_from_slice(body.as_bytes());
+        out.extend(encode(
+            self.gzip,
+            "response",
+            &response_id,
+            &[("WARC-Target-URI", target_uri.as_str())],
+            "application/http; msgtype=response",
+            &response,
+            date,
+        )?);
+
+        // Metadata: crawl details and outlinks, as WARC fields.
+        let mut metadata = String::new();
+        if let Some(depth) = crawl_field("depth").and_then(Value::as_u64) {
+            metadata.push_str(&format!("hopsFromSeed: {depth}\r\n"));
+        }
+        if let Some(duration) = crawl_field("fetch_duration_ms").and_then(Value::as_u64) {
+            metadata.push_str(&format!("fetchTimeMs: {duration}\r\n"));
+        }
+        if let Some(seed) = crawl_field("origin_seed").and_then(Value::as_str) {
+            metadata.push_str(&format!("seed: {seed}\r\n"));
+        }
+        for via in crawl_field("redirect_chain")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+        {
+            metadata.push_str(&format!("via: {via}\r\n"));
+        }
+        for link in record
+            .get("links")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            // links are `Link` objects, or plain URLs in older messages
+            let href = link
+                .get("href")
+                .and_then(Value::as_str)
+                .or_else(|| link.as_str());
+            if let Some(href) = href {
+                metadata.push_str(&format!("outlink: {href}\r\n"));
+            }
+        }
+        out.extend(encode(
+            self.gzip,
+            "metadata",
+            &record_id(),
+            &[
+                ("WARC-Target-URI", target_uri.as_str()),
+                ("WARC-Concurrent-To", response_id.as_str()),
+            ],
+            "application/warc-fields",
+            metadata.as_bytes(),
+            date,
+        )?);
+        Ok(out)
+    }
+}
+
+// Start a new WARC file in `dir` with its `warcinfo` record. Returns the file, its path and size.
+fn create(
+    dir: &Path,
+    prefix: &str,
+    gzip: bool,
+    serial: &AtomicU64,
+) -> Result<(File, PathBuf, u64), String> {
+    let extension = if gzip { "warc.gz" } else { "warc" };
+    let timestamp = compact_timestamp(unix_now());
+    let (mut file, path) = loop {
+        let serial = serial.fetch_add(1, Ordering::Relaxed);
+        let path = dir.join(format!("{prefix}-{timestamp}-{serial:05}.{extension}"));
+        match OpenOptions::new().create_new(true).append(true).open(&path) {
+            Ok(file) => break (file, path),
+            // left by a previous run started in the same second
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(format!("Failed to open {}: {e}", path.display())),
+        }
+    };
+
+    let filename = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let info = format!(
+        "software: distributed-web-crawler/{}\r\nformat: WARC File Format 1.1\r\n",
+        env!("CARGO_PKG_VERSION")
+    );
+    let record = encode(
+        gzip,
+        "warcinfo",
+        &record_id(),
+        &[("WARC-Filename", filename.as_str())],
+        "application/warc-fields",
+        info.as_bytes(),
+        unix_now(),
+    )?;
+    file.write_all(&record)
+        .map_err(|e| format!("Failed to write to {}: {e}", path.display()))?;
+    Ok((file, path, record.len() as u64))
+}
+
+// Serialize a record, as its own gzip member when `gzip` is set. `id` is a `<urn:uuid:...>`.
+fn encode(
+    gzip: bool,
+    kind: &str,
+    id: &str,
+    headers: &[(&str, &str)],
+    content_type: &str,
+    block: &[u8],
+    date: u64,
+) -> Result<Vec<u8>, String> {
+    let mut record = format!(
+        "WARC/1.1\r\nWARC-Type: {kind}\r\nWARC-Record-ID: {id}\r\nWARC-Date: {}\r\n",
+        iso8601(date)
+    );
+    for (name, value) in headers {
+        record.push_str(&format!("{name}: {value}\r\n"));
+    }
+    record.push_str(&format!(
+        "Content-Type: {content_type}\r\nContent-Length: {}\r\n\r\n",
+        block.len()
+    ));
+    let mut record = record.into_bytes();
+    record.extend_from_slice(block);
+    record.extend_from_slice(b"\r\n\r\n");
+
+    if !gzip {
+        return Ok(record);
+    }
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&record).map_err(|e| e.to_string())?;
+    encoder.finish().map_err(|e| e.to_string())
+}
+
+// Host and request target (path and query) of an absolute URL.
+fn split_target(url: &str) -> (&str, String) {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let rest = rest.split('#').next().unwrap_or_default();
+    match rest.find(['/', '?']) {
+        Some(at) if rest[at..].starts_with('/') => (&rest[..at], rest[at..].to_string()),
+        // `http://host?query` has an empty path
+        Some(at) => (&rest[..at], format!("/{}", &rest[at..])),
+        None => (rest, "/".to_string()),
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+// Random UUID identifying a record, as `<urn:uuid:...>`.
+fn record_id() -> String {
+    format!("<urn:uuid:{}>", random_uuid())
+}
+
+// Reason phrase of the common status codes (the crawler does not publish the original one).
+fn reason(status: u64) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        203 => "Non-Authoritative Information",
+        204 => "No Content",
+        206 => "Partial Content",
+        301 => "Moved Permanently",
+        302 => "Found",
+        303 => "See Other",
+        304 => "Not Modified",
+        307 => "Temporary Redirect",
+        308 => "Permanent Redirect",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        410 => "Gone",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        504 => "Gateway Timeout",
+        _ => "",
+    }
+}
@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+
+// Main content of a page (its article), without navigation, banners and footers.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MainContent {
+    // Title of the article, if found.
+    #[serde(default)]
+    pub title: Option<String>,
+    // Text of the article, one paragraph per line separated by a blank line.
+    pub text: String,
+}
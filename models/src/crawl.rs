@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+// How a page was fetched: when, how deep in the crawl, how long it took and through which
+// redirects, so consumers can analyse freshness and fetch performance.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrawlMetadata {
+    // Unix timestamp (seconds) at which the fetch started.
+    pub crawled_at: u64,
+    // Number of links followed from a seed to reach the page (0 for seeds).
+    pub depth: u32,
+    // Time taken to fetch the page, from sending the request to reading the body.
+    #[serde(default)]
+    pub fetch_duration_ms: u64,
+    // URLs that redirected the request, starting with the page URL (empty without redirects).
+    #[serde(default)]
+    pub redirect_chain: Vec<String>,
+    // URL the page was served from, after following redirects.
+    #[serde(default)]
+    pub final_url: String,
+    // Seed the page was discovered from (the page URL for seeds and injected URLs).
+    #[serde(default)]
+    pub origin_seed: Option<String>,
+    // Pages followed from the seed to reach the page, starting with the seed (empty for seeds).
+    #[serde(default)]
+    pub path_from_seed: Vec<String>,
+    // Character encoding the body was decoded from (e.g. `windows-1252`), from the Content-Type
+    // header, a `<meta>` declaration or sniffing. Bodies are always published as UTF-8.
+    #[serde(default)]
+    pub encoding: Option<String>,
+    // Media type of the response (e.g. `text/html`, `application/pdf`), from its Content-Type
+    // header. The body of PDF documents is their extracted text.
+    #[serde(default)]
+    pub content_type: Option<String>,
+}
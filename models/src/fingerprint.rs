@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+// Fingerprint of the visible text of a page, used to detect pages served at several URLs.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContentFingerprint {
+    // SHA-256 (hex) of the normalized text: identical for pages with the same content.
+    pub content_hash: String,
+    // 64-bit SimHash of the text: pages whose SimHashes differ in a few bits are near-duplicates.
+    pub simhash: u64,
+}
+
+impl ContentFingerprint {
+    // Number of bits that differ between the SimHashes of two fingerprints.
+    pub fn distance(&self, other: &ContentFingerprint) -> u32 {
+        (self.simhash ^ other.simhash).count_ones()
+    }
+}
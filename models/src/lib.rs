@@ -1,4 +1,26 @@
+mod content;
+mod crawl;
+mod fingerprint;
+mod link;
 mod pagedata;
+mod provenance;
+mod robots;
+mod schema;
+mod skipped;
+mod structured;
+mod usage;
+mod worker;
 
 // re-export for easier access
+pub use crate::content::MainContent;
+pub use crate::crawl::CrawlMetadata;
+pub use crate::fingerprint::ContentFingerprint;
+pub use crate::link::Link;
 pub use crate::pagedata::PageData;
+pub use crate::provenance::Provenance;
+pub use crate::robots::RobotsDirectives;
+pub use crate::schema::{DecodeError, SchemaVersion, decode_page};
+pub use crate::skipped::SkippedReason;
+pub use crate::structured::{MicrodataItem, MicrodataValue, StructuredData};
+pub use crate::usage::UsageRights;
+pub use crate::worker::WorkerIdentity;
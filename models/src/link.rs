@@ -0,0 +1,88 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+// Hyperlink found on a page, with the context needed to judge it (anchor text, `rel` values).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "LinkRepr")]
+pub struct Link {
+    // Absolute URL of the link target.
+    pub href: String,
+    // Whitespace-normalized text of the anchor (or its `aria-label`/`title` when it has no text).
+    pub anchor_text: String,
+    // Lowercased values of the `rel` attribute (e.g. `nofollow`, `ugc`, `sponsored`).
+    pub rel: Vec<String>,
+    // Whether `rel` asks crawlers not to follow the link (`nofollow`, `ugc` or `sponsored`).
+    pub is_nofollow: bool,
+    // Index of the link among the links of the page, in document order.
+    pub position: usize,
+}
+
+impl Link {
+    pub fn new(href: String, anchor_text: String, rel: Vec<String>, position: usize) -> Self {
+        let is_nofollow = rel
+            .iter()
+            .any(|value| matches!(value.as_str(), "nofollow" | "ugc" | "sponsored"));
+        Link {
+            href,
+            anchor_text,
+            rel,
+            is_nofollow,
+            position,
+        }
+    }
+}
+
+impl From<String> for Link {
+    fn from(href: String) -> Self {
+        Link {
+            href,
+            ..Link::default()
+        }
+    }
+}
+
+impl Display for Link {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.href)
+    }
+}
+
+// Messages published before links carried their context list them as plain URLs.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LinkRepr {
+    Href(String),
+    Full {
+        href: String,
+        #[serde(default)]
+        anchor_text: String,
+        #[serde(default)]
+        rel: Vec<String>,
+        #[serde(default)]
+        is_nofollow: bool,
+        #[serde(default)]
+        position: usize,
+    },
+}
+
+impl From<LinkRepr> for Link {
+    fn from(repr: LinkRepr) -> Self {
+        match repr {
+            LinkRepr::Href(href) => Link::from(href),
+            LinkRepr::Full {
+                href,
+                anchor_text,
+                rel,
+                is_nofollow,
+                position,
+            } => Link {
+                href,
+                anchor_text,
+                rel,
+                is_nofollow,
+                position,
+            },
+        }
+    }
+}
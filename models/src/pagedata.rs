@@ -1,30 +1,83 @@
-use std::fmt::Display;
+use std::{collections::HashMap, fmt::Display};
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::{
+    ContentFingerprint, CrawlMetadata, Link, MainContent, Provenance, RobotsDirectives,
+    SchemaVersion, SkippedReason, StructuredData, UsageRights,
+};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct PageData {
+    // Version of the message schema (see `decode_page`). Messages from crawlers that predate the
+    // field are version 1.
+    #[serde(default = "first_schema_version")]
+    pub schema_version: SchemaVersion,
     pub url: String,
     pub title: String,
     pub status_code: u16,
     pub headers: Vec<String>,
     pub meta: Vec<String>,
-    pub links: Vec<String>,
+    // Links found on the page; plain URL strings from older messages are accepted.
+    pub links: Vec<Link>,
     pub body: String,
+    // License and AI-usage signals declared by the page.
+    #[serde(default)]
+    pub usage: UsageRights,
+    // `noindex`/`nofollow` directives of the page: the body of noindex pages and the links of
+    // nofollow pages are left empty.
+    #[serde(default)]
+    pub robots: RobotsDirectives,
+    // URL the page declares as its canonical version (`<link rel="canonical">` or `og:url`).
+    #[serde(default)]
+    pub canonical_url: Option<String>,
+    // Fingerprint of the visible text of HTML pages (see `ContentFingerprint`).
+    #[serde(default)]
+    pub fingerprint: Option<ContentFingerprint>,
+    // ISO 639-3 code of the language of the page text (e.g. `eng`), when reliably detected.
+    #[serde(default)]
+    pub language: Option<String>,
+    // Article text and title of HTML pages, when main-content extraction is enabled and found.
+    #[serde(default)]
+    pub main_content: Option<MainContent>,
+    // JSON-LD, OpenGraph/Twitter card and microdata metadata of HTML pages, if any.
+    #[serde(default)]
+    pub structured_data: Option<StructuredData>,
+    // Fields extracted by the site-specific CSS selector profile matching the page (prices,
+    // authors, dates, ...), by name.
+    #[serde(default)]
+    pub custom_fields: HashMap<String, String>,
+    // Fetch time, latency, redirects and crawl depth of the page.
+    #[serde(default)]
+    pub crawl: CrawlMetadata,
+    // Agent, job and deployment that produced the message.
+    #[serde(default)]
+    pub provenance: Provenance,
+    // Set when the body was not downloaded (e.g. a binary content type); body and links are empty.
+    #[serde(default)]
+    pub skipped: Option<SkippedReason>,
+}
+
+fn first_schema_version() -> SchemaVersion {
+    SchemaVersion::V1
 }
 
 impl Display for PageData {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "PageData {{ url: {}, title: {}, status_code: {}, headers: {:?}, meta: {:?}, links: {:?}, body_length: {} }}",
+            "PageData {{ url: {}, title: {}, status_code: {}, headers: {:?}, meta: {:?}, links: {:?}, body_length: {}, provenance: {} }}",
             self.url,
             self.title,
             self.status_code,
             self.headers,
             self.meta,
-            self.links,
-            self.body.len()
+            self.links
+                .iter()
+                .map(|l| l.href.as_str())
+                .collect::<Vec<_>>(),
+            self.body.len(),
+            self.provenance
         )
     }
 }
@@ -0,0 +1,30 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::WorkerIdentity;
+
+// Records which deployment produced a message, so stored crawl corpora can be audited and
+// deployments sharing the same sinks can be told apart.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Provenance {
+    // Name of the agent that fetched the page (e.g. `crawler-generic-1`).
+    pub agent: String,
+    // Identifier of the crawl job the agent was running.
+    pub job: String,
+    // Version of the crawler crate.
+    pub crawler_version: String,
+    // Hash of the crawl configuration, identical for identically configured deployments.
+    pub config_hash: String,
+    pub worker: WorkerIdentity,
+}
+
+impl Display for Provenance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} on {} (job {}, v{}, config {})",
+            self.agent, self.worker, self.job, self.crawler_version, self.config_hash
+        )
+    }
+}
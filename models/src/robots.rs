@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+// Indexing directives of a page, from `<meta name="robots">` and the `X-Robots-Tag` header.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RobotsDirectives {
+    // The page asks not to be indexed (`noindex` or `none`); its body is not published.
+    pub noindex: bool,
+    // The page asks not to follow its links (`nofollow` or `none`); its links are not extracted.
+    pub nofollow: bool,
+}
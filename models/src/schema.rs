@@ -0,0 +1,85 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::PageData;
+
+// Version of the PageData message schema. Adding optional fields keeps the version; removing,
+// renaming or retyping fields bumps it, together with a migration from the previous version in
+// `MIGRATIONS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SchemaVersion(pub u32);
+
+impl SchemaVersion {
+    // Bare PageData JSON, as published before messages carried a version.
+    pub const V1: SchemaVersion = SchemaVersion(1);
+    // Version of the messages published by this version of the crate.
+    pub const CURRENT: SchemaVersion = SchemaVersion::V1;
+}
+
+impl Default for SchemaVersion {
+    fn default() -> Self {
+        SchemaVersion::CURRENT
+    }
+}
+
+impl Display for SchemaVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "v{}", self.0)
+    }
+}
+
+// Upgrades of a serialized page from version N (at index N - 1) to version N + 1.
+const MIGRATIONS: &[fn(&mut Map<String, Value>)] = &[];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    // The payload is not a PageData message of its declared version.
+    Malformed(String),
+    // The message declares a version no migration starts from.
+    Unsupported(SchemaVersion),
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::Malformed(msg) => write!(f, "Malformed message: {}", msg),
+            DecodeError::Unsupported(version) => {
+                write!(f, "Unsupported schema version {}", version)
+            }
+        }
+    }
+}
+
+// Decode a PageData message of any version, so that consumers keep working while crawlers are
+// upgraded (and the other way round). Older messages are migrated to the current schema. Newer
+// messages are read as the current schema: fields added since are ignored, and decoding only
+// fails if a field this version knows changed shape. `schema_version` keeps the version the
+// message was published with.
+pub fn decode_page(data: &[u8]) -> Result<PageData, DecodeError> {
+    let mut page: Map<String, Value> =
+        serde_json::from_slice(data).map_err(|e| DecodeError::Malformed(e.to_string()))?;
+
+    let version = match page.get("schema_version") {
+        None => SchemaVersion::V1,
+        Some(value) => value
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .map(SchemaVersion)
+            .ok_or_else(|| DecodeError::Malformed(format!("invalid schema_version {value}")))?,
+    };
+    if version < SchemaVersion::V1 {
+        return Err(DecodeError::Unsupported(version));
+    }
+
+    for migrate in MIGRATIONS.iter().skip(version.0 as usize - 1) {
+        migrate(&mut page);
+    }
+
+    let mut decoded: PageData = serde_json::from_value(Value::Object(page))
+        .map_err(|e| DecodeError::Malformed(format!("{version} message: {e}")))?;
+    decoded.schema_version = version;
+    Ok(decoded)
+}
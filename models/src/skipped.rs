@@ -0,0 +1,25 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+// Why the body of a fetched page was not downloaded. Skipped pages are still published with their
+// status code and headers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum SkippedReason {
+    // The response content type is not in the crawler's allowlist.
+    ContentType { content_type: String },
+    // The body exceeded the crawler's maximum size (in bytes) and the download was aborted.
+    BodyTooLarge { limit: u64 },
+}
+
+impl Display for SkippedReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkippedReason::ContentType { content_type } => {
+                write!(f, "content type '{content_type}' not allowed")
+            }
+            SkippedReason::BodyTooLarge { limit } => write!(f, "body larger than {limit} bytes"),
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+// Machine-readable metadata embedded in a page, so consumers building knowledge graphs do not
+// have to parse its HTML again.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StructuredData {
+    // Parsed `<script type="application/ld+json">` blocks, in document order.
+    #[serde(default)]
+    pub json_ld: Vec<serde_json::Value>,
+    // OpenGraph properties (`og:title`, `og:image`, `article:author`, ...) by name; properties
+    // declared several times keep every value.
+    #[serde(default)]
+    pub open_graph: BTreeMap<String, Vec<String>>,
+    // Twitter card properties (`twitter:card`, `twitter:title`, ...) by name.
+    #[serde(default)]
+    pub twitter: BTreeMap<String, Vec<String>>,
+    // Top-level schema.org microdata items (`itemscope` elements).
+    #[serde(default)]
+    pub microdata: Vec<MicrodataItem>,
+}
+
+impl StructuredData {
+    pub fn is_empty(&self) -> bool {
+        self.json_ld.is_empty()
+            && self.open_graph.is_empty()
+            && self.twitter.is_empty()
+            && self.microdata.is_empty()
+    }
+}
+
+// Microdata item: its types (`itemtype`), global id (`itemid`) and properties (`itemprop`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MicrodataItem {
+    #[serde(default)]
+    pub types: Vec<String>,
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub properties: BTreeMap<String, Vec<MicrodataValue>>,
+}
+
+// Value of a microdata property: text (URLs are resolved) or a nested item.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MicrodataValue {
+    Text(String),
+    Item(MicrodataItem),
+}
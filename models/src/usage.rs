@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+// Usage permissions declared by a page, so downstream pipelines can filter corpora by license or
+// honour opt-outs from AI training.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageRights {
+    // License URLs or identifiers from `rel="license"` links, license meta tags, and JSON-LD.
+    pub licenses: Vec<String>,
+    // Normalized Creative Commons license (e.g. `CC BY-SA 4.0`) if one of the licenses is one.
+    pub creative_commons: Option<String>,
+    // `noai` directive from robots meta tags or the `X-Robots-Tag` header.
+    pub noai: bool,
+    // `noimageai` directive from robots meta tags or the `X-Robots-Tag` header.
+    pub noimageai: bool,
+}
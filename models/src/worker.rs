@@ -0,0 +1,22 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+// Identity of the crawler process (worker) that produced a record. Lets operators running agents
+// on several hosts or regions attribute traffic and region-specific blocking.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkerIdentity {
+    pub hostname: String,
+    // Free-form region label (e.g. `eu-west-1`), if configured.
+    #[serde(default)]
+    pub region: Option<String>,
+}
+
+impl Display for WorkerIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.region {
+            Some(region) => write!(f, "{}@{}", self.hostname, region),
+            None => write!(f, "{}", self.hostname),
+        }
+    }
+}